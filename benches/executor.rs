@@ -0,0 +1,67 @@
+//! Times evaluation of large programs. Run with `cargo bench --bench executor`,
+//! and add `--features smallvec` to compare the inline stack or
+//! `--features jit` to compare native code.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use forth::Forth;
+
+fn time(name: &str, runs: u32, mut f: impl FnMut()) {
+    let mut best = Duration::MAX;
+    for _ in 0..runs {
+        let start = Instant::now();
+        f();
+        best = best.min(start.elapsed());
+    }
+    println!("{name:<32} {best:>12.2?}");
+}
+
+fn main() {
+    let literals = "1 ".repeat(100_000);
+    time("100k literals", 5, || {
+        let mut f = Forth::new();
+        f.eval(black_box(&literals)).unwrap();
+        black_box(f.stack());
+    });
+
+    let drops = "1 drop ".repeat(50_000);
+    time("50k literal/drop pairs", 5, || {
+        let mut f = Forth::new();
+        f.eval(black_box(&drops)).unwrap();
+    });
+
+    time("10k short scripts, fresh stacks", 5, || {
+        let mut f = Forth::new();
+        for _ in 0..10_000 {
+            f.eval(black_box("1 2 3 + *")).unwrap();
+            black_box(f.drain_stack());
+        }
+    });
+
+    let mut f = Forth::new();
+    f.eval(": sum dup if swap over + swap 1 - recurse then ;")
+        .unwrap();
+    time("sum 1..10k tail-recursively 100x", 5, || {
+        for _ in 0..100 {
+            f.eval(black_box("0 10000 sum drop drop")).unwrap();
+        }
+    });
+
+    let mut f = Forth::new();
+    for i in 0..1_000 {
+        f.eval(&format!(": word{i} {i} ; variable var{i}")).unwrap();
+    }
+    time("10k clones of a 2k-word interpreter", 5, || {
+        for _ in 0..10_000 {
+            black_box(f.clone());
+        }
+    });
+
+    let body = "1 + ".repeat(10_000);
+    let definition = format!(": big {body}; 0 big big big");
+    time("call a 20k-token word three times", 5, || {
+        let mut f = Forth::new();
+        f.eval(black_box(&definition)).unwrap();
+    });
+}
@@ -0,0 +1,46 @@
+//! Times programs dominated by dictionary lookups. Run with
+//! `cargo bench --bench lookup`, and add `--features fxhash` to compare the
+//! faster hasher.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use forth::Forth;
+
+fn time(name: &str, runs: u32, mut f: impl FnMut()) {
+    let mut best = Duration::MAX;
+    for _ in 0..runs {
+        let start = Instant::now();
+        f();
+        best = best.min(start.elapsed());
+    }
+    println!("{name:<32} {best:>12.2?}");
+}
+
+fn main() {
+    let builtins = "1 dup swap over drop drop drop ".repeat(20_000);
+    time("140k builtin lookups", 5, || {
+        let mut f = Forth::new();
+        f.eval(black_box(&builtins)).unwrap();
+    });
+
+    let mut f = Forth::new();
+    for i in 0..1_000 {
+        f.eval(&format!(": word{i} {i} drop ;")).unwrap();
+    }
+    let calls: String = (0..100_000)
+        .map(|i| format!("word{} ", i % 1_000))
+        .collect();
+    time("100k lookups among 1k words", 5, || {
+        f.clone().eval(black_box(&calls)).unwrap();
+    });
+
+    let definitions: String = (0..10_000)
+        .map(|i| format!(": d{i} d{} 1 + ; ", i.max(1) - 1))
+        .collect();
+    time("compile 10k definitions", 5, || {
+        let mut f = Forth::new();
+        f.eval(black_box(": d0 0 ;")).unwrap();
+        f.eval(black_box(&definitions)).unwrap();
+    });
+}
@@ -0,0 +1,74 @@
+//! Bindings the playground page uses to run Forth in the browser.
+//!
+//! There is no terminal to print to, so what the interpreter writes is kept
+//! until the page asks for it with `take_output`.
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use forth::{Forth, Value};
+use wasm_bindgen::prelude::*;
+
+/// Collects what the interpreter writes.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// One interpreter for the whole page, so definitions carry over from line
+/// to line.
+#[wasm_bindgen]
+pub struct Playground {
+    forth: Forth,
+    output: Captured,
+}
+
+impl Default for Playground {
+    fn default() -> Self {
+        Playground::new()
+    }
+}
+
+#[wasm_bindgen]
+impl Playground {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Playground {
+        let output = Captured::default();
+        let mut forth = Forth::new();
+        forth.set_output(output.clone());
+        Playground { forth, output }
+    }
+
+    /// Evaluates `input` and returns the error with its notes and
+    /// suggestions, or nothing if it succeeded.
+    pub fn eval(&mut self, input: &str) -> Option<String> {
+        self.forth
+            .eval_diagnostics(input)
+            .err()
+            .map(|diagnostics| diagnostics.to_string())
+    }
+
+    /// The stack, bottom first.
+    pub fn stack(&self) -> Vec<Value> {
+        self.forth.stack().to_vec()
+    }
+
+    /// What the interpreter wrote since the last call.
+    pub fn take_output(&mut self) -> String {
+        let bytes = std::mem::take(&mut *self.output.0.lock().unwrap_or_else(|e| e.into_inner()));
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    /// Names that can currently be looked up.
+    pub fn words(&self) -> Vec<String> {
+        self.forth.words().map(String::from).collect()
+    }
+}
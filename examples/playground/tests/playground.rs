@@ -0,0 +1,26 @@
+use forth_playground::Playground;
+
+#[test]
+fn lines_share_the_interpreter() {
+    let mut playground = Playground::new();
+    assert_eq!(None, playground.eval(": sq dup * ;"));
+    assert_eq!(None, playground.eval("3 sq 4"));
+    assert_eq!(vec![9, 4], playground.stack());
+    assert!(playground.words().contains(&"sq".to_string()));
+}
+
+#[test]
+fn errors_come_with_their_notes() {
+    let mut playground = Playground::new();
+    let error = playground.eval("1 0 /").unwrap();
+    assert!(error.starts_with("error: division by zero in `1 0 /`"));
+    assert!(error.contains("note:"));
+}
+
+#[test]
+fn output_is_kept_until_taken() {
+    let mut playground = Playground::new();
+    assert_eq!(None, playground.eval(": sq dup * ; dis sq"));
+    assert!(playground.take_output().contains("sq"));
+    assert_eq!("", playground.take_output());
+}
@@ -0,0 +1,35 @@
+import init, { Playground } from "./pkg/forth_playground.js";
+
+await init();
+const forth = new Playground();
+const input = document.getElementById("input");
+const output = document.getElementById("output");
+const stack = document.getElementById("stack");
+
+function print(text, className) {
+  const line = document.createElement("div");
+  line.textContent = text;
+  if (className) line.className = className;
+  output.append(line);
+  output.scrollTop = output.scrollHeight;
+}
+
+function showStack() {
+  stack.replaceChildren(...Array.from(forth.stack(), (value) => {
+    const cell = document.createElement("li");
+    cell.textContent = value;
+    return cell;
+  }));
+}
+
+input.addEventListener("keydown", (event) => {
+  if (event.key !== "Enter") return;
+  const line = input.value;
+  input.value = "";
+  print(`> ${line}`);
+  const error = forth.eval(line);
+  const written = forth.take_output();
+  if (written) print(written.trimEnd());
+  if (error) print(error, "error");
+  showStack();
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+use forth::Forth;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    // `recurse` is the only way to loop, and a loop may never end.
+    if input.to_lowercase().contains("recurse") {
+        return;
+    }
+    let mut forth = Forth::new();
+    let _ = forth.eval(input);
+    let _ = forth.eval_diagnostics(input);
+    let _ = forth.eval_lenient(input);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use forth::Program;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    let _ = Program::parse(input);
+    let _ = forth::check(input);
+});
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use crate::lexer::{number_in, Lexeme};
+use crate::vm::Input;
+use crate::{Forth, Result};
+
+/// The cell of data space `base` holds, before those of variables.
+pub(crate) const BASE: usize = 0;
+
+impl Forth {
+    /// The radix `base` holds, which numbers without a prefix are read in.
+    /// Any value outside 2 to 36 counts as ten.
+    pub(crate) fn base(&self) -> u32 {
+        u32::try_from(self.data_space[BASE])
+            .ok()
+            .filter(|base| (2..=36).contains(base))
+            .unwrap_or(10)
+    }
+
+    /// Stores `base` in its cell, as `base !` would.
+    pub(crate) fn set_base(&mut self, base: u32) {
+        Arc::make_mut(&mut self.data_space)[BASE] = base as crate::Value;
+        #[cfg(feature = "tagged")]
+        self.tag_cell(BASE, crate::Tag::Number);
+    }
+
+    /// The next token of `input`, read in the base `base` holds now, which
+    /// the word before may just have set.
+    pub(crate) fn next_token<'a>(&self, input: &mut Input<'a>) -> Option<Lexeme<'a>> {
+        let base = self.base();
+        input.set_base(base);
+        let token = input.next()?;
+        Some(self.in_base(token, base))
+    }
+
+    /// `token`, or the number it is in `base` if it is a word that isn't
+    /// defined. Words come first, so that in hexadecimal `add` may still
+    /// name a word.
+    pub(crate) fn in_base<'a>(&self, token: Lexeme<'a>, base: u32) -> Lexeme<'a> {
+        match &token {
+            Lexeme::Word(word) if base != 10 && self.lookup_word(word).is_err() => {
+                number_in(word, base).map_or(token, Lexeme::Number)
+            }
+            _ => token,
+        }
+    }
+
+    /// `hex ( -- )`
+    pub(crate) fn do_hex(&mut self) -> Result {
+        self.set_base(16);
+        Ok(())
+    }
+
+    /// `decimal ( -- )`
+    pub(crate) fn do_decimal(&mut self) -> Result {
+        self.set_base(10);
+        Ok(())
+    }
+}
@@ -0,0 +1,429 @@
+//! Jupyter kernel for Forth.
+//!
+//! `forth-jupyter install` registers the kernel with Jupyter, which then
+//! starts it as `forth-jupyter <connection-file>`. Every cell runs against
+//! the same interpreter, so definitions carry over from cell to cell. A cell
+//! that succeeds shows the stack it leaves; one that fails shows the error
+//! with its notes and suggestions. Text the interpreter writes, such as the
+//! listings of `dis` or the report of `test-summary`, is the cell's output.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use forth::{Forth, Value};
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value as Json};
+use sha2::Sha256;
+use zeromq::{PubSocket, RepSocket, RouterSocket, Socket, SocketRecv, SocketSend, ZmqMessage};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+const USAGE: &str = "usage: forth-jupyter <connection-file> | install";
+
+const PROTOCOL_VERSION: &str = "5.3";
+
+/// Separates the routing identities of a message from the message itself.
+const DELIMITER: &[u8] = b"<IDS|MSG>";
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), Error> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [command] if command == "install" => install(),
+        [path] => serve(serde_json::from_str(&std::fs::read_to_string(path)?)?).await,
+        _ => {
+            eprintln!("{USAGE}");
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Writes the kernel spec where Jupyter looks for kernels: under
+/// `$JUPYTER_DATA_DIR`, or `~/.local/share/jupyter` if that isn't set.
+fn install() -> Result<(), Error> {
+    let data = std::env::var_os("JUPYTER_DATA_DIR")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share/jupyter"))
+        })
+        .ok_or("no Jupyter data directory, set JUPYTER_DATA_DIR")?;
+    let dir = data.join("kernels").join("forth");
+    std::fs::create_dir_all(&dir)?;
+    let spec = json!({
+        "argv": [std::env::current_exe()?, "{connection_file}"],
+        "display_name": "Forth",
+        "language": "forth",
+    });
+    std::fs::write(
+        dir.join("kernel.json"),
+        serde_json::to_string_pretty(&spec)?,
+    )?;
+    println!("installed the forth kernel in {}", dir.display());
+    Ok(())
+}
+
+async fn serve(connection: Json) -> Result<(), Error> {
+    let endpoint = |port: &str| {
+        format!(
+            "{}://{}:{}",
+            connection["transport"].as_str().unwrap_or("tcp"),
+            connection["ip"].as_str().unwrap_or("127.0.0.1"),
+            connection[port]
+        )
+    };
+    let mut shell = RouterSocket::new();
+    shell.bind(&endpoint("shell_port")).await?;
+    let mut control = RouterSocket::new();
+    control.bind(&endpoint("control_port")).await?;
+    // Cells never ask for input, but frontends expect to connect.
+    let mut stdin = RouterSocket::new();
+    stdin.bind(&endpoint("stdin_port")).await?;
+    let mut iopub = PubSocket::new();
+    iopub.bind(&endpoint("iopub_port")).await?;
+    let mut heartbeat = RepSocket::new();
+    heartbeat.bind(&endpoint("hb_port")).await?;
+    tokio::spawn(async move {
+        while let Ok(ping) = heartbeat.recv().await {
+            if heartbeat.send(ping).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let key = connection["key"].as_str().unwrap_or("").as_bytes().to_vec();
+    let mut kernel = Kernel::new(key, iopub);
+    loop {
+        let (frames, from_control) = tokio::select! {
+            frames = shell.recv() => (frames?, false),
+            frames = control.recv() => (frames?, true),
+        };
+        let Some(request) = kernel.parse(frames) else {
+            continue;
+        };
+        let socket = if from_control {
+            &mut control
+        } else {
+            &mut shell
+        };
+        if !kernel.handle(socket, request).await? {
+            return Ok(());
+        }
+    }
+}
+
+/// A message received from a frontend.
+struct Request {
+    /// Where the reply is routed to.
+    identities: Vec<Vec<u8>>,
+    header: Json,
+    content: Json,
+}
+
+impl Request {
+    fn msg_type(&self) -> &str {
+        self.header["msg_type"].as_str().unwrap_or("")
+    }
+}
+
+/// Collects what the interpreter writes during a cell.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Captured {
+    fn take(&self) -> String {
+        let bytes = std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()));
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+struct Kernel {
+    key: Vec<u8>,
+    session: String,
+    messages_sent: u64,
+    iopub: PubSocket,
+    forth: Forth,
+    output: Captured,
+    execution_count: u64,
+}
+
+impl Kernel {
+    fn new(key: Vec<u8>, iopub: PubSocket) -> Kernel {
+        let output = Captured::default();
+        let mut forth = Forth::new();
+        forth.set_output(output.clone());
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Kernel {
+            key,
+            session: format!("{:x}-{:x}", std::process::id(), started.as_nanos()),
+            messages_sent: 0,
+            iopub,
+            forth,
+            output,
+            execution_count: 0,
+        }
+    }
+
+    /// Answers `request` on `socket`, publishing what happens meanwhile.
+    /// Returns `false` once the kernel has been asked to shut down.
+    async fn handle(&mut self, socket: &mut RouterSocket, request: Request) -> Result<bool, Error> {
+        self.publish(&request, "status", json!({ "execution_state": "busy" }))
+            .await?;
+        let mut running = true;
+        let reply = match request.msg_type() {
+            "kernel_info_request" => Some(kernel_info()),
+            "execute_request" => Some(self.execute(&request).await?),
+            "complete_request" => Some(self.complete(&request.content)),
+            "is_complete_request" => Some(json!({ "status": "complete" })),
+            "interrupt_request" => Some(json!({ "status": "ok" })),
+            "shutdown_request" => {
+                running = false;
+                Some(json!({ "status": "ok", "restart": request.content["restart"] }))
+            }
+            _ => None,
+        };
+        if let Some(content) = reply {
+            let msg_type = request.msg_type().replace("_request", "_reply");
+            let message = self.message(&request.identities, &msg_type, &request.header, content);
+            socket.send(message).await?;
+        }
+        self.publish(&request, "status", json!({ "execution_state": "idle" }))
+            .await?;
+        Ok(running)
+    }
+
+    async fn execute(&mut self, request: &Request) -> Result<Json, Error> {
+        let code = request.content["code"].as_str().unwrap_or("");
+        let silent = request.content["silent"].as_bool().unwrap_or(false);
+        if !silent {
+            self.execution_count += 1;
+        }
+        let count = self.execution_count;
+        self.publish(
+            request,
+            "execute_input",
+            json!({ "code": code, "execution_count": count }),
+        )
+        .await?;
+        let outcome = self.forth.eval_diagnostics(code);
+        let text = self.output.take();
+        if !silent && !text.is_empty() {
+            self.publish(request, "stream", json!({ "name": "stdout", "text": text }))
+                .await?;
+        }
+        match outcome {
+            Ok(()) => {
+                if !silent {
+                    let stack = stack_text(self.forth.stack());
+                    let result = json!({
+                        "execution_count": count,
+                        "data": { "text/plain": stack },
+                        "metadata": {},
+                    });
+                    self.publish(request, "execute_result", result).await?;
+                }
+                Ok(json!({
+                    "status": "ok",
+                    "execution_count": count,
+                    "user_expressions": {},
+                    "payload": [],
+                }))
+            }
+            Err(diagnostics) => {
+                let rendered = diagnostics.to_string();
+                let mut traceback: Vec<String> = rendered.lines().map(String::from).collect();
+                // Frontends render ANSI colours in tracebacks.
+                traceback[0] = format!("\x1b[31m{}\x1b[0m", traceback[0]);
+                let error = json!({
+                    "ename": format!("{:?}", diagnostics.error),
+                    "evalue": diagnostics.error.to_string(),
+                    "traceback": traceback,
+                });
+                self.publish(request, "error", error.clone()).await?;
+                let mut reply = json!({ "status": "error", "execution_count": count });
+                reply
+                    .as_object_mut()
+                    .expect("an object")
+                    .extend(error.as_object().expect("an object").clone());
+                Ok(reply)
+            }
+        }
+    }
+
+    /// Completes the word before the cursor from the words defined so far.
+    /// Positions count code points, as the protocol does.
+    fn complete(&self, content: &Json) -> Json {
+        let code = content["code"].as_str().unwrap_or("");
+        let cursor = content["cursor_pos"].as_u64().unwrap_or(0) as usize;
+        let end = code
+            .char_indices()
+            .nth(cursor)
+            .map_or(code.len(), |(i, _)| i);
+        let start = code[..end].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let prefix = code[start..end].to_lowercase();
+        let mut matches: Vec<&str> = self
+            .forth
+            .words()
+            .filter(|word| word.starts_with(&prefix))
+            .collect();
+        matches.sort();
+        json!({
+            "status": "ok",
+            "matches": matches,
+            "cursor_start": code[..start].chars().count(),
+            "cursor_end": code[..end].chars().count(),
+            "metadata": {},
+        })
+    }
+
+    /// Checks the signature of a received message, dropping it if it is
+    /// malformed or not signed with our key.
+    fn parse(&self, frames: ZmqMessage) -> Option<Request> {
+        let frames: Vec<Vec<u8>> = frames.into_vec().into_iter().map(|f| f.to_vec()).collect();
+        let delimiter = frames.iter().position(|frame| frame == DELIMITER)?;
+        let (identities, rest) = frames.split_at(delimiter);
+        let [signature, header, parent, metadata, content] = rest.get(1..6)? else {
+            return None;
+        };
+        if !self.key.is_empty() {
+            let expected = self.sign(&[header, parent, metadata, content]);
+            if signature != expected.as_bytes() {
+                return None;
+            }
+        }
+        Some(Request {
+            identities: identities.to_vec(),
+            header: serde_json::from_slice(header).ok()?,
+            content: serde_json::from_slice(content).ok()?,
+        })
+    }
+
+    fn sign(&self, parts: &[&[u8]]) -> String {
+        if self.key.is_empty() {
+            return String::new();
+        }
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("any key length works");
+        parts.iter().for_each(|part| mac.update(part));
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    fn message(
+        &mut self,
+        identities: &[Vec<u8>],
+        msg_type: &str,
+        parent: &Json,
+        content: Json,
+    ) -> ZmqMessage {
+        self.messages_sent += 1;
+        let header = json!({
+            "msg_id": format!("{}-{}", self.session, self.messages_sent),
+            "session": self.session,
+            "username": "forth",
+            "date": now(),
+            "msg_type": msg_type,
+            "version": PROTOCOL_VERSION,
+        });
+        let parts = [
+            header.to_string(),
+            parent.to_string(),
+            "{}".to_string(),
+            content.to_string(),
+        ];
+        let signature = self.sign(&parts.each_ref().map(|part| part.as_bytes()));
+        let mut message = ZmqMessage::from(DELIMITER.to_vec());
+        for identity in identities.iter().rev() {
+            message.push_front(identity.clone().into());
+        }
+        message.push_back(signature.into_bytes().into());
+        for part in parts {
+            message.push_back(part.into_bytes().into());
+        }
+        message
+    }
+
+    async fn publish(
+        &mut self,
+        request: &Request,
+        msg_type: &str,
+        content: Json,
+    ) -> Result<(), Error> {
+        let topic = vec![msg_type.as_bytes().to_vec()];
+        let message = self.message(&topic, msg_type, &request.header, content);
+        self.iopub.send(message).await?;
+        Ok(())
+    }
+}
+
+fn kernel_info() -> Json {
+    json!({
+        "status": "ok",
+        "protocol_version": PROTOCOL_VERSION,
+        "implementation": "forth",
+        "implementation_version": env!("CARGO_PKG_VERSION"),
+        "language_info": {
+            "name": "forth",
+            "version": "",
+            "mimetype": "text/x-forth",
+            "file_extension": ".fs",
+        },
+        "banner": "Forth",
+        "help_links": [],
+    })
+}
+
+/// The stack the way `.s` prints it: depth first, then the values from the
+/// bottom up.
+fn stack_text(stack: &[Value]) -> String {
+    let mut text = format!("<{}>", stack.len());
+    for value in stack {
+        text.push_str(&format!(" {value}"));
+    }
+    text
+}
+
+/// The current time in UTC, in the ISO 8601 form message headers carry.
+fn now() -> String {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let (days, seconds) = ((time.as_secs() / 86_400) as i64, time.as_secs() % 86_400);
+    // Howard Hinnant's civil_from_days, for days since 1970 that are never
+    // negative.
+    let z = days + 719_468;
+    let (era, day_of_era) = (z / 146_097, z % 146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:06}Z",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+        time.subsec_micros()
+    )
+}
@@ -0,0 +1,378 @@
+//! Language server for Forth over stdio.
+//!
+//! Each document is evaluated from scratch whenever it changes, and the
+//! first error is published as a diagnostic. Hover shows the stack-effect
+//! comment of a word, go-to-definition jumps to the `:` that is in effect at
+//! the cursor, and completion offers the words defined once the document
+//! has run.
+//!
+//! Evaluation stops after `WORD_BUDGET` words so a runaway loop can't hang
+//! the server.
+
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+
+use forth::{Forth, Span};
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{Completion, GotoDefinition, HoverRequest, Request as _};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, Diagnostic, DiagnosticSeverity,
+    GotoDefinitionResponse, Hover, HoverContents, HoverProviderCapability, Location, MarkupContent,
+    MarkupKind, OneOf, Position, PublishDiagnosticsParams, Range, ServerCapabilities,
+    TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind, Uri,
+};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+const WORD_BUDGET: usize = 1_000_000;
+
+/// The panic payload that stops an evaluation over budget.
+struct OverBudget;
+
+fn main() -> Result<(), Error> {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if !info.payload().is::<OverBudget>() {
+            default_hook(info)
+        }
+    }));
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+    serve(&connection)?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+fn serve(connection: &Connection) -> Result<(), Error> {
+    let mut documents: HashMap<String, Document> = HashMap::new();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                let response = respond(&documents, request)?;
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                let (uri, text) = match notification.method.as_str() {
+                    DidOpenTextDocument::METHOD => {
+                        let params = notification.extract::<lsp_types::DidOpenTextDocumentParams>(
+                            DidOpenTextDocument::METHOD,
+                        )?;
+                        (params.text_document.uri, params.text_document.text)
+                    }
+                    DidChangeTextDocument::METHOD => {
+                        let mut params = notification
+                            .extract::<lsp_types::DidChangeTextDocumentParams>(
+                                DidChangeTextDocument::METHOD,
+                            )?;
+                        let Some(change) = params.content_changes.pop() else {
+                            continue;
+                        };
+                        (params.text_document.uri, change.text)
+                    }
+                    DidCloseTextDocument::METHOD => {
+                        let params = notification
+                            .extract::<lsp_types::DidCloseTextDocumentParams>(
+                                DidCloseTextDocument::METHOD,
+                            )?;
+                        documents.remove(params.text_document.uri.as_str());
+                        continue;
+                    }
+                    _ => continue,
+                };
+                let document = Document::new(text);
+                let params = PublishDiagnosticsParams {
+                    diagnostics: document.diagnostics(),
+                    uri: uri.clone(),
+                    version: None,
+                };
+                connection
+                    .sender
+                    .send(Message::Notification(Notification::new(
+                        PublishDiagnostics::METHOD.to_string(),
+                        params,
+                    )))?;
+                documents.insert(uri.as_str().to_string(), document);
+            }
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+fn respond(documents: &HashMap<String, Document>, request: Request) -> Result<Response, Error> {
+    let id = request.id.clone();
+    let position = |request: Request, method| {
+        let (_, params): (_, TextDocumentPositionParams) = request.extract(method)?;
+        Ok::<_, Error>((params.text_document.uri, params.position))
+    };
+    let response = match request.method.as_str() {
+        HoverRequest::METHOD => {
+            let (uri, position) = position(request, HoverRequest::METHOD)?;
+            let hover = documents
+                .get(uri.as_str())
+                .and_then(|document| document.hover(position));
+            Response::new_ok(id, hover)
+        }
+        GotoDefinition::METHOD => {
+            let (uri, position) = position(request, GotoDefinition::METHOD)?;
+            let location = documents
+                .get(uri.as_str())
+                .and_then(|document| document.definition(&uri, position));
+            Response::new_ok(id, location.map(GotoDefinitionResponse::Scalar))
+        }
+        Completion::METHOD => {
+            let (_, params): (_, lsp_types::CompletionParams) =
+                request.extract(Completion::METHOD)?;
+            let uri = params.text_document_position.text_document.uri;
+            let items = documents
+                .get(uri.as_str())
+                .map(Document::completions)
+                .unwrap_or_default();
+            Response::new_ok(id, items)
+        }
+        _ => Response::new_err(
+            id,
+            lsp_server::ErrorCode::MethodNotFound as i32,
+            format!("unhandled method `{}`", request.method),
+        ),
+    };
+    Ok(response)
+}
+
+/// A `: name` in the document, with the comment following the name if it
+/// looks like a stack effect.
+struct Definition {
+    name: String,
+    span: Span,
+    effect: Option<String>,
+}
+
+struct Document {
+    text: String,
+    definitions: Vec<Definition>,
+    /// What went wrong running the document, if anything did.
+    failure: Option<(Span, String)>,
+    /// Words that can be looked up once the document has run.
+    words: Vec<String>,
+}
+
+impl Document {
+    fn new(text: String) -> Document {
+        let code = blank_comments(&text);
+        let mut forth = Forth::new();
+        forth.set_output(std::io::sink());
+        let mut words_run = 0;
+        forth.on_word(move |_, _, _| {
+            words_run += 1;
+            if words_run > WORD_BUDGET {
+                panic::panic_any(OverBudget);
+            }
+        });
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| forth.eval_diagnostics(&code)));
+        let failure = match outcome {
+            Ok(Ok(())) => None,
+            Ok(Err(diagnostics)) => {
+                let mut message = diagnostics.error.to_string();
+                for line in diagnostics.notes.iter().chain(&diagnostics.suggestions) {
+                    message.push('\n');
+                    message.push_str(line);
+                }
+                Some((diagnostics.span, message))
+            }
+            Err(_) => Some((
+                Span::new(0, 0),
+                format!("stopped evaluating after {WORD_BUDGET} words"),
+            )),
+        };
+        Document {
+            definitions: definitions(&text),
+            words: forth.words().map(String::from).collect(),
+            failure,
+            text,
+        }
+    }
+
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.failure
+            .iter()
+            .map(|(span, message)| Diagnostic {
+                range: self.range(*span),
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("forth".into()),
+                message: message.clone(),
+                ..Diagnostic::default()
+            })
+            .collect()
+    }
+
+    fn hover(&self, position: Position) -> Option<Hover> {
+        let (span, word) = self.word_at(position)?;
+        let effect = match self.definition_in_effect(&word, span.start) {
+            Some(definition) => definition.effect.clone()?,
+            None => Forth::new().doc(&word)?.to_string(),
+        };
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```forth\n{word} {effect}\n```"),
+            }),
+            range: Some(self.range(span)),
+        })
+    }
+
+    fn definition(&self, uri: &Uri, position: Position) -> Option<Location> {
+        let (span, word) = self.word_at(position)?;
+        let definition = self.definition_in_effect(&word, span.start)?;
+        Some(Location::new(uri.clone(), self.range(definition.span)))
+    }
+
+    fn completions(&self) -> Vec<CompletionItem> {
+        let builtins = Forth::new();
+        self.words
+            .iter()
+            .map(|word| CompletionItem {
+                label: word.clone(),
+                kind: Some(CompletionItemKind::FUNCTION),
+                detail: builtins.doc(word).map(String::from),
+                ..CompletionItem::default()
+            })
+            .collect()
+    }
+
+    /// The definition of `word` made last before `offset`: words are bound
+    /// when a definition is made, so later ones don't count. A word being
+    /// defined refers to its own `:`.
+    fn definition_in_effect(&self, word: &str, offset: usize) -> Option<&Definition> {
+        self.definitions
+            .iter()
+            .rev()
+            .find(|definition| definition.name == word && definition.span.start <= offset)
+    }
+
+    /// The whitespace-delimited word under `position`, lower-cased.
+    fn word_at(&self, position: Position) -> Option<(Span, String)> {
+        let offset = self.offset(position);
+        let is_word = |c: char| !c.is_whitespace();
+        let start = self.text[..offset]
+            .rfind(|c: char| !is_word(c))
+            .map_or(0, |i| i + 1);
+        let end = self.text[offset..]
+            .find(|c: char| !is_word(c))
+            .map_or(self.text.len(), |i| offset + i);
+        (start < end).then(|| (Span::new(start, end), self.text[start..end].to_lowercase()))
+    }
+
+    fn offset(&self, position: Position) -> usize {
+        let mut lines = self.text.split_inclusive('\n');
+        let line_start: usize = lines
+            .by_ref()
+            .take(position.line as usize)
+            .map(str::len)
+            .sum();
+        let line = lines.next().unwrap_or("");
+        let mut units = 0;
+        let column = line
+            .char_indices()
+            .find(|&(_, c)| {
+                units += c.len_utf16();
+                units > position.character as usize
+            })
+            .map_or(line.trim_end_matches('\n').len(), |(i, _)| i);
+        line_start + column
+    }
+
+    fn position(&self, offset: usize) -> Position {
+        let before = &self.text[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Position::new(
+            before.matches('\n').count() as u32,
+            before[line_start..].encode_utf16().count() as u32,
+        )
+    }
+
+    fn range(&self, span: Span) -> Range {
+        Range::new(self.position(span.start), self.position(span.end))
+    }
+}
+
+/// Spans of the whitespace-delimited words of `text`.
+fn words(text: &str) -> impl Iterator<Item = (Span, &str)> {
+    text.split_whitespace().map(move |word| {
+        let start = word.as_ptr() as usize - text.as_ptr() as usize;
+        (Span::new(start, start + word.len()), word)
+    })
+}
+
+/// Replaces `( ... )` and `\ ...` comments with spaces, which the
+/// interpreter skips, keeping every other byte where it was.
+fn blank_comments(text: &str) -> String {
+    let mut code = text.to_string();
+    for span in comments(text) {
+        code.replace_range(span.start..span.end, &" ".repeat(span.end - span.start));
+    }
+    code
+}
+
+fn comments(text: &str) -> Vec<Span> {
+    let mut comments = Vec::new();
+    let mut resume = 0;
+    for (span, word) in words(text) {
+        if span.start < resume {
+            continue;
+        }
+        let closing = match word {
+            "(" => ')',
+            "\\" => '\n',
+            _ => continue,
+        };
+        let end = text[span.end..]
+            .find(closing)
+            .map_or(text.len(), |i| span.end + i + 1);
+        comments.push(Span::new(span.start, end));
+        resume = end;
+    }
+    comments
+}
+
+fn definitions(text: &str) -> Vec<Definition> {
+    let comments = comments(text);
+    let code = blank_comments(text);
+    let mut definitions = Vec::new();
+    let mut words = words(&code);
+    while let Some((_, word)) = words.next() {
+        if word != ":" {
+            continue;
+        }
+        let Some((span, name)) = words.next() else {
+            break;
+        };
+        let effect = comments
+            .iter()
+            .find(|comment| {
+                comment.start >= span.end && code[span.end..comment.start].trim().is_empty()
+            })
+            .map(|comment| text[comment.start..comment.end].to_string())
+            .filter(|comment| comment.starts_with('(') && comment.contains("--"));
+        definitions.push(Definition {
+            name: name.to_lowercase(),
+            span,
+            effect,
+        });
+    }
+    definitions
+}
@@ -0,0 +1,651 @@
+use std::borrow::Cow;
+
+use crate::diagnostics::Fault;
+#[cfg(feature = "file-io")]
+use crate::files::FileWord;
+#[cfg(feature = "dlopen")]
+use crate::foreign::ForeignWord;
+use crate::lexer::Lexeme;
+#[cfg(feature = "net")]
+use crate::net::NetWord;
+#[cfg(feature = "peripherals")]
+use crate::peripherals::PeripheralWord;
+#[cfg(feature = "tagged")]
+use crate::Tag;
+use crate::{
+    push_address, push_char, Division, Error, Forth, Operation, Optimizations, Result,
+    UnknownWords, Value, PREDIFINED_OPERATIONS, RANDOM_OPERATIONS, TIME_OPERATIONS,
+};
+
+/// One instruction of compiled code. Each definition body is compiled to a
+/// `Vec<Op>` when the definition is made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Op {
+    Push(Value),
+    /// Pushes a value tagged other than as a number: the address of a
+    /// variable or a character literal.
+    #[cfg(feature = "tagged")]
+    PushTagged(Value, Tag),
+    /// Pushes the execution token of `[']`, see `Forth::xt`, which with the
+    /// `tagged` feature tags it as one.
+    PushXt(Value),
+    /// Runs the primitive through the function table in `vm.rs`.
+    Primitive(Primitive),
+    /// Runs the user-defined word with this index into `Forth::words`.
+    Call(usize),
+    /// Like `Call`, but in place of the current word, which has nothing left
+    /// to do.
+    TailCall(usize),
+    /// Skips this many of the following ops.
+    Branch(usize),
+    /// Pops a flag and skips this many of the following ops if it is zero.
+    BranchIfZero(usize),
+    /// Pops an execution token and runs the word it stands for, pushing 0
+    /// if it returns, or the code it throws, or the `Error::throw_code` of
+    /// how it failed, with the depth of the stack put back to what it was
+    /// once the token was popped; values the word took come back as 0.
+    Catch,
+    /// Defines the word parsed next from the input as a variable.
+    Variable,
+    /// Forgets the word parsed next from the input, see `Forth::forget`.
+    Forget,
+    /// Defines the word parsed next from the input as a marker.
+    Mark,
+    /// Defines the word parsed next from the input as an auxiliary stack.
+    DefineStack,
+    /// Spawns a task running the word parsed next from the input.
+    Spawn,
+    /// Gives the other tasks a turn, or ends the turn of the task running,
+    /// see `Forth::pause`.
+    Pause,
+    /// Defines the word parsed next from the input as a user variable.
+    User,
+    /// Pushes the execution token of the word parsed next from the input.
+    Tick,
+    /// Pops an execution token and runs the word it stands for.
+    Execute,
+    /// Defines the word parsed next from the input as a synonym of the one
+    /// parsed after it, see `Forth::alias`.
+    Synonym,
+    /// Cuts the dictionary back to this many entries, as a marker does.
+    Rewind(usize),
+    /// Writes the disassembly of the word parsed next from the input.
+    Disassemble,
+    /// Writes the stack-effect comment of the word parsed next from the
+    /// input.
+    Help,
+    /// Evaluates the source `Forth::set_include_resolver` finds for the
+    /// name parsed next from the input.
+    Include,
+    /// Fails with `Error::UnknownWord`, in place of a word that was not
+    /// defined when the body was compiled, see `UnknownWords::Defer`.
+    Unknown,
+    /// One of the file words.
+    #[cfg(feature = "file-io")]
+    File(FileWord),
+    #[cfg(feature = "env")]
+    GetEnv,
+    #[cfg(feature = "env")]
+    SetEnv,
+    /// One of the socket words.
+    #[cfg(feature = "net")]
+    Net(NetWord),
+    /// One of the peripheral words.
+    #[cfg(feature = "peripherals")]
+    Peripheral(PeripheralWord),
+    /// One of the words that call into shared libraries.
+    #[cfg(feature = "dlopen")]
+    Foreign(ForeignWord),
+}
+
+/// Where the body of a user-defined word lies in `Forth::code`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Body {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+impl Op {
+    fn is_branch(&self) -> bool {
+        matches!(self, Op::Branch(_) | Op::BranchIfZero(_))
+    }
+}
+
+/// Builtins that only need the interpreter state, indexing `vm::PRIMITIVES`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum Primitive {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    /// `/mod`, and `sm/rem`
+    DivMod,
+    /// `/` under `Division::Floored`
+    FlooredDivide,
+    /// `mod` under `Division::Floored`
+    FlooredModulo,
+    /// `fm/mod`, and `/mod` under `Division::Floored`
+    FlooredDivMod,
+    /// `w+`, and `+` under `Arithmetic::Wrapping`
+    WrappingAdd,
+    /// `w-`, and `-` under `Arithmetic::Wrapping`
+    WrappingSubtract,
+    /// `w*`, and `*` under `Arithmetic::Wrapping`
+    WrappingMultiply,
+    Dup,
+    Drop,
+    Swap,
+    Over,
+    Fetch,
+    Store,
+    /// `dup *`
+    Square,
+    /// `swap -`
+    SwapSubtract,
+    /// `over +`
+    OverAdd,
+    /// `t{`
+    TestOpen,
+    /// `->`
+    TestArrow,
+    /// `}t`
+    TestClose,
+    TestSummary,
+    Send,
+    Receive,
+    /// `recv?`
+    TryReceive,
+    /// `utime`
+    Utime,
+    /// `elapsed`
+    Elapsed,
+    /// `evaluate`
+    Evaluate,
+    /// `>s`
+    ToStack,
+    /// `s>`
+    FromStack,
+    Tasks,
+    Throw,
+    Emit,
+    Random,
+    Hex,
+    Decimal,
+}
+
+impl Primitive {
+    /// Every primitive, in declaration order.
+    pub(crate) const ALL: [Primitive; 39] = [
+        Primitive::Add,
+        Primitive::Subtract,
+        Primitive::Multiply,
+        Primitive::Divide,
+        Primitive::Modulo,
+        Primitive::DivMod,
+        Primitive::FlooredDivide,
+        Primitive::FlooredModulo,
+        Primitive::FlooredDivMod,
+        Primitive::WrappingAdd,
+        Primitive::WrappingSubtract,
+        Primitive::WrappingMultiply,
+        Primitive::Dup,
+        Primitive::Drop,
+        Primitive::Swap,
+        Primitive::Over,
+        Primitive::Fetch,
+        Primitive::Store,
+        Primitive::Square,
+        Primitive::SwapSubtract,
+        Primitive::OverAdd,
+        Primitive::TestOpen,
+        Primitive::TestArrow,
+        Primitive::TestClose,
+        Primitive::TestSummary,
+        Primitive::Send,
+        Primitive::Receive,
+        Primitive::TryReceive,
+        Primitive::Utime,
+        Primitive::Elapsed,
+        Primitive::Evaluate,
+        Primitive::ToStack,
+        Primitive::FromStack,
+        Primitive::Tasks,
+        Primitive::Throw,
+        Primitive::Emit,
+        Primitive::Random,
+        Primitive::Hex,
+        Primitive::Decimal,
+    ];
+
+    /// How the primitive rounds, if it divides.
+    pub(crate) fn division(self) -> Option<Division> {
+        match self {
+            Primitive::Divide | Primitive::Modulo | Primitive::DivMod => Some(Division::Symmetric),
+            Primitive::FlooredDivide | Primitive::FlooredModulo | Primitive::FlooredDivMod => {
+                Some(Division::Floored)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Words that direct compilation of a definition rather than having run-time
+/// behaviour of their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Control {
+    If,
+    Else,
+    Then,
+    /// Calls the word being defined.
+    Recurse,
+    /// `[']`: pushes the execution token of the word that follows.
+    Tick,
+}
+
+/// A definition body being compiled.
+struct Compiler<'a> {
+    forth: &'a Forth,
+    optimizations: Optimizations,
+    /// Index the body will have in `Forth::words`.
+    word: usize,
+    code: Vec<Op>,
+    /// The index of the token each op of `code` was compiled from.
+    origins: Vec<usize>,
+    /// The index of the token being compiled.
+    token: usize,
+    /// Branches waiting for their `else` or `then`.
+    pending: Vec<usize>,
+    /// Ops before this may be jumped to, so folding must not merge them
+    /// with later ones.
+    barrier: usize,
+    /// Whether the word before was `[']`, which takes this one.
+    ticking: bool,
+}
+
+impl Forth {
+    pub(crate) fn body(&self, word: usize) -> &[Op] {
+        let Body { start, end } = self.words[word];
+        &self.code[start..end]
+    }
+
+    /// The word `op` was compiled from, as far as it can be told.
+    pub(crate) fn op_name(&self, op: Op) -> Cow<'_, str> {
+        let named = |operation| {
+            self.dictionary
+                .name_of(operation)
+                .map(|name| Cow::Borrowed(self.names.resolve(name)))
+        };
+        let name = match op {
+            Op::Push(value) => Some(Cow::Owned(value.to_string())),
+            #[cfg(feature = "tagged")]
+            Op::PushTagged(value, _) => Some(Cow::Owned(value.to_string())),
+            Op::PushXt(xt) => self
+                .xt_op(xt)
+                .map(|op| Cow::Owned(format!("['] {}", self.op_name(op)))),
+            Op::Call(word) | Op::TailCall(word) => named(Operation::UserDefined(word)),
+            Op::Rewind(entry) => named(Operation::Marker(entry)),
+            Op::BranchIfZero(_) => Some(Cow::Borrowed("if")),
+            Op::Branch(_) => Some(Cow::Borrowed("else")),
+            Op::Unknown => Some(Cow::Borrowed("unknown")),
+            #[cfg(feature = "file-io")]
+            Op::File(_) => crate::files::OPERATIONS
+                .iter()
+                .find(|(_, builtin)| *builtin == op)
+                .map(|&(name, _)| Cow::Borrowed(name)),
+            #[cfg(feature = "env")]
+            Op::GetEnv | Op::SetEnv => crate::env::OPERATIONS
+                .iter()
+                .find(|(_, builtin)| *builtin == op)
+                .map(|&(name, _)| Cow::Borrowed(name)),
+            #[cfg(feature = "net")]
+            Op::Net(_) => crate::net::OPERATIONS
+                .iter()
+                .find(|(_, builtin)| *builtin == op)
+                .map(|&(name, _)| Cow::Borrowed(name)),
+            #[cfg(feature = "peripherals")]
+            Op::Peripheral(_) => crate::peripherals::OPERATIONS
+                .iter()
+                .find(|(_, builtin)| *builtin == op)
+                .map(|&(name, _)| Cow::Borrowed(name)),
+            #[cfg(feature = "dlopen")]
+            Op::Foreign(_) => crate::foreign::OPERATIONS
+                .iter()
+                .find(|(_, builtin)| *builtin == op)
+                .map(|&(name, _)| Cow::Borrowed(name)),
+            Op::Primitive(Primitive::Square) => Some(Cow::Borrowed("dup *")),
+            Op::Primitive(Primitive::SwapSubtract) => Some(Cow::Borrowed("swap -")),
+            Op::Primitive(Primitive::OverAdd) => Some(Cow::Borrowed("over +")),
+            Op::Primitive(Primitive::FlooredDivide) => Some(Cow::Borrowed("floored /")),
+            Op::Primitive(Primitive::FlooredModulo) => Some(Cow::Borrowed("floored mod")),
+            op => PREDIFINED_OPERATIONS
+                .iter()
+                .chain(&TIME_OPERATIONS)
+                .chain(&RANDOM_OPERATIONS)
+                .find(|(_, builtin)| *builtin == op)
+                .map(|&(name, _)| Cow::Borrowed(name)),
+        };
+        name.unwrap_or(Cow::Borrowed("?"))
+    }
+
+    /// A listing of the code `word` was compiled to, one op per line with
+    /// its offset, opcode and operand, and where branches go. `word` must
+    /// be user-defined.
+    pub fn disassemble(&self, word: &str) -> std::result::Result<String, Error> {
+        let name = word.to_lowercase();
+        let Operation::UserDefined(word) = self.lookup_word(&name)? else {
+            return Err(Error::InvalidWord);
+        };
+        let mut listing = format!(": {name}\n");
+        for (offset, &op) in self.body(word).iter().enumerate() {
+            let (opcode, operand) = match op {
+                Op::Push(value) => ("push", value.to_string()),
+                #[cfg(feature = "tagged")]
+                Op::PushTagged(value, tag) => match tag {
+                    Tag::Address => ("push-address", value.to_string()),
+                    _ => ("push-char", value.to_string()),
+                },
+                Op::PushXt(xt) => match self.xt_op(xt) {
+                    Some(op) => ("push-xt", format!("{} {xt}", self.op_name(op))),
+                    None => ("push-xt", xt.to_string()),
+                },
+                Op::Primitive(_) => ("primitive", self.op_name(op).into_owned()),
+                Op::Call(callee) => ("call", format!("{} #{callee}", self.op_name(op))),
+                Op::TailCall(callee) => ("tail-call", format!("{} #{callee}", self.op_name(op))),
+                Op::Branch(skip) => ("branch", format!("-> {}", offset + 1 + skip)),
+                Op::BranchIfZero(skip) => ("branch-if-zero", format!("-> {}", offset + 1 + skip)),
+                Op::Catch => ("catch", String::new()),
+                Op::Variable => ("variable", String::new()),
+                Op::Forget => ("forget", String::new()),
+                Op::Mark => ("marker", String::new()),
+                Op::DefineStack => ("stack", String::new()),
+                Op::Spawn => ("spawn", String::new()),
+                Op::Pause => ("pause", String::new()),
+                Op::User => ("user", String::new()),
+                Op::Tick => ("'", String::new()),
+                Op::Execute => ("execute", String::new()),
+                Op::Synonym => ("synonym", String::new()),
+                Op::Rewind(_) => ("rewind", self.op_name(op).into_owned()),
+                Op::Disassemble => ("dis", String::new()),
+                Op::Help => ("help", String::new()),
+                Op::Include => ("include", String::new()),
+                Op::Unknown => ("unknown", String::new()),
+                #[cfg(feature = "file-io")]
+                Op::File(_) => ("file", self.op_name(op).into_owned()),
+                #[cfg(feature = "env")]
+                Op::GetEnv => ("getenv", String::new()),
+                #[cfg(feature = "env")]
+                Op::SetEnv => ("setenv", String::new()),
+                #[cfg(feature = "net")]
+                Op::Net(_) => ("net", self.op_name(op).into_owned()),
+                #[cfg(feature = "peripherals")]
+                Op::Peripheral(_) => ("peripheral", self.op_name(op).into_owned()),
+                #[cfg(feature = "dlopen")]
+                Op::Foreign(_) => ("foreign", self.op_name(op).into_owned()),
+            };
+            listing.push_str(format!("{offset:>4}  {opcode:<14} {operand}").trim_end());
+            listing.push('\n');
+        }
+        listing.push_str(";\n");
+        Ok(listing)
+    }
+
+    /// Compiles a definition body against the current dictionary, so each
+    /// word keeps the meaning it has now even if it is redefined later.
+    /// Fails on unbalanced `if`, `else` and `then`, and at the first word
+    /// that is not defined unless `UnknownWords::Defer` is in effect.
+    ///
+    /// With `offsets`, the body is compiled as written, without
+    /// optimizations, and the offset of the op each token compiled to is
+    /// pushed to it, or `None` for tokens that compiled to nothing.
+    pub(crate) fn compile_definition(
+        &self,
+        tokens: &[Lexeme],
+        offsets: Option<&mut Vec<Option<usize>>>,
+    ) -> std::result::Result<Vec<Op>, Fault> {
+        self.compile_with_origins(tokens, offsets)
+            .map(|(code, _)| code)
+    }
+
+    /// `compile_definition`, also giving the index of the token each op
+    /// was compiled from, which for ops folded or fused together is the
+    /// last of their tokens, and for inlined ones the call.
+    pub(crate) fn compile_with_origins(
+        &self,
+        tokens: &[Lexeme],
+        mut offsets: Option<&mut Vec<Option<usize>>>,
+    ) -> std::result::Result<(Vec<Op>, Vec<usize>), Fault> {
+        let optimizations = if offsets.is_some() {
+            Optimizations {
+                fold_constants: false,
+                inline_threshold: 0,
+                superinstructions: false,
+                peephole: false,
+            }
+        } else {
+            self.optimizations
+        };
+        let mut compiler = Compiler {
+            forth: self,
+            optimizations,
+            word: self.words.len(),
+            code: Vec::with_capacity(tokens.len()),
+            origins: Vec::with_capacity(tokens.len()),
+            token: 0,
+            pending: Vec::new(),
+            barrier: 0,
+            ticking: false,
+        };
+        for (index, token) in tokens.iter().enumerate() {
+            let before = compiler.code.len();
+            compiler.token = index;
+            if std::mem::take(&mut compiler.ticking) {
+                let xt = match token {
+                    Lexeme::Word(word) => self.xt(word),
+                    Lexeme::Number(_) | Lexeme::Char(_) => Err(Error::InvalidWord),
+                };
+                let xt = xt.map_err(|error| Fault {
+                    error,
+                    token: Some(index),
+                    depth: 0,
+                })?;
+                compiler.emit(Op::PushXt(xt));
+            } else {
+                match token {
+                    Lexeme::Number(i) => compiler.emit(Op::Push(*i)),
+                    Lexeme::Char(c) => compiler.emit(push_char(*c)),
+                    Lexeme::Word(word) => match self.lookup_word(word) {
+                        Ok(Operation::Builtin(op)) => compiler.emit(op),
+                        Ok(Operation::Address(address)) => compiler.emit(push_address(address)),
+                        Ok(Operation::UserDefined(word)) => compiler.call(word),
+                        Ok(Operation::Control(control)) => compiler.control(control)?,
+                        Ok(Operation::Marker(entry)) => compiler.emit(Op::Rewind(entry)),
+                        Err(error) => match self.unknown_words {
+                            UnknownWords::Reject => {
+                                return Err(Fault {
+                                    error,
+                                    token: Some(index),
+                                    depth: 0,
+                                })
+                            }
+                            UnknownWords::Defer => compiler.emit(Op::Unknown),
+                        },
+                    },
+                }
+            }
+            compiler.origins.resize(compiler.code.len(), index);
+            if let Some(offsets) = &mut offsets {
+                offsets.push((compiler.code.len() > before).then_some(before));
+            }
+        }
+        Ok(compiler.finish()?)
+    }
+}
+
+impl Compiler<'_> {
+    fn emit(&mut self, op: Op) {
+        self.code.push(op);
+        let mut unchanged = self.code.len() - 1;
+        if self.optimizations.fold_constants {
+            unchanged = unchanged.min(fold_constants(&mut self.code, self.barrier));
+        }
+        if self.optimizations.superinstructions {
+            unchanged = unchanged.min(fuse(&mut self.code, self.barrier));
+        }
+        self.origins.truncate(unchanged);
+        self.origins.resize(self.code.len(), self.token);
+    }
+
+    /// Splices in the body of a word no longer than the inlining threshold,
+    /// so short helpers cost nothing to call. Bodies are already compiled,
+    /// and so already inlined themselves.
+    fn call(&mut self, word: usize) {
+        let body = self.forth.body(word);
+        let threshold = self.optimizations.inline_threshold;
+        let inline = threshold > 0 && body.len() <= threshold;
+        if !inline || body.iter().any(|op| matches!(op, Op::TailCall(_))) {
+            self.emit(Op::Call(word));
+        } else if body.iter().any(Op::is_branch) {
+            // Branch offsets are relative, so the body can be copied as is,
+            // but folding must not reach into it.
+            self.code.extend_from_slice(body);
+            self.barrier = self.code.len();
+        } else {
+            body.iter().for_each(|&op| self.emit(op));
+        }
+    }
+
+    fn control(&mut self, control: Control) -> Result {
+        match control {
+            Control::If => {
+                self.pending.push(self.code.len());
+                self.code.push(Op::BranchIfZero(0));
+            }
+            Control::Else => {
+                let branch = self.pending.pop().ok_or(Error::InvalidWord)?;
+                self.pending.push(self.code.len());
+                self.code.push(Op::Branch(0));
+                self.resolve(branch);
+            }
+            Control::Then => {
+                let branch = self.pending.pop().ok_or(Error::InvalidWord)?;
+                self.resolve(branch);
+            }
+            Control::Recurse => self.code.push(Op::Call(self.word)),
+            Control::Tick => self.ticking = true,
+        }
+        Ok(())
+    }
+
+    /// Points the branch at `branch` to the next op to be compiled.
+    fn resolve(&mut self, branch: usize) {
+        let offset = self.code.len() - branch - 1;
+        match &mut self.code[branch] {
+            Op::Branch(to) | Op::BranchIfZero(to) => *to = offset,
+            _ => unreachable!("only branches are pending"),
+        }
+        self.barrier = self.code.len();
+    }
+
+    fn finish(mut self) -> std::result::Result<(Vec<Op>, Vec<usize>), Error> {
+        if !self.pending.is_empty() || self.ticking {
+            return Err(Error::InvalidWord);
+        }
+        mark_tail_calls(&mut self.code);
+        Ok((self.code, self.origins))
+    }
+}
+
+/// Turns calls in tail position into jumps, so a word recursing as its last
+/// action runs in constant space.
+pub(crate) fn mark_tail_calls(code: &mut [Op]) {
+    for i in 0..code.len() {
+        if let Op::Call(word) = code[i] {
+            if is_tail(code, i) {
+                code[i] = Op::TailCall(word);
+            }
+        }
+    }
+}
+
+/// Whether nothing but unconditional branches follow the op at `at` before
+/// the word returns.
+fn is_tail(code: &[Op], at: usize) -> bool {
+    let mut next = at + 1;
+    loop {
+        match code.get(next) {
+            None => return true,
+            Some(Op::Branch(offset)) => next += offset + 1,
+            Some(_) => return false,
+        }
+    }
+}
+
+/// Evaluates stack shuffles and arithmetic on literals at compile time, so
+/// `2 3 +` compiles to a single push and `1 dup drop` to `1`. Ops that would
+/// fail or overflow are left for run time to report, and so is a shuffle
+/// such as `dup drop` that isn't applied to literals, because it may still
+/// underflow. Only the ops from `barrier` on are rewritten. Gives how many
+/// ops at the start were left as they were.
+fn fold_constants(code: &mut Vec<Op>, barrier: usize) -> usize {
+    use Op::{Primitive as P, Push};
+    use Primitive::*;
+    let mut unchanged = code.len();
+    loop {
+        let tail = &code[barrier..];
+        let n = tail.len();
+        let replacement = match tail[n.saturating_sub(3)..] {
+            [Push(b), Push(a), P(op)] => match op {
+                Add => b.checked_add(a).map(|v| vec![Push(v)]),
+                Subtract => b.checked_sub(a).map(|v| vec![Push(v)]),
+                Multiply => b.checked_mul(a).map(|v| vec![Push(v)]),
+                WrappingAdd => Some(vec![Push(b.wrapping_add(a))]),
+                WrappingSubtract => Some(vec![Push(b.wrapping_sub(a))]),
+                WrappingMultiply => Some(vec![Push(b.wrapping_mul(a))]),
+                Swap => Some(vec![Push(a), Push(b)]),
+                Over => Some(vec![Push(b), Push(a), Push(b)]),
+                _ => op
+                    .division()
+                    .and_then(|division| division.divide(b, a))
+                    .map(|(quotient, remainder)| match op {
+                        Divide | FlooredDivide => vec![Push(quotient)],
+                        Modulo | FlooredModulo => vec![Push(remainder)],
+                        _ => vec![Push(remainder), Push(quotient)],
+                    }),
+            }
+            .map(|ops| (3, ops)),
+            _ => None,
+        }
+        .or_else(|| match tail[n.saturating_sub(2)..] {
+            [Push(a), P(Dup)] => Some((2, vec![Push(a), Push(a)])),
+            [Push(_), P(Drop)] => Some((2, Vec::new())),
+            _ => None,
+        });
+        let Some((len, ops)) = replacement else {
+            break;
+        };
+        code.truncate(code.len() - len);
+        unchanged = unchanged.min(code.len());
+        code.extend(ops);
+    }
+    unchanged
+}
+
+/// Replaces common pairs of primitives with one op doing the work of both,
+/// saving a dispatch. Only the ops from `barrier` on are rewritten. Gives how
+/// many ops at the start were left as they were.
+fn fuse(code: &mut Vec<Op>, barrier: usize) -> usize {
+    use Op::Primitive as P;
+    use Primitive::*;
+    let tail = &code[barrier..];
+    let fused = match tail[tail.len().saturating_sub(2)..] {
+        [P(Dup), P(Multiply)] => Square,
+        [P(Swap), P(Subtract)] => SwapSubtract,
+        [P(Over), P(Add)] => OverAdd,
+        _ => return code.len(),
+    };
+    code.truncate(code.len() - 2);
+    code.push(P(fused));
+    code.len() - 1
+}
@@ -0,0 +1,94 @@
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+
+use crate::{Error, Forth, Result, Value};
+
+/// The interpreter's ends of the channels to and from the host, used by
+/// `send`, `recv` and `recv?`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Channels {
+    to_host: Option<Sender<Value>>,
+    /// Shared by clones of the interpreter, which take turns receiving.
+    from_host: Option<Arc<Mutex<Receiver<Value>>>>,
+}
+
+impl Channels {
+    pub(crate) fn new(to_host: Sender<Value>, from_host: Receiver<Value>) -> Channels {
+        Channels {
+            to_host: Some(to_host),
+            from_host: Some(Arc::new(Mutex::new(from_host))),
+        }
+    }
+
+    pub(crate) fn is_set(&self) -> bool {
+        self.to_host.is_some()
+    }
+}
+
+impl Forth {
+    /// Makes `send` pass values to `to_host` and `recv` take them from
+    /// `from_host`, so that a running program and its host can exchange
+    /// values while it runs rather than only through the stack between
+    /// evaluations. Without channels, the words fail with
+    /// `Error::ChannelClosed`, as they do once the host's end is dropped.
+    pub fn set_channels(&mut self, to_host: Sender<Value>, from_host: Receiver<Value>) {
+        self.channels = Channels::new(to_host, from_host);
+    }
+
+    /// Creates channels for `send` and `recv`, see `set_channels`, and
+    /// returns the host's ends: one to send values to the interpreter, and
+    /// one to receive what it sends.
+    pub fn open_channels(&mut self) -> (Sender<Value>, Receiver<Value>) {
+        let (to_host, from_interpreter) = mpsc::channel();
+        let (to_interpreter, from_host) = mpsc::channel();
+        self.set_channels(to_host, from_host);
+        (to_interpreter, from_interpreter)
+    }
+
+    pub(crate) fn do_send(&mut self) -> Result {
+        let to_host = self.channels.to_host.as_ref().ok_or(Error::ChannelClosed)?;
+        let value = *self.stack.last().ok_or(Error::StackUnderflow)?;
+        to_host.send(value).map_err(|_| Error::ChannelClosed)?;
+        self.stack.pop();
+        Ok(())
+    }
+
+    /// Waits for a value from the host.
+    pub(crate) fn do_receive(&mut self) -> Result {
+        let from_host = self
+            .channels
+            .from_host
+            .as_ref()
+            .ok_or(Error::ChannelClosed)?;
+        let value = from_host
+            .lock()
+            .unwrap()
+            .recv()
+            .map_err(|_| Error::ChannelClosed)?;
+        self.stack.push(value);
+        Ok(())
+    }
+
+    /// Takes a value from the host if one is waiting, pushing it and a true
+    /// flag, or 0 and a false flag.
+    pub(crate) fn do_try_receive(&mut self) -> Result {
+        let from_host = self
+            .channels
+            .from_host
+            .as_ref()
+            .ok_or(Error::ChannelClosed)?;
+        let received = from_host.lock().unwrap().try_recv();
+        match received {
+            Ok(value) => {
+                self.stack.push(value);
+                self.stack.push(-1);
+            }
+            Err(TryRecvError::Empty) => {
+                self.stack.push(0);
+                self.stack.push(0);
+            }
+            Err(TryRecvError::Disconnected) => return Err(Error::ChannelClosed),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::{Error, Forth, Result, Value};
+
+/// Where `utime` and `elapsed` get the time, so that tests can make it
+/// whatever they need.
+pub trait Clock: Send + Sync {
+    /// Time since a fixed point in the past, never going backwards.
+    fn now(&self) -> Duration;
+}
+
+/// Time since the clock was made, from `Instant`. The default clock, made
+/// with the interpreter.
+#[derive(Debug, Clone, Copy)]
+pub struct MonotonicClock {
+    start: Instant,
+}
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        MonotonicClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// The clock of an interpreter, which its clones share.
+#[derive(Clone)]
+pub(crate) struct SharedClock(Arc<dyn Clock>);
+
+impl SharedClock {
+    pub(crate) fn new(clock: impl Clock + 'static) -> SharedClock {
+        SharedClock(Arc::new(clock))
+    }
+
+    pub(crate) fn now(&self) -> Duration {
+        self.0.now()
+    }
+}
+
+impl Default for SharedClock {
+    fn default() -> Self {
+        SharedClock::new(MonotonicClock::default())
+    }
+}
+
+impl std::fmt::Debug for SharedClock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SharedClock").field(&self.0.now()).finish()
+    }
+}
+
+impl Forth {
+    /// Makes `utime` and `elapsed` read `clock`.
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = SharedClock::new(clock);
+    }
+
+    /// Microseconds on the clock, wrapped to a cell. A cell holds about 35
+    /// minutes of them, so only differences of times closer than that, as
+    /// `elapsed` takes, mean anything.
+    fn microseconds(&self) -> Value {
+        self.clock.0.now().as_micros() as Value
+    }
+
+    pub(crate) fn do_utime(&mut self) -> Result {
+        self.stack.push(self.microseconds());
+        Ok(())
+    }
+
+    /// Replaces a time left by `utime` with the microseconds since.
+    pub(crate) fn do_elapsed(&mut self) -> Result {
+        let start = self.stack.pop().ok_or(Error::StackUnderflow)?;
+        self.stack.push(self.microseconds().wrapping_sub(start));
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::diagnostics::{commands, Fault, Located};
+use crate::lexer::lex;
+use crate::vm::Input;
+use crate::{Forth, Result};
+
+/// Pending the first time it is polled, so that the executor may run other
+/// tasks before it is polled again.
+#[derive(Default)]
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+impl Forth {
+    pub fn yield_interval(&self) -> usize {
+        self.yield_interval
+    }
+
+    /// Sets how many ops `eval_async` runs between yields, at least 1.
+    pub fn set_yield_interval(&mut self, interval: usize) {
+        self.yield_interval = interval.max(1);
+    }
+
+    /// Like `eval`, but as a future that yields to the executor every
+    /// `yield_interval` ops, so that a long script shares the thread it runs
+    /// on with other tasks; a word run as native code counts as one op. It
+    /// needs no particular runtime. Dropping the future cancels the
+    /// evaluation at the point it last yielded, leaving the stack and the
+    /// dictionary as they were there, as a failure would.
+    pub async fn eval_async(&mut self, input: &str) -> Result {
+        let outcome = self.eval_commands_async(input).await;
+        #[cfg(feature = "log")]
+        crate::log_failure(input, &outcome);
+        let outcome = outcome.map_err(|located| located.fault.error);
+        self.record(input, &outcome);
+        outcome
+    }
+
+    async fn eval_commands_async(&mut self, input: &str) -> std::result::Result<(), Located> {
+        self.start_run();
+        self.check_syntax(input)?;
+        let mut budget = self.yield_interval;
+        for command in commands(input) {
+            let command = command.map_err(|malformed| self.malformed(malformed))?;
+            if let Some(coverage) = &mut self.coverage {
+                coverage.locate(input, command);
+            }
+            let text = &input[command.start..command.end];
+            #[cfg(feature = "log")]
+            log::trace!("evaluating `{text}`");
+            let outcome = if lex(text).next().is_some_and(|token| token.is_word(":")) {
+                // Definitions run no code, so they are never cut short.
+                self.eval_command(text)
+            } else {
+                self.run_expression_async(text, &mut budget).await
+            };
+            if let Some(coverage) = &mut self.coverage {
+                coverage.unlocate();
+            }
+            outcome.map_err(|fault| Located { fault, command })?;
+        }
+        Ok(())
+    }
+
+    async fn run_expression_async(
+        &mut self,
+        text: &str,
+        budget: &mut usize,
+    ) -> std::result::Result<(), Fault> {
+        let mut input = Input::text(lex(text));
+        let mut frames = Vec::new();
+        while let Some(token) = self.next_token(&mut input) {
+            let (index, depth) = (input.position(), self.stack.len());
+            let fault = |error| Fault {
+                error,
+                token: Some(index),
+                depth,
+            };
+            let op = self.token_op(&token).map_err(fault)?;
+            self.execute_sliced(op, &mut input, &mut frames, budget)
+                .map_err(fault)?;
+            while *budget == 0 {
+                YieldNow::default().await;
+                *budget = self.yield_interval;
+                self.run_frames(&mut frames, &mut input, budget)
+                    .map_err(fault)?;
+            }
+        }
+        Ok(())
+    }
+}
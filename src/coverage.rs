@@ -0,0 +1,211 @@
+use std::fmt::Write;
+
+use crate::bytecode::Op;
+use crate::diagnostics::token_spans;
+use crate::lexer::Lexeme;
+use crate::{Forth, Span};
+
+/// Which tokens of each definition ran since coverage was turned on, see
+/// `Forth::set_coverage`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    /// Definitions made while coverage was on, in the order they were made,
+    /// redefinitions and forgotten words included.
+    pub definitions: Vec<DefinitionCoverage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefinitionCoverage {
+    pub name: String,
+    /// Line of the `:`, counted from 1 in the input that was evaluated, or 0
+    /// for definitions that didn't come from text, such as those of a
+    /// `Program`.
+    pub line: usize,
+    /// Calls of the word.
+    pub calls: u64,
+    /// The body, stack-effect comment left out.
+    pub tokens: Vec<TokenCoverage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenCoverage {
+    pub text: String,
+    /// Line of the token, like `DefinitionCoverage::line`.
+    pub line: usize,
+    /// Runs of the token, or `None` if it compiled to nothing, as `then`
+    /// does.
+    pub hits: Option<u64>,
+}
+
+impl Coverage {
+    /// The report in lcov's tracefile format, with `source` as the file
+    /// name. A line is as covered as the token on it that ran least, so one
+    /// that is only partly run shows as not run. Tokens without a line are
+    /// left out.
+    pub fn to_lcov(&self, source: &str) -> String {
+        let mut lcov = format!("TN:\nSF:{source}\n");
+        let mut lines: Vec<(usize, u64)> = Vec::new();
+        for definition in &self.definitions {
+            let name = &definition.name;
+            writeln!(lcov, "FN:{},{name}", definition.line.max(1)).unwrap();
+            writeln!(lcov, "FNDA:{},{name}", definition.calls).unwrap();
+            for token in &definition.tokens {
+                let Some(hits) = token.hits.filter(|_| token.line > 0) else {
+                    continue;
+                };
+                match lines.iter_mut().find(|(line, _)| *line == token.line) {
+                    Some((_, least)) => *least = (*least).min(hits),
+                    None => lines.push((token.line, hits)),
+                }
+            }
+        }
+        let called = self.definitions.iter().filter(|d| d.calls > 0).count();
+        writeln!(lcov, "FNF:{}", self.definitions.len()).unwrap();
+        writeln!(lcov, "FNH:{called}").unwrap();
+        lines.sort_unstable();
+        for (line, hits) in &lines {
+            writeln!(lcov, "DA:{line},{hits}").unwrap();
+        }
+        let hit = lines.iter().filter(|&&(_, hits)| hits > 0).count();
+        writeln!(lcov, "LF:{}", lines.len()).unwrap();
+        writeln!(lcov, "LH:{hit}").unwrap();
+        lcov.push_str("end_of_record\n");
+        lcov
+    }
+}
+
+/// A definition made while coverage was on.
+#[derive(Debug, Clone)]
+struct Instrumented {
+    /// Index into `Forth::words`.
+    word: usize,
+    name: String,
+    line: usize,
+    /// Text and line of each token, with where in `Forth::code` its op is.
+    tokens: Vec<(String, usize, Option<usize>)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Recorder {
+    /// Line of each token of the command being evaluated, see `locate`.
+    lines: Vec<usize>,
+    definitions: Vec<Instrumented>,
+    /// Indexed by position in `Forth::code`.
+    hits: Vec<u64>,
+    /// Indexed by word, like `Forth::words`.
+    calls: Vec<u64>,
+}
+
+impl Recorder {
+    /// Notes the lines of the tokens of `command`, the next command of
+    /// `input` to be evaluated, for a definition it may make.
+    pub(crate) fn locate(&mut self, input: &str, command: Span) {
+        let mut line = 1 + input[..command.start].matches('\n').count();
+        let mut from = command.start;
+        self.lines.clear();
+        for span in token_spans(&input[command.start..command.end]) {
+            let start = command.start + span.start;
+            line += input[from..start].matches('\n').count();
+            from = start;
+            self.lines.push(line);
+        }
+    }
+
+    /// Forgets the lines noted by `locate`, once the command has run.
+    pub(crate) fn unlocate(&mut self) {
+        self.lines.clear();
+    }
+
+    /// Records the definition of `word` from the tokens of a command that,
+    /// after the `:` and the name, compiled `body` to `offsets` from `start`
+    /// in `Forth::code`.
+    pub(crate) fn instrument(
+        &mut self,
+        word: usize,
+        name: &str,
+        body: &[Lexeme],
+        start: usize,
+        offsets: &[Option<usize>],
+    ) {
+        let lines = std::mem::take(&mut self.lines);
+        let line = |index: usize| lines.get(index).copied().unwrap_or(0);
+        let tokens = body
+            .iter()
+            .zip(offsets)
+            .enumerate()
+            .map(|(index, (token, offset))| {
+                let text = token.to_string();
+                (text, line(2 + index), offset.map(|o| start + o))
+            })
+            .collect();
+        self.definitions.push(Instrumented {
+            word,
+            name: name.to_string(),
+            line: line(0),
+            tokens,
+        });
+    }
+
+    /// Counts `op`, found at `at` in `Forth::code` if it is part of a body.
+    pub(crate) fn count(&mut self, at: Option<usize>, op: Op) {
+        let bump = |counts: &mut Vec<u64>, index: usize| {
+            if counts.len() <= index {
+                counts.resize(index + 1, 0);
+            }
+            counts[index] += 1;
+        };
+        if let Some(at) = at {
+            bump(&mut self.hits, at);
+        }
+        if let Op::Call(word) | Op::TailCall(word) = op {
+            bump(&mut self.calls, word);
+        }
+    }
+
+    /// Forgets the definitions past the first `words` words, whose code
+    /// past `code` is being given back.
+    pub(crate) fn truncate(&mut self, words: usize, code: usize) {
+        self.definitions
+            .retain(|definition| definition.word < words);
+        self.calls.truncate(words);
+        self.hits.truncate(code);
+    }
+}
+
+impl Forth {
+    /// Turns recording of which tokens of each definition run on or off.
+    /// Turning it on starts from nothing; turning it off discards what was
+    /// recorded. Only definitions made while it is on are covered, and they
+    /// are compiled as written, without optimizations, so that each token
+    /// has an op of its own. Native code is not run while it is on.
+    pub fn set_coverage(&mut self, enabled: bool) {
+        self.coverage = enabled.then(Box::default);
+    }
+
+    /// What was recorded so far, empty if coverage is off.
+    pub fn coverage(&self) -> Coverage {
+        let Some(recorder) = &self.coverage else {
+            return Coverage::default();
+        };
+        let count = |counts: &[u64], index: usize| counts.get(index).copied().unwrap_or(0);
+        let definitions = recorder
+            .definitions
+            .iter()
+            .map(|definition| DefinitionCoverage {
+                name: definition.name.clone(),
+                line: definition.line,
+                calls: count(&recorder.calls, definition.word),
+                tokens: definition
+                    .tokens
+                    .iter()
+                    .map(|(text, line, at)| TokenCoverage {
+                        text: text.clone(),
+                        line: *line,
+                        hits: at.map(|at| count(&recorder.hits, at)),
+                    })
+                    .collect(),
+            })
+            .collect();
+        Coverage { definitions }
+    }
+}
@@ -0,0 +1,385 @@
+use crate::lexer::{character, looks_numeric, words, Words};
+use crate::{Dialect, Error, Forth, Operation, SourceLocation};
+
+/// Byte range into the input given to `Forth::eval_diagnostics`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+
+    pub(crate) fn trim(self, input: &str) -> Span {
+        let text = &input[self.start..self.end];
+        let start = self.start + (text.len() - text.trim_start().len());
+        Span::new(start, start + text.trim().len())
+    }
+
+    pub(crate) fn offset(self, by: usize) -> Span {
+        Span::new(self.start + by, self.end + by)
+    }
+}
+
+/// Everything known about a failed evaluation, for frontends that want to
+/// render more than the bare `Error`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostics {
+    pub error: Error,
+    /// The offending token, or the whole command when no single token is to
+    /// blame.
+    pub span: Span,
+    /// Text of the command that failed.
+    pub command: String,
+    /// Where, inside the user-defined word the command ran, the op that
+    /// failed was compiled from, if that is known.
+    pub location: Option<Box<SourceLocation>>,
+    pub notes: Vec<String>,
+    pub suggestions: Vec<String>,
+}
+
+impl std::fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error: {} in `{}`", self.error, self.command)?;
+        for note in &self.notes {
+            write!(f, "\n  note: {note}")?;
+        }
+        for suggestion in &self.suggestions {
+            write!(f, "\n  help: {suggestion}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostics {}
+
+/// Where evaluation stopped, relative to the command being run.
+#[derive(Debug)]
+pub(crate) struct Fault {
+    pub(crate) error: Error,
+    /// Index of the offending token within its command.
+    pub(crate) token: Option<usize>,
+    /// Stack depth when the offending token started executing.
+    pub(crate) depth: usize,
+}
+
+impl From<Error> for Fault {
+    fn from(error: Error) -> Self {
+        Fault {
+            error,
+            token: None,
+            depth: 0,
+        }
+    }
+}
+
+/// A fault together with the span of the command it happened in.
+#[derive(Debug)]
+pub(crate) struct Located {
+    pub(crate) fault: Fault,
+    pub(crate) command: Span,
+}
+
+/// A command the parser rejected, with the token in it to blame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Malformed {
+    pub(crate) command: Span,
+    /// Index of the offending token within the command.
+    pub(crate) token: usize,
+}
+
+/// The words of an input that are code, with their indices among its
+/// tokens: not those of comments, nor character literals such as `':'`.
+pub(crate) struct CodeWords<'a>(std::iter::Enumerate<Words<'a>>);
+
+pub(crate) fn code_words(input: &str) -> CodeWords<'_> {
+    CodeWords(words(input).enumerate())
+}
+
+impl<'a> Iterator for CodeWords<'a> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.find(|(_, word)| character(word).is_none())
+    }
+}
+
+/// Splits input into commands in one pass without allocating: a definition
+/// runs from `:` to `;`, and whatever lies between definitions is an
+/// expression. Only `:` and `;` in code words count, so comments and
+/// character literals may hold them. They delimit commands even when glued
+/// to other words, as the lenient dialect allows; the strict dialect
+/// rejects those before parsing. A `;` with no definition open is malformed
+/// on its own, after the expression before it. A `:` inside a definition
+/// makes the outer definition malformed, up to the `;` that closes it, so
+/// nested ones are rejected as a whole.
+pub(crate) struct Commands<'a> {
+    input: &'a str,
+    words: CodeWords<'a>,
+    /// The rest of the code word being searched for `:` and `;`, and where
+    /// it starts.
+    glued: Option<(usize, &'a str)>,
+    /// Definitions open: 0 between definitions, more than 1 when nested.
+    depth: usize,
+    /// Where the first `:` nested in the current definition is.
+    nested: Option<usize>,
+    /// Where the command being parsed starts, `None` once the input is used
+    /// up.
+    start: Option<usize>,
+    /// A stray `;` to report once the expression before it is returned.
+    stray: Option<Malformed>,
+}
+
+pub(crate) fn commands(input: &str) -> Commands<'_> {
+    Commands {
+        input,
+        words: code_words(input),
+        glued: None,
+        depth: 0,
+        nested: None,
+        start: Some(0),
+        stray: None,
+    }
+}
+
+impl Commands<'_> {
+    /// The next `:` or `;` delimiting commands, and where it is.
+    fn delimiter(&mut self) -> Option<(usize, char)> {
+        loop {
+            if let Some((start, rest)) = self.glued.take() {
+                if let Some(at) = rest.find([':', ';']) {
+                    self.glued = Some((start + at + 1, &rest[at + 1..]));
+                    return Some((start + at, char::from(rest.as_bytes()[at])));
+                }
+            }
+            let (_, word) = self.words.next()?;
+            let start = word.as_ptr() as usize - self.input.as_ptr() as usize;
+            self.glued = Some((start, word));
+        }
+    }
+}
+
+impl Iterator for Commands<'_> {
+    type Item = std::result::Result<Span, Malformed>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(stray) = self.stray.take() {
+                return Some(Err(stray));
+            }
+            let from = self.start?;
+            let (command, next) = match self.delimiter() {
+                Some((i, ':')) if self.depth == 0 => {
+                    self.depth = 1;
+                    (Span::new(from, i), i)
+                }
+                Some((i, ':')) => {
+                    self.depth += 1;
+                    self.nested.get_or_insert(i);
+                    continue;
+                }
+                Some((i, ';')) if self.depth == 0 => {
+                    let command = Span::new(i, i + 1);
+                    self.stray = Some(Malformed { command, token: 0 });
+                    (Span::new(from, i), i + 1)
+                }
+                Some((i, ';')) => {
+                    self.depth -= 1;
+                    if self.depth > 0 {
+                        continue;
+                    }
+                    (Span::new(from, i + 1), i + 1)
+                }
+                Some(_) => continue,
+                None => {
+                    self.start = None;
+                    (Span::new(from, self.input.len()), self.input.len())
+                }
+            };
+            if self.start.is_some() {
+                self.start = Some(next);
+            }
+            let command = command.trim(self.input);
+            if let Some(nested) = self.nested.take() {
+                let text = &self.input[command.start..command.end];
+                let token = token_spans(text)
+                    .iter()
+                    .position(|span| command.start + span.end > nested)
+                    .unwrap_or(0);
+                return Some(Err(Malformed { command, token }));
+            }
+            if !command.is_empty() {
+                return Some(Ok(command));
+            }
+        }
+    }
+}
+
+/// Where the tokens of `text` are, as lexed, so indexed like them.
+pub(crate) fn token_spans(text: &str) -> Vec<Span> {
+    spans(text, words(text))
+}
+
+/// Where all the words of `text` are, those of comments included.
+pub(crate) fn word_spans(text: &str) -> Vec<Span> {
+    spans(text, text.split_whitespace())
+}
+
+fn spans<'a>(text: &str, words: impl Iterator<Item = &'a str>) -> Vec<Span> {
+    words
+        .map(|word| {
+            let start = word.as_ptr() as usize - text.as_ptr() as usize;
+            Span::new(start, start + word.len())
+        })
+        .collect()
+}
+
+/// Optimal string alignment distance: edits and adjacent transpositions.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+impl Forth {
+    pub(crate) fn diagnose(&self, input: &str, located: Located) -> Diagnostics {
+        let Located { fault, command } = located;
+        let text = &input[command.start..command.end];
+        let span = fault
+            .token
+            .and_then(|i| token_spans(text).get(i).copied())
+            .map_or(command, |span| span.offset(command.start));
+        let word = input[span.start..span.end].to_lowercase();
+        let mut notes = Vec::new();
+        let mut suggestions = Vec::new();
+        match fault.error {
+            Error::StackUnderflow => notes.push(format!(
+                "the stack held {} value(s) when `{word}` started",
+                fault.depth
+            )),
+            Error::DivisionByZero => notes.push("the divisor on top of the stack was 0".into()),
+            Error::UnknownWord => {
+                if looks_numeric(&word) {
+                    notes.push("it looks like a number, but is malformed or out of range".into());
+                }
+                let mut close: Vec<(usize, &str)> = self
+                    .words()
+                    .map(|name| (edit_distance(name, &word), name))
+                    .filter(|(distance, _)| *distance <= 1 + word.len() / 4)
+                    .collect();
+                close.sort_unstable();
+                close.dedup();
+                suggestions.extend(
+                    close
+                        .into_iter()
+                        .map(|(_, name)| format!("did you mean `{name}`?")),
+                );
+            }
+            Error::InvalidWord if text == ";" => {
+                notes.push("`;` ends a definition, but none was open".into())
+            }
+            Error::InvalidWord if word == ":" && fault.token.is_some_and(|token| token > 0) => {
+                notes.push("`:` can't start a definition inside another".into())
+            }
+            Error::InvalidWord
+                if self.dialect == Dialect::Strict
+                    && word.len() > 1
+                    && word.contains([':', ';']) =>
+            {
+                notes.push("strict dialect: `:` and `;` must be separated by whitespace".into())
+            }
+            Error::InvalidWord if matches!(self.lookup_word(&word), Ok(Operation::Control(_))) => {
+                notes.push(format!("`{word}` can only be used inside a definition"))
+            }
+            Error::InvalidWord => notes.push(
+                "definitions look like `: name body ;`, with each `if` closed by `then`".into(),
+            ),
+            Error::QuotaExceeded => notes.push(format!("quotas are {:?}", self.quotas)),
+            Error::InvalidAddress => notes.push(format!(
+                "valid addresses are those of the {} variable(s) defined so far",
+                self.data_space.len()
+            )),
+            Error::OutOfRange => {}
+            Error::Overflow => notes.push(format!(
+                "values are between {} and {}",
+                crate::Value::MIN,
+                crate::Value::MAX
+            )),
+            Error::ReturnStackOverflow => notes.push(format!(
+                "calls may nest at most {} deep; does a recursion lack a base case?",
+                self.max_call_depth
+            )),
+            Error::ChannelClosed if self.channels.is_set() => {
+                notes.push("the host has dropped its end of the channel".to_string())
+            }
+            Error::ChannelClosed => notes
+                .push("no channels to the host were set, see `Forth::set_channels`".to_string()),
+            Error::IncludeNotFound if self.resolver.is_set() => {
+                notes.push("the include resolver has no source by that name".to_string())
+            }
+            Error::IncludeNotFound => notes
+                .push("no include resolver was set, see `Forth::set_include_resolver`".to_string()),
+            Error::UnbalancedBranches { taken, skipped } => notes.push(format!(
+                "the part run when the flag is true changes the depth by {taken}, \
+                 but the rest by {skipped}"
+            )),
+            #[cfg(feature = "tagged")]
+            Error::TagMismatch { expected, found } => {
+                notes.push(format!("`{word}` takes {expected}, but was given {found}"))
+            }
+            Error::Uncaught(code) => notes.push(format!(
+                "`throw` was given {code}, and no `catch` around it caught it"
+            )),
+            Error::Sealed => notes.push(
+                "the host sealed the dictionary, so words can't be added or forgotten".into(),
+            ),
+            Error::Impure => notes.push(format!("`{word}` does more than stack math")),
+        }
+        let mut location = None;
+        if fault.error != Error::UnknownWord && self.is_user_word(&word) {
+            location = self.failure_location().cloned().map(Box::new);
+            notes.push(match &location {
+                Some(location) => {
+                    format!("raised at {location}, while running the user-defined word `{word}`")
+                }
+                None => format!("raised while running the user-defined word `{word}`"),
+            });
+        }
+        Diagnostics {
+            error: fault.error,
+            span,
+            command: text.to_string(),
+            location,
+            notes,
+            suggestions,
+        }
+    }
+
+    fn is_user_word(&self, word: &str) -> bool {
+        matches!(self.lookup_word(word), Ok(Operation::UserDefined(_)))
+    }
+}
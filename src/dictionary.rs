@@ -0,0 +1,124 @@
+use std::borrow::Cow;
+
+use crate::interner::{HashMap, Symbol};
+use crate::Operation;
+
+/// Every definition ever made, oldest first. A redefinition shadows the
+/// previous meaning of its name rather than replacing it, so removing the
+/// newer entries brings the older meaning back.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Dictionary {
+    entries: Vec<Entry>,
+    /// Index of the newest entry of each name.
+    latest: HashMap<Symbol, usize>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    name: Symbol,
+    operation: Operation,
+    /// The entry of the same name this one shadows.
+    shadows: Option<usize>,
+    /// Stack-effect comment, such as `( n1 n2 -- n3 )`.
+    effect: Option<Cow<'static, str>>,
+}
+
+impl Dictionary {
+    pub(crate) fn with_capacity(capacity: usize) -> Dictionary {
+        Dictionary {
+            entries: Vec::with_capacity(capacity),
+            latest: HashMap::with_capacity_and_hasher(capacity, Default::default()),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn get(&self, name: Symbol) -> Option<Operation> {
+        self.find(name).map(|index| self.entries[index].operation)
+    }
+
+    /// Index of the entry `name` currently refers to.
+    pub(crate) fn find(&self, name: Symbol) -> Option<usize> {
+        self.latest.get(&name).copied()
+    }
+
+    pub(crate) fn define(&mut self, name: Symbol, operation: Operation) {
+        let shadows = self.latest.insert(name, self.entries.len());
+        self.entries.push(Entry {
+            name,
+            operation,
+            shadows,
+            effect: None,
+        });
+    }
+
+    /// Attaches a stack-effect comment to the newest entry.
+    pub(crate) fn document(&mut self, effect: Cow<'static, str>) {
+        if let Some(entry) = self.entries.last_mut() {
+            entry.effect = Some(effect);
+        }
+    }
+
+    /// Stack-effect comment of the entry `name` currently refers to.
+    pub(crate) fn effect(&self, name: Symbol) -> Option<&str> {
+        self.entries[self.find(name)?].effect.as_deref()
+    }
+
+    /// Operations of the entries from `len` on, newest first.
+    pub(crate) fn operations_from(&self, len: usize) -> impl Iterator<Item = Operation> + '_ {
+        self.entries[len.min(self.entries.len())..]
+            .iter()
+            .rev()
+            .map(|entry| entry.operation)
+    }
+
+    /// Names, operations and stack-effect comments of the entries from `len`
+    /// on, oldest first.
+    pub(crate) fn entries_from(
+        &self,
+        len: usize,
+    ) -> impl Iterator<Item = (Symbol, Operation, Option<&str>)> + '_ {
+        self.entries[len.min(self.entries.len())..]
+            .iter()
+            .map(|entry| (entry.name, entry.operation, entry.effect.as_deref()))
+    }
+
+    /// Removes the entries from `len` on, uncovering what they shadowed.
+    pub(crate) fn truncate(&mut self, len: usize) {
+        while self.entries.len() > len {
+            let entry = self.entries.pop().expect("longer than `len`");
+            match entry.shadows {
+                Some(shadowed) => self.latest.insert(entry.name, shadowed),
+                None => self.latest.remove(&entry.name),
+            };
+        }
+    }
+
+    /// Name of the oldest entry for `operation`, even if it has been
+    /// shadowed, so that a word goes by the name it was defined as rather
+    /// than a synonym.
+    pub(crate) fn name_of(&self, operation: Operation) -> Option<Symbol> {
+        self.entries
+            .iter()
+            .find(|entry| entry.operation == operation)
+            .map(|entry| entry.name)
+    }
+
+    /// Names that can be looked up, in the order they were defined.
+    pub(crate) fn names(&self) -> impl Iterator<Item = Symbol> + '_ {
+        self.names_from(0)
+    }
+
+    /// Names of the entries from `len` on that can be looked up, in the order
+    /// they were defined.
+    pub(crate) fn names_from(&self, len: usize) -> impl Iterator<Item = Symbol> + '_ {
+        self.entries
+            .iter()
+            .enumerate()
+            .skip(len)
+            .filter(|&(index, entry)| self.latest.get(&entry.name) == Some(&index))
+            .map(|(_, entry)| entry.name)
+    }
+}
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use crate::dictionary::Dictionary;
+use crate::interner::Interner;
+use crate::{Operation, Snapshot};
+
+/// How the dictionary changed between two snapshots, see `Snapshot::diff`.
+/// Names are in the order they were defined.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DictDiff {
+    /// Names that can be looked up after but not before.
+    pub added: Vec<String>,
+    /// Names that could be looked up before but no longer can.
+    pub removed: Vec<String>,
+    /// Names that mean something else after, builtins included.
+    pub redefined: Vec<String>,
+}
+
+impl DictDiff {
+    /// Whether the dictionary is as it was.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.redefined.is_empty()
+    }
+}
+
+/// The names that can be looked up, each with the entry it refers to and
+/// what it means, in the order they were defined.
+fn visible<'a>(
+    dictionary: &'a Dictionary,
+    names: &'a Interner,
+) -> Vec<(&'a str, (usize, Operation))> {
+    dictionary
+        .names()
+        .filter_map(|name| {
+            let meaning = (dictionary.find(name)?, dictionary.get(name)?);
+            Some((names.resolve(name), meaning))
+        })
+        .collect()
+}
+
+impl Snapshot {
+    /// The words added, removed and redefined from this snapshot to
+    /// `later`, so that tooling can tell what a script defined, and a test
+    /// that it left the dictionary as it found it.
+    pub fn diff(&self, later: &Snapshot) -> DictDiff {
+        let before = visible(&self.dictionary, &self.names);
+        let after = visible(&later.dictionary, &later.names);
+        let was: HashMap<&str, _> = before.iter().copied().collect();
+        let is: HashMap<&str, _> = after.iter().copied().collect();
+        let mut diff = DictDiff::default();
+        for &(name, meaning) in &after {
+            match was.get(name) {
+                None => diff.added.push(name.to_string()),
+                Some(&old) if old != meaning => diff.redefined.push(name.to_string()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = before
+            .iter()
+            .filter(|(name, _)| !is.contains_key(name))
+            .map(|&(name, _)| name.to_string())
+            .collect();
+        diff
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{
+    Forth, BUILTINS, ENV_OPERATIONS, FILE_OPERATIONS, FOREIGN_OPERATIONS, NET_OPERATIONS,
+    PERIPHERAL_OPERATIONS,
+};
+
+/// Stack effects of the builtins, which document them in the dictionary.
+const BUILTIN_EFFECTS: [(&str, &str);
+    BUILTINS
+        - FILE_OPERATIONS.len()
+        - ENV_OPERATIONS.len()
+        - NET_OPERATIONS.len()
+        - PERIPHERAL_OPERATIONS.len()
+        - FOREIGN_OPERATIONS.len()] = [
+    ("+", "( n1 n2 -- n3 )"),
+    ("-", "( n1 n2 -- n3 )"),
+    ("*", "( n1 n2 -- n3 )"),
+    ("/", "( n1 n2 -- n3 )"),
+    ("mod", "( n1 n2 -- n3 )"),
+    ("/mod", "( n1 n2 -- n3 n4 )"),
+    ("sm/rem", "( n1 n2 -- n3 n4 )"),
+    ("fm/mod", "( n1 n2 -- n3 n4 )"),
+    ("w+", "( n1 n2 -- n3 )"),
+    ("w-", "( n1 n2 -- n3 )"),
+    ("w*", "( n1 n2 -- n3 )"),
+    ("dup", "( x -- x x )"),
+    ("drop", "( x -- )"),
+    ("swap", "( x1 x2 -- x2 x1 )"),
+    ("over", "( x1 x2 -- x1 x2 x1 )"),
+    ("variable", "( \"name\" -- )"),
+    ("forget", "( \"name\" -- )"),
+    ("marker", "( \"name\" -- )"),
+    ("t{", "( -- )"),
+    ("->", "( x* -- )"),
+    ("}t", "( x* -- )"),
+    ("test-summary", "( -- )"),
+    ("dis", "( \"name\" -- )"),
+    ("help", "( \"name\" -- )"),
+    ("@", "( addr -- x )"),
+    ("!", "( x addr -- )"),
+    ("send", "( x -- )"),
+    ("recv", "( -- x )"),
+    ("recv?", "( -- x flag )"),
+    ("utime", "( -- u )"),
+    ("elapsed", "( u1 -- u2 )"),
+    ("random", "( -- u )"),
+    ("evaluate", "( i*x addr u -- j*x )"),
+    ("include", "( i*x \"name\" -- j*x )"),
+    ("stack", "( \"name\" -- )"),
+    (">s", "( x stack -- )"),
+    ("s>", "( stack -- x )"),
+    ("spawn", "( \"name\" -- )"),
+    ("pause", "( -- )"),
+    ("user", "( \"name\" -- )"),
+    ("tasks", "( -- n )"),
+    ("throw", "( k*x n -- k*x | i*x n )"),
+    ("catch", "( i*x xt -- j*x 0 | i*x n )"),
+    ("synonym", "( \"new\" \"old\" -- )"),
+    ("'", "( \"name\" -- xt )"),
+    ("execute", "( i*x xt -- j*x )"),
+    ("emit", "( char -- )"),
+    ("base", "( -- addr )"),
+    ("hex", "( -- )"),
+    ("decimal", "( -- )"),
+    ("if", "( flag -- )"),
+    ("else", "( -- )"),
+    ("then", "( -- )"),
+    ("recurse", "( -- )"),
+    ("[']", "( \"name\" -- xt )"),
+];
+
+#[cfg(feature = "file-io")]
+const FILE_EFFECTS: &[(&str, &str)] = &crate::files::EFFECTS;
+#[cfg(not(feature = "file-io"))]
+const FILE_EFFECTS: &[(&str, &str)] = &[];
+#[cfg(feature = "env")]
+const ENV_EFFECTS: &[(&str, &str)] = &crate::env::EFFECTS;
+#[cfg(not(feature = "env"))]
+const ENV_EFFECTS: &[(&str, &str)] = &[];
+#[cfg(feature = "net")]
+const NET_EFFECTS: &[(&str, &str)] = &crate::net::EFFECTS;
+#[cfg(not(feature = "net"))]
+const NET_EFFECTS: &[(&str, &str)] = &[];
+#[cfg(feature = "peripherals")]
+const PERIPHERAL_EFFECTS: &[(&str, &str)] = &crate::peripherals::EFFECTS;
+#[cfg(not(feature = "peripherals"))]
+const PERIPHERAL_EFFECTS: &[(&str, &str)] = &[];
+#[cfg(feature = "dlopen")]
+const FOREIGN_EFFECTS: &[(&str, &str)] = &crate::foreign::EFFECTS;
+#[cfg(not(feature = "dlopen"))]
+const FOREIGN_EFFECTS: &[(&str, &str)] = &[];
+
+/// The `( ... )` right after the name of `definition`, the source of one
+/// from its `:` on, with its words lower-cased and separated by single
+/// spaces.
+pub(crate) fn stack_effect(definition: &str) -> Option<String> {
+    let mut words = definition.split_whitespace().skip(2);
+    if words.next() != Some("(") {
+        return None;
+    }
+    let mut comment = vec!["(".to_string()];
+    for word in words {
+        comment.push(word.to_lowercase());
+        if word == ")" {
+            return Some(comment.join(" "));
+        }
+    }
+    None
+}
+
+/// The stack effect of the builtin `name`.
+pub(crate) fn builtin_effect(name: &str) -> Option<&'static str> {
+    BUILTIN_EFFECTS
+        .iter()
+        .chain(FILE_EFFECTS)
+        .chain(ENV_EFFECTS)
+        .chain(NET_EFFECTS)
+        .chain(PERIPHERAL_EFFECTS)
+        .chain(FOREIGN_EFFECTS)
+        .find(|(builtin, _)| *builtin == name)
+        .map(|&(_, effect)| effect)
+}
+
+impl Forth {
+    /// The stack-effect comment of `word`, such as `( n1 n2 -- n3 )`. It is
+    /// the `( ... )` that opens the body of a definition, as in
+    /// `: sq ( n -- n*n ) dup * ;`, or the builtin's own. Words defined
+    /// without one, variables and markers have none.
+    pub fn doc(&self, word: &str) -> Option<&str> {
+        let name = self.names.get(&word.to_lowercase())?;
+        self.dictionary.effect(name)
+    }
+
+    /// Names defined since the interpreter was created that can still be
+    /// looked up, in the order they were defined, redefined builtins
+    /// included.
+    pub fn user_words(&self) -> impl Iterator<Item = &str> {
+        self.dictionary
+            .names_from(BUILTINS)
+            .map(|name| self.names.resolve(name))
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use crate::bytecode::Op;
+use crate::diagnostics::Fault;
+use crate::lexer::Lexeme;
+use crate::{lint, Error, Forth};
+
+/// What stretch of code does to the stack: how far it moves the depth, and
+/// the lowest the depth gets on the way, both from where it starts.
+#[derive(Debug, Clone, Copy, Default)]
+struct Shape {
+    depth: isize,
+    lowest: isize,
+}
+
+impl Shape {
+    fn apply(&mut self, (inputs, outputs): (usize, usize)) {
+        self.depth -= inputs as isize;
+        self.lowest = self.lowest.min(self.depth);
+        self.depth += outputs as isize;
+    }
+
+    /// Follows a stretch of code of shape `then` run from here.
+    fn then(&mut self, then: Shape) {
+        self.lowest = self.lowest.min(self.depth + then.lowest);
+        self.depth += then.depth;
+    }
+
+    /// Values taken and left, as `lint::effect` gives them.
+    fn effect(self) -> (usize, usize) {
+        (
+            self.lowest.unsigned_abs(),
+            (self.depth - self.lowest).unsigned_abs(),
+        )
+    }
+}
+
+/// An `if` whose arms leave different depths, at the index of its branch.
+struct Unbalanced {
+    at: usize,
+    taken: isize,
+    skipped: isize,
+}
+
+/// The inference of what definitions do to the stack, from their compiled
+/// code.
+struct Inference<'f> {
+    forth: &'f Forth,
+    /// Shapes of the user-defined words called, if they are known.
+    callees: HashMap<usize, Option<Shape>>,
+}
+
+impl Inference<'_> {
+    /// The shape of `code`, part of the body of the user-defined word
+    /// `word`, whose branches all land within it or right after it, or
+    /// `None` if there is no telling. Ops are numbered from `base`.
+    fn run(
+        &mut self,
+        word: usize,
+        code: &[Op],
+        base: usize,
+    ) -> std::result::Result<Option<Shape>, Unbalanced> {
+        let mut shape = Some(Shape::default());
+        let mut i = 0;
+        while i < code.len() {
+            let effect = match code[i] {
+                Op::BranchIfZero(skip) => {
+                    let (taken, skipped, next) = match code[i + 1..=i + skip].split_last() {
+                        Some((&Op::Branch(other), taken)) => {
+                            let end = i + skip + 1;
+                            (taken, &code[end..end + other], end + other)
+                        }
+                        _ => (&code[i + 1..=i + skip], &[][..], i + skip + 1),
+                    };
+                    let arms = (
+                        self.run(word, taken, base + i + 1)?,
+                        self.run(word, skipped, base + i + skip + 1)?,
+                    );
+                    shape = match (shape, arms) {
+                        (_, (Some(taken), Some(skipped))) if taken.depth != skipped.depth => {
+                            return Err(Unbalanced {
+                                at: base + i,
+                                taken: taken.depth,
+                                skipped: skipped.depth,
+                            })
+                        }
+                        (Some(mut shape), (Some(taken), Some(skipped))) => {
+                            shape.apply((1, 0));
+                            shape.then(Shape {
+                                depth: taken.depth,
+                                lowest: taken.lowest.min(skipped.lowest),
+                            });
+                            Some(shape)
+                        }
+                        _ => None,
+                    };
+                    i = next;
+                    continue;
+                }
+                // Not the `else` of an `if`, so not code compiled from a
+                // definition.
+                Op::Branch(_) => return Ok(None),
+                Op::Call(callee) | Op::TailCall(callee) => self.callee(word, callee),
+                op => effect(op),
+            };
+            shape = shape.zip(effect).map(|(mut shape, effect)| {
+                shape.apply(effect);
+                shape
+            });
+            i += 1;
+        }
+        Ok(shape)
+    }
+
+    /// What calling `callee` from `word` does, unless it is `word` itself,
+    /// as with `recurse`, or its arms are unbalanced.
+    fn callee(&mut self, word: usize, callee: usize) -> Option<(usize, usize)> {
+        if callee == word || callee >= self.forth.words.len() {
+            return None;
+        }
+        if let Some(shape) = self.callees.get(&callee) {
+            return shape.map(Shape::effect);
+        }
+        let shape = self.run(callee, self.forth.body(callee), 0).ok().flatten();
+        self.callees.insert(callee, shape);
+        shape.map(Shape::effect)
+    }
+}
+
+/// Values an op other than a branch or call takes and leaves, if that is
+/// fixed.
+pub(crate) fn effect(op: Op) -> Option<(usize, usize)> {
+    match op {
+        Op::Push(_) | Op::PushXt(_) => Some((0, 1)),
+        #[cfg(feature = "tagged")]
+        Op::PushTagged(..) => Some((0, 1)),
+        Op::Primitive(primitive) => lint::effect(primitive),
+        Op::Variable
+        | Op::Forget
+        | Op::Mark
+        | Op::DefineStack
+        | Op::Spawn
+        | Op::Pause
+        | Op::User
+        | Op::Synonym
+        | Op::Rewind(_)
+        | Op::Disassemble
+        | Op::Help => Some((0, 0)),
+        #[cfg(feature = "env")]
+        Op::GetEnv => Some((0, 2)),
+        #[cfg(feature = "peripherals")]
+        Op::Peripheral(word) => Some(word.effect()),
+        _ => None,
+    }
+}
+
+impl Forth {
+    /// Values the user-defined word with index `word` and compiled code
+    /// `code` takes and leaves, as far as the code tells.
+    pub(crate) fn inferred_effect(&self, word: usize, code: &[Op]) -> Option<(usize, usize)> {
+        let mut inference = Inference {
+            forth: self,
+            callees: HashMap::new(),
+        };
+        inference
+            .run(word, code, 0)
+            .ok()
+            .flatten()
+            .map(Shape::effect)
+    }
+
+    /// Fails with `Error::UnbalancedBranches` at the `if` to blame, if the
+    /// definition of `body` has one whose arms leave different depths, see
+    /// `StackEffects::Checked`.
+    pub(crate) fn check_branches(&self, body: &[Lexeme]) -> std::result::Result<(), Fault> {
+        // Compiled as written, so that ops can be traced back to tokens.
+        let mut offsets = Vec::new();
+        let code = self.compile_definition(body, Some(&mut offsets))?;
+        let mut inference = Inference {
+            forth: self,
+            callees: HashMap::new(),
+        };
+        match inference.run(self.words.len(), &code, 0) {
+            Ok(_) => Ok(()),
+            Err(Unbalanced { at, taken, skipped }) => Err(Fault {
+                error: Error::UnbalancedBranches { taken, skipped },
+                token: offsets.iter().position(|&offset| offset == Some(at)),
+                depth: 0,
+            }),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use crate::bytecode::Op;
+use crate::lexer::number;
+use crate::vm::Input;
+use crate::{Error, Forth, Result, Value};
+
+pub(crate) const OPERATIONS: [(&str, Op); 2] = [("getenv", Op::GetEnv), ("setenv", Op::SetEnv)];
+
+/// Stack effects of the environment words, like `doc::BUILTIN_EFFECTS`.
+pub(crate) const EFFECTS: [(&str, &str); 2] = [
+    ("getenv", "( \"name\" -- n flag )"),
+    ("setenv", "( n \"name\" -- )"),
+];
+
+type Lookup = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+type Store = Arc<dyn Fn(&str, &str) + Send + Sync>;
+
+/// Where `getenv` and `setenv` find variables, shared between an
+/// interpreter and its clones. The process environment unless the host
+/// says otherwise.
+#[derive(Clone)]
+pub(crate) struct Environment {
+    lookup: Lookup,
+    store: Store,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Environment {
+            lookup: Arc::new(|name| std::env::var(name).ok()),
+            store: Arc::new(|name, value| std::env::set_var(name, value)),
+        }
+    }
+}
+
+impl std::fmt::Debug for Environment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Environment")
+    }
+}
+
+/// Fails unless `name` can name a variable of the process environment.
+fn check_name(name: &str) -> Result {
+    if name.is_empty() || name.contains(['=', '\0']) {
+        return Err(Error::InvalidWord);
+    }
+    Ok(())
+}
+
+impl Forth {
+    /// Makes `getenv` ask `lookup` for the value of a variable, instead of
+    /// the process environment, so that the host decides what scripts see.
+    pub fn set_env_lookup(
+        &mut self,
+        lookup: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    ) {
+        self.environment.lookup = Arc::new(lookup);
+    }
+
+    /// Makes `setenv` hand variables to `store`, instead of setting them in
+    /// the process environment.
+    pub fn set_env_store(&mut self, store: impl Fn(&str, &str) + Send + Sync + 'static) {
+        self.environment.store = Arc::new(store);
+    }
+
+    /// Pushes the value of the variable named next in the input and a true
+    /// flag, or 0 and a false flag if it isn't set or isn't a number. Values
+    /// are read like numbers in source, surrounding whitespace aside.
+    pub(crate) fn getenv(&mut self, input: &mut Input<'_>) -> Result {
+        let name = input.name().ok_or(Error::InvalidWord)?;
+        check_name(&name)?;
+        let value = (self.environment.lookup)(&name).and_then(|value| number(value.trim()));
+        self.stack.push(value.unwrap_or(0));
+        self.stack.push(if value.is_some() { -1 } else { 0 });
+        Ok(())
+    }
+
+    /// Sets the variable named next in the input to the number on top of
+    /// the stack, written in decimal.
+    pub(crate) fn setenv(&mut self, input: &mut Input<'_>) -> Result {
+        let value: Value = self.stack.pop().ok_or(Error::StackUnderflow)?;
+        let name = input.name().ok_or(Error::InvalidWord)?;
+        check_name(&name)?;
+        (self.environment.store)(&name, &value.to_string());
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::SplitWhitespace;
+
+use crate::lexer::{lex, skip_comment, Lexeme};
+use crate::stacks::STACK_EFFECT;
+use crate::{Forth, Operation};
+
+/// What is known of the stack, its items from the bottom up, or `None` once
+/// a word that leaves no fixed number of them has run.
+type Picture = Option<Vec<String>>;
+
+/// A stack-effect comment such as `( n1 n2 -- n3 )`, taken apart.
+struct Effect<'a> {
+    inputs: Vec<&'a str>,
+    outputs: Vec<&'a str>,
+    /// How many names the word parses from the input, such as `"name"`.
+    parsed: usize,
+    /// Whether the effect doesn't fix the depth, as with `i*x` or `x*`.
+    varies: bool,
+}
+
+/// `comment` taken apart, unless it isn't of the form `( before -- after )`.
+fn parse_effect(comment: &str) -> Option<Effect<'_>> {
+    let inner = comment.trim().strip_prefix('(')?.strip_suffix(')')?;
+    let (before, after) = inner
+        .split_once(" -- ")
+        .or_else(|| inner.split_once("--"))?;
+    let varies = |item: &str| {
+        item == ".." || item.ends_with('*') || matches!(item.as_bytes(), [b'i'..=b'k', b'*', _])
+    };
+    let (parsed, inputs): (Vec<&str>, Vec<&str>) = before
+        .split_whitespace()
+        .partition(|item| item.starts_with('"'));
+    let outputs: Vec<&str> = after.split_whitespace().collect();
+    Some(Effect {
+        varies: inputs.iter().chain(&outputs).any(|item| varies(item)),
+        inputs,
+        outputs,
+        parsed: parsed.len(),
+    })
+}
+
+/// The definition being explained.
+struct Definition {
+    name: String,
+    effect: Option<String>,
+    /// For each `if` open, the picture where it started and, once its
+    /// `else` is reached, the picture where the part before that ended.
+    ifs: Vec<(Picture, Option<Picture>)>,
+    /// The picture outside the definition, which `;` brings back.
+    outside: Picture,
+}
+
+struct Explainer<'f> {
+    forth: &'f Forth,
+    /// Stack-effect comments of the words the input defines, if they have
+    /// one.
+    defined: HashMap<String, Option<String>>,
+    definition: Option<Definition>,
+    picture: Picture,
+    lines: Vec<(String, Picture)>,
+}
+
+impl Explainer<'_> {
+    fn word<'a>(&mut self, word: &'a str, rest: &mut Peekable<SplitWhitespace<'a>>) {
+        let Some(lexeme) = lex(word).next() else {
+            return;
+        };
+        let Lexeme::Word(name) = lexeme else {
+            self.push(word);
+            return self.line(word.to_string());
+        };
+        match (&*name, &mut self.definition) {
+            (":", None) => self.open(rest),
+            (";", Some(_)) => self.close(),
+            ("if", Some(_)) => {
+                self.apply(Some("( flag -- )"), rest);
+                let start = self.picture.clone();
+                self.definition_mut().ifs.push((start, None));
+                self.line(word.to_string());
+            }
+            ("else", Some(definition)) => {
+                if let Some((start, before_else)) = definition.ifs.last_mut() {
+                    *before_else = Some(std::mem::replace(&mut self.picture, start.clone()));
+                }
+                self.line(word.to_string());
+            }
+            ("then", Some(definition)) => {
+                if let Some((start, before_else)) = definition.ifs.pop() {
+                    let other = before_else.unwrap_or(start);
+                    self.picture = merge(self.picture.take(), other);
+                }
+                self.line(word.to_string());
+            }
+            ("recurse", Some(definition)) => {
+                let effect = definition.effect.clone();
+                self.apply(effect.as_deref(), rest);
+                self.line(word.to_string());
+            }
+            _ => {
+                let effect = self.effect_of(&name);
+                let parsed = self.apply(effect.as_deref(), rest);
+                let defines = match &*name {
+                    "variable" => Some("( -- addr )"),
+                    "marker" => Some("( -- )"),
+                    "stack" => Some(STACK_EFFECT),
+                    "user" => Some("( -- addr )"),
+                    _ => None,
+                };
+                if let (Some(effect), Some(defined)) = (defines, parsed.first()) {
+                    self.defined
+                        .insert(defined.to_lowercase(), Some(effect.to_string()));
+                }
+                if let ("synonym", [new, old]) = (&*name, parsed.as_slice()) {
+                    let effect = self.effect_of(&old.to_lowercase());
+                    self.defined.insert(new.to_lowercase(), effect);
+                }
+                self.line(
+                    std::iter::once(word)
+                        .chain(parsed)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                );
+            }
+        }
+    }
+
+    fn definition_mut(&mut self) -> &mut Definition {
+        self.definition.as_mut().expect("inside a definition")
+    }
+
+    /// Starts a definition at its `:`, taking its name and stack-effect
+    /// comment, whose inputs it starts with.
+    fn open(&mut self, rest: &mut Peekable<SplitWhitespace<'_>>) {
+        let name = rest.next().unwrap_or_default();
+        let mut header = format!(": {name}");
+        let mut effect = None;
+        if rest.peek() == Some(&"(") {
+            let mut comment = Vec::new();
+            for word in rest.by_ref() {
+                comment.push(word);
+                if word == ")" {
+                    break;
+                }
+            }
+            let comment = comment.join(" ");
+            header = format!("{header} {comment}");
+            effect = Some(comment);
+        }
+        let inputs = effect
+            .as_deref()
+            .and_then(parse_effect)
+            .filter(|effect| !effect.varies)
+            .map_or_else(Vec::new, |effect| {
+                effect
+                    .inputs
+                    .iter()
+                    .map(|input| input.to_string())
+                    .collect()
+            });
+        let outside = self.picture.replace(inputs);
+        self.line(header);
+        self.definition = Some(Definition {
+            name: name.to_lowercase(),
+            effect,
+            ifs: Vec::new(),
+            outside,
+        });
+    }
+
+    fn close(&mut self) {
+        let definition = self.definition.take().expect("inside a definition");
+        self.line(";".to_string());
+        self.defined.insert(definition.name, definition.effect);
+        self.picture = definition.outside;
+    }
+
+    /// The stack-effect comment of `name` as it would be looked up here.
+    fn effect_of(&self, name: &str) -> Option<String> {
+        if let Some(effect) = self.defined.get(name) {
+            return effect.clone();
+        }
+        match self.forth.lookup_word(name).ok()? {
+            Operation::Address(_) => Some("( -- addr )".to_string()),
+            Operation::Marker(_) => Some("( -- )".to_string()),
+            Operation::Control(_) => None,
+            Operation::Builtin(_) | Operation::UserDefined(_) => {
+                self.forth.doc(name).map(str::to_string)
+            }
+        }
+    }
+
+    /// Follows `effect` on the picture, taking the names it parses from
+    /// `rest`, which it returns. Items it takes from below what is known are
+    /// named after its inputs.
+    fn apply<'a>(
+        &mut self,
+        effect: Option<&str>,
+        rest: &mut Peekable<SplitWhitespace<'a>>,
+    ) -> Vec<&'a str> {
+        let Some(effect) = effect.and_then(parse_effect) else {
+            self.picture = None;
+            return Vec::new();
+        };
+        let parsed = rest.by_ref().take(effect.parsed).collect();
+        let Some(stack) = self.picture.as_mut().filter(|_| !effect.varies) else {
+            self.picture = None;
+            return parsed;
+        };
+        let taken = stack.split_off(stack.len().saturating_sub(effect.inputs.len()));
+        let missing = effect.inputs.len() - taken.len();
+        let bound: Vec<String> = effect.inputs[..missing]
+            .iter()
+            .map(|input| input.to_string())
+            .chain(taken)
+            .collect();
+        for output in &effect.outputs {
+            match effect.inputs.iter().rposition(|input| input == output) {
+                Some(input) => stack.push(bound[input].clone()),
+                None => stack.push(output.to_string()),
+            }
+        }
+        parsed
+    }
+
+    fn push(&mut self, item: &str) {
+        if let Some(stack) = &mut self.picture {
+            stack.push(item.to_string());
+        }
+    }
+
+    fn line(&mut self, text: String) {
+        let inside = self.definition.is_some() && !text.starts_with(':');
+        let text = if inside { format!("  {text}") } else { text };
+        self.lines.push((text, self.picture.clone()));
+    }
+}
+
+/// What is known of the stack where two branches meet: items that differ
+/// between them become `x`, and differing depths leave nothing known.
+fn merge(a: Picture, b: Picture) -> Picture {
+    match (a, b) {
+        (Some(a), Some(b)) if a.len() == b.len() => Some(
+            a.into_iter()
+                .zip(b)
+                .map(|(a, b)| if a == b { a } else { "x".to_string() })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+impl Forth {
+    /// `input` one word to a line, each followed by a picture of the stack
+    /// it leaves, as in `dup  ( n n )`. The pictures are inferred from the
+    /// stack-effect comments of builtins and of definitions that declare
+    /// one, without running anything: numbers appear as written, results as
+    /// their effects name them, and values the words find below what is
+    /// known as their effects name their inputs. After a word with no fixed
+    /// effect, the picture is `( ? )`.
+    pub fn explain(&self, input: &str) -> String {
+        let mut explainer = Explainer {
+            forth: self,
+            defined: HashMap::new(),
+            definition: None,
+            picture: Some(Vec::new()),
+            lines: Vec::new(),
+        };
+        let mut words = input.split_whitespace().peekable();
+        while let Some(word) = words.next() {
+            if !skip_comment(input, word, &mut words) {
+                explainer.word(word, &mut words);
+            }
+        }
+        let width = explainer
+            .lines
+            .iter()
+            .map(|(text, _)| text.len())
+            .max()
+            .unwrap_or(0);
+        let mut explained = String::new();
+        for (text, picture) in explainer.lines {
+            let picture = match picture {
+                Some(items) if items.is_empty() => "( )".to_string(),
+                Some(items) => format!("( {} )", items.join(" ")),
+                None => "( ? )".to_string(),
+            };
+            explained.push_str(&format!("{text:<width$}  {picture}\n"));
+        }
+        explained
+    }
+}
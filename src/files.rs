@@ -0,0 +1,269 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use crate::bytecode::Op;
+use crate::vm::Input;
+use crate::{Error, Forth, Result, Value};
+
+/// Access methods, as pushed by `r/o`, `w/o` and `r/w`.
+const READ_ONLY: Value = 0;
+const WRITE_ONLY: Value = 1;
+const READ_WRITE: Value = 2;
+
+/// The file words, which run as `Op::File`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum FileWord {
+    Open,
+    Create,
+    Close,
+    Read,
+    Write,
+    Position,
+    Reposition,
+    Size,
+    Delete,
+}
+
+impl FileWord {
+    /// Every file word, in declaration order.
+    pub(crate) const ALL: [FileWord; 9] = [
+        FileWord::Open,
+        FileWord::Create,
+        FileWord::Close,
+        FileWord::Read,
+        FileWord::Write,
+        FileWord::Position,
+        FileWord::Reposition,
+        FileWord::Size,
+        FileWord::Delete,
+    ];
+
+    /// Whether the word parses a file name from the input, as `variable`
+    /// parses the name it defines.
+    pub(crate) fn parses_name(self) -> bool {
+        matches!(self, FileWord::Open | FileWord::Create | FileWord::Delete)
+    }
+}
+
+pub(crate) const OPERATIONS: [(&str, Op); 12] = [
+    ("r/o", Op::Push(READ_ONLY)),
+    ("w/o", Op::Push(WRITE_ONLY)),
+    ("r/w", Op::Push(READ_WRITE)),
+    ("open-file", Op::File(FileWord::Open)),
+    ("create-file", Op::File(FileWord::Create)),
+    ("close-file", Op::File(FileWord::Close)),
+    ("read-file", Op::File(FileWord::Read)),
+    ("write-file", Op::File(FileWord::Write)),
+    ("file-position", Op::File(FileWord::Position)),
+    ("reposition-file", Op::File(FileWord::Reposition)),
+    ("file-size", Op::File(FileWord::Size)),
+    ("delete-file", Op::File(FileWord::Delete)),
+];
+
+/// Stack effects of the file words, like `doc::BUILTIN_EFFECTS`.
+pub(crate) const EFFECTS: [(&str, &str); 12] = [
+    ("r/o", "( -- fam )"),
+    ("w/o", "( -- fam )"),
+    ("r/w", "( -- fam )"),
+    ("open-file", "( fam \"name\" -- fileid ior )"),
+    ("create-file", "( fam \"name\" -- fileid ior )"),
+    ("close-file", "( fileid -- ior )"),
+    ("read-file", "( addr u1 fileid -- u2 ior )"),
+    ("write-file", "( addr u fileid -- ior )"),
+    ("file-position", "( fileid -- u ior )"),
+    ("reposition-file", "( u fileid -- ior )"),
+    ("file-size", "( fileid -- u ior )"),
+    ("delete-file", "( \"name\" -- ior )"),
+];
+
+/// Files scripts have opened, and where they may open them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Files {
+    root: Option<PathBuf>,
+    /// Indexed by file id less one. Clones of the interpreter share the
+    /// files open when they were made, position included.
+    open: Vec<Option<Arc<File>>>,
+}
+
+impl Files {
+    pub(crate) fn rooted(root: Option<PathBuf>) -> Files {
+        Files {
+            root,
+            open: Vec::new(),
+        }
+    }
+
+    /// `name` within the root, unless there is no root or `name` would lead
+    /// out of it, through `..` or a symbolic link.
+    fn resolve(&self, name: &str) -> io::Result<PathBuf> {
+        let denied = || io::Error::from(io::ErrorKind::PermissionDenied);
+        let root = self.root.as_deref().ok_or_else(denied)?;
+        let relative = Path::new(name);
+        let normal = |component| matches!(component, Component::Normal(_));
+        if name.is_empty() || !relative.components().all(normal) {
+            return Err(denied());
+        }
+        let root = root.canonicalize()?;
+        let path = root.join(relative);
+        let parent = path.parent().unwrap_or(&root).canonicalize()?;
+        match path.canonicalize() {
+            Ok(real) if !real.starts_with(&root) => Err(denied()),
+            _ if !parent.starts_with(&root) => Err(denied()),
+            _ => Ok(path),
+        }
+    }
+
+    fn open(&mut self, name: &str, fam: Value, create: bool) -> io::Result<Value> {
+        let mut options = OpenOptions::new();
+        match fam {
+            READ_ONLY => options.read(true),
+            WRITE_ONLY => options.write(true),
+            READ_WRITE => options.read(true).write(true),
+            _ => return Err(io::Error::from(io::ErrorKind::InvalidInput)),
+        };
+        let file = options
+            .create(create)
+            .truncate(create)
+            .open(self.resolve(name)?)?;
+        let slot = match self.open.iter().position(Option::is_none) {
+            Some(slot) => slot,
+            None => {
+                self.open.push(None);
+                self.open.len() - 1
+            }
+        };
+        self.open[slot] = Some(Arc::new(file));
+        Value::try_from(slot + 1).map_err(|_| io::Error::other("too many open files"))
+    }
+
+    fn file(&self, id: Value) -> io::Result<&File> {
+        usize::try_from(id)
+            .ok()
+            .and_then(|id| self.open.get(id.checked_sub(1)?)?.as_deref())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+
+    fn close(&mut self, id: Value) -> io::Result<()> {
+        self.file(id)?;
+        self.open[id as usize - 1] = None;
+        Ok(())
+    }
+}
+
+/// The I/O result code of `outcome`: 0 on success, otherwise the operating
+/// system's error number, or -1 where there is none.
+fn ior<T>(outcome: &io::Result<T>) -> Value {
+    match outcome {
+        Ok(_) => 0,
+        Err(error) => error.raw_os_error().filter(|&code| code != 0).unwrap_or(-1),
+    }
+}
+
+/// `value` as a position or size, which a cell may not hold.
+fn offset(value: u64) -> io::Result<Value> {
+    Value::try_from(value).map_err(|_| io::Error::from(io::ErrorKind::InvalidData))
+}
+
+impl Forth {
+    /// Where scripts may open files, if anywhere.
+    pub fn file_root(&self) -> Option<&Path> {
+        self.files.root.as_deref()
+    }
+
+    /// Lets the file words open and delete files under `root` only, or no
+    /// files at all if it is `None`, which is the default. Names are taken
+    /// relative to it and can't lead out of it. Files already open stay
+    /// open.
+    pub fn set_file_root(&mut self, root: Option<PathBuf>) {
+        self.files.root = root;
+    }
+
+    /// Runs `word`. Failures to open, read or write files are left as an
+    /// I/O result code on the stack, as in standard Forth; a bad buffer or
+    /// a missing name fails the word.
+    pub(crate) fn file_word(&mut self, word: FileWord, input: &mut Input<'_>) -> Result {
+        match word {
+            FileWord::Open | FileWord::Create => {
+                let fam = self.pop()?;
+                let name = input.name().ok_or(Error::InvalidWord)?;
+                let outcome = self.files.open(&name, fam, word == FileWord::Create);
+                self.stack.push(*outcome.as_ref().unwrap_or(&0));
+                self.stack.push(ior(&outcome));
+            }
+            FileWord::Close => {
+                let id = self.pop()?;
+                let outcome = self.files.close(id);
+                self.stack.push(ior(&outcome));
+            }
+            FileWord::Read => {
+                let id = self.pop()?;
+                let len = self.pop()?;
+                let address = self.pop_address()?;
+                let buffer = self.buffer(address, len)?;
+                let mut bytes = vec![0; buffer.len()];
+                let outcome = self.files.file(id).and_then(|mut file| {
+                    let mut read = 0;
+                    while read < bytes.len() {
+                        match file.read(&mut bytes[read..]) {
+                            Ok(0) => break,
+                            Ok(n) => read += n,
+                            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                            Err(error) => return Err(error),
+                        }
+                    }
+                    Ok(read)
+                });
+                let read = *outcome.as_ref().unwrap_or(&0);
+                let cells = &mut Arc::make_mut(&mut self.data_space)[buffer];
+                for (cell, &byte) in cells.iter_mut().zip(&bytes[..read]) {
+                    *cell = Value::from(byte);
+                }
+                self.stack.push(read as Value);
+                self.stack.push(ior(&outcome));
+            }
+            FileWord::Write => {
+                let id = self.pop()?;
+                let len = self.pop()?;
+                let address = self.pop_address()?;
+                let buffer = self.buffer(address, len)?;
+                let bytes: Vec<u8> = self.data_space[buffer]
+                    .iter()
+                    .map(|&cell| cell as u8)
+                    .collect();
+                let outcome = self
+                    .files
+                    .file(id)
+                    .and_then(|mut file| file.write_all(&bytes));
+                self.stack.push(ior(&outcome));
+            }
+            FileWord::Position | FileWord::Size => {
+                let id = self.pop()?;
+                let outcome = self.files.file(id).and_then(|mut file| match word {
+                    FileWord::Position => offset(file.stream_position()?),
+                    _ => offset(file.metadata()?.len()),
+                });
+                self.stack.push(*outcome.as_ref().unwrap_or(&0));
+                self.stack.push(ior(&outcome));
+            }
+            FileWord::Reposition => {
+                let id = self.pop()?;
+                let position = self.pop()?;
+                let outcome = self.files.file(id).and_then(|mut file| {
+                    let position = u64::try_from(position)
+                        .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+                    file.seek(SeekFrom::Start(position))
+                });
+                self.stack.push(ior(&outcome));
+            }
+            FileWord::Delete => {
+                let name = input.name().ok_or(Error::InvalidWord)?;
+                let outcome = self.files.resolve(&name).and_then(std::fs::remove_file);
+                self.stack.push(ior(&outcome));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+#[cfg(not(unix))]
+compile_error!("the `dlopen` feature needs the `dlopen` of a Unix system");
+
+use std::ffi::{c_void, CString};
+use std::sync::Arc;
+
+use crate::bytecode::Op;
+use crate::vm::Input;
+use crate::{Error, Forth, Result, Value};
+
+/// The words that call into shared libraries, which run as `Op::Foreign`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum ForeignWord {
+    Open,
+    Symbol,
+    Call,
+}
+
+impl ForeignWord {
+    /// Every foreign word, in declaration order.
+    pub(crate) const ALL: [ForeignWord; 3] =
+        [ForeignWord::Open, ForeignWord::Symbol, ForeignWord::Call];
+
+    /// Whether the word parses a name from the input, as `variable` parses
+    /// the name it defines.
+    pub(crate) fn parses_name(self) -> bool {
+        matches!(self, ForeignWord::Open | ForeignWord::Symbol)
+    }
+}
+
+pub(crate) const OPERATIONS: [(&str, Op); 3] = [
+    ("dlopen", Op::Foreign(ForeignWord::Open)),
+    ("dlsym", Op::Foreign(ForeignWord::Symbol)),
+    ("dlcall", Op::Foreign(ForeignWord::Call)),
+];
+
+/// Stack effects of the foreign words, like `doc::BUILTIN_EFFECTS`.
+pub(crate) const EFFECTS: [(&str, &str); 3] = [
+    ("dlopen", "( \"path\" -- lib ior )"),
+    ("dlsym", "( lib \"name\" -- sym ior )"),
+    ("dlcall", "( x1 .. xn n sym -- x ior )"),
+];
+
+/// The most arguments `dlcall` passes.
+const MAX_ARGUMENTS: usize = 6;
+
+/// A library loaded by `dlopen`, closed once no symbol of it is left.
+struct Library(*mut c_void);
+
+// The handles `dlopen` returns may be used from any thread.
+unsafe impl Send for Library {}
+unsafe impl Sync for Library {}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        // SAFETY: the handle came from `dlopen` and is closed only here.
+        unsafe { libc::dlclose(self.0) };
+    }
+}
+
+/// A function resolved by `dlsym`, which keeps its library loaded.
+#[derive(Clone)]
+struct Symbol {
+    address: usize,
+    _library: Arc<Library>,
+}
+
+/// Libraries and symbols scripts have loaded, which clones of the
+/// interpreter share; both are numbered from 1.
+#[derive(Clone, Default)]
+pub(crate) struct Libraries {
+    allowed: bool,
+    libraries: Vec<Arc<Library>>,
+    symbols: Vec<Symbol>,
+}
+
+impl std::fmt::Debug for Libraries {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Libraries")
+            .field("allowed", &self.allowed)
+            .field("libraries", &self.libraries.len())
+            .field("symbols", &self.symbols.len())
+            .finish()
+    }
+}
+
+impl Libraries {
+    fn open(&mut self, path: &str) -> Option<Value> {
+        let path = CString::new(path).ok()?;
+        // SAFETY: loading a library runs its initializers, which the host
+        // vouched for in `Forth::allow_foreign_calls`.
+        let handle = unsafe { libc::dlopen(path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if handle.is_null() {
+            return None;
+        }
+        self.libraries.push(Arc::new(Library(handle)));
+        Value::try_from(self.libraries.len()).ok()
+    }
+
+    fn symbol(&mut self, library: Value, name: &str) -> Option<Value> {
+        let library = usize::try_from(library).ok()?.checked_sub(1)?;
+        let library = Arc::clone(self.libraries.get(library)?);
+        let name = CString::new(name).ok()?;
+        // SAFETY: the handle is open while `library` lives.
+        let address = unsafe { libc::dlsym(library.0, name.as_ptr()) };
+        if address.is_null() {
+            return None;
+        }
+        self.symbols.push(Symbol {
+            address: address as usize,
+            _library: library,
+        });
+        Value::try_from(self.symbols.len()).ok()
+    }
+
+    fn function(&self, symbol: Value) -> Option<&Symbol> {
+        self.symbols
+            .get(usize::try_from(symbol).ok()?.checked_sub(1)?)
+    }
+}
+
+/// Calls the C function at `address` with `arguments`, taking each and the
+/// result as a C `intptr_t`.
+///
+/// # Safety
+///
+/// `address` must be a function of as many arguments, no more than
+/// `MAX_ARGUMENTS`, that is sound to call with them.
+unsafe fn call(address: usize, arguments: &[isize]) -> isize {
+    use std::mem::transmute;
+    type F0 = extern "C" fn() -> isize;
+    type F1 = extern "C" fn(isize) -> isize;
+    type F2 = extern "C" fn(isize, isize) -> isize;
+    type F3 = extern "C" fn(isize, isize, isize) -> isize;
+    type F4 = extern "C" fn(isize, isize, isize, isize) -> isize;
+    type F5 = extern "C" fn(isize, isize, isize, isize, isize) -> isize;
+    type F6 = extern "C" fn(isize, isize, isize, isize, isize, isize) -> isize;
+    match *arguments {
+        [] => transmute::<usize, F0>(address)(),
+        [a] => transmute::<usize, F1>(address)(a),
+        [a, b] => transmute::<usize, F2>(address)(a, b),
+        [a, b, c] => transmute::<usize, F3>(address)(a, b, c),
+        [a, b, c, d] => transmute::<usize, F4>(address)(a, b, c, d),
+        [a, b, c, d, e] => transmute::<usize, F5>(address)(a, b, c, d, e),
+        [a, b, c, d, e, f] => transmute::<usize, F6>(address)(a, b, c, d, e, f),
+        _ => unreachable!("`dlcall` checks the number of arguments"),
+    }
+}
+
+/// The I/O result code of `outcome`; the dynamic loader reports no error
+/// numbers, so every failure is -1.
+fn ior<T>(outcome: &Option<T>) -> Value {
+    if outcome.is_some() {
+        0
+    } else {
+        -1
+    }
+}
+
+impl Forth {
+    /// Lets `dlopen`, `dlsym` and `dlcall` load libraries and call their
+    /// functions; until then they fail with an I/O result code of -1. Clones
+    /// made afterwards may call them too.
+    ///
+    /// # Safety
+    ///
+    /// Scripts can then call any function with any arguments, as unsafe
+    /// code could, so the caller must trust whatever it evaluates to call
+    /// only functions that take and return integers, with valid arguments.
+    pub unsafe fn allow_foreign_calls(&mut self) {
+        self.libraries.allowed = true;
+    }
+
+    /// Runs `word`. Failures to load, resolve or call are left as an I/O
+    /// result code on the stack; a missing name or more than six arguments
+    /// fails the word.
+    pub(crate) fn foreign_word(&mut self, word: ForeignWord, input: &mut Input<'_>) -> Result {
+        let allowed = self.libraries.allowed;
+        let outcome = match word {
+            ForeignWord::Open => {
+                let path = input.name().ok_or(Error::InvalidWord)?;
+                allowed.then(|| self.libraries.open(&path)).flatten()
+            }
+            ForeignWord::Symbol => {
+                let library = self.pop()?;
+                let name = input.name().ok_or(Error::InvalidWord)?;
+                allowed
+                    .then(|| self.libraries.symbol(library, &name))
+                    .flatten()
+            }
+            ForeignWord::Call => {
+                let symbol = self.pop()?;
+                let count = usize::try_from(self.pop()?)
+                    .ok()
+                    .filter(|&count| count <= MAX_ARGUMENTS)
+                    .ok_or(Error::OutOfRange)?;
+                let start = self
+                    .stack
+                    .len()
+                    .checked_sub(count)
+                    .ok_or(Error::StackUnderflow)?;
+                let arguments: Vec<isize> = self
+                    .stack
+                    .split_off(start)
+                    .into_iter()
+                    .map(|argument| argument as isize)
+                    .collect();
+                let function = self.libraries.function(symbol).filter(|_| allowed);
+                // SAFETY: the host vouched for the functions scripts call in
+                // `allow_foreign_calls`.
+                function.map(|function| unsafe { call(function.address, &arguments) } as Value)
+            }
+        };
+        self.stack.push(outcome.unwrap_or(0));
+        self.stack.push(ior(&outcome));
+        Ok(())
+    }
+}
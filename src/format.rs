@@ -0,0 +1,200 @@
+use crate::diagnostics::word_spans;
+use crate::highlight::delimited;
+use crate::TokenClass;
+
+/// How `format` lays out source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Spaces per level of indentation inside definitions and `if`s.
+    pub indent: usize,
+    /// Lines are wrapped before they grow past this many characters, unless
+    /// a single word or comment is longer.
+    pub line_length: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            indent: 2,
+            line_length: 80,
+        }
+    }
+}
+
+/// A word, or a comment or string as a whole.
+struct Unit<'a> {
+    text: &'a str,
+    /// Line breaks between the previous unit and this one.
+    breaks: usize,
+}
+
+impl Unit<'_> {
+    fn is(&self, word: &str) -> bool {
+        self.text.eq_ignore_ascii_case(word)
+    }
+
+    fn is_line_comment(&self) -> bool {
+        self.text.starts_with('\\')
+    }
+}
+
+fn units(source: &str) -> Vec<Unit<'_>> {
+    let mut units = Vec::new();
+    let mut end = 0;
+    for span in word_spans(source) {
+        if span.start < end {
+            continue;
+        }
+        let span = match delimited(source, span) {
+            Some((delimited, TokenClass::Comment | TokenClass::String)) => delimited,
+            _ => span,
+        };
+        let breaks = source[end..span.start].matches('\n').count();
+        units.push(Unit {
+            text: &source[span.start..span.end],
+            breaks,
+        });
+        end = span.end;
+    }
+    units
+}
+
+/// Lays `source` out the same way whatever its spacing was: each definition
+/// starts a line of its own, and one that doesn't fit on a line puts its
+/// body on the lines after its name and stack-effect comment, indented, with
+/// a line for each `if`, `else` and `then` branch. Outside definitions, line
+/// breaks are kept, blank lines are squeezed to one and long lines are
+/// wrapped. Words, comments and strings are left as they are, so the
+/// formatted source means the same.
+pub fn format(source: &str, options: FormatOptions) -> String {
+    let units = units(source);
+    let mut layout = Layout {
+        options,
+        out: String::with_capacity(source.len()),
+        column: 0,
+        indent: 0,
+    };
+    let mut i = 0;
+    while i < units.len() {
+        let unit = &units[i];
+        match unit.breaks {
+            _ if layout.out.is_empty() => {}
+            0 => {}
+            1 => layout.end_line(),
+            _ => layout.blank_line(),
+        }
+        if unit.is(":") {
+            let close = units[i..].iter().position(|unit| unit.is(";"));
+            let end = close.map_or(units.len(), |close| i + close);
+            layout.definition(&units[i + 1..end], close.is_some());
+            i = end + 1;
+        } else {
+            layout.put(unit.text);
+            if unit.is_line_comment() {
+                layout.end_line();
+            }
+            i += 1;
+        }
+    }
+    layout.end_line();
+    layout.out
+}
+
+struct Layout {
+    options: FormatOptions,
+    out: String,
+    /// Width of the current line so far; 0 if nothing is on it yet.
+    column: usize,
+    /// Spaces before the next unit put at the start of a line.
+    indent: usize,
+}
+
+impl Layout {
+    /// Appends `text` to the current line, or to a new one if it would not
+    /// fit.
+    fn put(&mut self, text: &str) {
+        let width = text.lines().next().unwrap_or_default().chars().count();
+        if self.column > 0 {
+            if self.column + 1 + width > self.options.line_length {
+                self.end_line();
+            } else {
+                self.out.push(' ');
+                self.column += 1;
+            }
+        }
+        if self.column == 0 {
+            self.out.extend(std::iter::repeat_n(' ', self.indent));
+            self.column = self.indent;
+        }
+        self.out.push_str(text);
+        self.column = match text.rfind('\n') {
+            Some(i) => text[i + 1..].chars().count(),
+            None => self.column + width,
+        };
+    }
+
+    fn end_line(&mut self) {
+        if self.column > 0 {
+            self.out.push('\n');
+            self.column = 0;
+        }
+    }
+
+    fn blank_line(&mut self) {
+        self.end_line();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    /// Lays out the definition whose units after the `:` are `units`, ending
+    /// in `;` if it is `closed`.
+    fn definition(&mut self, units: &[Unit], closed: bool) {
+        self.end_line();
+        self.indent = 0;
+        let header = match units {
+            [_, effect, ..] if effect.text.starts_with('(') => 2,
+            _ => units.len().min(1),
+        };
+        let (header, body) = units.split_at(header);
+        let words: Vec<&str> = std::iter::once(":")
+            .chain(units.iter().map(|unit| unit.text))
+            .chain(closed.then_some(";"))
+            .collect();
+        let width = words.iter().map(|word| word.chars().count()).sum::<usize>() + words.len() - 1;
+        let one_line = width <= self.options.line_length
+            && !units
+                .iter()
+                .any(|unit| unit.is_line_comment() || unit.text.contains('\n'));
+        if one_line {
+            words.iter().for_each(|word| self.put(word));
+            self.end_line();
+            return;
+        }
+        self.put(":");
+        header.iter().for_each(|unit| self.put(unit.text));
+        self.end_line();
+        let mut depth = 1;
+        for unit in body {
+            let branch = unit.is("else") || unit.is("then");
+            if branch {
+                self.end_line();
+                depth = (depth - 1).max(1);
+            }
+            self.indent = depth * self.options.indent;
+            self.put(unit.text);
+            if unit.is("if") || unit.is("else") {
+                self.end_line();
+                depth += 1;
+            } else if unit.is_line_comment() {
+                self.end_line();
+            }
+        }
+        if closed {
+            self.indent = depth * self.options.indent;
+            self.put(";");
+        }
+        self.end_line();
+        self.indent = 0;
+    }
+}
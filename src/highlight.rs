@@ -0,0 +1,99 @@
+use std::collections::HashSet;
+
+use crate::diagnostics::word_spans;
+use crate::lexer::number;
+use crate::{Forth, Span, BUILTINS};
+
+/// What a stretch of source is, for colouring it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenClass {
+    Number,
+    /// A word the interpreter starts out with, `:` and `;` included.
+    Builtin,
+    /// A word from the dictionary or defined earlier in the source, and the
+    /// name being defined by `:`, `variable` or `marker`.
+    UserWord,
+    /// From `."`, `s"`, `c"` or `abort"` to the closing `"`.
+    String,
+    /// From `(` to `)`, or from `\` to the end of the line.
+    Comment,
+    /// A word that is none of the above.
+    Unknown,
+}
+
+/// Classifies the tokens of `input` against the builtins, see
+/// `Forth::highlight`.
+pub fn highlight(input: &str) -> Vec<(Span, TokenClass)> {
+    Forth::new().highlight(input)
+}
+
+/// The whole comment or string opened by the token at `span`, if it opens
+/// one. A line comment ends before the newline, the others after their
+/// closing character; an unclosed one runs to the end of `input`.
+pub(crate) fn delimited(input: &str, span: Span) -> Option<(Span, TokenClass)> {
+    let (closing, class) = match input[span.start..span.end].to_lowercase().as_str() {
+        "(" => (')', TokenClass::Comment),
+        "\\" => ('\n', TokenClass::Comment),
+        ".\"" | "s\"" | "c\"" | "abort\"" => ('"', TokenClass::String),
+        _ => return None,
+    };
+    let end = match input[span.end..].find(closing) {
+        Some(i) if closing == '\n' => span.end + i,
+        Some(i) => span.end + i + 1,
+        None => input.len(),
+    };
+    Some((Span::new(span.start, end), class))
+}
+
+impl Forth {
+    /// Classifies the tokens of `input` for syntax highlighting, without
+    /// running it. A word is a user word if this interpreter's dictionary has
+    /// it, or if `input` defines it before that point. Strings and comments
+    /// are one span each, however many words they hold; an unclosed one runs
+    /// to the end of the input.
+    pub fn highlight(&self, input: &str) -> Vec<(Span, TokenClass)> {
+        let mut classes = Vec::new();
+        let mut defined = HashSet::new();
+        let mut naming = false;
+        let mut resume = 0;
+        for span in word_spans(input) {
+            if span.start < resume {
+                continue;
+            }
+            if let Some((delimited, class)) = delimited(input, span) {
+                classes.push((delimited, class));
+                resume = delimited.end;
+                continue;
+            }
+            let text = &input[span.start..span.end];
+            let word = text.to_lowercase();
+            let class = if naming {
+                naming = false;
+                defined.insert(word);
+                TokenClass::UserWord
+            } else if number(text).is_some() {
+                TokenClass::Number
+            } else if word == ":" || word == ";" {
+                naming = word == ":";
+                TokenClass::Builtin
+            } else if defined.contains(&word) {
+                TokenClass::UserWord
+            } else {
+                let entry = self
+                    .names
+                    .get(&word)
+                    .and_then(|name| self.dictionary.find(name));
+                match entry {
+                    Some(entry) if entry < BUILTINS => {
+                        naming = word == "variable" || word == "marker";
+                        TokenClass::Builtin
+                    }
+                    Some(_) => TokenClass::UserWord,
+                    None => TokenClass::Unknown,
+                }
+            };
+            classes.push((span, class));
+        }
+        classes
+    }
+}
@@ -0,0 +1,465 @@
+use std::borrow::Cow;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::bytecode::{Body, Control, Op, Primitive};
+use crate::stacks::Stacks;
+use crate::tasks::Tasks;
+#[cfg(feature = "tagged")]
+use crate::Tag;
+use crate::{builtin_ops, Forth, Operation, Usage, Value, BUILTINS};
+
+const MAGIC: &[u8; 8] = b"FORTHIMG";
+
+/// Bumped whenever the layout changes, or the encoding of ops and
+/// primitives does.
+const VERSION: u32 = 12;
+
+impl Forth {
+    /// Writes the user-defined part of the dictionary, the compiled code and
+    /// the data space to `path`, so `load_image` can bring them back without
+    /// compiling any source. The stack and settings such as quotas and
+    /// optimizations are not saved. Save a copy from `pruned` to leave out
+    /// what the words a host runs don't need.
+    pub fn save_image(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut image = Writer(MAGIC.to_vec());
+        image.u32(VERSION);
+        image.len(BUILTINS);
+        image.len(self.data_space.len());
+        self.data_space.iter().for_each(|&value| image.value(value));
+        image.len(self.code.len());
+        self.code.iter().for_each(|&op| image.op(op));
+        image.len(self.words.len());
+        for body in self.words.iter() {
+            image.len(body.start);
+            image.len(body.end);
+        }
+        image.len(self.dictionary.len() - BUILTINS);
+        for (name, operation, effect) in self.dictionary.entries_from(BUILTINS) {
+            image.str(self.names.resolve(name));
+            image.operation(operation);
+            match effect {
+                Some(effect) => {
+                    image.u8(1);
+                    image.str(effect);
+                }
+                None => image.u8(0),
+            }
+        }
+        image.len(self.usage.charges.len());
+        self.usage
+            .charges
+            .iter()
+            .for_each(|&tokens| image.len(tokens));
+        image.len(self.stacks.names().len());
+        self.stacks.names().iter().for_each(|name| image.str(name));
+        image.len(self.tasks.user_cells().len());
+        self.tasks
+            .user_cells()
+            .iter()
+            .for_each(|&cell| image.len(cell));
+        std::fs::write(path, image.0)
+    }
+
+    /// Replaces the user-defined words, the compiled code and the data space
+    /// with those saved by `save_image`; the stack is left alone. Fails with
+    /// `io::ErrorKind::InvalidData`, leaving the interpreter unchanged, if
+    /// `path` is not an image this version can read, or if its code or
+    /// dictionary uses builtins the capabilities don't grant.
+    pub fn load_image(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let bytes = std::fs::read(path)?;
+        let mut image = Reader(&bytes);
+        if image.take(MAGIC.len())? != MAGIC {
+            return Err(invalid("not a forth image"));
+        }
+        let version = image.u32()?;
+        if version != VERSION {
+            return Err(invalid(&format!("unsupported image version {version}")));
+        }
+        if image.len()? != BUILTINS {
+            return Err(invalid("image was saved with different builtins"));
+        }
+        let data_space = image.list(Reader::value)?;
+        let code = image.list(Reader::op)?;
+        let words = image.list(|image| {
+            Ok(Body {
+                start: image.len()?,
+                end: image.len()?,
+            })
+        })?;
+        let entries = image.list(|image| {
+            let (name, operation) = (image.str()?.to_string(), image.operation()?);
+            let effect = match image.u8()? {
+                0 => None,
+                1 => Some(image.str()?.to_string()),
+                _ => return Err(corrupt()),
+            };
+            Ok((name, operation, effect))
+        })?;
+        let charges = image.list(Reader::len)?;
+        let stacks = image.list(|image| Ok(image.str()?.to_string()))?;
+        let user_cells = image.list(Reader::len)?;
+        if !image.0.is_empty() {
+            return Err(corrupt());
+        }
+
+        let dictionary_len = BUILTINS + entries.len();
+        let valid_op = |op| match op {
+            Op::Call(word) | Op::TailCall(word) => word < words.len(),
+            Op::Rewind(len) => (BUILTINS..=dictionary_len).contains(&len),
+            Op::PushXt(xt) => match usize::try_from(xt) {
+                Ok(word) => word < words.len(),
+                Err(_) => builtin_ops().nth((-1 - xt) as usize).is_some(),
+            },
+            _ => true,
+        };
+        let valid_body = |body: &Body| {
+            body.start <= body.end
+                && body.end <= code.len()
+                && (body.start..body.end).all(|ip| match code[ip] {
+                    Op::Branch(skip) | Op::BranchIfZero(skip) => ip
+                        .checked_add(1)
+                        .and_then(|next| next.checked_add(skip))
+                        .is_some_and(|target| target <= body.end),
+                    op => valid_op(op),
+                })
+        };
+        let valid_entry =
+            |(index, (_, operation, _)): (usize, &(String, Operation, _))| match *operation {
+                Operation::Builtin(op) => valid_op(op),
+                Operation::Address(address) => address < data_space.len(),
+                Operation::UserDefined(word) => word < words.len(),
+                Operation::Control(_) => true,
+                Operation::Marker(entry) => (BUILTINS..=BUILTINS + index).contains(&entry),
+            };
+        if !words.iter().all(valid_body)
+            || !entries.iter().enumerate().all(valid_entry)
+            || !user_cells.iter().all(|&cell| cell < data_space.len())
+            || charges.len() != entries.len()
+        {
+            return Err(corrupt());
+        }
+        let granted = |op| match op {
+            Op::PushXt(xt) if xt < 0 => self
+                .xt_op(xt)
+                .is_some_and(|op| self.capabilities.grants_op(op)),
+            op => self.capabilities.grants_op(op),
+        };
+        let granted_entry = |(_, operation, _): &(String, Operation, _)| match *operation {
+            Operation::Builtin(op) => granted(op),
+            _ => true,
+        };
+        if !code.iter().all(|&op| granted(op)) || !entries.iter().all(granted_entry) {
+            return Err(invalid("image uses words the capabilities don't grant"));
+        }
+
+        let names = Arc::make_mut(&mut self.names);
+        let dictionary = Arc::make_mut(&mut self.dictionary);
+        dictionary.truncate(BUILTINS);
+        for (name, operation, effect) in entries {
+            dictionary.define(names.intern(&name), operation);
+            if let Some(effect) = effect {
+                dictionary.document(Cow::Owned(effect));
+            }
+        }
+        self.data_space = Arc::new(data_space);
+        #[cfg(feature = "tagged")]
+        self.cell_tags.clear();
+        self.code = Arc::new(code);
+        self.words = Arc::new(words);
+        self.sources = Arc::default();
+        self.usage = Usage::new(charges);
+        self.stacks = Stacks::named(stacks);
+        self.tasks = Tasks::with_user_cells(user_cells);
+        #[cfg(feature = "jit")]
+        self.jit.truncate(0);
+        // The counts are by word index, which now means other words.
+        if self.profile.is_some() {
+            self.set_profiling(true);
+        }
+        if self.coverage.is_some() {
+            self.set_coverage(true);
+        }
+        Ok(())
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn corrupt() -> io::Error {
+    invalid("corrupt forth image")
+}
+
+/// Little-endian encoding of an image, with lengths and indices as `u64`.
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, byte: u8) {
+        self.0.push(byte);
+    }
+
+    fn u32(&mut self, n: u32) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn len(&mut self, n: usize) {
+        self.0.extend_from_slice(&(n as u64).to_le_bytes());
+    }
+
+    fn value(&mut self, value: Value) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn str(&mut self, s: &str) {
+        self.len(s.len());
+        self.0.extend_from_slice(s.as_bytes());
+    }
+
+    fn op(&mut self, op: Op) {
+        match op {
+            Op::Push(value) => {
+                self.u8(0);
+                self.value(value);
+            }
+            Op::Primitive(primitive) => {
+                self.u8(1);
+                self.u8(primitive as u8);
+            }
+            Op::Call(word) => {
+                self.u8(2);
+                self.len(word);
+            }
+            Op::TailCall(word) => {
+                self.u8(3);
+                self.len(word);
+            }
+            Op::Branch(skip) => {
+                self.u8(4);
+                self.len(skip);
+            }
+            Op::BranchIfZero(skip) => {
+                self.u8(5);
+                self.len(skip);
+            }
+            Op::Variable => self.u8(6),
+            Op::Forget => self.u8(7),
+            Op::Mark => self.u8(8),
+            Op::Rewind(len) => {
+                self.u8(9);
+                self.len(len);
+            }
+            Op::Disassemble => self.u8(10),
+            Op::Help => self.u8(11),
+            Op::Unknown => self.u8(12),
+            Op::Include => self.u8(19),
+            Op::DefineStack => self.u8(21),
+            Op::Spawn => self.u8(22),
+            Op::Pause => self.u8(23),
+            Op::User => self.u8(24),
+            Op::Synonym => self.u8(26),
+            Op::Tick => self.u8(27),
+            Op::Execute => self.u8(28),
+            Op::PushXt(xt) => {
+                self.u8(29);
+                self.value(xt);
+            }
+            Op::Catch => self.u8(25),
+            #[cfg(feature = "file-io")]
+            Op::File(word) => {
+                self.u8(13);
+                self.u8(word as u8);
+            }
+            #[cfg(feature = "env")]
+            Op::GetEnv => self.u8(14),
+            #[cfg(feature = "env")]
+            Op::SetEnv => self.u8(15),
+            #[cfg(feature = "net")]
+            Op::Net(word) => {
+                self.u8(16);
+                self.u8(word as u8);
+            }
+            #[cfg(feature = "peripherals")]
+            Op::Peripheral(word) => {
+                self.u8(17);
+                self.u8(word as u8);
+            }
+            #[cfg(feature = "dlopen")]
+            Op::Foreign(word) => {
+                self.u8(18);
+                self.u8(word as u8);
+            }
+            #[cfg(feature = "tagged")]
+            Op::PushTagged(value, tag) => {
+                self.u8(match tag {
+                    Tag::Address => 20,
+                    Tag::Char => 30,
+                    Tag::Number | Tag::Xt => unreachable!("these are pushed by ops of their own"),
+                });
+                self.value(value);
+            }
+        }
+    }
+
+    fn operation(&mut self, operation: Operation) {
+        match operation {
+            Operation::Builtin(op) => {
+                self.u8(0);
+                self.op(op);
+            }
+            Operation::Address(address) => {
+                self.u8(1);
+                self.len(address);
+            }
+            Operation::UserDefined(word) => {
+                self.u8(2);
+                self.len(word);
+            }
+            Operation::Control(control) => {
+                self.u8(3);
+                self.u8(match control {
+                    Control::If => 0,
+                    Control::Else => 1,
+                    Control::Then => 2,
+                    Control::Recurse => 3,
+                    Control::Tick => 4,
+                });
+            }
+            Operation::Marker(entry) => {
+                self.u8(4);
+                self.len(entry);
+            }
+        }
+    }
+}
+
+/// Decodes what `Writer` encoded, failing on anything it would not have
+/// written.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < n {
+            return Err(corrupt());
+        }
+        let (taken, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn len(&mut self) -> io::Result<usize> {
+        usize::try_from(u64::from_le_bytes(self.array()?)).map_err(|_| corrupt())
+    }
+
+    fn value(&mut self) -> io::Result<Value> {
+        self.array().map(Value::from_le_bytes)
+    }
+
+    fn str(&mut self) -> io::Result<&'a str> {
+        let len = self.len()?;
+        std::str::from_utf8(self.take(len)?).map_err(|_| corrupt())
+    }
+
+    /// A length followed by that many items.
+    fn list<T>(&mut self, item: impl Fn(&mut Self) -> io::Result<T>) -> io::Result<Vec<T>> {
+        let len = self.len()?;
+        // Every item takes at least a byte, so a corrupt length can't make
+        // this allocate more than the image holds.
+        if len > self.0.len() {
+            return Err(corrupt());
+        }
+        (0..len).map(|_| item(self)).collect()
+    }
+
+    fn op(&mut self) -> io::Result<Op> {
+        Ok(match self.u8()? {
+            0 => Op::Push(self.value()?),
+            1 => {
+                let primitive = Primitive::ALL.get(usize::from(self.u8()?));
+                Op::Primitive(*primitive.ok_or_else(corrupt)?)
+            }
+            2 => Op::Call(self.len()?),
+            3 => Op::TailCall(self.len()?),
+            4 => Op::Branch(self.len()?),
+            5 => Op::BranchIfZero(self.len()?),
+            6 => Op::Variable,
+            7 => Op::Forget,
+            8 => Op::Mark,
+            9 => Op::Rewind(self.len()?),
+            10 => Op::Disassemble,
+            11 => Op::Help,
+            12 => Op::Unknown,
+            19 => Op::Include,
+            21 => Op::DefineStack,
+            22 => Op::Spawn,
+            23 => Op::Pause,
+            24 => Op::User,
+            25 => Op::Catch,
+            26 => Op::Synonym,
+            27 => Op::Tick,
+            28 => Op::Execute,
+            #[cfg(feature = "file-io")]
+            13 => {
+                let word = crate::files::FileWord::ALL.get(usize::from(self.u8()?));
+                Op::File(*word.ok_or_else(corrupt)?)
+            }
+            #[cfg(feature = "env")]
+            14 => Op::GetEnv,
+            #[cfg(feature = "env")]
+            15 => Op::SetEnv,
+            #[cfg(feature = "net")]
+            16 => {
+                let word = crate::net::NetWord::ALL.get(usize::from(self.u8()?));
+                Op::Net(*word.ok_or_else(corrupt)?)
+            }
+            #[cfg(feature = "peripherals")]
+            17 => {
+                let word = crate::peripherals::PeripheralWord::ALL.get(usize::from(self.u8()?));
+                Op::Peripheral(*word.ok_or_else(corrupt)?)
+            }
+            #[cfg(feature = "dlopen")]
+            18 => {
+                let word = crate::foreign::ForeignWord::ALL.get(usize::from(self.u8()?));
+                Op::Foreign(*word.ok_or_else(corrupt)?)
+            }
+            #[cfg(feature = "tagged")]
+            20 => Op::PushTagged(self.value()?, Tag::Address),
+            29 => Op::PushXt(self.value()?),
+            #[cfg(feature = "tagged")]
+            30 => Op::PushTagged(self.value()?, Tag::Char),
+            _ => return Err(corrupt()),
+        })
+    }
+
+    fn operation(&mut self) -> io::Result<Operation> {
+        Ok(match self.u8()? {
+            0 => Operation::Builtin(self.op()?),
+            1 => Operation::Address(self.len()?),
+            2 => Operation::UserDefined(self.len()?),
+            3 => Operation::Control(match self.u8()? {
+                0 => Control::If,
+                1 => Control::Else,
+                2 => Control::Then,
+                3 => Control::Recurse,
+                4 => Control::Tick,
+                _ => return Err(corrupt()),
+            }),
+            4 => Operation::Marker(self.len()?),
+            _ => return Err(corrupt()),
+        })
+    }
+}
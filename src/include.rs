@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use crate::diagnostics::commands;
+use crate::vm::Input;
+use crate::{Error, Forth, Result};
+
+/// How deep `evaluate` and `include` may nest within each other.
+pub(crate) const MAX_NESTING: usize = 64;
+
+type Resolve = dyn Fn(&str) -> Option<String> + Send + Sync;
+
+/// What `include` finds sources with, if anything, which clones of the
+/// interpreter share.
+#[derive(Clone, Default)]
+pub(crate) struct Resolver(Option<Arc<Resolve>>);
+
+impl std::fmt::Debug for Resolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Resolver").field(&self.0.is_some()).finish()
+    }
+}
+
+impl Resolver {
+    pub(crate) fn new(
+        resolve: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    ) -> Resolver {
+        Resolver(Some(Arc::new(resolve)))
+    }
+
+    pub(crate) fn is_set(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+impl Forth {
+    /// Makes `include name` evaluate the source `resolve` returns for
+    /// `name`, as written, so that a prelude can be split into modules kept
+    /// wherever the host likes. Without a resolver, or when it returns
+    /// `None`, `include` fails with `Error::IncludeNotFound`.
+    pub fn set_include_resolver(
+        &mut self,
+        resolve: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    ) {
+        self.resolver = Resolver::new(resolve);
+    }
+
+    /// Runs `include`, which parses the name of the source to evaluate.
+    pub(crate) fn include(&mut self, input: &mut Input<'_>) -> Result {
+        let name = input.name().ok_or(Error::InvalidWord)?;
+        let resolve = self.resolver.0.clone().ok_or(Error::IncludeNotFound)?;
+        let source = resolve(&name).ok_or(Error::IncludeNotFound)?;
+        self.evaluate_nested(&source, Some(name.into_owned()))
+    }
+
+    /// `evaluate ( addr u -- )`, which evaluates the text held in data
+    /// space from `addr` on, a byte to a cell.
+    pub(crate) fn do_evaluate(&mut self) -> Result {
+        let len = self.pop()?;
+        let address = self.pop_address()?;
+        let buffer = self.buffer(address, len)?;
+        let bytes: Vec<u8> = self.data_space[buffer]
+            .iter()
+            .map(|&cell| cell as u8)
+            .collect();
+        let text = String::from_utf8(bytes).map_err(|_| Error::InvalidWord)?;
+        self.evaluate_nested(&text, None)
+    }
+
+    /// Evaluates `text` as `eval` would, from within the word running. The
+    /// failure of any command is that of the word; the commands before it
+    /// keep their effects. Definitions it makes are located in the source
+    /// `name`, if it has one.
+    fn evaluate_nested(&mut self, text: &str, name: Option<String>) -> Result {
+        if self.nesting == MAX_NESTING {
+            return Err(Error::ReturnStackOverflow);
+        }
+        self.nesting += 1;
+        let outer = std::mem::replace(&mut self.source_name, name);
+        let outcome = self
+            .check_syntax(text)
+            .map_err(|located| located.fault.error)
+            .and_then(|()| {
+                commands(text).try_for_each(|command| {
+                    let command =
+                        command.map_err(|malformed| self.malformed(malformed).fault.error)?;
+                    self.locate(text, command);
+                    let outcome = self.eval_command(&text[command.start..command.end]);
+                    self.locating = None;
+                    outcome.map_err(|fault| fault.error)
+                })
+            });
+        self.source_name = outer;
+        self.nesting -= 1;
+        outcome
+    }
+}
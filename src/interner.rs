@@ -0,0 +1,39 @@
+/// Hash maps keyed by names and symbols. The `fxhash` feature trades the
+/// default DoS-resistant hasher for a much faster one.
+#[cfg(feature = "fxhash")]
+pub(crate) type HashMap<K, V> = std::collections::HashMap<K, V, rustc_hash::FxBuildHasher>;
+#[cfg(not(feature = "fxhash"))]
+pub(crate) type HashMap<K, V> = std::collections::HashMap<K, V>;
+
+/// Small integer standing for an interned word name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct Symbol(u32);
+
+/// Maps word names to symbols and back. Names are never removed, so a symbol
+/// stays valid for the life of the interpreter and its clones.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Interner {
+    names: Vec<String>,
+    symbols: HashMap<String, Symbol>,
+}
+
+impl Interner {
+    pub(crate) fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(&symbol) = self.symbols.get(name) {
+            return symbol;
+        }
+        let symbol = Symbol(self.names.len() as u32);
+        self.names.push(name.to_string());
+        self.symbols.insert(name.to_string(), symbol);
+        symbol
+    }
+
+    /// The symbol of `name` if it was interned before; never allocates.
+    pub(crate) fn get(&self, name: &str) -> Option<Symbol> {
+        self.symbols.get(name).copied()
+    }
+
+    pub(crate) fn resolve(&self, symbol: Symbol) -> &str {
+        &self.names[symbol.0 as usize]
+    }
+}
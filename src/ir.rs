@@ -0,0 +1,283 @@
+use std::collections::BTreeSet;
+
+use crate::bytecode::Op;
+#[cfg(feature = "tagged")]
+use crate::Tag;
+use crate::{effects, Forth, Token, Value};
+
+/// The compiled code of every user-defined word, as `Forth::ir` gives it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ir {
+    /// In the order they were defined, redefined words included, so that
+    /// `IrOp::Call` can number them.
+    pub words: Vec<IrWord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrWord {
+    pub name: String,
+    /// Values the word takes and leaves, if its code tells.
+    pub effect: Option<(usize, usize)>,
+    /// The first runs when the word is called.
+    pub blocks: Vec<Block>,
+}
+
+/// Ops that run one after the other, then go where `exit` says.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub ops: Vec<IrOp>,
+    pub exit: Exit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IrOp {
+    Push(Value),
+    /// Pushes the address of a variable, tagged as one. Without the `tagged`
+    /// feature addresses are pushed as numbers are, and so are characters.
+    Address(Value),
+    /// Pushes a character literal, tagged as one.
+    Char(Value),
+    /// Pushes the execution token of the word with this index into
+    /// `Ir::words`, or of the builtin `name` if there is none, as `[']`
+    /// does.
+    Xt {
+        word: Option<usize>,
+        name: String,
+    },
+    /// Runs the builtin `name`, as `dis` names it, which takes and leaves
+    /// `effect` values if that is fixed.
+    Builtin {
+        name: String,
+        effect: Option<(usize, usize)>,
+    },
+    /// Calls the word with this index into `Ir::words`.
+    Call {
+        word: usize,
+        name: String,
+    },
+    /// Fails with `Error::UnknownWord`, in place of a word that was not
+    /// defined when the body was compiled.
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// Returns from the word.
+    Return,
+    /// Goes on to the block with this index.
+    Jump(usize),
+    /// Pops a flag, going on to `then` unless it is zero, and to
+    /// `otherwise` if it is.
+    If { then: usize, otherwise: usize },
+}
+
+/// The words for the builtin `name` where it isn't one: `floored /` is `/`
+/// under `Division::Floored`, the only place it is compiled, and `/mod` is
+/// `sm/rem`, which stays symmetric there.
+fn word_for(name: &str) -> &str {
+    match name {
+        "/mod" => "sm/rem",
+        "floored /" => "/",
+        "floored mod" => "mod",
+        name => name,
+    }
+}
+
+impl Forth {
+    /// The compiled code of the user-defined words, split into basic blocks
+    /// with the calls they make resolved, for tools that analyse or
+    /// transform programs. `Ir::tokens` gives a word back as a body.
+    pub fn ir(&self) -> Ir {
+        Ir {
+            words: (0..self.words.len())
+                .map(|word| {
+                    let name = self.op_name(Op::Call(word));
+                    self.ir_word(&name, word, self.body(word))
+                })
+                .collect(),
+        }
+    }
+
+    /// The IR of `code`, the body of the word `name` with index `word`.
+    pub(crate) fn ir_word(&self, name: &str, word: usize, code: &[Op]) -> IrWord {
+        // Where blocks start, the end included if a branch lands there.
+        let mut starts = BTreeSet::new();
+        for (i, op) in code.iter().enumerate() {
+            match *op {
+                Op::Branch(skip) | Op::BranchIfZero(skip) => {
+                    starts.insert(i + 1);
+                    starts.insert(i + 1 + skip);
+                }
+                _ => {}
+            }
+        }
+        let lands_at_end = code.iter().enumerate().any(|(i, op)| {
+            matches!(*op, Op::Branch(skip) | Op::BranchIfZero(skip) if i + 1 + skip == code.len())
+        });
+        if !lands_at_end {
+            starts.remove(&code.len());
+        }
+        starts.insert(0);
+        let starts: Vec<usize> = starts.into_iter().collect();
+        let block = |offset: usize| starts.binary_search(&offset).expect("a block starts there");
+        let mut blocks = Vec::with_capacity(starts.len());
+        for (index, &start) in starts.iter().enumerate() {
+            let end = starts.get(index + 1).copied().unwrap_or(code.len());
+            let next = match index + 1 < starts.len() {
+                true => Exit::Jump(index + 1),
+                false => Exit::Return,
+            };
+            let (ops, exit) = match code[start..end].split_last() {
+                Some((&Op::Branch(skip), ops)) => (ops, Exit::Jump(block(end + skip))),
+                Some((&Op::BranchIfZero(skip), ops)) => (
+                    ops,
+                    Exit::If {
+                        then: index + 1,
+                        otherwise: block(end + skip),
+                    },
+                ),
+                _ => (&code[start..end], next),
+            };
+            blocks.push(Block {
+                ops: ops.iter().map(|&op| self.ir_op(op)).collect(),
+                exit,
+            });
+        }
+        IrWord {
+            name: name.to_string(),
+            effect: self.inferred_effect(word, code),
+            blocks,
+        }
+    }
+
+    fn ir_op(&self, op: Op) -> IrOp {
+        match op {
+            Op::Push(value) => IrOp::Push(value),
+            #[cfg(feature = "tagged")]
+            Op::PushTagged(value, tag) => match tag {
+                Tag::Address => IrOp::Address(value),
+                _ => IrOp::Char(value),
+            },
+            Op::PushXt(xt) => {
+                let op = self.xt_op(xt).unwrap_or(Op::Unknown);
+                IrOp::Xt {
+                    word: usize::try_from(xt).ok(),
+                    name: self.op_name(op).into_owned(),
+                }
+            }
+            Op::Call(word) | Op::TailCall(word) => IrOp::Call {
+                word,
+                name: self.op_name(op).into_owned(),
+            },
+            Op::Unknown => IrOp::Unknown,
+            op => IrOp::Builtin {
+                name: self.op_name(op).into_owned(),
+                effect: effects::effect(op),
+            },
+        }
+    }
+}
+
+impl Ir {
+    /// The body of the word with index `word`, for `Program::definition`, or,
+    /// if it has no branches, `Forth::eval_tokens`. Blocks give back the
+    /// `if`, `else` and `then` they were compiled from, in order, so blocks
+    /// that don't nest as those do give a body that fails to compile.
+    /// Calls of other words, and their execution tokens, are by name, so
+    /// should find the same words where the body is compiled; addresses and
+    /// characters are plain numbers.
+    pub fn tokens(&self, word: usize) -> Vec<Token> {
+        let blocks = &self.words[word].blocks;
+        // Whether the block ends with the jump of an `else`.
+        let jumps_over =
+            |index: usize| matches!(blocks[index].exit, Exit::Jump(target) if target != index + 1);
+        let mut tokens = Vec::new();
+        for (index, block) in blocks.iter().enumerate() {
+            for (from, other) in blocks[..index].iter().enumerate() {
+                let then = match other.exit {
+                    Exit::Jump(target) => target == index && jumps_over(from),
+                    Exit::If { otherwise, .. } => otherwise == index && !jumps_over(index - 1),
+                    Exit::Return => false,
+                };
+                if then {
+                    tokens.push(Token::word("then"));
+                }
+            }
+            for op in &block.ops {
+                match op {
+                    IrOp::Push(value) | IrOp::Address(value) | IrOp::Char(value) => {
+                        tokens.push(Token::Number(*value))
+                    }
+                    IrOp::Xt { name, .. } => {
+                        tokens.push(Token::word("[']"));
+                        tokens.extend(word_for(name).split(' ').map(Token::word));
+                    }
+                    IrOp::Builtin { name, .. } => {
+                        tokens.extend(word_for(name).split(' ').map(Token::word))
+                    }
+                    IrOp::Call { word: callee, .. } if *callee == word => {
+                        tokens.push(Token::word("recurse"))
+                    }
+                    IrOp::Call { name, .. } => tokens.push(Token::word(name)),
+                    IrOp::Unknown => tokens.push(Token::word("unknown")),
+                }
+            }
+            match block.exit {
+                Exit::If { .. } => tokens.push(Token::word("if")),
+                Exit::Jump(_) if jumps_over(index) => tokens.push(Token::word("else")),
+                _ => {}
+            }
+        }
+        tokens
+    }
+
+    /// The words as a JSON array of objects with their `name`, `effect` as
+    /// `[taken, left]` or `null`, and `blocks`, each with its `ops` and its
+    /// `exit`. Ops are `{"push": n}`, `{"address": n}`, `{"char": n}`,
+    /// `{"xt": index or null, "name": name}`, `{"builtin": name, "effect": ...}`,
+    /// `{"call": index, "name": name}` or `"unknown"`; exits are `"return"`,
+    /// `{"jump": index}` or `{"if": [then, otherwise]}`.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        use serde_json::{json, Value as Json};
+        let effect = |effect: Option<(usize, usize)>| match effect {
+            Some((taken, left)) => json!([taken, left]),
+            None => Json::Null,
+        };
+        let words: Vec<Json> = self
+            .words
+            .iter()
+            .map(|word| {
+                let blocks: Vec<Json> = word
+                    .blocks
+                    .iter()
+                    .map(|block| {
+                        let ops: Vec<Json> = block
+                            .ops
+                            .iter()
+                            .map(|op| match op {
+                                IrOp::Push(value) => json!({ "push": value }),
+                                IrOp::Address(address) => json!({ "address": address }),
+                                IrOp::Char(c) => json!({ "char": c }),
+                                IrOp::Xt { word, name } => json!({ "xt": word, "name": name }),
+                                IrOp::Builtin { name, effect: e } => {
+                                    json!({ "builtin": name, "effect": effect(*e) })
+                                }
+                                IrOp::Call { word, name } => json!({ "call": word, "name": name }),
+                                IrOp::Unknown => json!("unknown"),
+                            })
+                            .collect();
+                        let exit = match block.exit {
+                            Exit::Return => json!("return"),
+                            Exit::Jump(target) => json!({ "jump": target }),
+                            Exit::If { then, otherwise } => json!({ "if": [then, otherwise] }),
+                        };
+                        json!({ "ops": ops, "exit": exit })
+                    })
+                    .collect();
+                json!({ "name": word.name, "effect": effect(word.effect), "blocks": blocks })
+            })
+            .collect();
+        Json::Array(words).to_string()
+    }
+}
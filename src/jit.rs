@@ -0,0 +1,658 @@
+//! Native code for hot user-defined words, behind the `jit` feature.
+//!
+//! A word is compiled once it has been called `HOT_CALLS` times, provided its
+//! body only does arithmetic, stack shuffles and branches on the data stack;
+//! anything else, such as calls to other words or variables, keeps it in the
+//! interpreter. Native code reads and writes the stack in place and stops
+//! exactly where the interpreter would on an error, so both leave the same
+//! stack behind. It is counted as one word executed per call. With the
+//! `observers` feature every word is interpreted.
+
+use cranelift_codegen::ir::condcodes::IntCC;
+use cranelift_codegen::ir::{self, types, AbiParam, InstBuilder, MemFlagsData, Type, UserFuncName};
+use cranelift_codegen::isa::TargetFrontendConfig;
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, Linkage, Module};
+
+use crate::bytecode::{Body, Op, Primitive};
+use crate::stack::Values;
+use crate::{Division, Error, Forth, Value};
+
+/// Calls after which a word is compiled.
+const HOT_CALLS: u32 = 16;
+
+const OK: u32 = 0;
+const STACK_UNDERFLOW: u32 = 1;
+const DIVISION_BY_ZERO: u32 = 2;
+const OVERFLOW: u32 = 3;
+
+/// The data stack as native code sees it. `len` is only written back to
+/// `values` when native code returns or has to grow the stack, and
+/// `lowest`, the lowest `len` got, only when it returns.
+#[repr(C)]
+struct RawStack {
+    ptr: *mut Value,
+    len: usize,
+    cap: usize,
+    values: *mut Values,
+    lowest: usize,
+}
+
+type NativeFn = unsafe extern "C" fn(*mut RawStack) -> u32;
+
+/// Makes room for at least `extra` more values.
+extern "C" fn grow(stack: *mut RawStack, extra: usize) {
+    // SAFETY: only called from native code, with the `RawStack` it was
+    // given, whose `values` outlives the call.
+    let stack = unsafe { &mut *stack };
+    let values = unsafe { &mut *stack.values };
+    unsafe { values.set_len(stack.len) };
+    values.reserve(extra);
+    stack.ptr = values.as_mut_ptr();
+    stack.cap = values.capacity();
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Slot {
+    Cold(u32),
+    Native(NativeFn),
+    Unsupported,
+}
+
+/// Compiled words of one interpreter, indexed like `Forth::words`.
+#[derive(Default)]
+pub(crate) struct Jit {
+    module: Option<JITModule>,
+    slots: Vec<Slot>,
+}
+
+impl std::fmt::Debug for Jit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Jit").field("slots", &self.slots).finish()
+    }
+}
+
+/// Native code can't be shared between interpreters, so a clone starts over
+/// and compiles its own hot words.
+impl Clone for Jit {
+    fn clone(&self) -> Self {
+        Jit::default()
+    }
+}
+
+// SAFETY: the module is only touched through `&mut Jit`, and the code it owns
+// doesn't refer to any thread-local state.
+unsafe impl Send for Jit {}
+unsafe impl Sync for Jit {}
+
+impl Drop for Jit {
+    fn drop(&mut self) {
+        if let Some(module) = self.module.take() {
+            // SAFETY: the functions are only reachable through `slots`, which
+            // go away with `self`.
+            unsafe { module.free_memory() };
+        }
+    }
+}
+
+impl Jit {
+    /// Forgets words from `words` on, which `Forth::restore` took away.
+    pub(crate) fn truncate(&mut self, words: usize) {
+        self.slots.truncate(words);
+    }
+}
+
+impl Forth {
+    /// Runs `word` natively if it is hot and can be compiled. Returns whether
+    /// it did; if not, the interpreter has to run it.
+    pub(crate) fn run_native(&mut self, word: usize) -> std::result::Result<bool, Error> {
+        // Native code doesn't report the pushes and pops observers expect,
+        // nor the words it runs, and doesn't keep tags.
+        if cfg!(feature = "observers")
+            || cfg!(feature = "tagged")
+            || self.trace
+            || self.profile.is_some()
+            || self.coverage.is_some()
+            || self.timeline.is_some()
+        {
+            return Ok(false);
+        }
+        let slots = &mut self.jit.slots;
+        if slots.len() <= word {
+            slots.resize(word + 1, Slot::Cold(0));
+        }
+        let native = match slots[word] {
+            Slot::Native(native) => native,
+            Slot::Unsupported => return Ok(false),
+            Slot::Cold(calls) if calls + 1 < HOT_CALLS => {
+                slots[word] = Slot::Cold(calls + 1);
+                return Ok(false);
+            }
+            Slot::Cold(_) => {
+                let slot = self
+                    .compile_native(word)
+                    .map_or(Slot::Unsupported, Slot::Native);
+                self.jit.slots[word] = slot;
+                match slot {
+                    Slot::Native(native) => native,
+                    _ => return Ok(false),
+                }
+            }
+        };
+        let values = self.stack.values_mut();
+        let mut raw = RawStack {
+            ptr: values.as_mut_ptr(),
+            len: values.len(),
+            cap: values.capacity(),
+            values,
+            lowest: values.len(),
+        };
+        // SAFETY: `native` was compiled from a body checked by `compile_native`
+        // and only accesses `raw`, which describes a live stack buffer.
+        let status = unsafe { native(&mut raw) };
+        unsafe { (*raw.values).set_len(raw.len) };
+        let lowest = self.stack.lowest().min(raw.lowest);
+        self.stack.set_lowest(lowest);
+        let max = &mut self.metrics.max_stack_depth;
+        *max = (*max).max(raw.len);
+        let max = &mut self.run_stats.max_stack_depth;
+        *max = (*max).max(raw.len);
+        match status {
+            OK => Ok(true),
+            STACK_UNDERFLOW => Err(Error::StackUnderflow),
+            DIVISION_BY_ZERO => Err(Error::DivisionByZero),
+            OVERFLOW => Err(Error::Overflow),
+            _ => unreachable!("native code returns a known status"),
+        }
+    }
+
+    fn compile_native(&mut self, word: usize) -> Option<NativeFn> {
+        let Body { start, end } = self.words[word];
+        let code = &self.code[start..end];
+        if !code.iter().all(|op| supported(op, word)) {
+            return None;
+        }
+        if self.jit.module.is_none() {
+            self.jit.module = Some(new_module()?);
+        }
+        let module = self.jit.module.as_mut()?;
+        let mut context = module.make_context();
+        let pointer = module.target_config().pointer_type();
+        context.func.signature.params.push(AbiParam::new(pointer));
+        context
+            .func
+            .signature
+            .returns
+            .push(AbiParam::new(types::I32));
+        let id = module
+            .declare_function(
+                &format!("word{word}"),
+                Linkage::Local,
+                &context.func.signature,
+            )
+            .ok()?;
+        context.func.name = UserFuncName::user(0, id.as_u32());
+        let mut builder_context = FunctionBuilderContext::new();
+        let builder = FunctionBuilder::new(&mut context.func, &mut builder_context);
+        Codegen::new(builder, module.target_config()).body(code);
+        module.define_function(id, &mut context).ok()?;
+        module.finalize_definitions().ok()?;
+        let address = module.get_finalized_function(id);
+        // SAFETY: the function was declared with the signature of `NativeFn`.
+        Some(unsafe { std::mem::transmute::<*const u8, NativeFn>(address) })
+    }
+}
+
+fn new_module() -> Option<JITModule> {
+    let mut flags = settings::builder();
+    flags.set("use_colocated_libcalls", "false").ok()?;
+    flags.set("is_pic", "false").ok()?;
+    let isa = cranelift_native::builder()
+        .ok()?
+        .finish(settings::Flags::new(flags))
+        .ok()?;
+    Some(JITModule::new(JITBuilder::with_isa(
+        isa,
+        default_libcall_names(),
+    )))
+}
+
+fn supported(op: &Op, word: usize) -> bool {
+    match op {
+        Op::Push(_) | Op::PushXt(_) | Op::Branch(_) | Op::BranchIfZero(_) => true,
+        Op::TailCall(callee) => *callee == word,
+        Op::Primitive(primitive) => !matches!(
+            primitive,
+            Primitive::Fetch
+                | Primitive::Store
+                | Primitive::TestOpen
+                | Primitive::TestArrow
+                | Primitive::TestClose
+                | Primitive::TestSummary
+                | Primitive::Send
+                | Primitive::Receive
+                | Primitive::TryReceive
+                | Primitive::Utime
+                | Primitive::Elapsed
+                | Primitive::Evaluate
+                | Primitive::ToStack
+                | Primitive::FromStack
+                | Primitive::Tasks
+                | Primitive::Throw
+                | Primitive::Emit
+                | Primitive::Random
+                | Primitive::Hex
+                | Primitive::Decimal
+        ),
+        Op::Call(_)
+        | Op::Catch
+        | Op::Variable
+        | Op::Forget
+        | Op::Mark
+        | Op::DefineStack
+        | Op::Spawn
+        | Op::Pause
+        | Op::User
+        | Op::Tick
+        | Op::Execute
+        | Op::Synonym
+        | Op::Rewind(_)
+        | Op::Disassemble
+        | Op::Help
+        | Op::Include
+        | Op::Unknown => false,
+        #[cfg(feature = "file-io")]
+        Op::File(_) => false,
+        #[cfg(feature = "env")]
+        Op::GetEnv | Op::SetEnv => false,
+        #[cfg(feature = "net")]
+        Op::Net(_) => false,
+        #[cfg(feature = "peripherals")]
+        Op::Peripheral(_) => false,
+        #[cfg(feature = "dlopen")]
+        Op::Foreign(_) => false,
+        #[cfg(feature = "tagged")]
+        Op::PushTagged(..) => false,
+    }
+}
+
+/// Emits one function running a definition body against a `RawStack`.
+struct Codegen<'a> {
+    b: FunctionBuilder<'a>,
+    config: TargetFrontendConfig,
+    pointer: Type,
+    raw: Variable,
+    ptr: Variable,
+    len: Variable,
+    cap: Variable,
+    lowest: Variable,
+    status: Variable,
+    exit: ir::Block,
+}
+
+impl<'a> Codegen<'a> {
+    fn new(mut b: FunctionBuilder<'a>, config: TargetFrontendConfig) -> Codegen<'a> {
+        let pointer = config.pointer_type();
+        let raw = b.declare_var(pointer);
+        let ptr = b.declare_var(pointer);
+        let len = b.declare_var(pointer);
+        let cap = b.declare_var(pointer);
+        let lowest = b.declare_var(pointer);
+        let status = b.declare_var(types::I32);
+        let exit = b.create_block();
+        Codegen {
+            b,
+            config,
+            pointer,
+            raw,
+            ptr,
+            len,
+            cap,
+            lowest,
+            status,
+            exit,
+        }
+    }
+
+    fn offset(&self, field: i32) -> i32 {
+        field * self.pointer.bytes() as i32
+    }
+
+    fn body(mut self, code: &[Op]) {
+        let entry = self.b.create_block();
+        self.b.append_block_params_for_function_params(entry);
+        self.b.switch_to_block(entry);
+        let raw = self.b.block_params(entry)[0];
+        self.b.def_var(self.raw, raw);
+        self.load_buffer();
+        let len = self.load(1);
+        self.b.def_var(self.len, len);
+        self.b.def_var(self.lowest, len);
+        let ops: Vec<_> = (0..=code.len()).map(|_| self.b.create_block()).collect();
+        self.b.ins().jump(ops[0], &[]);
+
+        for (ip, &op) in code.iter().enumerate() {
+            self.b.switch_to_block(ops[ip]);
+            let next = ops[ip + 1];
+            match op {
+                Op::Push(value) | Op::PushXt(value) => {
+                    self.reserve(1);
+                    let value = self.b.ins().iconst(types::I32, i64::from(value));
+                    self.push(value);
+                }
+                Op::Primitive(primitive) => self.primitive(primitive),
+                Op::Branch(offset) => {
+                    self.b.ins().jump(ops[ip + 1 + offset], &[]);
+                    continue;
+                }
+                Op::BranchIfZero(offset) => {
+                    let flag = self.pop();
+                    self.b
+                        .ins()
+                        .brif(flag, next, &[], ops[ip + 1 + offset], &[]);
+                    continue;
+                }
+                Op::TailCall(_) => {
+                    self.b.ins().jump(ops[0], &[]);
+                    continue;
+                }
+                Op::Call(_)
+                | Op::Catch
+                | Op::Variable
+                | Op::Forget
+                | Op::Mark
+                | Op::DefineStack
+                | Op::Spawn
+                | Op::Pause
+                | Op::User
+                | Op::Tick
+                | Op::Execute
+                | Op::Synonym
+                | Op::Rewind(_)
+                | Op::Disassemble
+                | Op::Help
+                | Op::Include
+                | Op::Unknown => {
+                    unreachable!("filtered out by `supported`")
+                }
+                #[cfg(feature = "file-io")]
+                Op::File(_) => unreachable!("filtered out by `supported`"),
+                #[cfg(feature = "env")]
+                Op::GetEnv | Op::SetEnv => unreachable!("filtered out by `supported`"),
+                #[cfg(feature = "net")]
+                Op::Net(_) => unreachable!("filtered out by `supported`"),
+                #[cfg(feature = "peripherals")]
+                Op::Peripheral(_) => unreachable!("filtered out by `supported`"),
+                #[cfg(feature = "dlopen")]
+                Op::Foreign(_) => unreachable!("filtered out by `supported`"),
+                #[cfg(feature = "tagged")]
+                Op::PushTagged(..) => unreachable!("filtered out by `supported`"),
+            }
+            self.b.ins().jump(next, &[]);
+        }
+
+        self.b.switch_to_block(ops[code.len()]);
+        self.leave(OK);
+
+        self.b.switch_to_block(self.exit);
+        let len = self.b.use_var(self.len);
+        self.store_len(len);
+        let (raw, lowest) = (self.b.use_var(self.raw), self.b.use_var(self.lowest));
+        let offset = self.offset(4);
+        self.b
+            .ins()
+            .store(MemFlagsData::trusted(), lowest, raw, offset);
+        let status = self.b.use_var(self.status);
+        self.b.ins().return_(&[status]);
+        self.b.seal_all_blocks();
+        let config = self.config;
+        self.b.finalize(config);
+    }
+
+    fn primitive(&mut self, primitive: Primitive) {
+        match primitive {
+            Primitive::Add => self.binary(|b, x, y| b.ins().sadd_overflow(x, y)),
+            Primitive::Subtract => self.binary(|b, x, y| b.ins().ssub_overflow(x, y)),
+            Primitive::Multiply => self.binary(|b, x, y| b.ins().smul_overflow(x, y)),
+            Primitive::Divide
+            | Primitive::Modulo
+            | Primitive::DivMod
+            | Primitive::FlooredDivide
+            | Primitive::FlooredModulo
+            | Primitive::FlooredDivMod => {
+                let a = self.pop_nonzero();
+                let b = self.pop();
+                // `MIN / -1` doesn't fit, and `sdiv` would trap on it.
+                let minus_one = self.b.ins().icmp_imm_s(IntCC::Equal, a, -1);
+                let min = self.b.ins().icmp_imm_s(IntCC::Equal, b, Value::MIN as i64);
+                let overflow = self.b.ins().band(minus_one, min);
+                self.fail_if(overflow, OVERFLOW);
+                let mut quotient = self.b.ins().sdiv(b, a);
+                let mut remainder = self.b.ins().srem(b, a);
+                if primitive.division() == Some(Division::Floored) {
+                    // Round down rather than towards zero when the remainder
+                    // and the divisor differ in sign.
+                    let signs = self.b.ins().bxor(remainder, a);
+                    let differ = self.b.ins().icmp_imm_s(IntCC::SignedLessThan, signs, 0);
+                    let inexact = self.b.ins().icmp_imm_s(IntCC::NotEqual, remainder, 0);
+                    let adjust = self.b.ins().band(differ, inexact);
+                    let down = self.b.ins().iadd_imm_s(quotient, -1);
+                    quotient = self.b.ins().select(adjust, down, quotient);
+                    let up = self.b.ins().iadd(remainder, a);
+                    remainder = self.b.ins().select(adjust, up, remainder);
+                }
+                match primitive {
+                    Primitive::Divide | Primitive::FlooredDivide => self.push(quotient),
+                    Primitive::Modulo | Primitive::FlooredModulo => self.push(remainder),
+                    _ => {
+                        self.push(remainder);
+                        self.push(quotient);
+                    }
+                }
+            }
+            Primitive::WrappingAdd => self.wrapping(|b, x, y| b.ins().iadd(x, y)),
+            Primitive::WrappingSubtract => self.wrapping(|b, x, y| b.ins().isub(x, y)),
+            Primitive::WrappingMultiply => self.wrapping(|b, x, y| b.ins().imul(x, y)),
+            Primitive::Dup => {
+                let a = self.pop();
+                self.reserve(2);
+                self.push(a);
+                self.push(a);
+            }
+            Primitive::Drop => {
+                self.pop();
+            }
+            Primitive::Swap => {
+                let a = self.pop();
+                let b = self.pop();
+                self.push(a);
+                self.push(b);
+            }
+            Primitive::Over => {
+                let a = self.pop();
+                let b = self.pop();
+                self.reserve(3);
+                self.push(b);
+                self.push(a);
+                self.push(b);
+            }
+            Primitive::Square => {
+                let a = self.pop();
+                let value = self.checked(|b| b.ins().smul_overflow(a, a));
+                self.push(value);
+            }
+            Primitive::SwapSubtract => self.binary(|b, x, y| b.ins().ssub_overflow(y, x)),
+            Primitive::OverAdd => {
+                let a = self.pop();
+                let b = self.pop();
+                self.push(b);
+                let value = self.checked(|f| f.ins().sadd_overflow(b, a));
+                self.push(value);
+            }
+            Primitive::Fetch
+            | Primitive::Store
+            | Primitive::TestOpen
+            | Primitive::TestArrow
+            | Primitive::TestClose
+            | Primitive::TestSummary
+            | Primitive::Send
+            | Primitive::Receive
+            | Primitive::TryReceive
+            | Primitive::Utime
+            | Primitive::Elapsed
+            | Primitive::Evaluate
+            | Primitive::ToStack
+            | Primitive::FromStack
+            | Primitive::Tasks
+            | Primitive::Throw
+            | Primitive::Emit
+            | Primitive::Random
+            | Primitive::Hex
+            | Primitive::Decimal => unreachable!("filtered out by `supported`"),
+        }
+    }
+
+    /// Pops `a`, then `b`, and pushes `f(b, a)`, failing if it overflows.
+    fn binary(
+        &mut self,
+        f: impl FnOnce(&mut FunctionBuilder<'a>, ir::Value, ir::Value) -> (ir::Value, ir::Value),
+    ) {
+        let a = self.pop();
+        let b = self.pop();
+        let value = self.checked(|builder| f(builder, b, a));
+        self.push(value);
+    }
+
+    /// Pops `a`, then `b`, and pushes `f(b, a)`.
+    fn wrapping(
+        &mut self,
+        f: impl FnOnce(&mut FunctionBuilder<'a>, ir::Value, ir::Value) -> ir::Value,
+    ) {
+        let a = self.pop();
+        let b = self.pop();
+        let value = f(&mut self.b, b, a);
+        self.push(value);
+    }
+
+    /// The value of an `*_overflow` instruction from `f`, leaving with
+    /// `OVERFLOW` if its flag is set.
+    fn checked(
+        &mut self,
+        f: impl FnOnce(&mut FunctionBuilder<'a>) -> (ir::Value, ir::Value),
+    ) -> ir::Value {
+        let (value, overflow) = f(&mut self.b);
+        self.fail_if(overflow, OVERFLOW);
+        value
+    }
+
+    fn load(&mut self, field: i32) -> ir::Value {
+        let (raw, offset) = (self.b.use_var(self.raw), self.offset(field));
+        self.b
+            .ins()
+            .load(self.pointer, MemFlagsData::trusted(), raw, offset)
+    }
+
+    fn store_len(&mut self, len: ir::Value) {
+        let (raw, offset) = (self.b.use_var(self.raw), self.offset(1));
+        self.b
+            .ins()
+            .store(MemFlagsData::trusted(), len, raw, offset);
+    }
+
+    fn load_buffer(&mut self) {
+        let ptr = self.load(0);
+        self.b.def_var(self.ptr, ptr);
+        let cap = self.load(2);
+        self.b.def_var(self.cap, cap);
+    }
+
+    fn address(&mut self, index: ir::Value) -> ir::Value {
+        let ptr = self.b.use_var(self.ptr);
+        let bytes = self
+            .b
+            .ins()
+            .imul_imm_s(index, std::mem::size_of::<Value>() as i64);
+        self.b.ins().iadd(ptr, bytes)
+    }
+
+    /// Sets `status` and jumps to the exit.
+    fn leave(&mut self, status: u32) {
+        let status = self.b.ins().iconst(types::I32, i64::from(status));
+        self.b.def_var(self.status, status);
+        self.b.ins().jump(self.exit, &[]);
+    }
+
+    /// Jumps to the exit with `status` if `condition` holds.
+    fn fail_if(&mut self, condition: ir::Value, status: u32) {
+        let (failed, ok) = (self.b.create_block(), self.b.create_block());
+        self.b.ins().brif(condition, failed, &[], ok, &[]);
+        self.b.switch_to_block(failed);
+        self.leave(status);
+        self.b.switch_to_block(ok);
+    }
+
+    fn pop(&mut self) -> ir::Value {
+        let len = self.b.use_var(self.len);
+        let empty = self.b.ins().icmp_imm_s(IntCC::Equal, len, 0);
+        self.fail_if(empty, STACK_UNDERFLOW);
+        let len = self.b.ins().iadd_imm_s(len, -1);
+        self.b.def_var(self.len, len);
+        let lowest = self.b.use_var(self.lowest);
+        let lowest = self.b.ins().umin(lowest, len);
+        self.b.def_var(self.lowest, lowest);
+        let address = self.address(len);
+        self.b
+            .ins()
+            .load(types::I32, MemFlagsData::trusted(), address, 0)
+    }
+
+    fn pop_nonzero(&mut self) -> ir::Value {
+        let value = self.pop();
+        let zero = self.b.ins().icmp_imm_s(IntCC::Equal, value, 0);
+        self.fail_if(zero, DIVISION_BY_ZERO);
+        value
+    }
+
+    /// Pushes without checking for room, see `reserve`.
+    fn push(&mut self, value: ir::Value) {
+        let len = self.b.use_var(self.len);
+        let address = self.address(len);
+        self.b
+            .ins()
+            .store(MemFlagsData::trusted(), value, address, 0);
+        let len = self.b.ins().iadd_imm_s(len, 1);
+        self.b.def_var(self.len, len);
+    }
+
+    /// Makes sure `extra` more values can be pushed, calling `grow` if not.
+    fn reserve(&mut self, extra: i64) {
+        let (len, cap) = (self.b.use_var(self.len), self.b.use_var(self.cap));
+        let room = self.b.ins().isub(cap, len);
+        let full = self
+            .b
+            .ins()
+            .icmp_imm_u(IntCC::UnsignedLessThan, room, extra);
+        let (grow_block, done) = (self.b.create_block(), self.b.create_block());
+        self.b.ins().brif(full, grow_block, &[], done, &[]);
+
+        self.b.switch_to_block(grow_block);
+        self.store_len(len);
+        let raw = self.b.use_var(self.raw);
+        let mut signature = self.b.func.signature.clone();
+        signature.params = vec![AbiParam::new(self.pointer), AbiParam::new(self.pointer)];
+        signature.returns.clear();
+        let signature = self.b.import_signature(signature);
+        let callee = self
+            .b
+            .ins()
+            .iconst(self.pointer, grow as extern "C" fn(_, _) as usize as i64);
+        let extra = self.b.ins().iconst(self.pointer, extra);
+        self.b.ins().call_indirect(signature, callee, &[raw, extra]);
+        self.load_buffer();
+        self.b.ins().jump(done, &[]);
+
+        self.b.switch_to_block(done);
+    }
+}
@@ -0,0 +1,247 @@
+use std::borrow::Cow;
+
+use crate::{Token, Value};
+
+/// A token borrowing its text from the input wherever it can. Words are
+/// lower case; only words that weren't already are copied.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Lexeme<'a> {
+    Word(Cow<'a, str>),
+    Number(Value),
+    /// A character literal, such as `'a'`, by its code point.
+    Char(Value),
+}
+
+impl Lexeme<'_> {
+    pub(crate) fn is_word(&self, word: &str) -> bool {
+        matches!(self, Lexeme::Word(w) if w == word)
+    }
+
+    /// A copy borrowing from `self` rather than from the input.
+    pub(crate) fn borrowed(&self) -> Lexeme<'_> {
+        match self {
+            Lexeme::Word(word) => Lexeme::Word(Cow::Borrowed(word)),
+            Lexeme::Number(i) => Lexeme::Number(*i),
+            Lexeme::Char(c) => Lexeme::Char(*c),
+        }
+    }
+
+    pub(crate) fn into_owned(self) -> Lexeme<'static> {
+        match self {
+            Lexeme::Word(word) => Lexeme::Word(Cow::Owned(word.into_owned())),
+            Lexeme::Number(i) => Lexeme::Number(i),
+            Lexeme::Char(c) => Lexeme::Char(c),
+        }
+    }
+}
+
+/// As it would be written.
+impl std::fmt::Display for Lexeme<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Lexeme::Word(word) => f.write_str(word),
+            Lexeme::Number(value) => write!(f, "{value}"),
+            Lexeme::Char(c) => match u32::try_from(*c).ok().and_then(char::from_u32) {
+                Some(c) => write!(f, "'{c}'"),
+                None => write!(f, "{c}"),
+            },
+        }
+    }
+}
+
+impl<'a> From<&'a Token> for Lexeme<'a> {
+    fn from(token: &'a Token) -> Self {
+        match token {
+            Token::Word(word) => Lexeme::Word(Cow::Borrowed(word)),
+            Token::Number(i) => Lexeme::Number(*i),
+        }
+    }
+}
+
+impl From<Token> for Lexeme<'static> {
+    fn from(token: Token) -> Self {
+        match token {
+            Token::Word(word) => Lexeme::Word(Cow::Owned(word)),
+            Token::Number(i) => Lexeme::Number(i),
+        }
+    }
+}
+
+/// The words of an input split at whitespace, leaving out comments: from a
+/// `(` word to the next `)` word, and from a `\` word to the end of its
+/// line.
+pub(crate) struct Words<'a> {
+    input: &'a str,
+    words: std::iter::Peekable<std::str::SplitWhitespace<'a>>,
+}
+
+pub(crate) fn words(input: &str) -> Words<'_> {
+    Words {
+        input,
+        words: input.split_whitespace().peekable(),
+    }
+}
+
+impl<'a> Iterator for Words<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        loop {
+            let word = self.words.next()?;
+            if !skip_comment(self.input, word, &mut self.words) {
+                return Some(word);
+            }
+        }
+    }
+}
+
+/// Whether `word`, one of the words of `input` split at whitespace, opens a
+/// comment, which is then skipped in `rest`, the words after it.
+pub(crate) fn skip_comment<'a>(
+    input: &str,
+    word: &str,
+    rest: &mut std::iter::Peekable<std::str::SplitWhitespace<'a>>,
+) -> bool {
+    let offset = |word: &str| word.as_ptr() as usize - input.as_ptr() as usize;
+    match word {
+        "(" => while rest.next().is_some_and(|word| word != ")") {},
+        "\\" => {
+            let end = offset(word);
+            let line = input[end..].find('\n').map_or(input.len(), |at| end + at);
+            while rest.next_if(|word| offset(word) < line).is_some() {}
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// Splits its input at whitespace without allocating, except to lower-case
+/// words that aren't lower case already. Comments are skipped.
+pub(crate) struct Lexer<'a> {
+    words: Words<'a>,
+    base: u32,
+}
+
+impl<'a> Lexer<'a> {
+    /// The next word as it was written, neither lower-cased nor parsed.
+    pub(crate) fn next_raw(&mut self) -> Option<&'a str> {
+        self.words.next()
+    }
+
+    /// Reads the words from now on in `base`. In any but ten, a word
+    /// without a prefix is left a word, for the interpreter to read as a
+    /// number once it finds it isn't defined, since its letters may be
+    /// digits.
+    pub(crate) fn set_base(&mut self, base: u32) {
+        self.base = base;
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Lexeme<'a>;
+
+    fn next(&mut self) -> Option<Lexeme<'a>> {
+        let word = self.words.next()?;
+        if let Some(c) = character(word) {
+            return Some(Lexeme::Char(c));
+        }
+        Some(match number(word) {
+            Some(i) if self.base == 10 || prefixed(word) => Lexeme::Number(i),
+            _ => Lexeme::Word(lowercase(word)),
+        })
+    }
+}
+
+pub(crate) fn lex(input: &str) -> Lexer<'_> {
+    Lexer {
+        words: words(input),
+        base: 10,
+    }
+}
+
+/// The value of `word` if it is a number: decimal digits, hexadecimal ones
+/// after `$` or `0x`, binary ones after `%`, or decimal ones again after
+/// `#`, with an optional sign and `_` between digits, as in `-$ff` or
+/// `1_000`; or a character in single quotes, such as `'a'`. See `number_in`
+/// for digits in another base. Numbers not in decimal may use all the bits
+/// of a cell, so `$ffffffff` is -1.
+pub(crate) fn number(word: &str) -> Option<Value> {
+    number_in(word, 10)
+}
+
+/// The value of `word` if it is a number, as `number` reads it, but with
+/// digits that have no prefix in `base`, from 2 to 36.
+pub(crate) fn number_in(word: &str, base: u32) -> Option<Value> {
+    if let Some(c) = character(word) {
+        return Some(c);
+    }
+    let (negative, unsigned) = match word.as_bytes().first()? {
+        b'-' => (true, &word[1..]),
+        b'+' => (false, &word[1..]),
+        _ => (false, word),
+    };
+    let (radix, digits) = match unsigned.as_bytes() {
+        [b'$', ..] => (16, &unsigned[1..]),
+        [b'0', b'x' | b'X', ..] => (16, &unsigned[2..]),
+        [b'%', ..] => (2, &unsigned[1..]),
+        [b'#', ..] => (10, &unsigned[1..]),
+        _ => (base, unsigned),
+    };
+    let magnitude = magnitude(digits, radix)?;
+    if radix == 10 {
+        let value = if negative { -magnitude } else { magnitude };
+        return Value::try_from(value).ok();
+    }
+    let bits = u32::try_from(magnitude).ok()? as Value;
+    Some(if negative { bits.wrapping_neg() } else { bits })
+}
+
+/// Whether `word` starts with a prefix setting the base of its digits.
+fn prefixed(word: &str) -> bool {
+    let unsigned = word.strip_prefix(['-', '+']).unwrap_or(word);
+    unsigned.starts_with(['$', '%', '#'])
+        || unsigned.starts_with("0x")
+        || unsigned.starts_with("0X")
+}
+
+/// Whether `word`, not being a number, was likely meant as one.
+pub(crate) fn looks_numeric(word: &str) -> bool {
+    let unsigned = word.strip_prefix(['-', '+']).unwrap_or(word);
+    match unsigned.as_bytes() {
+        [digit, ..] if digit.is_ascii_digit() => true,
+        [b'$' | b'%' | b'#', _, ..] => true,
+        _ => false,
+    }
+}
+
+pub(crate) fn character(word: &str) -> Option<Value> {
+    let mut chars = word.strip_prefix('\'')?.strip_suffix('\'')?.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(u32::from(c) as Value),
+        _ => None,
+    }
+}
+
+/// The value of `digits` in `radix`, any `_` between two of them ignored.
+fn magnitude(digits: &str, radix: u32) -> Option<i64> {
+    let separated = digits.starts_with('_') || digits.ends_with('_') || digits.contains("__");
+    if digits.is_empty() || separated {
+        return None;
+    }
+    digits
+        .chars()
+        .filter(|&c| c != '_')
+        .try_fold(0i64, |value, c| {
+            value
+                .checked_mul(i64::from(radix))?
+                .checked_add(i64::from(c.to_digit(radix)?))
+        })
+}
+
+fn lowercase(word: &str) -> Cow<'_, str> {
+    if word.is_ascii() && !word.bytes().any(|b| b.is_ascii_uppercase()) {
+        Cow::Borrowed(word)
+    } else {
+        Cow::Owned(word.to_lowercase())
+    }
+}
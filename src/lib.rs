@@ -1,229 +1,1232 @@
-use std::collections::HashMap;
+mod base;
+mod bytecode;
+mod channels;
+mod clock;
+mod cooperative;
+mod coverage;
+mod diagnostics;
+mod dictionary;
+mod diff;
+mod doc;
+mod effects;
+#[cfg(feature = "env")]
+mod env;
+mod explain;
+#[cfg(feature = "file-io")]
+mod files;
+#[cfg(feature = "dlopen")]
+mod foreign;
+mod format;
+mod highlight;
+mod image;
+mod include;
+mod interner;
+mod ir;
+#[cfg(feature = "jit")]
+mod jit;
+mod lexer;
+mod lint;
+#[cfg(feature = "net")]
+mod net;
+#[cfg(feature = "observers")]
+mod observers;
+mod output;
+mod passes;
+mod peephole;
+#[cfg(feature = "peripherals")]
+mod peripherals;
+mod profile;
+mod prune;
+mod pure;
+mod random;
+pub mod runtime;
+mod source_map;
+mod stack;
+mod stacks;
+mod steps;
+#[cfg(feature = "tagged")]
+mod tags;
+mod tasks;
+mod tester;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod timeline;
+mod transpile;
+mod vm;
+mod warnings;
+mod wasm;
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use base::BASE;
+use bytecode::{Body, Control, Op, Primitive};
+pub use clock::{Clock, MonotonicClock};
+pub use coverage::{Coverage, DefinitionCoverage, TokenCoverage};
+use diagnostics::{commands, Fault, Located, Malformed};
+pub use diagnostics::{Diagnostics, Span};
+use dictionary::Dictionary;
+pub use diff::DictDiff;
+pub use format::{format, FormatOptions};
+pub use highlight::{highlight, TokenClass};
+use interner::Interner;
+pub use ir::{Block, Exit, Ir, IrOp, IrWord};
+use lexer::{lex, Lexeme};
+pub use lint::{check, Lint, LintKind};
+#[cfg(feature = "net")]
+pub use net::{Connection, NetProvider, TcpProvider};
+use output::Output;
+pub use passes::Pass;
+pub use peephole::PeepholeStats;
+#[cfg(feature = "peripherals")]
+pub use peripherals::Peripherals;
+pub use profile::Profile;
+pub use source_map::SourceLocation;
+use stack::Stack;
+use stacks::Stacks;
+pub use steps::{StepState, Steps};
+#[cfg(feature = "tagged")]
+pub use tags::Tag;
+use tasks::Tasks;
+pub use tester::TestSummary;
+pub use timeline::{TimedCall, Timeline};
+use vm::Input;
+pub use warnings::Warning;
 
 pub type Value = i32;
 pub type Result = std::result::Result<(), Error>;
 
 #[derive(Debug, Clone)]
 pub struct Forth {
-    stack: Vec<Value>,
-    expanded_definitions: HashMap<String, Operation>,
-    raw_definitions: Vec<(String, Vec<Token>)>,
+    stack: Stack,
+    stacks: Stacks,
+    tasks: Tasks,
+    // Everything but the stack is shared between clones and snapshots, and
+    // copied on the first change after the split.
+    data_space: Arc<Vec<Value>>,
+    names: Arc<Interner>,
+    dictionary: Arc<Dictionary>,
+    /// Compiled bodies of all user-defined words, one after the other.
+    code: Arc<Vec<Op>>,
+    /// Bodies of user-defined words in `code`, indexed by `Op::Call`.
+    words: Arc<Vec<Body>>,
+    /// Where the ops of `code` were compiled from, see `Forth::source_map`.
+    sources: Arc<source_map::SourceMap>,
+    /// Name of the inputs being evaluated, see `Forth::set_source_name`.
+    source_name: Option<String>,
+    /// Where the tokens of the definition being evaluated are.
+    locating: Option<source_map::Locating>,
+    /// Position in `code` of the op the last call of a word failed at.
+    failed_at: Option<usize>,
+    /// `(ip, end)` in `code` of each word being run, kept between calls so
+    /// running a word doesn't allocate.
+    frames: Vec<(usize, usize)>,
+    /// Most frames `frames` may hold, see `Forth::set_max_call_depth`.
+    max_call_depth: usize,
+    /// Ops `eval_async` runs between yields, see `Forth::set_yield_interval`.
+    yield_interval: usize,
+    history: Option<Vec<HistoryEntry>>,
+    /// Warnings the host hasn't taken yet.
+    warnings: Vec<Warning>,
+    /// Whether `Forth::seal` fixed the dictionary.
+    sealed: bool,
+    quotas: Quotas,
+    usage: Usage,
+    dialect: Dialect,
+    unknown_words: UnknownWords,
+    stack_effects: StackEffects,
+    division: Division,
+    arithmetic: Arithmetic,
+    optimizations: Optimizations,
+    metrics: Metrics,
+    peephole_stats: PeepholeStats,
+    run_stats: RunStats,
+    trace: bool,
+    output: Output,
+    profile: Option<Box<profile::Counts>>,
+    coverage: Option<Box<coverage::Recorder>>,
+    timeline: Option<Box<timeline::Recorder>>,
+    tester: tester::Tester,
+    channels: channels::Channels,
+    clock: clock::SharedClock,
+    random: random::Random,
+    resolver: include::Resolver,
+    passes: passes::Passes,
+    capabilities: Capabilities,
+    /// Tags of the values in data space that aren't numbers.
+    #[cfg(feature = "tagged")]
+    cell_tags: std::collections::HashMap<usize, Tag>,
+    /// How deep `evaluate` and `include` are nested at the moment.
+    nesting: usize,
+    #[cfg(feature = "file-io")]
+    files: files::Files,
+    #[cfg(feature = "env")]
+    environment: env::Environment,
+    #[cfg(feature = "net")]
+    sockets: net::Sockets,
+    #[cfg(feature = "peripherals")]
+    board: peripherals::Board,
+    #[cfg(feature = "dlopen")]
+    libraries: foreign::Libraries,
+    #[cfg(feature = "jit")]
+    jit: jit::Jit,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Execution counters since the interpreter was created or
+/// `Forth::reset_metrics` was last called.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Words executed, counting both user-defined words and the words they
+    /// expand to.
+    pub words_executed: u64,
+    pub max_stack_depth: usize,
+    pub definitions_created: usize,
+    /// Bytes of data space allotted.
+    pub data_space_bytes: usize,
+}
+
+/// What the most recent evaluation used, see `Forth::last_run_stats`, for
+/// hosts that bill or throttle the scripts they run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunStats {
+    /// Primitives run by the interpreter; a word run as native code counts
+    /// as none.
+    pub primitives_executed: u64,
+    pub max_stack_depth: usize,
+    /// Most calls of user-defined words nested at once.
+    pub max_return_depth: usize,
+    /// Bytes of data space read or written, each time they are.
+    pub data_space_bytes_touched: usize,
+    /// Bytes written to the output.
+    pub output_bytes: usize,
+}
+
+/// How closely input has to follow standard Forth syntax.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dialect {
+    /// Accepts liberties such as `1: inc 1 + ;2`, where `:` and `;` are glued
+    /// to neighbouring words.
+    #[default]
+    Lenient,
+    /// Rejects anything a standard Forth would not parse the same way.
+    Strict,
+}
+
+/// What a definition does with a word that is not defined when it is made.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownWords {
+    /// The definition fails with `Error::UnknownWord`, so a misspelt word
+    /// is caught where it is written.
+    #[default]
+    Reject,
+    /// Late binding, as far as errors go: the definition is made, and the
+    /// word fails with `Error::UnknownWord` when it is run. Defining the word
+    /// afterwards doesn't change that, as definitions keep the meanings
+    /// their words had.
+    Defer,
+}
+
+/// Whether definitions are checked for what they do to the stack when they
+/// are made.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StackEffects {
+    /// Anything that compiles is defined, and mistakes show when it runs.
+    #[default]
+    Unchecked,
+    /// The change in depth each part of a definition makes is inferred from
+    /// the stack effects of its words, and a definition fails with
+    /// `Error::UnbalancedBranches` if the two arms of an `if` make different
+    /// ones, as when one drops a value the other doesn't. Where a word's
+    /// effect varies, as with `evaluate` or `recurse`, the arms it is in
+    /// can't be told apart and pass.
+    Checked,
+}
+
+/// Which way `/`, `mod` and `/mod` round a quotient that isn't whole.
+/// Forth systems differ: `-7 2 /` is -3 where division is symmetric and -4
+/// where it is floored. `sm/rem` and `fm/mod` always divide one way each.
+/// They take a single-cell dividend, unlike the standard words of those
+/// names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Division {
+    /// Quotients are rounded towards zero, and a remainder has the sign of
+    /// the dividend, as with `sm/rem`.
+    #[default]
+    Symmetric,
+    /// Quotients are rounded towards negative infinity, and a remainder has
+    /// the sign of the divisor, as with `fm/mod`.
+    Floored,
+}
+
+impl Division {
+    /// Quotient and remainder of `b` divided by `a`, or `None` if `a` is 0 or
+    /// the quotient doesn't fit.
+    pub(crate) fn divide(self, b: Value, a: Value) -> Option<(Value, Value)> {
+        let (quotient, remainder) = (b.checked_div(a)?, b.checked_rem(a)?);
+        match self {
+            Division::Floored if remainder != 0 && (remainder < 0) != (a < 0) => {
+                Some((quotient - 1, remainder + a))
+            }
+            _ => Some((quotient, remainder)),
+        }
+    }
+}
+
+/// What `+`, `-` and `*` do with a result that doesn't fit in a cell.
+/// `w+`, `w-` and `w*` always wrap. Division is not affected: `MIN -1 /`
+/// fails with `Error::Overflow` either way, as it traps on most machines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Arithmetic {
+    /// The word fails with `Error::Overflow`.
+    #[default]
+    Checked,
+    /// The result wraps around, keeping its low 32 bits as two's-complement
+    /// machine arithmetic does, so `2147483647 1 +` is -2147483648.
+    Wrapping,
+}
+
+/// Compile-time rewrites of definition bodies. They never change what a
+/// program computes, but they do change
+/// `Metrics`, so turn them off to count or debug the code exactly as
+/// written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Optimizations {
+    /// Evaluates arithmetic and stack shuffles on literals, such as `2 3 +`.
+    pub fold_constants: bool,
+    /// Calls to user-defined words whose compiled body is at most this many
+    /// ops long are replaced by the body; 0 turns inlining off.
+    pub inline_threshold: usize,
+    /// Runs pairs such as `dup *` and `over +` as a single op.
+    pub superinstructions: bool,
+    /// Takes out pairs that do nothing, such as `swap swap` and `0 +`, once
+    /// a definition is compiled, see `PeepholeStats`. Only pairs the
+    /// definition is known to have pushed enough values for are taken out,
+    /// so one on a stack too short for it still underflows.
+    pub peephole: bool,
+}
+
+impl Default for Optimizations {
+    fn default() -> Self {
+        Optimizations {
+            fold_constants: true,
+            inline_threshold: 8,
+            superinstructions: true,
+            peephole: true,
+        }
+    }
+}
+
+/// Caps on how far scripts may grow the dictionary; `None` means unlimited.
+/// What `forget` or a marker cuts from the dictionary no longer counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Quotas {
+    /// Number of user definitions, redefinitions included.
+    pub max_definitions: Option<usize>,
+    /// Number of tokens across all user definition bodies.
+    pub max_tokens: Option<usize>,
+    /// Bytes of data space allotted to variables, and of the values held on
+    /// auxiliary stacks.
+    pub max_data_space: Option<usize>,
+}
+
+/// Word sets a script may use, see `ForthBuilder::capabilities`. The words
+/// of a set that isn't granted are unknown to the interpreter, so neither
+/// expressions nor definitions can use them. All are granted by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Capabilities(u8);
+
+impl Capabilities {
+    pub const NONE: Capabilities = Capabilities(0);
+    /// The file words, with the `file-io` feature.
+    pub const FILE_IO: Capabilities = Capabilities(1);
+    /// The socket words, with the `net` feature.
+    pub const NET: Capabilities = Capabilities(1 << 1);
+    /// `getenv` and `setenv`, with the `env` feature.
+    pub const ENV: Capabilities = Capabilities(1 << 2);
+    /// `utime` and `elapsed`.
+    pub const TIME: Capabilities = Capabilities(1 << 3);
+    /// `random`.
+    pub const RANDOM: Capabilities = Capabilities(1 << 4);
+    pub const ALL: Capabilities = Capabilities((1 << 5) - 1);
+
+    pub const fn contains(self, other: Capabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn union(self, other: Capabilities) -> Capabilities {
+        Capabilities(self.0 | other.0)
+    }
+
+    pub const fn difference(self, other: Capabilities) -> Capabilities {
+        Capabilities(self.0 & !other.0)
+    }
+
+    /// Whether the builtin `word` may be used.
+    fn grants(self, word: &str) -> bool {
+        self.grants_any(|&(name, _)| name != word)
+    }
+
+    /// Whether the builtin compiled to `op` may be run. Pushes always may:
+    /// gated constants such as `r/o` are numbers anyone can write.
+    pub(crate) fn grants_op(self, op: Op) -> bool {
+        matches!(op, Op::Push(_)) || self.grants_any(|&(_, builtin)| builtin != op)
+    }
+
+    /// Whether no capability this lacks gates a builtin other than `other`
+    /// says.
+    fn grants_any(self, other: impl Fn(&(&str, Op)) -> bool) -> bool {
+        self == Capabilities::ALL
+            || GATED_OPERATIONS.iter().all(|&(capability, operations)| {
+                self.contains(capability) || operations.iter().all(&other)
+            })
+    }
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities::ALL
+    }
+}
+
+impl std::ops::BitOr for Capabilities {
+    type Output = Capabilities;
+
+    fn bitor(self, other: Capabilities) -> Capabilities {
+        self.union(other)
+    }
+}
+
+impl std::ops::BitOrAssign for Capabilities {
+    fn bitor_assign(&mut self, other: Capabilities) {
+        *self = self.union(other);
+    }
+}
+
+impl std::ops::Sub for Capabilities {
+    type Output = Capabilities;
+
+    fn sub(self, other: Capabilities) -> Capabilities {
+        self.difference(other)
+    }
+}
+
+/// What the dictionary uses of the quotas.
+#[derive(Debug, Clone, Default)]
+struct Usage {
+    /// The tokens charged for each entry after the builtins, in order.
+    charges: Arc<Vec<usize>>,
+    tokens: usize,
+}
+
+impl Usage {
+    fn new(charges: Vec<usize>) -> Usage {
+        Usage {
+            tokens: charges.iter().sum(),
+            charges: Arc::new(charges),
+        }
+    }
+
+    fn definitions(&self) -> usize {
+        self.charges.len()
+    }
+
+    /// Gives back what the entries after the first `definitions` charged.
+    fn truncate(&mut self, definitions: usize) {
+        if definitions < self.charges.len() {
+            let charges = Arc::make_mut(&mut self.charges);
+            self.tokens -= charges.drain(definitions..).sum::<usize>();
+        }
+    }
+}
+
+/// Configures a `Forth` before creating it, see `Forth::builder`.
+#[derive(Debug, Clone, Default)]
+pub struct ForthBuilder {
+    quotas: Quotas,
+    dialect: Dialect,
+    unknown_words: UnknownWords,
+    stack_effects: StackEffects,
+    division: Division,
+    arithmetic: Arithmetic,
+    max_call_depth: Option<usize>,
+    yield_interval: Option<usize>,
+    channels: channels::Channels,
+    clock: Option<clock::SharedClock>,
+    resolver: include::Resolver,
+    passes: passes::Passes,
+    capabilities: Capabilities,
+    #[cfg(feature = "file-io")]
+    file_root: Option<std::path::PathBuf>,
+    #[cfg(feature = "net")]
+    sockets: net::Sockets,
+    #[cfg(feature = "peripherals")]
+    board: peripherals::Board,
+    optimizations: Optimizations,
+}
+
+impl ForthBuilder {
+    /// Caps the entries scripts may add to the dictionary at `max`: each
+    /// definition, variable, marker, synonym and stack counts as one, even
+    /// when it redefines a name. Those `forget` or a marker cut count no
+    /// longer.
+    pub fn max_definitions(mut self, max: usize) -> Self {
+        self.quotas.max_definitions = Some(max);
+        self
+    }
+
+    /// Caps the words and numbers in the bodies of the definitions in the
+    /// dictionary at `max` in all, comments left out. Those of definitions
+    /// `forget` or a marker cut count no longer.
+    pub fn max_tokens(mut self, max: usize) -> Self {
+        self.quotas.max_tokens = Some(max);
+        self
+    }
+
+    /// Caps data space at `bytes`, a cell taking `size_of::<Value>()`: the
+    /// cell of each variable and user variable, and each value held on an
+    /// auxiliary stack.
+    pub fn max_data_space(mut self, bytes: usize) -> Self {
+        self.quotas.max_data_space = Some(bytes);
+        self
+    }
+
+    pub fn quotas(mut self, quotas: Quotas) -> Self {
+        self.quotas = quotas;
+        self
+    }
+
+    /// Grants scripts only the word sets in `capabilities`, so that an
+    /// untrusted one can't reach the files, network or environment of the
+    /// host. The interpreter can't be granted more later.
+    pub fn capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    pub fn max_call_depth(mut self, depth: usize) -> Self {
+        self.max_call_depth = Some(depth);
+        self
+    }
+
+    /// See `Forth::set_yield_interval`.
+    pub fn yield_interval(mut self, interval: usize) -> Self {
+        self.yield_interval = Some(interval.max(1));
+        self
+    }
+
+    /// See `Forth::set_channels`.
+    pub fn channels(
+        mut self,
+        to_host: std::sync::mpsc::Sender<Value>,
+        from_host: std::sync::mpsc::Receiver<Value>,
+    ) -> Self {
+        self.channels = channels::Channels::new(to_host, from_host);
+        self
+    }
+
+    /// See `Forth::set_clock`.
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Some(clock::SharedClock::new(clock));
+        self
+    }
+
+    /// See `Forth::set_include_resolver`.
+    pub fn include_resolver(
+        mut self,
+        resolve: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.resolver = include::Resolver::new(resolve);
+        self
+    }
+
+    /// See `Forth::set_file_root`.
+    #[cfg(feature = "file-io")]
+    pub fn file_root(mut self, root: impl Into<std::path::PathBuf>) -> Self {
+        self.file_root = Some(root.into());
+        self
+    }
+
+    /// See `Forth::set_net_provider`.
+    #[cfg(feature = "net")]
+    pub fn net_provider(mut self, provider: impl NetProvider + 'static) -> Self {
+        self.sockets = net::Sockets::provided(Arc::new(provider));
+        self
+    }
+
+    /// See `Forth::set_peripherals`.
+    #[cfg(feature = "peripherals")]
+    pub fn peripherals(mut self, peripherals: impl Peripherals + 'static) -> Self {
+        self.board = peripherals::Board::new(peripherals);
+        self
+    }
+
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    pub fn unknown_words(mut self, unknown_words: UnknownWords) -> Self {
+        self.unknown_words = unknown_words;
+        self
+    }
+
+    pub fn stack_effects(mut self, stack_effects: StackEffects) -> Self {
+        self.stack_effects = stack_effects;
+        self
+    }
+
+    pub fn division(mut self, division: Division) -> Self {
+        self.division = division;
+        self
+    }
+
+    pub fn arithmetic(mut self, arithmetic: Arithmetic) -> Self {
+        self.arithmetic = arithmetic;
+        self
+    }
+
+    pub fn fold_constants(mut self, enabled: bool) -> Self {
+        self.optimizations.fold_constants = enabled;
+        self
+    }
+
+    pub fn inline_threshold(mut self, ops: usize) -> Self {
+        self.optimizations.inline_threshold = ops;
+        self
+    }
+
+    pub fn superinstructions(mut self, enabled: bool) -> Self {
+        self.optimizations.superinstructions = enabled;
+        self
+    }
+
+    pub fn peephole(mut self, enabled: bool) -> Self {
+        self.optimizations.peephole = enabled;
+        self
+    }
+
+    pub fn optimizations(mut self, optimizations: Optimizations) -> Self {
+        self.optimizations = optimizations;
+        self
+    }
+
+    /// Runs `pass` on each definition compiled, after the passes added
+    /// before it, see `Pass`.
+    pub fn add_pass(mut self, pass: impl Pass + 'static) -> Self {
+        self.passes.push(pass);
+        self
+    }
+
+    pub fn build(self) -> Forth {
+        Forth {
+            quotas: self.quotas,
+            dialect: self.dialect,
+            unknown_words: self.unknown_words,
+            stack_effects: self.stack_effects,
+            division: self.division,
+            arithmetic: self.arithmetic,
+            max_call_depth: self.max_call_depth.unwrap_or(DEFAULT_MAX_CALL_DEPTH),
+            yield_interval: self.yield_interval.unwrap_or(DEFAULT_YIELD_INTERVAL),
+            optimizations: self.optimizations,
+            channels: self.channels,
+            clock: self.clock.unwrap_or_default(),
+            resolver: self.resolver,
+            passes: self.passes,
+            capabilities: self.capabilities,
+            #[cfg(feature = "file-io")]
+            files: files::Files::rooted(self.file_root),
+            #[cfg(feature = "net")]
+            sockets: self.sockets,
+            #[cfg(feature = "peripherals")]
+            board: self.board,
+            ..Forth::default()
+        }
+    }
+}
+
+/// One `eval` call recorded while history is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub input: String,
+    pub outcome: Result,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Error {
     DivisionByZero,
     StackUnderflow,
     UnknownWord,
     InvalidWord,
+    OutOfRange,
+    QuotaExceeded,
+    InvalidAddress,
+    /// The result of an arithmetic word doesn't fit in a `Value`.
+    Overflow,
+    /// Calls nested deeper than `Forth::max_call_depth`.
+    ReturnStackOverflow,
+    /// `send` or `recv` found nobody at the host's end of its channel.
+    ChannelClosed,
+    /// `include` found no source by the name it was given.
+    IncludeNotFound,
+    /// Under `StackEffects::Checked`, the two arms of an `if` change the
+    /// depth by different amounts: `taken` when the flag is true, `skipped`
+    /// when it is false.
+    UnbalancedBranches {
+        taken: isize,
+        skipped: isize,
+    },
+    /// With the `tagged` feature, a word was given a value tagged other
+    /// than as it takes, such as a number for an address.
+    #[cfg(feature = "tagged")]
+    TagMismatch {
+        expected: Tag,
+        found: Tag,
+    },
+    /// `throw` was given this code, and no `catch` caught it.
+    Uncaught(Value),
+    /// Something was to be defined or forgotten after `Forth::seal`.
+    Sealed,
+    /// `Forth::eval_pure` was given more than stack math.
+    Impure,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-enum Operation {
-    Addition,
-    Subtraction,
-    Multiplication,
-    Division,
-    Duplicate,
-    Drop,
-    Swap,
-    Over,
-    UserDefined(Vec<Token>),
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Error::DivisionByZero => "division by zero",
+            Error::StackUnderflow => "stack underflow",
+            Error::UnknownWord => "unknown word",
+            Error::InvalidWord => "invalid word",
+            Error::OutOfRange => "value out of range for the requested type",
+            Error::QuotaExceeded => "quota exceeded",
+            Error::InvalidAddress => "invalid address",
+            Error::Overflow => "arithmetic overflow",
+            Error::ReturnStackOverflow => "return stack overflow",
+            Error::ChannelClosed => "channel closed",
+            Error::IncludeNotFound => "source to include not found",
+            Error::UnbalancedBranches { .. } => "branches leave the stack at different depths",
+            #[cfg(feature = "tagged")]
+            Error::TagMismatch { .. } => "value of the wrong kind",
+            Error::Uncaught(_) => "uncaught throw",
+            Error::Sealed => "dictionary is sealed",
+            Error::Impure => "side effects in a pure expression",
+        };
+        f.write_str(msg)
+    }
 }
 
-enum Command {
-    Expression(Vec<Token>),
-    Definition(String, Vec<Token>),
+impl std::error::Error for Error {}
+
+impl Error {
+    /// The standard throw code of the error, which `catch` pushes when it
+    /// catches it: -4 for a stack underflow, -10 for a division by zero,
+    /// -13 for an undefined word and so on, or the code given to `throw`.
+    pub fn throw_code(&self) -> Value {
+        match self {
+            Error::DivisionByZero => -10,
+            Error::StackUnderflow => -4,
+            Error::UnknownWord => -13,
+            // Unsupported operation.
+            Error::InvalidWord => -21,
+            // Invalid numeric argument.
+            Error::OutOfRange => -24,
+            // Dictionary overflow.
+            Error::QuotaExceeded => -8,
+            Error::InvalidAddress => -9,
+            // Result out of range.
+            Error::Overflow => -11,
+            Error::ReturnStackOverflow => -5,
+            // Exception in sending or receiving a character.
+            Error::ChannelClosed => -57,
+            // Non-existent file.
+            Error::IncludeNotFound => -38,
+            // Control structure mismatch.
+            Error::UnbalancedBranches { .. } => -22,
+            // Argument type mismatch.
+            #[cfg(feature = "tagged")]
+            Error::TagMismatch { .. } => -12,
+            Error::Uncaught(code) => *code,
+            // Unsupported operation.
+            Error::Sealed | Error::Impure => -21,
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Clone)]
-enum Token {
-    Word(String),
-    Number(Value),
+/// A saved copy of the stack and dictionary, see `Forth::snapshot`.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    stack: Vec<Value>,
+    data_space: Arc<Vec<Value>>,
+    names: Arc<Interner>,
+    dictionary: Arc<Dictionary>,
+    code: Arc<Vec<Op>>,
+    words: Arc<Vec<Body>>,
+    usage: Usage,
+    stacks: Stacks,
+    tasks: Tasks,
+    #[cfg(feature = "tagged")]
+    stack_tags: Vec<Tag>,
+    #[cfg(feature = "tagged")]
+    cell_tags: std::collections::HashMap<usize, Tag>,
 }
 
-const PREDIFINED_OPERATIONS: [(&str, Operation); 8] = [
-    ("+", Operation::Addition),
-    ("-", Operation::Subtraction),
-    ("*", Operation::Multiplication),
-    ("/", Operation::Division),
-    ("dup", Operation::Duplicate),
-    ("drop", Operation::Drop),
-    ("swap", Operation::Swap),
-    ("over", Operation::Over),
-];
+/// What `Forth::reset` clears; nothing by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResetOptions {
+    /// Empties the stack.
+    pub stack: bool,
+    /// Drops the call frames left over and the tasks waiting for a turn,
+    /// whose return stacks they are.
+    pub return_stack: bool,
+    /// Forgets every user-defined word, variables included, giving
+    /// redefined builtins their meaning back.
+    pub dictionary: bool,
+    /// Sets every variable to 0, keeping them defined, and `base` back to 10.
+    pub data_space: bool,
+}
+
+/// Outcome of `Forth::eval_lenient`: every command that failed, in order.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct EvalReport {
+    pub failures: Vec<CommandFailure>,
+}
 
-fn do_operation(op: &Operation) -> fn(&mut Vec<Value>) -> Result {
-    match op {
-        Operation::Addition => do_addition,
-        Operation::Subtraction => do_substraction,
-        Operation::Multiplication => do_multiplication,
-        Operation::Division => do_division,
-        Operation::Duplicate => do_dup,
-        Operation::Drop => do_drop,
-        Operation::Swap => do_swap,
-        Operation::Over => do_over,
-        _ => do_nothing,
+#[derive(Debug, PartialEq, Eq)]
+pub struct CommandFailure {
+    pub command: String,
+    pub error: Error,
+}
+
+impl EvalReport {
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
     }
 }
 
-fn do_addition(stack: &mut Vec<Value>) -> Result {
-    let a = stack.pop().ok_or(Error::StackUnderflow)?;
-    let b = stack.pop().ok_or(Error::StackUnderflow)?;
-    stack.push(a + b);
-    Ok(())
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operation {
+    Builtin(Op),
+    Address(usize),
+    /// Index into `Forth::words`.
+    UserDefined(usize),
+    /// Only meaningful inside a definition.
+    Control(Control),
+    /// Forgets the dictionary from this entry on, itself included.
+    Marker(usize),
 }
 
-fn do_substraction(stack: &mut Vec<Value>) -> Result {
-    let a = stack.pop().ok_or(Error::StackUnderflow)?;
-    let b = stack.pop().ok_or(Error::StackUnderflow)?;
-    stack.push(b - a);
-    Ok(())
+#[derive(Debug, Clone, PartialEq)]
+enum Command<'a> {
+    Expression(Vec<Lexeme<'a>>),
+    /// A name, a stack-effect comment and a body.
+    Definition(Cow<'a, str>, Option<String>, Vec<Lexeme<'a>>),
 }
 
-fn do_multiplication(stack: &mut Vec<Value>) -> Result {
-    let a = stack.pop().ok_or(Error::StackUnderflow)?;
-    let b = stack.pop().ok_or(Error::StackUnderflow)?;
-    stack.push(a * b);
-    Ok(())
+impl Command<'_> {
+    fn into_owned(self) -> Command<'static> {
+        let owned = |tokens: Vec<Lexeme>| tokens.into_iter().map(Lexeme::into_owned).collect();
+        match self {
+            Command::Expression(tokens) => Command::Expression(owned(tokens)),
+            Command::Definition(name, effect, tokens) => {
+                Command::Definition(Cow::Owned(name.into_owned()), effect, owned(tokens))
+            }
+        }
+    }
 }
 
-fn do_division(stack: &mut Vec<Value>) -> Result {
-    let a = stack.pop().ok_or(Error::StackUnderflow)?;
-    if a == 0 {
-        return Err(Error::DivisionByZero);
+/// A word or a number. Words are looked up as they are, so they are expected
+/// in lower case; `Token::word` takes care of that.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Token {
+    Word(String),
+    Number(Value),
+}
+
+impl Token {
+    pub fn word(name: &str) -> Token {
+        Token::Word(name.to_lowercase())
     }
-    let b = stack.pop().ok_or(Error::StackUnderflow)?;
-    stack.push(b / a);
-    Ok(())
 }
 
-fn do_dup(stack: &mut Vec<Value>) -> Result {
-    let a = stack.pop().ok_or(Error::StackUnderflow)?;
-    stack.push(a);
-    stack.push(a);
-    Ok(())
+impl From<Value> for Token {
+    fn from(value: Value) -> Self {
+        Token::Number(value)
+    }
 }
 
-fn do_drop(stack: &mut Vec<Value>) -> Result {
-    stack.pop().ok_or(Error::StackUnderflow)?;
-    Ok(())
+/// A pre-parsed sequence of expressions and definitions, run with
+/// `Forth::run` without lexing the source again.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Program {
+    commands: Vec<Command<'static>>,
 }
 
-fn do_swap(stack: &mut Vec<Value>) -> Result {
-    let a = stack.pop().ok_or(Error::StackUnderflow)?;
-    let b = stack.pop().ok_or(Error::StackUnderflow)?;
-    stack.push(a);
-    stack.push(b);
-    Ok(())
+impl Program {
+    pub fn new() -> Program {
+        Program::default()
+    }
+
+    /// Parses `input` ahead of running it, so digits without a prefix are
+    /// read in decimal, whatever `base` holds when the program runs.
+    pub fn parse(input: &str) -> std::result::Result<Program, Error> {
+        let commands = commands(input)
+            .map(|span| {
+                let span = span.map_err(|_| Error::InvalidWord)?;
+                parse_command(&input[span.start..span.end], 10)
+                    .map(Command::into_owned)
+                    .map_err(|fault| fault.error)
+            })
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(Program { commands })
+    }
+
+    /// Appends tokens to be evaluated.
+    pub fn expression(mut self, tokens: impl IntoIterator<Item = Token>) -> Program {
+        self.commands.push(Command::Expression(
+            tokens.into_iter().map(Lexeme::from).collect(),
+        ));
+        self
+    }
+
+    /// Appends the definition `: name body ;`.
+    pub fn definition(mut self, name: &str, body: impl IntoIterator<Item = Token>) -> Program {
+        self.commands.push(Command::Definition(
+            Cow::Owned(name.to_lowercase()),
+            None,
+            body.into_iter().map(Lexeme::from).collect(),
+        ));
+        self
+    }
 }
 
-fn do_over(stack: &mut Vec<Value>) -> Result {
-    let a = stack.pop().ok_or(Error::StackUnderflow)?;
-    let b = stack.pop().ok_or(Error::StackUnderflow)?;
-    stack.push(b);
-    stack.push(a);
-    stack.push(b);
-    Ok(())
+/// Source text for the program, one command per line.
+impl std::fmt::Display for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for command in &self.commands {
+            let tokens = match command {
+                Command::Expression(tokens) => tokens,
+                Command::Definition(name, effect, tokens) => {
+                    write!(f, ": {name}")?;
+                    if let Some(effect) = effect {
+                        write!(f, " {effect}")?;
+                    }
+                    tokens
+                }
+            };
+            for (i, token) in tokens.iter().enumerate() {
+                let expression = matches!(command, Command::Expression(_));
+                let separator = if i == 0 && expression { "" } else { " " };
+                write!(f, "{separator}{token}")?;
+            }
+            if matches!(command, Command::Definition(..)) {
+                write!(f, " ;")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
 }
 
-#[allow(clippy::ptr_arg)]
-fn do_nothing(_stack: &mut Vec<Value>) -> Result {
-    Ok(())
+const PREDIFINED_OPERATIONS: [(&str, Op); 47] = [
+    ("+", Op::Primitive(Primitive::Add)),
+    ("-", Op::Primitive(Primitive::Subtract)),
+    ("*", Op::Primitive(Primitive::Multiply)),
+    ("/", Op::Primitive(Primitive::Divide)),
+    ("mod", Op::Primitive(Primitive::Modulo)),
+    ("/mod", Op::Primitive(Primitive::DivMod)),
+    ("sm/rem", Op::Primitive(Primitive::DivMod)),
+    ("fm/mod", Op::Primitive(Primitive::FlooredDivMod)),
+    ("w+", Op::Primitive(Primitive::WrappingAdd)),
+    ("w-", Op::Primitive(Primitive::WrappingSubtract)),
+    ("w*", Op::Primitive(Primitive::WrappingMultiply)),
+    ("dup", Op::Primitive(Primitive::Dup)),
+    ("drop", Op::Primitive(Primitive::Drop)),
+    ("swap", Op::Primitive(Primitive::Swap)),
+    ("over", Op::Primitive(Primitive::Over)),
+    ("variable", Op::Variable),
+    ("forget", Op::Forget),
+    ("marker", Op::Mark),
+    ("t{", Op::Primitive(Primitive::TestOpen)),
+    ("->", Op::Primitive(Primitive::TestArrow)),
+    ("}t", Op::Primitive(Primitive::TestClose)),
+    ("test-summary", Op::Primitive(Primitive::TestSummary)),
+    ("dis", Op::Disassemble),
+    ("help", Op::Help),
+    ("@", Op::Primitive(Primitive::Fetch)),
+    ("!", Op::Primitive(Primitive::Store)),
+    ("send", Op::Primitive(Primitive::Send)),
+    ("recv", Op::Primitive(Primitive::Receive)),
+    ("recv?", Op::Primitive(Primitive::TryReceive)),
+    ("evaluate", Op::Primitive(Primitive::Evaluate)),
+    ("include", Op::Include),
+    ("stack", Op::DefineStack),
+    (">s", Op::Primitive(Primitive::ToStack)),
+    ("s>", Op::Primitive(Primitive::FromStack)),
+    ("spawn", Op::Spawn),
+    ("pause", Op::Pause),
+    ("user", Op::User),
+    ("tasks", Op::Primitive(Primitive::Tasks)),
+    ("throw", Op::Primitive(Primitive::Throw)),
+    ("catch", Op::Catch),
+    ("synonym", Op::Synonym),
+    ("'", Op::Tick),
+    ("execute", Op::Execute),
+    ("emit", Op::Primitive(Primitive::Emit)),
+    ("base", push_address(BASE)),
+    ("hex", Op::Primitive(Primitive::Hex)),
+    ("decimal", Op::Primitive(Primitive::Decimal)),
+];
+
+/// The ops of the builtins in the order their execution tokens number them,
+/// see `Forth::xt`.
+pub(crate) fn builtin_ops() -> impl Iterator<Item = Op> {
+    PREDIFINED_OPERATIONS
+        .iter()
+        .chain(&FLOORED_OPERATIONS)
+        .chain(&WRAPPING_OPERATIONS)
+        .chain(&TIME_OPERATIONS)
+        .chain(&RANDOM_OPERATIONS)
+        .chain(FILE_OPERATIONS)
+        .chain(ENV_OPERATIONS)
+        .chain(NET_OPERATIONS)
+        .chain(PERIPHERAL_OPERATIONS)
+        .chain(FOREIGN_OPERATIONS)
+        .map(|&(_, op)| op)
 }
 
-fn parse_tokens(input: &str) -> Vec<Token> {
-    input
-        .to_lowercase()
-        .split_whitespace()
-        .map(|s| match s.parse::<i32>() {
-            Ok(i) => Token::Number(i),
-            _ => Token::Word(s.to_string()),
-        })
-        .collect()
-}
-
-fn is_definition(tokens: &[Token]) -> std::result::Result<bool, Error> {
-    let empty = &Token::Word("".to_string());
-    let colon = &Token::Word(":".to_string());
-    let semicolon = &Token::Word(";".to_string());
-    let fst = tokens.first().unwrap_or(empty);
-    let lst = tokens.last().unwrap_or(empty);
-    if fst == colon {
-        if lst == semicolon {
-            Ok(true)
-        } else {
-            Err(Error::InvalidWord)
+/// What the builtins that follow `Division::Floored` mean under it.
+const FLOORED_OPERATIONS: [(&str, Op); 3] = [
+    ("/", Op::Primitive(Primitive::FlooredDivide)),
+    ("mod", Op::Primitive(Primitive::FlooredModulo)),
+    ("/mod", Op::Primitive(Primitive::FlooredDivMod)),
+];
+
+/// What the builtins that follow `Arithmetic::Wrapping` mean under it.
+const WRAPPING_OPERATIONS: [(&str, Op); 3] = [
+    ("+", Op::Primitive(Primitive::WrappingAdd)),
+    ("-", Op::Primitive(Primitive::WrappingSubtract)),
+    ("*", Op::Primitive(Primitive::WrappingMultiply)),
+];
+
+/// The builtins `Capabilities::TIME` grants.
+const TIME_OPERATIONS: [(&str, Op); 2] = [
+    ("utime", Op::Primitive(Primitive::Utime)),
+    ("elapsed", Op::Primitive(Primitive::Elapsed)),
+];
+
+/// The builtins `Capabilities::RANDOM` grants.
+const RANDOM_OPERATIONS: [(&str, Op); 1] = [("random", Op::Primitive(Primitive::Random))];
+
+const CONTROL_WORDS: [(&str, Control); 5] = [
+    ("if", Control::If),
+    ("else", Control::Else),
+    ("then", Control::Then),
+    ("recurse", Control::Recurse),
+    ("[']", Control::Tick),
+];
+
+#[cfg(feature = "file-io")]
+const FILE_OPERATIONS: &[(&str, Op)] = &files::OPERATIONS;
+#[cfg(not(feature = "file-io"))]
+const FILE_OPERATIONS: &[(&str, Op)] = &[];
+#[cfg(feature = "env")]
+const ENV_OPERATIONS: &[(&str, Op)] = &env::OPERATIONS;
+#[cfg(not(feature = "env"))]
+const ENV_OPERATIONS: &[(&str, Op)] = &[];
+#[cfg(feature = "net")]
+const NET_OPERATIONS: &[(&str, Op)] = &net::OPERATIONS;
+#[cfg(not(feature = "net"))]
+const NET_OPERATIONS: &[(&str, Op)] = &[];
+#[cfg(feature = "peripherals")]
+const PERIPHERAL_OPERATIONS: &[(&str, Op)] = &peripherals::OPERATIONS;
+#[cfg(not(feature = "peripherals"))]
+const PERIPHERAL_OPERATIONS: &[(&str, Op)] = &[];
+#[cfg(feature = "dlopen")]
+const FOREIGN_OPERATIONS: &[(&str, Op)] = &foreign::OPERATIONS;
+#[cfg(not(feature = "dlopen"))]
+const FOREIGN_OPERATIONS: &[(&str, Op)] = &[];
+
+/// The builtins each capability grants, which are unknown without it.
+const GATED_OPERATIONS: [(Capabilities, &[(&str, Op)]); 5] = [
+    (Capabilities::FILE_IO, FILE_OPERATIONS),
+    (Capabilities::NET, NET_OPERATIONS),
+    (Capabilities::ENV, ENV_OPERATIONS),
+    (Capabilities::TIME, &TIME_OPERATIONS),
+    (Capabilities::RANDOM, &RANDOM_OPERATIONS),
+];
+
+/// Entries of the dictionary taken by builtins, which `forget` won't touch.
+const BUILTINS: usize = PREDIFINED_OPERATIONS.len()
+    + TIME_OPERATIONS.len()
+    + RANDOM_OPERATIONS.len()
+    + FILE_OPERATIONS.len()
+    + ENV_OPERATIONS.len()
+    + NET_OPERATIONS.len()
+    + PERIPHERAL_OPERATIONS.len()
+    + FOREIGN_OPERATIONS.len()
+    + CONTROL_WORDS.len();
+
+/// How deep calls may nest unless configured otherwise, enough for any
+/// reasonable recursion while keeping a runaway one to a few megabytes.
+const DEFAULT_MAX_CALL_DEPTH: usize = 100_000;
+
+/// Ops `eval_async` runs between yields unless configured otherwise, a
+/// fraction of a millisecond's work.
+const DEFAULT_YIELD_INTERVAL: usize = 10_000;
+
+/// Size in bytes of one cell of data space.
+const CELL_SIZE: usize = std::mem::size_of::<Value>();
+
+/// Whether `tokens` are a definition. One that is not closed by `;` fails
+/// at its `:`.
+fn is_definition(tokens: &[Lexeme]) -> std::result::Result<bool, Fault> {
+    match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) if first.is_word(":") => {
+            if last.is_word(";") {
+                Ok(true)
+            } else {
+                Err(invalid_token(0))
+            }
         }
-    } else {
-        Ok(false)
+        _ => Ok(false),
     }
 }
 
-fn parse_command(input: &str) -> std::result::Result<Command, Error> {
-    let tokens = parse_tokens(input);
+/// Parses a command as split off by `commands`, with numbers in `base`. A
+/// definition without a name fails at its `:`, and one named by a number at
+/// the number.
+fn parse_command(input: &str, base: u32) -> std::result::Result<Command<'_>, Fault> {
+    let mut lexer = lex(input);
+    lexer.set_base(base);
+    let mut tokens: Vec<Lexeme> = lexer.collect();
     if is_definition(&tokens)? {
-        if let Token::Word(str) = &tokens[1] {
-            let tokens = tokens[2..tokens.len() - 1].to_vec();
-            Ok(Command::Definition(str.to_owned(), tokens))
-        } else {
-            Err(Error::InvalidWord)
+        tokens.pop();
+        let mut tokens = tokens.into_iter().skip(1);
+        match tokens.next() {
+            Some(Lexeme::Word(name)) => {
+                let effect = doc::stack_effect(input);
+                Ok(Command::Definition(name, effect, tokens.collect()))
+            }
+            Some(Lexeme::Number(_) | Lexeme::Char(_)) => Err(invalid_token(1)),
+            None => Err(invalid_token(0)),
         }
     } else {
         Ok(Command::Expression(tokens))
     }
 }
 
+/// Fails unless `name` could be looked up from source text: it must not be
+/// empty or a number, nor hold whitespace, `:` or `;`, which split words
+/// and commands.
+fn check_name(name: &str) -> Result {
+    let splits = |c: char| c.is_whitespace() || c == ':' || c == ';';
+    if name.is_empty() || name.contains(splits) || lexer::number(name).is_some() {
+        return Err(Error::InvalidWord);
+    }
+    Ok(())
+}
+
+fn invalid_token(index: usize) -> Fault {
+    Fault {
+        error: Error::InvalidWord,
+        token: Some(index),
+        depth: 0,
+    }
+}
+
 impl Default for Forth {
     fn default() -> Self {
-        let predifined = PREDIFINED_OPERATIONS
-            .into_iter()
-            .map(|(s, o)| (s.to_string(), o))
-            .collect();
+        let mut names = Interner::default();
+        let mut dictionary = Dictionary::with_capacity(64);
+        PREDIFINED_OPERATIONS
+            .iter()
+            .chain(&TIME_OPERATIONS)
+            .chain(&RANDOM_OPERATIONS)
+            .chain(FILE_OPERATIONS)
+            .chain(ENV_OPERATIONS)
+            .chain(NET_OPERATIONS)
+            .chain(PERIPHERAL_OPERATIONS)
+            .chain(FOREIGN_OPERATIONS)
+            .map(|&(s, o)| (s, Operation::Builtin(o)))
+            .chain(
+                CONTROL_WORDS
+                    .into_iter()
+                    .map(|(s, c)| (s, Operation::Control(c))),
+            )
+            .for_each(|(s, operation)| {
+                dictionary.define(names.intern(s), operation);
+                if let Some(effect) = doc::builtin_effect(s) {
+                    dictionary.document(Cow::Borrowed(effect));
+                }
+            });
         Forth {
-            stack: Vec::new(),
-            expanded_definitions: predifined,
-            raw_definitions: Vec::new(),
+            names: Arc::new(names),
+            stack: Stack::default(),
+            stacks: Stacks::default(),
+            tasks: Tasks::default(),
+            data_space: Arc::new(vec![10]),
+            dictionary: Arc::new(dictionary),
+            code: Arc::default(),
+            words: Arc::default(),
+            sources: Arc::default(),
+            source_name: None,
+            locating: None,
+            failed_at: None,
+            frames: Vec::new(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            yield_interval: DEFAULT_YIELD_INTERVAL,
+            history: None,
+            warnings: Vec::new(),
+            sealed: false,
+            quotas: Quotas::default(),
+            usage: Usage::default(),
+            dialect: Dialect::default(),
+            unknown_words: UnknownWords::default(),
+            stack_effects: StackEffects::default(),
+            division: Division::default(),
+            arithmetic: Arithmetic::default(),
+            optimizations: Optimizations::default(),
+            metrics: Metrics::default(),
+            peephole_stats: PeepholeStats::default(),
+            run_stats: RunStats::default(),
+            trace: false,
+            output: Output::default(),
+            profile: None,
+            coverage: None,
+            timeline: None,
+            tester: tester::Tester::default(),
+            channels: channels::Channels::default(),
+            clock: clock::SharedClock::default(),
+            random: random::Random::default(),
+            resolver: include::Resolver::default(),
+            passes: passes::Passes::default(),
+            capabilities: Capabilities::ALL,
+            #[cfg(feature = "tagged")]
+            cell_tags: std::collections::HashMap::new(),
+            nesting: 0,
+            #[cfg(feature = "file-io")]
+            files: files::Files::default(),
+            #[cfg(feature = "env")]
+            environment: env::Environment::default(),
+            #[cfg(feature = "net")]
+            sockets: net::Sockets::default(),
+            #[cfg(feature = "peripherals")]
+            board: peripherals::Board::default(),
+            #[cfg(feature = "dlopen")]
+            libraries: foreign::Libraries::default(),
+            #[cfg(feature = "jit")]
+            jit: jit::Jit::default(),
         }
     }
 }
 
-fn split_commands(input: &str) -> Vec<String> {
-    let tmp = input.chars().fold(
-        (Vec::<String>::new(), String::new()),
-        |mut acc, c| match c {
-            ':' => {
-                acc.0.push(acc.1);
-                (acc.0, c.to_string())
-            }
-            ';' => {
-                let mut s = acc.1;
-                s.push(c);
-                acc.0.push(s);
-                (acc.0, String::new())
-            }
-            _ => {
-                let mut s = acc.1;
-                s.push(c);
-                (acc.0, s)
-            }
-        },
-    );
-    let mut cmds = tmp.0;
-    if !tmp.1.is_empty() {
-        cmds.push(tmp.1)
-    };
-    cmds.into_iter()
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty())
-        .collect()
-}
-
-fn append_front(tokens: &mut Vec<Token>, mut op_tokens: Vec<Token>) {
-    op_tokens.reverse();
-    for t in op_tokens {
-        tokens.insert(0, t)
-    }
+/// In standard Forth `:` and `;` are ordinary words, so they only delimit a
+/// definition when they stand alone between whitespace. Comments and
+/// character literals may hold them.
+fn find_glued_word(input: &str) -> Option<usize> {
+    diagnostics::code_words(input)
+        .find(|(_, word)| word.len() > 1 && word.contains([':', ';']))
+        .map(|(index, _)| index)
 }
 
 impl Forth {
@@ -231,77 +1234,885 @@ impl Forth {
         Forth::default()
     }
 
+    pub fn builder() -> ForthBuilder {
+        ForthBuilder::default()
+    }
+
+    pub fn quotas(&self) -> Quotas {
+        self.quotas
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    pub fn max_call_depth(&self) -> usize {
+        self.max_call_depth
+    }
+
+    /// Limits how deeply calls of user-defined words may nest, so a runaway
+    /// recursion fails with `Error::ReturnStackOverflow` before it exhausts
+    /// memory. Tail calls don't nest, and the outermost call counts as 1.
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = depth;
+    }
+
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    pub fn set_dialect(&mut self, dialect: Dialect) {
+        self.dialect = dialect;
+    }
+
+    pub fn unknown_words(&self) -> UnknownWords {
+        self.unknown_words
+    }
+
+    pub fn set_unknown_words(&mut self, unknown_words: UnknownWords) {
+        self.unknown_words = unknown_words;
+    }
+
+    pub fn stack_effects(&self) -> StackEffects {
+        self.stack_effects
+    }
+
+    /// Applies to definitions made from now on.
+    pub fn set_stack_effects(&mut self, stack_effects: StackEffects) {
+        self.stack_effects = stack_effects;
+    }
+
+    pub fn division(&self) -> Division {
+        self.division
+    }
+
+    /// Applies to input evaluated and definitions made from now on;
+    /// existing definitions keep dividing the way they were compiled to.
+    pub fn set_division(&mut self, division: Division) {
+        self.division = division;
+    }
+
+    pub fn arithmetic(&self) -> Arithmetic {
+        self.arithmetic
+    }
+
+    /// Applies like `set_division`.
+    pub fn set_arithmetic(&mut self, arithmetic: Arithmetic) {
+        self.arithmetic = arithmetic;
+    }
+
+    pub fn optimizations(&self) -> Optimizations {
+        self.optimizations
+    }
+
+    /// Applies to definitions made from now on; existing ones keep the code
+    /// they were compiled to.
+    pub fn set_optimizations(&mut self, optimizations: Optimizations) {
+        self.optimizations = optimizations;
+    }
+
     pub fn stack(&self) -> &[Value] {
-        &self.stack
+        self.stack.as_slice()
+    }
+
+    /// Removes and returns the whole stack, leaving it empty.
+    pub fn drain_stack(&mut self) -> Vec<Value> {
+        self.stack.split_off(0)
     }
 
-    fn lookup_word(&mut self, input: &str) -> std::result::Result<Operation, Error> {
-        self.expanded_definitions
-            .get(input)
-            .cloned()
-            .ok_or(Error::UnknownWord)
+    /// Replaces the whole stack with `values`, the last element being the top.
+    pub fn replace_stack(&mut self, values: Vec<Value>) {
+        self.stack.replace(values);
     }
 
-    fn is_raw_definition(&self, input: &str) -> bool {
-        self.raw_definitions.iter().any(|(name, _)| name == input)
+    /// The stack as a JSON array of numbers, the last element being the top.
+    #[cfg(feature = "serde")]
+    pub fn stack_to_json(&self) -> String {
+        serde_json::to_string(self.stack()).expect("numbers always serialize")
     }
 
-    fn expand_word(&mut self, word: &str) -> std::result::Result<Operation, Error> {
-        if !self.expanded_definitions.contains_key(word) || self.is_raw_definition(word) {
-            while !self.raw_definitions.is_empty() {
-                let (name, tokens) = self.raw_definitions.remove(0);
-                let tokens = self.expand_raw_definition(tokens);
-                self.expanded_definitions
-                    .insert(name, Operation::UserDefined(tokens));
+    /// Replaces the whole stack with a JSON array of numbers, as
+    /// `stack_to_json` writes it. Anything else, or a number a cell can't
+    /// hold, leaves the stack as it was.
+    #[cfg(feature = "serde")]
+    pub fn load_stack_json(&mut self, json: &str) -> serde_json::Result<()> {
+        self.replace_stack(serde_json::from_str(json)?);
+        Ok(())
+    }
+
+    /// Pops the top of the stack as a flag: zero is false, anything else true.
+    pub fn pop_bool(&mut self) -> std::result::Result<bool, Error> {
+        self.stack
+            .pop()
+            .map(|v| v != 0)
+            .ok_or(Error::StackUnderflow)
+    }
+
+    /// Pops the top two values, returned in the order they were pushed.
+    /// The stack is left untouched if it holds fewer than two values.
+    pub fn pop_pair(&mut self) -> std::result::Result<(Value, Value), Error> {
+        if self.stack.len() < 2 {
+            return Err(Error::StackUnderflow);
+        }
+        let b = self.stack.pop().expect("two values");
+        let a = self.stack.pop().expect("two values");
+        Ok((a, b))
+    }
+
+    /// Pops the top of the stack converted to `T`. If the value does not fit,
+    /// `Error::OutOfRange` is returned and the value stays on the stack.
+    pub fn try_pop<T: TryFrom<Value>>(&mut self) -> std::result::Result<T, Error> {
+        let top = *self.stack.last().ok_or(Error::StackUnderflow)?;
+        let value = T::try_from(top).map_err(|_| Error::OutOfRange)?;
+        self.stack.pop();
+        Ok(value)
+    }
+
+    /// Evaluates `input` and returns the values it left above the lowest
+    /// depth it took the stack down to, removing them from the stack: what
+    /// it consumed from below the starting depth is replaced by what it
+    /// made of it, so `+ 10` on `1 2 3` gives `[5, 10]` and leaves `1`. A
+    /// value a word only reads, as `dup` does, counts as consumed too, so
+    /// `dup drop` on `1` gives `[1]` and leaves the stack empty.
+    pub fn eval_expr(&mut self, input: &str) -> std::result::Result<Vec<Value>, Error> {
+        // An expression evaluated by one being evaluated counts for both.
+        let outer = self.stack.lowest();
+        self.stack.set_lowest(self.stack.len());
+        let outcome = self.eval(input);
+        let lowest = self.stack.lowest();
+        self.stack.set_lowest(outer.min(lowest));
+        outcome?;
+        Ok(self.stack.split_off(lowest))
+    }
+
+    pub(crate) fn check_syntax(&self, input: &str) -> std::result::Result<(), Located> {
+        match self.dialect {
+            Dialect::Strict => match find_glued_word(input) {
+                Some(index) => Err(Located {
+                    fault: Fault {
+                        error: Error::InvalidWord,
+                        token: Some(index),
+                        depth: self.stack.len(),
+                    },
+                    command: Span::new(0, input.len()),
+                }),
+                None => Ok(()),
+            },
+            Dialect::Lenient => Ok(()),
+        }
+    }
+
+    /// The failure of a command the parser rejected.
+    pub(crate) fn malformed(&self, malformed: Malformed) -> Located {
+        Located {
+            fault: Fault {
+                error: Error::InvalidWord,
+                token: Some(malformed.token),
+                depth: self.stack.len(),
+            },
+            command: malformed.command,
+        }
+    }
+
+    /// Fixes the dictionary as it is: from now on, defining words, `forget`
+    /// and markers fail with `Error::Sealed`, whether run by input or called
+    /// by the host, so input can only run the words there are. `reset`,
+    /// `restore` and `load_image` are still free to change it.
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    pub fn is_sealed(&self) -> bool {
+        self.sealed
+    }
+
+    fn check_unsealed(&self) -> Result {
+        if self.sealed {
+            return Err(Error::Sealed);
+        }
+        Ok(())
+    }
+
+    fn charge_definition(&mut self, tokens: usize) -> Result {
+        let within = |used, max: Option<usize>| max.is_none_or(|max| used <= max);
+        if !within(self.usage.definitions() + 1, self.quotas.max_definitions)
+            || !within(self.usage.tokens + tokens, self.quotas.max_tokens)
+        {
+            return Err(Error::QuotaExceeded);
+        }
+        Arc::make_mut(&mut self.usage.charges).push(tokens);
+        self.usage.tokens += tokens;
+        Ok(())
+    }
+
+    /// Fails unless `cells` more cells fit in the data-space quota, which
+    /// variables share with the values on auxiliary stacks.
+    pub(crate) fn check_data_space(&self, cells: usize) -> Result {
+        let Some(max) = self.quotas.max_data_space else {
+            return Ok(());
+        };
+        // The cell of `base` comes with the interpreter.
+        let used = self.data_space.len() - 1 + self.stacks.cells();
+        if (used + cells) * CELL_SIZE > max {
+            return Err(Error::QuotaExceeded);
+        }
+        Ok(())
+    }
+
+    /// What `word` means now. The builtin arithmetic words divide and
+    /// overflow as `Forth::division` and `Forth::arithmetic` say at the time
+    /// they are looked up.
+    fn lookup_word(&self, word: &str) -> std::result::Result<Operation, Error> {
+        let name = self.names.get(word).ok_or(Error::UnknownWord)?;
+        let index = self.dictionary.find(name).ok_or(Error::UnknownWord)?;
+        // Builtins the capabilities don't grant are unknown, and so are
+        // synonyms of them.
+        if index < BUILTINS && !self.capabilities.grants(word) {
+            return Err(Error::UnknownWord);
+        }
+        if let Some(Operation::Builtin(op)) = self.dictionary.get(name) {
+            if !self.capabilities.grants_op(op) {
+                return Err(Error::UnknownWord);
             }
         }
-        self.lookup_word(word)
-    }
-
-    fn expand_raw_definition(&mut self, mut tokens: Vec<Token>) -> Vec<Token> {
-        let mut buf = Vec::new();
-        while !tokens.is_empty() {
-            let token = tokens.remove(0);
-            match token {
-                Token::Number(_) => buf.push(token),
-                Token::Word(input) => {
-                    if let Ok(op) = self.lookup_word(&input) {
-                        match op {
-                            Operation::UserDefined(_tokens) => append_front(&mut tokens, _tokens),
-                            _ => buf.push(Token::Word(input)),
-                        }
-                    }
-                }
+        let settings: [(bool, &[(&str, Op)]); 2] = [
+            (self.division == Division::Floored, &FLOORED_OPERATIONS),
+            (
+                self.arithmetic == Arithmetic::Wrapping,
+                &WRAPPING_OPERATIONS,
+            ),
+        ];
+        let variant = settings
+            .into_iter()
+            .filter(|&(applies, _)| applies && index < BUILTINS)
+            .flat_map(|(_, operations)| operations)
+            .find(|(variant, _)| *variant == word);
+        match variant {
+            Some(&(_, op)) => Ok(Operation::Builtin(op)),
+            None => self.dictionary.get(name).ok_or(Error::UnknownWord),
+        }
+    }
+
+    /// Defines `name` as `body`, documented by the stack-effect comment
+    /// `effect`. A failure to compile the body points at the token to blame
+    /// counting from the `:`, as in the command it came from.
+    fn define(
+        &mut self,
+        name: &str,
+        effect: Option<String>,
+        body: &[Lexeme],
+    ) -> std::result::Result<(), Fault> {
+        self.check_unsealed()?;
+        check_name(name)?;
+        let base = self.base();
+        let body: Cow<[Lexeme]> = if base == 10 {
+            Cow::Borrowed(body)
+        } else {
+            Cow::Owned(
+                body.iter()
+                    .map(|token| self.in_base(token.clone(), base))
+                    .collect(),
+            )
+        };
+        let body = &*body;
+        let from_colon = |mut fault: Fault| {
+            fault.token = fault.token.map(|token| 2 + token);
+            fault
+        };
+        let mut offsets = Vec::new();
+        let (mut code, mut origins) = self
+            .compile_with_origins(body, self.coverage.is_some().then_some(&mut offsets))
+            .map_err(from_colon)?;
+        // Coverage needs the code as written.
+        if self.coverage.is_none() {
+            (code, origins) = self.run_peephole(code, origins);
+            if !self.passes.is_empty() {
+                code = self.run_passes(name, code)?;
+                origins.clear();
             }
         }
-        buf
+        if self.stack_effects == StackEffects::Checked {
+            self.check_branches(body).map_err(from_colon)?;
+        }
+        self.charge_definition(body.len())?;
+        #[cfg(feature = "observers")]
+        self.stack.observers.defined(name);
+        self.warn_if_defined(name);
+        #[cfg(feature = "log")]
+        log::debug!("defined `{name}`");
+        let name = Arc::make_mut(&mut self.names).intern(name);
+        let dictionary = Arc::make_mut(&mut self.dictionary);
+        dictionary.define(name, Operation::UserDefined(self.words.len()));
+        if let Some(effect) = effect {
+            dictionary.document(Cow::Owned(effect));
+        }
+        let arena = Arc::make_mut(&mut self.code);
+        let start = arena.len();
+        arena.extend(code);
+        let end = arena.len();
+        if let Some(coverage) = &mut self.coverage {
+            let name = self.names.resolve(name);
+            coverage.instrument(self.words.len(), name, body, start, &offsets);
+        }
+        origins.iter_mut().for_each(|origin| *origin += 2);
+        let names = self.names.clone();
+        self.map_sources(names.resolve(name), start, &origins);
+        Arc::make_mut(&mut self.words).push(Body { start, end });
+        self.metrics.definitions_created += 1;
+        Ok(())
+    }
+
+    /// Defines `name` as a word pushing `value`, as if by `: name value ;`.
+    pub fn define_constant(&mut self, name: &str, value: Value) -> Result {
+        self.define(&name.to_lowercase(), None, &[Lexeme::Number(value)])
+            .map_err(|fault| fault.error)
+    }
+
+    /// Defines every `(name, value)` pair with `define_constant`, stopping at
+    /// the first failure.
+    pub fn define_constants<'a>(
+        &mut self,
+        constants: impl IntoIterator<Item = (&'a str, Value)>,
+    ) -> Result {
+        constants
+            .into_iter()
+            .try_for_each(|(name, value)| self.define_constant(name, value))
+    }
+
+    fn define_variable(&mut self, name: &str) -> Result {
+        self.check_unsealed()?;
+        check_name(name)?;
+        self.check_data_space(1)?;
+        self.charge_definition(0)?;
+        #[cfg(feature = "observers")]
+        self.stack.observers.defined(name);
+        self.warn_if_defined(name);
+        #[cfg(feature = "log")]
+        log::debug!("defined variable `{name}`");
+        let name = Arc::make_mut(&mut self.names).intern(name);
+        Arc::make_mut(&mut self.dictionary).define(name, Operation::Address(self.data_space.len()));
+        Arc::make_mut(&mut self.data_space).push(0);
+        self.metrics.definitions_created += 1;
+        self.metrics.data_space_bytes += CELL_SIZE;
+        Ok(())
+    }
+
+    fn define_marker(&mut self, name: &str) -> Result {
+        self.check_unsealed()?;
+        check_name(name)?;
+        self.charge_definition(0)?;
+        #[cfg(feature = "observers")]
+        self.stack.observers.defined(name);
+        self.warn_if_defined(name);
+        #[cfg(feature = "log")]
+        log::debug!("defined marker `{name}`");
+        let name = Arc::make_mut(&mut self.names).intern(name);
+        let dictionary = Arc::make_mut(&mut self.dictionary);
+        dictionary.define(name, Operation::Marker(dictionary.len()));
+        self.metrics.definitions_created += 1;
+        Ok(())
+    }
+
+    /// Defines `new` as another name for what `old` means now, as `synonym
+    /// new old` does. A user-defined word keeps a single compiled body, which
+    /// both names call, and its stack-effect comment is shared too.
+    /// Redefining `old` afterwards leaves `new` as it was.
+    pub fn alias(&mut self, new: &str, old: &str) -> Result {
+        let (new, old) = (new.to_lowercase(), old.to_lowercase());
+        self.check_unsealed()?;
+        check_name(&new)?;
+        let operation = self.lookup_word(&old)?;
+        let effect = self.doc(&old).map(str::to_string);
+        self.charge_definition(0)?;
+        #[cfg(feature = "observers")]
+        self.stack.observers.defined(&new);
+        self.warn_if_defined(&new);
+        #[cfg(feature = "log")]
+        log::debug!("defined `{new}` as a synonym of `{old}`");
+        let name = Arc::make_mut(&mut self.names).intern(&new);
+        let dictionary = Arc::make_mut(&mut self.dictionary);
+        dictionary.define(name, operation);
+        if let Some(effect) = effect {
+            dictionary.document(Cow::Owned(effect));
+        }
+        self.metrics.definitions_created += 1;
+        Ok(())
+    }
+
+    /// Removes `name` and everything defined after it from the dictionary. A
+    /// name that was redefined gets its previous meaning back, builtins
+    /// included; the builtins themselves cannot be forgotten.
+    pub fn forget(&mut self, name: &str) -> Result {
+        self.check_unsealed()?;
+        let entry = self
+            .names
+            .get(&name.to_lowercase())
+            .and_then(|name| self.dictionary.find(name))
+            .ok_or(Error::UnknownWord)?;
+        if entry < BUILTINS {
+            return Err(Error::InvalidWord);
+        }
+        self.rewind(entry);
+        Ok(())
+    }
+
+    /// Cuts the dictionary back to its first `len` entries, giving up the
+    /// data space of the variables defined since. Compiled code stays in the
+    /// arena, as words still running may be in the middle of it.
+    fn rewind(&mut self, len: usize) {
+        let address = |operation| match operation {
+            Operation::Address(address) => Some(address),
+            _ => None,
+        };
+        // A synonym may name a variable of an entry that stays.
+        let kept = self
+            .dictionary
+            .entries_from(0)
+            .take(len)
+            .filter_map(|(_, operation, _)| address(operation))
+            .max()
+            .map_or(0, |address| address + 1);
+        let cells = self
+            .dictionary
+            .operations_from(len)
+            .filter_map(address)
+            .min()
+            .map(|cells| cells.max(kept));
+        if let Some(cells) = cells {
+            Arc::make_mut(&mut self.data_space).truncate(cells);
+            #[cfg(feature = "tagged")]
+            self.cell_tags.retain(|&cell, _| cell < cells);
+            self.tasks.truncate(cells);
+        }
+        Arc::make_mut(&mut self.dictionary).truncate(len);
+        self.usage.truncate(len.saturating_sub(BUILTINS));
+    }
+
+    /// Names that can currently be looked up, builtins first and the rest in
+    /// the order they were defined.
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.dictionary
+            .names()
+            .map(|name| self.names.resolve(name))
+            .filter(|name| self.lookup_word(name).is_ok())
+    }
+
+    fn variable_address(&self, name: &str) -> Option<usize> {
+        match self.lookup_word(&name.to_lowercase()) {
+            Ok(Operation::Address(address)) => Some(address),
+            _ => None,
+        }
+    }
+
+    /// Current value of the variable `name`, if `name` is a variable.
+    pub fn get_var(&self, name: &str) -> Option<Value> {
+        self.variable_address(name)
+            .map(|address| self.data_space[address])
+    }
+
+    /// Stores `value` in the variable `name`.
+    pub fn set_var(&mut self, name: &str, value: Value) -> Result {
+        let address = self.variable_address(name).ok_or(Error::UnknownWord)?;
+        Arc::make_mut(&mut self.data_space)[address] = value;
+        #[cfg(feature = "tagged")]
+        self.tag_cell(address, Tag::Number);
+        Ok(())
     }
 
     pub fn eval(&mut self, input: &str) -> Result {
-        for command in split_commands(input) {
-            self.eval_command(&command)?
+        let outcome = self
+            .eval_input(input)
+            .map_err(|located| located.fault.error);
+        self.record(input, &outcome);
+        outcome
+    }
+
+    /// Like `eval`, but a failure comes with the offending span, the command
+    /// text, and notes and suggestions for rendering a helpful message.
+    pub fn eval_diagnostics(&mut self, input: &str) -> std::result::Result<(), Diagnostics> {
+        let outcome = self.eval_input(input);
+        self.record(
+            input,
+            &outcome
+                .as_ref()
+                .map_err(|located| located.fault.error.clone())
+                .copied(),
+        );
+        outcome.map_err(|located| self.diagnose(input, located))
+    }
+
+    fn eval_input(&mut self, input: &str) -> std::result::Result<(), Located> {
+        let outcome = self.eval_commands(input);
+        #[cfg(feature = "log")]
+        log_failure(input, &outcome);
+        outcome
+    }
+
+    fn eval_commands(&mut self, input: &str) -> std::result::Result<(), Located> {
+        self.start_run();
+        self.check_syntax(input)?;
+        for command in commands(input) {
+            let command = command.map_err(|malformed| self.malformed(malformed))?;
+            if let Some(coverage) = &mut self.coverage {
+                coverage.locate(input, command);
+            }
+            self.locate(input, command);
+            let text = &input[command.start..command.end];
+            #[cfg(feature = "log")]
+            log::trace!("evaluating `{text}`");
+            let outcome = self.eval_command(text);
+            if let Some(coverage) = &mut self.coverage {
+                coverage.unlocate();
+            }
+            self.locating = None;
+            outcome.map_err(|fault| Located { fault, command })?;
         }
         Ok(())
     }
 
-    fn eval_command(&mut self, command: &str) -> Result {
-        match parse_command(command)? {
-            Command::Definition(name, tokens) => self.raw_definitions.push((name, tokens)),
-            Command::Expression(mut tokens) => {
-                while !tokens.is_empty() {
-                    let token = tokens.remove(0);
-                    match token {
-                        Token::Number(i) => self.stack.push(i),
-                        Token::Word(str) => match self.expand_word(&str)? {
-                            Operation::UserDefined(op_tokens) => {
-                                append_front(&mut tokens, op_tokens)
-                            }
-                            op => do_operation(&op)(&mut self.stack)?,
-                        },
+    /// Turns recording of evaluated inputs on or off. Turning it off discards
+    /// what was recorded so far.
+    pub fn set_history(&mut self, enabled: bool) {
+        self.history = if enabled {
+            Some(self.history.take().unwrap_or_default())
+        } else {
+            None
+        };
+    }
+
+    /// Every input evaluated since history was enabled, with its outcome.
+    pub fn history(&self) -> &[HistoryEntry] {
+        self.history.as_deref().unwrap_or_default()
+    }
+
+    /// Evaluates the inputs of `entries` in order and returns the index of the
+    /// first one whose outcome differs from the recorded one, if any.
+    pub fn replay(&mut self, entries: &[HistoryEntry]) -> Option<usize> {
+        entries
+            .iter()
+            .position(|entry| self.eval(&entry.input) != entry.outcome)
+    }
+
+    pub(crate) fn record(&mut self, input: &str, outcome: &Result) {
+        if let Some(history) = &mut self.history {
+            history.push(HistoryEntry {
+                input: input.to_string(),
+                outcome: outcome.clone(),
+            });
+        }
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        self.metrics
+    }
+
+    /// What the most recent call of `eval`, `eval_async`, `eval_diagnostics`,
+    /// `eval_tokens` or `run` used, whether it succeeded or not.
+    pub fn last_run_stats(&self) -> RunStats {
+        RunStats {
+            output_bytes: self.output.written(),
+            ..self.run_stats
+        }
+    }
+
+    /// Starts counting `RunStats` afresh for an evaluation.
+    pub(crate) fn start_run(&mut self) {
+        self.run_stats = RunStats::default();
+        self.failed_at = None;
+        self.output.reset_written();
+    }
+
+    pub fn reset_metrics(&mut self) {
+        self.metrics = Metrics::default();
+        self.peephole_stats = PeepholeStats::default();
+    }
+
+    /// Clears what `options` asks for, as `quit` and `abort` do in a
+    /// traditional Forth, so that a REPL can clear the stack without
+    /// destroying definitions. Settings and metrics are kept.
+    pub fn reset(&mut self, options: ResetOptions) {
+        if options.stack {
+            self.stack.replace(Vec::new());
+        }
+        if options.return_stack {
+            self.frames.clear();
+            self.tasks.clear();
+        }
+        if options.dictionary {
+            self.rewind(BUILTINS);
+        }
+        if options.data_space {
+            Arc::make_mut(&mut self.data_space).fill(0);
+            #[cfg(feature = "tagged")]
+            self.cell_tags.clear();
+            self.set_base(10);
+        }
+    }
+
+    /// Evaluates `input` as a whole: if any command fails, the stack and the
+    /// dictionary are restored to what they were before the call.
+    pub fn eval_atomic(&mut self, input: &str) -> Result {
+        let snapshot = self.snapshot();
+        self.eval(input).inspect_err(|_| self.restore(snapshot))
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            stack: self.stack.as_slice().to_vec(),
+            data_space: self.data_space.clone(),
+            names: self.names.clone(),
+            dictionary: self.dictionary.clone(),
+            code: self.code.clone(),
+            words: self.words.clone(),
+            usage: self.usage.clone(),
+            stacks: self.stacks.clone(),
+            tasks: self.tasks.clone(),
+            #[cfg(feature = "tagged")]
+            stack_tags: self.stack.tags().to_vec(),
+            #[cfg(feature = "tagged")]
+            cell_tags: self.cell_tags.clone(),
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        self.stack.replace(snapshot.stack);
+        #[cfg(feature = "tagged")]
+        {
+            self.stack.set_tags(snapshot.stack_tags);
+            self.cell_tags = snapshot.cell_tags;
+        }
+        self.data_space = snapshot.data_space;
+        self.dictionary = snapshot.dictionary;
+        self.code = snapshot.code;
+        self.words = snapshot.words;
+        self.stacks = snapshot.stacks;
+        self.tasks = snapshot.tasks;
+        #[cfg(feature = "jit")]
+        self.jit.truncate(self.words.len());
+        if let Some(coverage) = &mut self.coverage {
+            coverage.truncate(self.words.len(), self.code.len());
+        }
+        if self.sources.range(self.code.len()..).next().is_some() {
+            Arc::make_mut(&mut self.sources).split_off(&self.code.len());
+        }
+        self.usage = snapshot.usage;
+    }
+
+    /// Like `eval`, but a failing command does not abort the input: evaluation
+    /// carries on with the next command and every failure is reported.
+    /// Stack effects of a failed command up to the point of failure are kept.
+    pub fn eval_lenient(&mut self, input: &str) -> EvalReport {
+        let mut report = EvalReport::default();
+        if let Err(located) = self.check_syntax(input) {
+            let command = input.to_string();
+            let error = located.fault.error;
+            report.failures.push(CommandFailure { command, error });
+        } else {
+            for span in commands(input) {
+                let span = match span {
+                    Ok(span) => span,
+                    Err(Malformed { command, .. }) => {
+                        report.failures.push(CommandFailure {
+                            command: input[command.start..command.end].to_string(),
+                            error: Error::InvalidWord,
+                        });
+                        continue;
                     }
+                };
+                let command = &input[span.start..span.end];
+                if let Some(coverage) = &mut self.coverage {
+                    coverage.locate(input, span);
+                }
+                self.locate(input, span);
+                let outcome = self.eval_command(command);
+                if let Some(coverage) = &mut self.coverage {
+                    coverage.unlocate();
+                }
+                self.locating = None;
+                if let Err(fault) = outcome {
+                    let (command, error) = (command.to_string(), fault.error);
+                    report.failures.push(CommandFailure { command, error });
                 }
             }
         }
+        let outcome = match report.failures.first() {
+            Some(failure) => Err(failure.error.clone()),
+            None => Ok(()),
+        };
+        self.record(input, &outcome);
+        report
+    }
+
+    /// Evaluates each program against its own copy of this interpreter, so
+    /// definitions made here act as a shared prelude and programs can't see
+    /// each other. `self` is left as it is.
+    pub fn eval_batch(&self, programs: &[&str]) -> Vec<Result> {
+        programs
+            .iter()
+            .map(|program| self.clone().eval(program))
+            .collect()
+    }
+
+    /// Like `eval_batch`, but spreads the programs over rayon's thread pool.
+    #[cfg(feature = "parallel")]
+    pub fn par_eval_batch(&self, programs: &[&str]) -> Vec<Result> {
+        use rayon::prelude::*;
+        programs
+            .par_iter()
+            .map(|program| self.clone().eval(program))
+            .collect()
+    }
+
+    /// Runs a pre-parsed program, see `Program`.
+    pub fn run(&mut self, program: &Program) -> Result {
+        self.start_run();
+        program
+            .commands
+            .iter()
+            .try_for_each(|command| self.run_command(command))
+            .map_err(|fault| fault.error)
+    }
+
+    /// The user-defined word `name` as a function from the stack it starts
+    /// with, the last of the values given being the top, to the stack it
+    /// leaves, run without lexing or looking anything up. The function has a
+    /// copy of the interpreter to itself, sharing its compiled code, so what
+    /// is defined here afterwards doesn't change it, and what the word
+    /// stores stays in the copy from one call to the next.
+    pub fn compile_fn(
+        &self,
+        name: &str,
+    ) -> std::result::Result<
+        impl FnMut(&mut [Value]) -> std::result::Result<Vec<Value>, Error>,
+        Error,
+    > {
+        let Operation::UserDefined(word) = self.lookup_word(&name.to_lowercase())? else {
+            return Err(Error::InvalidWord);
+        };
+        let mut forth = self.clone();
+        forth.history = None;
+        Ok(move |args: &mut [Value]| {
+            forth.stack.replace(args.to_vec());
+            forth.execute(&[Op::Call(word)], &mut Input::new(&[]))?;
+            Ok(forth.stack.split_off(0))
+        })
+    }
+
+    /// Evaluates a single pre-parsed expression.
+    pub fn eval_tokens(&mut self, tokens: &[Token]) -> Result {
+        self.start_run();
+        self.run_command(&Command::Expression(
+            tokens.iter().map(Lexeme::from).collect(),
+        ))
+        .map_err(|fault| fault.error)
+    }
+
+    pub(crate) fn eval_command(&mut self, command: &str) -> std::result::Result<(), Fault> {
+        if lex(command).next().is_some_and(|token| token.is_word(":")) {
+            self.run_command(&parse_command(command, self.base())?)
+        } else {
+            self.run_expression(Input::text(lex(command)))
+        }
+    }
+
+    fn run_command(&mut self, command: &Command) -> std::result::Result<(), Fault> {
+        match command {
+            Command::Definition(name, effect, tokens) => self.define(name, effect.clone(), tokens),
+            Command::Expression(tokens) => self.run_expression(Input::new(tokens)),
+        }
+    }
+
+    fn run_expression(&mut self, mut input: Input<'_>) -> std::result::Result<(), Fault> {
+        while let Some(token) = self.next_token(&mut input) {
+            let (index, depth) = (input.position(), self.stack.len());
+            let fault = |error| Fault {
+                error,
+                token: Some(index),
+                depth,
+            };
+            let op = self.token_op(&token).map_err(fault)?;
+            self.execute(&[op], &mut input).map_err(fault)?
+        }
         Ok(())
     }
+
+    /// The execution token of what `word` means, as `'` and `[']` push it:
+    /// the index of a user-defined word, or for a builtin one less than
+    /// minus where its op comes in `builtin_ops`, so `/` gives whichever of
+    /// its variants the settings pick. Variables, markers and control words
+    /// have none, and fail with `Error::InvalidWord`.
+    pub(crate) fn xt(&self, word: &str) -> std::result::Result<Value, Error> {
+        match self.lookup_word(word)? {
+            Operation::UserDefined(word) => Ok(word as Value),
+            Operation::Builtin(op) => {
+                let position = builtin_ops()
+                    .position(|builtin| builtin == op)
+                    .expect("every builtin is in a table");
+                Ok(-1 - position as Value)
+            }
+            _ => Err(Error::InvalidWord),
+        }
+    }
+
+    /// What running `token` outside a definition amounts to.
+    pub(crate) fn token_op(&self, token: &Lexeme) -> std::result::Result<Op, Error> {
+        Ok(match token {
+            Lexeme::Number(i) => Op::Push(*i),
+            Lexeme::Char(c) => push_char(*c),
+            Lexeme::Word(word) => match self.lookup_word(word)? {
+                Operation::Builtin(op) => op,
+                Operation::Address(address) => push_address(address),
+                Operation::UserDefined(word) => Op::Call(word),
+                Operation::Control(_) => return Err(Error::InvalidWord),
+                Operation::Marker(entry) => Op::Rewind(entry),
+            },
+        })
+    }
+}
+
+/// The op pushing the address of a variable, which with the `tagged`
+/// feature tags it as one.
+pub(crate) const fn push_address(address: usize) -> Op {
+    #[cfg(feature = "tagged")]
+    return Op::PushTagged(address as Value, Tag::Address);
+    #[cfg(not(feature = "tagged"))]
+    Op::Push(address as Value)
+}
+
+/// The op pushing the character literal `c`, which with the `tagged`
+/// feature tags it as one.
+pub(crate) fn push_char(c: Value) -> Op {
+    #[cfg(feature = "tagged")]
+    return Op::PushTagged(c, Tag::Char);
+    #[cfg(not(feature = "tagged"))]
+    Op::Push(c)
+}
+
+/// Logs why evaluating `input` failed, if it did.
+#[cfg(feature = "log")]
+pub(crate) fn log_failure(input: &str, outcome: &std::result::Result<(), Located>) {
+    if let Err(Located { fault, command }) = outcome {
+        let text = input.get(command.start..command.end).unwrap_or(input);
+        log::debug!("`{text}` failed: {}", fault.error);
+    }
+}
+
+/// Evaluates `prelude` once, then each program against its own copy of the
+/// result, spread over rayon's thread pool. Returns the final stack of each
+/// program, or `Err` with the prelude's error if the prelude fails.
+#[cfg(feature = "parallel")]
+pub fn par_eval_prelude(
+    programs: &[&str],
+    prelude: &str,
+) -> std::result::Result<Vec<std::result::Result<Vec<Value>, Error>>, Error> {
+    use rayon::prelude::*;
+    let mut shared = Forth::new();
+    shared.eval(prelude)?;
+    Ok(programs
+        .par_iter()
+        .map(|program| {
+            let mut f = shared.clone();
+            f.eval(program).map(|()| f.drain_stack())
+        })
+        .collect())
 }
@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+
+use crate::bytecode::{Control, Op, Primitive};
+use crate::diagnostics::token_spans;
+use crate::lexer::{lex, Lexeme};
+use crate::{Forth, Operation, Span};
+
+/// A problem `check` found in a script without running it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    pub kind: LintKind,
+    /// The offending token.
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintKind {
+    /// Neither defined before this point nor a number.
+    UnknownWord,
+    /// A `:` without a `;`, or with another `:` before it.
+    UnclosedDefinition,
+    /// A `;` outside a definition.
+    UnmatchedSemicolon,
+    /// A `:`, `variable` or `marker` at the end of the input, or a `:` right
+    /// before `;`.
+    MissingName,
+    /// An `if` without a `then` before the end of its definition.
+    UnclosedIf,
+    /// An `else` or `then` without an `if`.
+    UnmatchedControl,
+    /// `if`, `else`, `then` or `recurse` outside a definition.
+    ControlOutsideDefinition,
+    /// A builtin that will find too few values on the stack.
+    StackUnderflow,
+}
+
+impl std::fmt::Display for LintKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LintKind::UnknownWord => "unknown word",
+            LintKind::UnclosedDefinition => "definition is not closed by `;`",
+            LintKind::UnmatchedSemicolon => "`;` outside a definition",
+            LintKind::MissingName => "missing name",
+            LintKind::UnclosedIf => "`if` is not closed by `then`",
+            LintKind::UnmatchedControl => "no `if` to match",
+            LintKind::ControlOutsideDefinition => "only allowed inside a definition",
+            LintKind::StackUnderflow => "stack underflow",
+        })
+    }
+}
+
+/// Checks `input` against the builtins, see `Forth::check`.
+pub fn check(input: &str) -> Vec<Lint> {
+    Forth::new().check(input)
+}
+
+/// What the script itself defined, which takes precedence over the
+/// dictionary.
+#[derive(Clone, Copy)]
+enum Local {
+    Word,
+    Variable,
+    /// A name that is only referred to, as by `forget`, and needn't have
+    /// been defined by the script.
+    Referenced,
+}
+
+struct Checker<'f> {
+    forth: &'f Forth,
+    locals: HashMap<String, Local>,
+    lints: Vec<Lint>,
+    /// Values on the stack at the top level, as long as it can be told.
+    depth: Option<usize>,
+}
+
+/// Where the checker is in the input.
+enum State {
+    TopLevel,
+    /// After `:`, at the span of the `:`.
+    Naming(Span),
+    /// Inside a definition, with the spans of its `:` and of its open `if`s.
+    Defining {
+        name: String,
+        colon: Span,
+        ifs: Vec<Span>,
+    },
+    /// After a word that parses the next one as a name.
+    Declaring(Local),
+    /// After `synonym`, and then after the new name it parsed.
+    Aliasing(Option<String>),
+}
+
+impl Forth {
+    /// Looks for mistakes in `input` without running it: words that are
+    /// unknown at the point they are used, given this interpreter's
+    /// dictionary and what `input` defines before them; unbalanced `: ;` and
+    /// `if then`; and builtins at the top level that will underflow a stack
+    /// starting out as this one is. The stack can't be followed past calls
+    /// of user-defined words, and `forget` and markers are not followed.
+    pub fn check(&self, input: &str) -> Vec<Lint> {
+        let mut checker = Checker {
+            forth: self,
+            locals: HashMap::new(),
+            lints: Vec::new(),
+            depth: Some(self.stack.len()),
+        };
+        let mut state = State::TopLevel;
+        for (lexeme, span) in lex(input).zip(token_spans(input)) {
+            state = checker.token(state, lexeme, span);
+        }
+        match state {
+            State::TopLevel => {}
+            State::Naming(span) => checker.lint(LintKind::MissingName, span),
+            State::Defining { colon, ifs, .. } => {
+                checker.lint(LintKind::UnclosedDefinition, colon);
+                ifs.into_iter()
+                    .for_each(|span| checker.lint(LintKind::UnclosedIf, span));
+            }
+            State::Declaring(_) | State::Aliasing(_) => {
+                let end = Span::new(input.len(), input.len());
+                checker.lint(LintKind::MissingName, end)
+            }
+        }
+        checker.lints
+    }
+}
+
+impl Checker<'_> {
+    fn lint(&mut self, kind: LintKind, span: Span) {
+        self.lints.push(Lint { kind, span });
+    }
+
+    fn token(&mut self, state: State, lexeme: Lexeme<'_>, span: Span) -> State {
+        match (state, lexeme) {
+            (State::TopLevel, Lexeme::Number(_) | Lexeme::Char(_)) => {
+                self.depth = self.depth.map(|depth| depth + 1);
+                State::TopLevel
+            }
+            (State::TopLevel, Lexeme::Word(word)) => match &*word {
+                ":" => State::Naming(span),
+                ";" => {
+                    self.lint(LintKind::UnmatchedSemicolon, span);
+                    State::TopLevel
+                }
+                _ => self.run(&word, span),
+            },
+            (State::Naming(colon), Lexeme::Word(word)) if word == ";" => {
+                self.lint(LintKind::MissingName, colon);
+                State::TopLevel
+            }
+            (State::Naming(colon), lexeme) => {
+                let name = match lexeme {
+                    Lexeme::Word(word) => word.into_owned(),
+                    lexeme => lexeme.to_string(),
+                };
+                State::Defining {
+                    name,
+                    colon,
+                    ifs: Vec::new(),
+                }
+            }
+            (defining @ State::Defining { .. }, Lexeme::Number(_) | Lexeme::Char(_)) => defining,
+            (
+                State::Defining {
+                    name,
+                    colon,
+                    mut ifs,
+                },
+                Lexeme::Word(word),
+            ) => {
+                match &*word {
+                    ":" => {
+                        self.lint(LintKind::UnclosedDefinition, colon);
+                        return State::Naming(span);
+                    }
+                    ";" => {
+                        ifs.into_iter()
+                            .for_each(|span| self.lint(LintKind::UnclosedIf, span));
+                        self.locals.insert(name, Local::Word);
+                        return State::TopLevel;
+                    }
+                    _ => {}
+                }
+                match self.lookup(&word) {
+                    Some(Meaning::Control(Control::If)) => ifs.push(span),
+                    Some(Meaning::Control(Control::Else)) if !ifs.is_empty() => {}
+                    Some(Meaning::Control(Control::Then)) if ifs.pop().is_some() => {}
+                    Some(Meaning::Control(Control::Else | Control::Then)) => {
+                        self.lint(LintKind::UnmatchedControl, span)
+                    }
+                    Some(_) => {}
+                    None => self.lint(LintKind::UnknownWord, span),
+                }
+                State::Defining { name, colon, ifs }
+            }
+            (State::Declaring(Local::Referenced), _) => State::TopLevel,
+            (State::Aliasing(None), lexeme) => State::Aliasing(Some(match lexeme {
+                Lexeme::Word(word) => word.into_owned(),
+                lexeme => lexeme.to_string(),
+            })),
+            (State::Aliasing(Some(name)), lexeme) => {
+                let local = match lexeme {
+                    Lexeme::Word(word) => match self.lookup(&word) {
+                        None => {
+                            self.lint(LintKind::UnknownWord, span);
+                            Local::Word
+                        }
+                        Some(
+                            Meaning::Local(Local::Variable)
+                            | Meaning::Operation(Operation::Address(_)),
+                        ) => Local::Variable,
+                        Some(_) => Local::Word,
+                    },
+                    Lexeme::Number(_) | Lexeme::Char(_) => Local::Word,
+                };
+                self.locals.insert(name, local);
+                State::TopLevel
+            }
+            (State::Declaring(local), lexeme) => {
+                if let Lexeme::Word(word) = lexeme {
+                    self.locals.insert(word.into_owned(), local);
+                }
+                State::TopLevel
+            }
+        }
+    }
+
+    /// Follows `word` run at the top level.
+    fn run(&mut self, word: &str, span: Span) -> State {
+        let meaning = self.lookup(word);
+        let (inputs, outputs) = match meaning {
+            None => {
+                self.lint(LintKind::UnknownWord, span);
+                return State::TopLevel;
+            }
+            Some(Meaning::Control(_)) => {
+                self.lint(LintKind::ControlOutsideDefinition, span);
+                return State::TopLevel;
+            }
+            Some(Meaning::Operation(Operation::Builtin(op))) => match op {
+                // A stack's word pushes its number as a variable's does its
+                // address.
+                Op::Variable | Op::DefineStack | Op::User => {
+                    return State::Declaring(Local::Variable)
+                }
+                Op::Mark => return State::Declaring(Local::Word),
+                Op::Synonym => return State::Aliasing(None),
+                Op::Forget | Op::Disassemble | Op::Help | Op::Spawn => {
+                    return State::Declaring(Local::Referenced)
+                }
+                Op::Tick => {
+                    self.depth = self.depth.map(|depth| depth + 1);
+                    return State::Declaring(Local::Referenced);
+                }
+                Op::Include => {
+                    // What the source does is only known when it runs.
+                    self.depth = None;
+                    return State::Declaring(Local::Referenced);
+                }
+                #[cfg(feature = "env")]
+                Op::GetEnv => {
+                    self.depth = self.depth.map(|depth| depth + 2);
+                    return State::Declaring(Local::Referenced);
+                }
+                #[cfg(feature = "env")]
+                Op::SetEnv => {
+                    self.depth = None;
+                    return State::Declaring(Local::Referenced);
+                }
+                #[cfg(feature = "file-io")]
+                Op::File(word) => {
+                    // Whether the word succeeds is only known when it runs.
+                    self.depth = None;
+                    if word.parses_name() {
+                        return State::Declaring(Local::Referenced);
+                    }
+                    return State::TopLevel;
+                }
+                #[cfg(feature = "net")]
+                Op::Net(word) => {
+                    // Whether the word succeeds is only known when it runs.
+                    self.depth = None;
+                    if word == crate::net::NetWord::Open {
+                        return State::Declaring(Local::Referenced);
+                    }
+                    return State::TopLevel;
+                }
+                #[cfg(feature = "peripherals")]
+                Op::Peripheral(word) => word.effect(),
+                #[cfg(feature = "dlopen")]
+                Op::Foreign(word) => {
+                    // How many arguments `dlcall` takes is only known when it
+                    // runs.
+                    self.depth = None;
+                    if word.parses_name() {
+                        return State::Declaring(Local::Referenced);
+                    }
+                    return State::TopLevel;
+                }
+                Op::Primitive(primitive) => match effect(primitive) {
+                    Some(effect) => effect,
+                    None => {
+                        self.depth = None;
+                        return State::TopLevel;
+                    }
+                },
+                _ => (0, 0),
+            },
+            Some(Meaning::Local(Local::Variable) | Meaning::Operation(Operation::Address(_))) => {
+                (0, 1)
+            }
+            _ => {
+                self.depth = None;
+                return State::TopLevel;
+            }
+        };
+        if let Some(depth) = self.depth {
+            if depth < inputs {
+                self.lint(LintKind::StackUnderflow, span);
+                // Evaluation stops here, so there is nothing more to tell.
+                self.depth = None;
+            } else {
+                self.depth = Some(depth - inputs + outputs);
+            }
+        }
+        State::TopLevel
+    }
+
+    /// What `word` means at this point.
+    fn lookup(&self, word: &str) -> Option<Meaning> {
+        if let Some(&local) = self.locals.get(word) {
+            return Some(Meaning::Local(local));
+        }
+        match self.forth.lookup_word(word).ok()? {
+            Operation::Control(control) => Some(Meaning::Control(control)),
+            operation => Some(Meaning::Operation(operation)),
+        }
+    }
+}
+
+enum Meaning {
+    Local(Local),
+    Control(Control),
+    Operation(Operation),
+}
+
+/// Values a primitive pops and pushes, if that is fixed.
+pub(crate) fn effect(primitive: Primitive) -> Option<(usize, usize)> {
+    Some(match primitive {
+        Primitive::Add | Primitive::Subtract | Primitive::Multiply | Primitive::Divide => (2, 1),
+        Primitive::Modulo | Primitive::FlooredDivide | Primitive::FlooredModulo => (2, 1),
+        Primitive::DivMod | Primitive::FlooredDivMod => (2, 2),
+        Primitive::WrappingAdd | Primitive::WrappingSubtract | Primitive::WrappingMultiply => {
+            (2, 1)
+        }
+        Primitive::Dup => (1, 2),
+        Primitive::Drop => (1, 0),
+        Primitive::Swap => (2, 2),
+        Primitive::Over => (2, 3),
+        Primitive::Fetch => (1, 1),
+        Primitive::Store => (2, 0),
+        Primitive::Square => (1, 1),
+        Primitive::SwapSubtract | Primitive::OverAdd => (2, 1),
+        Primitive::TestSummary => (0, 0),
+        Primitive::Send => (1, 0),
+        Primitive::Receive => (0, 1),
+        Primitive::TryReceive => (0, 2),
+        Primitive::Utime => (0, 1),
+        Primitive::Elapsed => (1, 1),
+        Primitive::ToStack => (2, 0),
+        Primitive::FromStack => (1, 1),
+        Primitive::Tasks => (0, 1),
+        // Unless it throws, which ends the evaluation if nothing catches it.
+        Primitive::Throw => (1, 0),
+        Primitive::TestOpen | Primitive::TestArrow | Primitive::TestClose => return None,
+        Primitive::Evaluate => return None,
+        Primitive::Emit => (1, 0),
+        Primitive::Random => (0, 1),
+        Primitive::Hex | Primitive::Decimal => (0, 0),
+    })
+}
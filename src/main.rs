@@ -0,0 +1,568 @@
+//! `forth` starts an interactive prompt: each line is evaluated against the
+//! same interpreter, so definitions carry over, and the stack or the error is
+//! printed after it. At a terminal, lines can be edited, Tab completes the
+//! words defined so far, and the lines entered are kept across sessions in
+//! `$FORTH_HISTORY`, or `~/.forth_history` if that isn't set.
+//!
+//! `forth run script.fs 3 4` evaluates the script with `3 4` already on the
+//! stack and prints the stack it leaves.
+//!
+//! `--trace` writes each word run and the stack it leaves. `--profile`
+//! prints how often each word and primitive ran to stderr when done; words
+//! are not inlined, so every call is counted. `--coverage`, with `run` or
+//! `debug`, writes which lines of the script's definitions ran to
+//! `script.fs.lcov`, in lcov's format. `--timeline`, with `run` or `debug`,
+//! writes when each call of a word started and how long it took to
+//! `script.fs.trace.json`, in the Chrome trace-event format Perfetto opens;
+//! words are not inlined, so every call is there.
+//!
+//! `forth debug script.fs 3 4` does the same, stopping before each word to
+//! take debugger commands; `help` lists them.
+//!
+//! `forth tui` is a prompt that redraws the screen after each line, with
+//! panes for the stack, the words that were running when the line stopped,
+//! and the last error.
+//!
+//! `forth doc script.fs` evaluates the script and prints a Markdown table of
+//! the words it defines with their stack-effect comments.
+//!
+//! `forth transpile script.fs` evaluates the script and prints Rust source
+//! with a function for each word it defines, to be built into a host that
+//! runs them through `forth::runtime`. Words named after the script, as in
+//! `forth transpile script.fs main`, keep only those and what they call.
+//!
+//! `forth wasm script.fs square cube` compiles the script to `script.wasm`, a
+//! WebAssembly module exporting what the script runs outside its
+//! definitions as `main`, and the words named after it.
+//!
+//! `forth fmt a.fs b.fs` formats the files in place. `--check` changes
+//! nothing, but names the files that aren't formatted and fails if there are
+//! any; `--indent=2` and `--line-length=80` are the defaults.
+
+use std::collections::HashSet;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+
+use forth::{FormatOptions, Forth, Optimizations, Value};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+const USAGE: &str =
+    "usage: forth [--trace] [--profile] [--coverage] [--timeline] [tui | doc <script.fs> | transpile <script.fs> [words...] | wasm <script.fs> [words...] | fmt [--check] [--indent=N] [--line-length=N] <files...> | run|debug <script.fs> [numbers...]]";
+
+const DEBUG_HELP: &str = "\
+step        run the next word, stepping into definitions
+next        run the next word, stepping over definitions
+continue    run until a breakpoint
+stack       print the stack
+locals      print the variables and their values
+break WORD  stop whenever WORD is about to run";
+
+fn main() -> ExitCode {
+    let (flags, args): (Vec<String>, Vec<String>) = std::env::args()
+        .skip(1)
+        .partition(|arg| arg.starts_with("--"));
+    let (mut trace, mut profile, mut coverage, mut timeline) = (false, false, false, false);
+    let (mut check, mut layout, mut formatting) = (false, FormatOptions::default(), false);
+    for flag in &flags {
+        let setting = flag
+            .split_once('=')
+            .and_then(|(name, value)| Some((name, value.parse::<usize>().ok()?)));
+        match (flag.as_str(), setting) {
+            ("--trace", _) => trace = true,
+            ("--profile", _) => profile = true,
+            ("--coverage", _) => coverage = true,
+            ("--timeline", _) => timeline = true,
+            ("--check", _) => check = true,
+            (_, Some(("--indent", spaces))) => layout.indent = spaces,
+            (_, Some(("--line-length", length))) if length > 0 => layout.line_length = length,
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+        }
+        formatting |= setting.is_some() || flag == "--check";
+    }
+    if formatting && args.first().is_none_or(|command| command != "fmt") {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    }
+    let script = match args.first().map(String::as_str) {
+        Some("run" | "debug") => args.get(1),
+        _ => None,
+    };
+    if (coverage || timeline) && script.is_none() {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    }
+    let debugging = args
+        .first()
+        .is_some_and(|command| command == "debug" || command == "tui");
+    let mut optimizations = Optimizations::default();
+    if debugging {
+        // Words are run as written, so each step is one of them.
+        optimizations = Optimizations {
+            fold_constants: false,
+            inline_threshold: 0,
+            superinstructions: false,
+            peephole: false,
+        };
+    }
+    if profile || timeline {
+        optimizations.inline_threshold = 0;
+    }
+    let mut forth = Forth::builder().optimizations(optimizations).build();
+    forth.set_trace(trace);
+    forth.set_profiling(profile);
+    forth.set_coverage(coverage);
+    forth.set_timeline(timeline);
+    let outcome = match args.first().map(String::as_str) {
+        None if io::stdin().is_terminal() => interactive(&mut forth).map(|()| ExitCode::SUCCESS),
+        None => {
+            repl(&mut forth, io::stdin().lock(), io::stdout().lock()).map(|()| ExitCode::SUCCESS)
+        }
+        Some("tui") if args.len() == 1 => {
+            tui(&mut forth, io::stdin().lock(), io::stdout().lock()).map(|()| ExitCode::SUCCESS)
+        }
+        Some("doc") if args.len() == 2 => doc(&mut forth, &args[1]),
+        Some("transpile") if args.len() >= 2 => transpile(&mut forth, &args[1], &args[2..]),
+        Some("wasm") if args.len() >= 2 => wasm(&mut forth, &args[1], &args[2..]),
+        Some("fmt") if args.len() >= 2 => fmt(&args[1..], layout, check),
+        Some("run") if args.len() >= 2 => run(&mut forth, &args[1], &args[2..]),
+        Some("debug") if args.len() >= 2 => {
+            attach_debugger(&mut forth);
+            run(&mut forth, &args[1], &args[2..])
+        }
+        Some(_) => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    if profile {
+        print_profile(&forth.profile());
+    }
+    if let Some(path) = script.filter(|_| coverage) {
+        let lcov = forth.coverage().to_lcov(path);
+        if let Err(error) = std::fs::write(format!("{path}.lcov"), lcov) {
+            eprintln!("forth: {error}");
+            return ExitCode::FAILURE;
+        }
+    }
+    if let Some(path) = script.filter(|_| timeline) {
+        let trace = forth.timeline().to_chrome_trace();
+        if let Err(error) = std::fs::write(format!("{path}.trace.json"), trace) {
+            eprintln!("forth: {error}");
+            return ExitCode::FAILURE;
+        }
+    }
+    outcome.unwrap_or_else(|error| {
+        eprintln!("forth: {error}");
+        ExitCode::FAILURE
+    })
+}
+
+fn repl(forth: &mut Forth, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut lines = input.lines();
+    loop {
+        write!(output, "> ")?;
+        output.flush()?;
+        let Some(line) = lines.next().transpose()? else {
+            return writeln!(output);
+        };
+        if !respond(forth, &line, &mut output)? {
+            return Ok(());
+        }
+    }
+}
+
+/// Like `repl`, but reading from the terminal with line editing, history and
+/// completion.
+fn interactive(forth: &mut Forth) -> io::Result<()> {
+    let mut editor = Editor::new().map_err(io::Error::other)?;
+    editor.set_helper(Some(Words::of(forth)));
+    let history = history_path();
+    if let Some(path) = &history {
+        // There is nothing to load the first time.
+        let _ = editor.load_history(path);
+    }
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(error) => return Err(io::Error::other(error)),
+        };
+        editor
+            .add_history_entry(line.as_str())
+            .map_err(io::Error::other)?;
+        if !respond(forth, &line, &mut io::stdout().lock())? {
+            break;
+        }
+        editor.set_helper(Some(Words::of(forth)));
+    }
+    match &history {
+        Some(path) => editor.save_history(path).map_err(io::Error::other),
+        None => Ok(()),
+    }
+}
+
+/// Evaluates a line typed at the prompt and prints the warnings it raised,
+/// then the stack or the error.
+/// Returns `false` once the session is over.
+fn respond(forth: &mut Forth, line: &str, output: &mut impl Write) -> io::Result<bool> {
+    if line.trim().eq_ignore_ascii_case("bye") {
+        return Ok(false);
+    }
+    let outcome = forth.eval_diagnostics(line);
+    for warning in forth.take_warnings() {
+        writeln!(output, "warning: {warning}")?;
+    }
+    match outcome {
+        Ok(()) => print_stack(output, forth.stack())?,
+        Err(diagnostics) => writeln!(output, "{diagnostics}")?,
+    }
+    Ok(true)
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("FORTH_HISTORY")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".forth_history")))
+}
+
+/// Completes the word under the cursor from the words defined when the line
+/// was started, sorted.
+struct Words(Vec<String>);
+
+impl Words {
+    fn of(forth: &Forth) -> Words {
+        let mut words: Vec<String> = forth.words().map(String::from).collect();
+        words.sort();
+        Words(words)
+    }
+}
+
+impl Completer for Words {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let prefix = line[start..pos].to_lowercase();
+        let candidates = self.0.iter().filter(|word| word.starts_with(&prefix));
+        Ok((start, candidates.cloned().collect()))
+    }
+}
+
+impl Hinter for Words {
+    type Hint = String;
+}
+
+impl Highlighter for Words {}
+
+impl Validator for Words {}
+
+impl Helper for Words {}
+
+/// Width of the stack and return stack panes, borders included.
+const PANE_WIDTH: usize = 24;
+
+/// Reads lines like `repl`, redrawing the panes after each one.
+fn tui(forth: &mut Forth, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    // Each word run is told how deep it is, so the words it is nested in are
+    // the first `depth` of the last ones seen.
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&calls);
+    forth.on_word(move |_, word, depth| {
+        let mut calls = seen.lock().unwrap_or_else(|e| e.into_inner());
+        calls.truncate(depth);
+        calls.push(word.to_string());
+    });
+    let (mut running, mut diagnostics) = (Vec::new(), String::new());
+    let mut lines = input.lines();
+    loop {
+        draw(&mut output, forth.stack(), &running, &diagnostics)?;
+        write!(output, "> ")?;
+        output.flush()?;
+        let Some(line) = lines.next().transpose()? else {
+            return writeln!(output);
+        };
+        if line.trim().eq_ignore_ascii_case("bye") {
+            return Ok(());
+        }
+        calls.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        (running, diagnostics) = match forth.eval_diagnostics(&line) {
+            Ok(()) => (Vec::new(), String::new()),
+            Err(diagnostics) => {
+                let mut running =
+                    std::mem::take(&mut *calls.lock().unwrap_or_else(|e| e.into_inner()));
+                // The last word seen is the one that failed, not a caller.
+                running.pop();
+                (running, diagnostics.to_string())
+            }
+        };
+    }
+}
+
+/// Clears the screen and draws the stack, top first, and the words that were
+/// running side by side, innermost first, with the diagnostics below.
+fn draw(
+    output: &mut impl Write,
+    stack: &[Value],
+    calls: &[String],
+    diagnostics: &str,
+) -> io::Result<()> {
+    let inner = PANE_WIDTH - 4;
+    let top = |title: &str| format!("┌ {title} {:─<1$}┐", "", PANE_WIDTH - title.len() - 4);
+    let bottom = format!("└{:─<1$}┘", "", PANE_WIDTH - 2);
+    write!(output, "\x1b[2J\x1b[H")?;
+    writeln!(
+        output,
+        "{}{}",
+        top(&format!("stack <{}>", stack.len())),
+        top("return stack")
+    )?;
+    let values: Vec<String> = stack.iter().rev().map(Value::to_string).collect();
+    for row in 0..values.len().max(calls.len()).max(1) {
+        let value = values.get(row).map_or("", String::as_str);
+        let call = calls.iter().rev().nth(row).map_or("", String::as_str);
+        writeln!(output, "│ {value:>inner$} ││ {call:<inner$} │")?;
+    }
+    writeln!(output, "{bottom}{bottom}")?;
+    writeln!(output, "{}", top("diagnostics"))?;
+    for line in diagnostics.lines() {
+        writeln!(output, "│ {line}")?;
+    }
+    writeln!(output, "{bottom}")
+}
+
+fn run(forth: &mut Forth, path: &str, args: &[String]) -> io::Result<ExitCode> {
+    let Ok(stack) = args
+        .iter()
+        .map(|arg| arg.parse())
+        .collect::<Result<Vec<Value>, _>>()
+    else {
+        eprintln!("{USAGE}");
+        return Ok(ExitCode::from(2));
+    };
+    let script = std::fs::read_to_string(path)?;
+    forth.replace_stack(stack);
+    forth.set_source_name(Some(path));
+    match forth.eval_diagnostics(&script) {
+        Ok(()) => {
+            print_stack(&mut io::stdout().lock(), forth.stack())?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(diagnostics) => {
+            report(path, &script, &diagnostics);
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+fn doc(forth: &mut Forth, path: &str) -> io::Result<ExitCode> {
+    let script = std::fs::read_to_string(path)?;
+    forth.set_output(io::sink());
+    if let Err(diagnostics) = forth.eval_diagnostics(&script) {
+        report(path, &script, &diagnostics);
+        return Ok(ExitCode::FAILURE);
+    }
+    let escape = |text: &str| text.replace('|', "\\|");
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "# Words in `{path}`\n")?;
+    writeln!(stdout, "| Word | Stack effect |")?;
+    writeln!(stdout, "| --- | --- |")?;
+    for word in forth.user_words() {
+        let effect = forth
+            .doc(word)
+            .map_or(String::new(), |effect| format!("`{}`", escape(effect)));
+        writeln!(stdout, "| `{}` | {effect} |", escape(word))?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn transpile(forth: &mut Forth, path: &str, words: &[String]) -> io::Result<ExitCode> {
+    let script = std::fs::read_to_string(path)?;
+    forth.set_output(io::sink());
+    if let Err(diagnostics) = forth.eval_diagnostics(&script) {
+        report(path, &script, &diagnostics);
+        return Ok(ExitCode::FAILURE);
+    }
+    let source = if words.is_empty() {
+        forth.transpile()
+    } else {
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        match forth.pruned(&words) {
+            Ok(pruned) => pruned.transpile(),
+            Err(error) => {
+                eprintln!("{path}: {error}");
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    };
+    io::stdout().lock().write_all(source.as_bytes())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn wasm(forth: &mut Forth, path: &str, words: &[String]) -> io::Result<ExitCode> {
+    let script = std::fs::read_to_string(path)?;
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    match forth.compile_wasm(&script, &words) {
+        Ok(module) => {
+            std::fs::write(std::path::Path::new(path).with_extension("wasm"), module)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(error) => {
+            eprintln!("{path}: {error}");
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+/// Formats each file in place, or with `check` only names those that would
+/// change and fails if there are any.
+fn fmt(paths: &[String], options: FormatOptions, check: bool) -> io::Result<ExitCode> {
+    let mut unformatted = false;
+    for path in paths {
+        let source = std::fs::read_to_string(path)?;
+        let formatted = forth::format(&source, options);
+        if formatted == source {
+            continue;
+        }
+        if check {
+            eprintln!("{path}: not formatted");
+            unformatted = true;
+        } else {
+            std::fs::write(path, formatted)?;
+        }
+    }
+    Ok(if unformatted {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn report(path: &str, script: &str, diagnostics: &forth::Diagnostics) {
+    let (line, column) = line_column(script, diagnostics.span.start);
+    eprintln!("{path}:{line}:{column}: {diagnostics}");
+}
+
+/// Stops before each word to take commands from stdin.
+fn attach_debugger(forth: &mut Forth) {
+    let mut debugger = Debugger {
+        mode: Mode::Step,
+        breakpoints: HashSet::new(),
+    };
+    forth.on_word(move |forth, word, depth| {
+        if debugger.stops_at(word, depth) {
+            // Without a terminal to talk to, let the script run to its end.
+            if debugger.prompt(forth, word, depth).is_err() {
+                debugger.mode = Mode::Continue;
+            }
+        }
+    });
+}
+
+#[derive(Clone, Copy)]
+enum Mode {
+    Step,
+    /// Stop at the next word at most this deep.
+    Next(usize),
+    Continue,
+}
+
+struct Debugger {
+    mode: Mode,
+    breakpoints: HashSet<String>,
+}
+
+impl Debugger {
+    fn stops_at(&self, word: &str, depth: usize) -> bool {
+        match self.mode {
+            Mode::Step => true,
+            Mode::Next(max) if depth <= max => true,
+            _ => self.breakpoints.contains(word),
+        }
+    }
+
+    fn prompt(&mut self, forth: &Forth, word: &str, depth: usize) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        writeln!(stdout, "{:indent$}{word}", "", indent = 2 * depth)?;
+        loop {
+            write!(stdout, "(debug) ")?;
+            stdout.flush()?;
+            let mut line = String::new();
+            if io::stdin().lock().read_line(&mut line)? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let mut words = line.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some("step" | "s"), None) => self.mode = Mode::Step,
+                (Some("next" | "n"), None) => self.mode = Mode::Next(depth),
+                (Some("continue" | "c"), None) => self.mode = Mode::Continue,
+                (Some("stack"), None) => {
+                    print_stack(&mut stdout, forth.stack())?;
+                    continue;
+                }
+                (Some("locals"), None) => {
+                    for name in forth.words() {
+                        if let Some(value) = forth.get_var(name) {
+                            writeln!(stdout, "{name} = {value}")?;
+                        }
+                    }
+                    continue;
+                }
+                (Some("break" | "b"), Some(word)) => {
+                    self.breakpoints.insert(word.to_lowercase());
+                    continue;
+                }
+                _ => {
+                    writeln!(stdout, "{DEBUG_HELP}")?;
+                    continue;
+                }
+            }
+            return Ok(());
+        }
+    }
+}
+
+fn print_profile(profile: &forth::Profile) {
+    let sections = [("calls", &profile.words), ("runs", &profile.primitives)];
+    for (heading, counts) in sections {
+        eprintln!("{heading:>10}  word");
+        for (word, count) in counts {
+            eprintln!("{count:>10}  {word}");
+        }
+    }
+}
+
+/// One-based line and column of the byte `offset` into `text`.
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Prints the stack the way `.s` does: depth first, then the values from the
+/// bottom up.
+fn print_stack(output: &mut impl Write, stack: &[Value]) -> io::Result<()> {
+    write!(output, "<{}>", stack.len())?;
+    for value in stack {
+        write!(output, " {value}")?;
+    }
+    writeln!(output)
+}
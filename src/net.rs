@@ -0,0 +1,196 @@
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+
+use crate::bytecode::Op;
+use crate::vm::Input;
+use crate::{Error, Forth, Result, Value};
+
+/// The socket words, which run as `Op::Net`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum NetWord {
+    Open,
+    Close,
+    Read,
+    Write,
+}
+
+impl NetWord {
+    /// Every socket word, in declaration order.
+    pub(crate) const ALL: [NetWord; 4] =
+        [NetWord::Open, NetWord::Close, NetWord::Read, NetWord::Write];
+}
+
+pub(crate) const OPERATIONS: [(&str, Op); 4] = [
+    ("open-socket", Op::Net(NetWord::Open)),
+    ("close-socket", Op::Net(NetWord::Close)),
+    ("read-socket", Op::Net(NetWord::Read)),
+    ("write-socket", Op::Net(NetWord::Write)),
+];
+
+/// Stack effects of the socket words, like `doc::BUILTIN_EFFECTS`.
+pub(crate) const EFFECTS: [(&str, &str); 4] = [
+    ("open-socket", "( port \"host\" -- socket ior )"),
+    ("close-socket", "( socket -- ior )"),
+    ("read-socket", "( addr u1 socket -- u2 ior )"),
+    ("write-socket", "( addr u socket -- ior )"),
+];
+
+/// A connection made by a `NetProvider`.
+pub trait Connection: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Connection for T {}
+
+/// How `open-socket` connects, so that hosts can decide where scripts may
+/// connect to, or hand them a mock transport in tests.
+pub trait NetProvider: Send + Sync {
+    fn connect(&self, host: &str, port: u16) -> io::Result<Box<dyn Connection>>;
+}
+
+/// Connects over TCP, to any host. The default provider.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TcpProvider;
+
+impl NetProvider for TcpProvider {
+    fn connect(&self, host: &str, port: u16) -> io::Result<Box<dyn Connection>> {
+        Ok(Box::new(TcpStream::connect((host, port))?))
+    }
+}
+
+/// An open socket, which clones of the interpreter share.
+type Socket = Arc<Mutex<Box<dyn Connection>>>;
+
+/// Sockets scripts have opened, and the provider that opened them.
+#[derive(Clone)]
+pub(crate) struct Sockets {
+    provider: Arc<dyn NetProvider>,
+    /// Indexed by socket id less one.
+    open: Vec<Option<Socket>>,
+}
+
+impl Default for Sockets {
+    fn default() -> Self {
+        Sockets::provided(Arc::new(TcpProvider))
+    }
+}
+
+impl std::fmt::Debug for Sockets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sockets")
+            .field("open", &self.open.iter().flatten().count())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Sockets {
+    pub(crate) fn provided(provider: Arc<dyn NetProvider>) -> Sockets {
+        Sockets {
+            provider,
+            open: Vec::new(),
+        }
+    }
+
+    fn open(&mut self, host: &str, port: Value) -> io::Result<Value> {
+        let port = u16::try_from(port).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+        let connection = self.provider.connect(host, port)?;
+        let slot = match self.open.iter().position(Option::is_none) {
+            Some(slot) => slot,
+            None => {
+                self.open.push(None);
+                self.open.len() - 1
+            }
+        };
+        self.open[slot] = Some(Arc::new(Mutex::new(connection)));
+        Value::try_from(slot + 1).map_err(|_| io::Error::other("too many open sockets"))
+    }
+
+    fn socket(&self, id: Value) -> io::Result<&Mutex<Box<dyn Connection>>> {
+        usize::try_from(id)
+            .ok()
+            .and_then(|id| self.open.get(id.checked_sub(1)?)?.as_deref())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotConnected))
+    }
+
+    fn close(&mut self, id: Value) -> io::Result<()> {
+        self.socket(id)?;
+        self.open[id as usize - 1] = None;
+        Ok(())
+    }
+}
+
+/// The I/O result code of `outcome`, as for the file words: 0 on success,
+/// otherwise the operating system's error number, or -1 where there is none.
+fn ior<T>(outcome: &io::Result<T>) -> Value {
+    match outcome {
+        Ok(_) => 0,
+        Err(error) => error.raw_os_error().filter(|&code| code != 0).unwrap_or(-1),
+    }
+}
+
+impl Forth {
+    /// Makes `open-socket` connect through `provider` rather than plain TCP.
+    /// Sockets already open stay open.
+    pub fn set_net_provider(&mut self, provider: impl NetProvider + 'static) {
+        self.sockets.provider = Arc::new(provider);
+    }
+
+    /// Runs `word`. Failures to connect, send or receive are left as an I/O
+    /// result code on the stack; a bad buffer or a missing host fails the
+    /// word.
+    pub(crate) fn net_word(&mut self, word: NetWord, input: &mut Input<'_>) -> Result {
+        match word {
+            NetWord::Open => {
+                let port = self.pop()?;
+                let host = input.name().ok_or(Error::InvalidWord)?;
+                let outcome = self.sockets.open(&host, port);
+                self.stack.push(*outcome.as_ref().unwrap_or(&0));
+                self.stack.push(ior(&outcome));
+            }
+            NetWord::Close => {
+                let id = self.pop()?;
+                let outcome = self.sockets.close(id);
+                self.stack.push(ior(&outcome));
+            }
+            NetWord::Read => {
+                let id = self.pop()?;
+                let len = self.pop()?;
+                let address = self.pop_address()?;
+                let buffer = self.buffer(address, len)?;
+                let mut bytes = vec![0; buffer.len()];
+                // Whatever has arrived, without waiting for the buffer to
+                // fill; 0 bytes means the peer closed the connection.
+                let outcome = self.sockets.socket(id).and_then(|socket| loop {
+                    match socket.lock().unwrap().read(&mut bytes) {
+                        Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                        read => break read,
+                    }
+                });
+                let read = *outcome.as_ref().unwrap_or(&0);
+                let cells = &mut Arc::make_mut(&mut self.data_space)[buffer];
+                for (cell, &byte) in cells.iter_mut().zip(&bytes[..read]) {
+                    *cell = Value::from(byte);
+                }
+                self.stack.push(read as Value);
+                self.stack.push(ior(&outcome));
+            }
+            NetWord::Write => {
+                let id = self.pop()?;
+                let len = self.pop()?;
+                let address = self.pop_address()?;
+                let buffer = self.buffer(address, len)?;
+                let bytes: Vec<u8> = self.data_space[buffer]
+                    .iter()
+                    .map(|&cell| cell as u8)
+                    .collect();
+                let outcome = self.sockets.socket(id).and_then(|socket| {
+                    let mut connection = socket.lock().unwrap();
+                    connection.write_all(&bytes)?;
+                    connection.flush()
+                });
+                self.stack.push(ior(&outcome));
+            }
+        }
+        Ok(())
+    }
+}
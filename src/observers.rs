@@ -0,0 +1,92 @@
+use std::sync::Mutex;
+
+use crate::bytecode::Op;
+use crate::{Forth, Value};
+
+type Callback<T> = Box<dyn FnMut(T) + Send>;
+type DefineCallback = Box<dyn FnMut(&str) + Send>;
+type WordCallback = Box<dyn FnMut(&Forth, &str, usize) + Send>;
+
+#[derive(Default)]
+struct Observers {
+    define: Vec<DefineCallback>,
+    push: Vec<Callback<Value>>,
+    pop: Vec<Callback<Value>>,
+    word: Vec<WordCallback>,
+}
+
+/// The callbacks of one interpreter. Clones, and the stacks of tasks,
+/// start without any, as callbacks can't be copied.
+#[derive(Default)]
+pub(crate) struct Callbacks(Mutex<Observers>);
+
+impl Clone for Callbacks {
+    fn clone(&self) -> Self {
+        Callbacks::default()
+    }
+}
+
+impl std::fmt::Debug for Callbacks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Observers")
+    }
+}
+
+impl Callbacks {
+    fn with(&self, f: impl FnOnce(&mut Observers)) {
+        f(&mut self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    pub(crate) fn defined(&self, name: &str) {
+        self.with(|o| o.define.iter_mut().for_each(|f| f(name)))
+    }
+
+    pub(crate) fn pushed(&self, value: Value) {
+        self.with(|o| o.push.iter_mut().for_each(|f| f(value)))
+    }
+
+    pub(crate) fn popped(&self, value: Value) {
+        self.with(|o| o.pop.iter_mut().for_each(|f| f(value)))
+    }
+}
+
+impl Forth {
+    /// Tells the word observers `op` is about to run `depth` user-defined
+    /// words deep.
+    pub(crate) fn running(&self, op: Op, depth: usize) {
+        self.stack.observers.with(|o| {
+            if !o.word.is_empty() {
+                let word = self.op_name(op);
+                o.word.iter_mut().for_each(|f| f(self, &word, depth))
+            }
+        })
+    }
+}
+
+impl Forth {
+    /// Calls `f` with the name of every word defined from now on.
+    pub fn on_define(&mut self, f: impl FnMut(&str) + Send + 'static) {
+        self.stack.observers.with(|o| o.define.push(Box::new(f)))
+    }
+
+    /// Calls `f` with every value pushed onto the stack from now on. Like
+    /// the other observers, `f` watches this interpreter alone: not its
+    /// clones, nor the tasks it spawns, which start without observers.
+    pub fn on_push(&mut self, f: impl FnMut(Value) + Send + 'static) {
+        self.stack.observers.with(|o| o.push.push(Box::new(f)))
+    }
+
+    /// Calls `f` with every value popped off the stack from now on.
+    pub fn on_pop(&mut self, f: impl FnMut(Value) + Send + 'static) {
+        self.stack.observers.with(|o| o.pop.push(Box::new(f)))
+    }
+
+    /// Calls `f` before every word runs from now on, with the interpreter,
+    /// the word, and how many user-defined words it's nested in: 0 for words
+    /// typed at the top level. Words inside definitions are seen as compiled,
+    /// so turn the optimizations off to see them as written. Words run by
+    /// clones or spawned tasks aren't seen.
+    pub fn on_word(&mut self, f: impl FnMut(&Forth, &str, usize) + Send + 'static) {
+        self.stack.observers.with(|o| o.word.push(Box::new(f)))
+    }
+}
@@ -0,0 +1,115 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::bytecode::Op;
+use crate::Forth;
+
+type Sink = Arc<Mutex<Box<dyn Write + Send>>>;
+
+/// Where the interpreter writes text, shared between an interpreter and its
+/// clones. Stdout unless `Forth::set_output` says otherwise.
+pub(crate) struct Output {
+    sink: Sink,
+    /// Bytes this interpreter wrote since `reset_written`, which its clones
+    /// count apart.
+    written: AtomicUsize,
+}
+
+impl Output {
+    fn new(sink: Sink) -> Output {
+        Output {
+            sink,
+            written: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn written(&self) -> usize {
+        self.written.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn reset_written(&self) {
+        self.written.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Clone for Output {
+    fn clone(&self) -> Self {
+        Output::new(Arc::clone(&self.sink))
+    }
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Output::new(Arc::new(Mutex::new(Box::new(std::io::stdout()))))
+    }
+}
+
+/// Counts the bytes written through it.
+struct Counting<'a> {
+    sink: &'a mut dyn Write,
+    written: usize,
+}
+
+impl Write for Counting<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.sink.write(buf)?;
+        self.written += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.sink.flush()
+    }
+}
+
+impl std::fmt::Debug for Output {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Output")
+    }
+}
+
+impl Output {
+    /// Writes `args`, ignoring failures: a broken sink shouldn't stop the
+    /// program it is watching.
+    pub(crate) fn write_fmt(&self, args: std::fmt::Arguments<'_>) {
+        let mut sink = self.sink.lock().unwrap_or_else(|e| e.into_inner());
+        let mut counting = Counting {
+            sink: sink.as_mut(),
+            written: 0,
+        };
+        let _ = counting.write_fmt(args);
+        self.written.fetch_add(counting.written, Ordering::Relaxed);
+    }
+}
+
+impl Forth {
+    /// Sends the text the interpreter writes, such as trace lines, to `sink`.
+    pub fn set_output(&mut self, sink: impl Write + Send + 'static) {
+        self.output = Output::new(Arc::new(Mutex::new(Box::new(sink))));
+    }
+
+    /// While on, every word run writes a line to the output with the word
+    /// and the stack it leaves, indented by how many user-defined words it's
+    /// nested in. User-defined words are written as they are entered, ahead
+    /// of the words in their bodies.
+    pub fn set_trace(&mut self, enabled: bool) {
+        self.trace = enabled;
+    }
+
+    pub(crate) fn trace_op(&self, op: Op, depth: usize) {
+        let word = self.op_name(op);
+        let stack = self.stack.as_slice();
+        write!(
+            self.output,
+            "{:indent$}{word} <{}>",
+            "",
+            stack.len(),
+            indent = 2 * depth
+        );
+        for value in stack {
+            write!(self.output, " {value}");
+        }
+        writeln!(self.output);
+    }
+}
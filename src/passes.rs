@@ -0,0 +1,165 @@
+use std::sync::Arc;
+
+use crate::bytecode::{mark_tail_calls, Op, Primitive};
+use crate::ir::{Exit, IrOp, IrWord};
+use crate::{
+    builtin_ops, push_address, push_char, Error, Forth, Operation, Value, PREDIFINED_OPERATIONS,
+    RANDOM_OPERATIONS, TIME_OPERATIONS,
+};
+
+/// A transformation of definitions, run on each one as it is compiled, after
+/// the built-in optimizations and before the code is stored, see
+/// `ForthBuilder::add_pass`. Builtins go by the names `dis` gives them, so a
+/// pass replaces `2 *` with `dup +` by putting `IrOp::Builtin`s named `dup`
+/// and `+` in its place; the effects it gives them are ignored.
+pub trait Pass: Send + Sync {
+    fn run(&self, word: &mut IrWord);
+}
+
+/// The passes added, in the order they run, which clones of the interpreter
+/// share.
+#[derive(Clone, Default)]
+pub(crate) struct Passes(Vec<Arc<dyn Pass>>);
+
+impl std::fmt::Debug for Passes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Passes").field(&self.0.len()).finish()
+    }
+}
+
+impl Passes {
+    pub(crate) fn push(&mut self, pass: impl Pass + 'static) {
+        self.0.push(Arc::new(pass));
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Every builtin op `IrOp::Builtin` may name but a marker's.
+fn builtins() -> impl Iterator<Item = Op> {
+    let tables = PREDIFINED_OPERATIONS
+        .iter()
+        .chain(&TIME_OPERATIONS)
+        .chain(&RANDOM_OPERATIONS);
+    #[cfg(feature = "file-io")]
+    let tables = tables.chain(&crate::files::OPERATIONS);
+    #[cfg(feature = "env")]
+    let tables = tables.chain(&crate::env::OPERATIONS);
+    #[cfg(feature = "net")]
+    let tables = tables.chain(&crate::net::OPERATIONS);
+    #[cfg(feature = "peripherals")]
+    let tables = tables.chain(&crate::peripherals::OPERATIONS);
+    #[cfg(feature = "dlopen")]
+    let tables = tables.chain(&crate::foreign::OPERATIONS);
+    Primitive::ALL
+        .into_iter()
+        .map(Op::Primitive)
+        .chain(tables.map(|&(_, op)| op))
+}
+
+impl Forth {
+    /// `code`, compiled for the word `name`, as the passes leave it. Fails
+    /// with `Error::InvalidWord` if one leaves a block jumping back, a call of
+    /// a word defined after this one, or a builtin by a name there is none
+    /// by.
+    pub(crate) fn run_passes(
+        &self,
+        name: &str,
+        code: Vec<Op>,
+    ) -> std::result::Result<Vec<Op>, Error> {
+        if self.passes.is_empty() {
+            return Ok(code);
+        }
+        let word = self.words.len();
+        let mut ir = self.ir_word(name, word, &code);
+        for pass in &self.passes.0 {
+            pass.run(&mut ir);
+        }
+        self.lower(word, &ir)
+    }
+
+    /// The code of `ir`, the IR of the word with index `word`, with its
+    /// blocks laid out in order.
+    fn lower(&self, word: usize, ir: &IrWord) -> std::result::Result<Vec<Op>, Error> {
+        let blocks = &ir.blocks;
+        let forward = |from: usize, to: usize| {
+            (from < to && to < blocks.len())
+                .then_some(to)
+                .ok_or(Error::InvalidWord)
+        };
+        // Where each block starts, and the end after the last.
+        let mut starts = Vec::with_capacity(blocks.len() + 1);
+        let mut len = 0;
+        for (index, block) in blocks.iter().enumerate() {
+            starts.push(len);
+            let last = index + 1 == blocks.len();
+            len += block.ops.len()
+                + match block.exit {
+                    Exit::Return => usize::from(!last),
+                    Exit::Jump(to) => usize::from(forward(index, to)? != index + 1),
+                    Exit::If { then, otherwise } => {
+                        forward(index, otherwise)?;
+                        1 + usize::from(forward(index, then)? != index + 1)
+                    }
+                };
+        }
+        starts.push(len);
+        let mut code = Vec::with_capacity(len);
+        let branch = |code: &Vec<Op>, to: usize| starts[to] - code.len() - 1;
+        for (index, block) in blocks.iter().enumerate() {
+            for op in &block.ops {
+                code.push(self.lower_op(word, op)?);
+            }
+            match block.exit {
+                Exit::Return if index + 1 < blocks.len() => {
+                    code.push(Op::Branch(branch(&code, blocks.len())))
+                }
+                Exit::Return => {}
+                Exit::Jump(to) if to == index + 1 => {}
+                Exit::Jump(to) => code.push(Op::Branch(branch(&code, to))),
+                Exit::If { then, otherwise } => {
+                    code.push(Op::BranchIfZero(branch(&code, otherwise)));
+                    if then != index + 1 {
+                        code.push(Op::Branch(branch(&code, then)));
+                    }
+                }
+            }
+        }
+        mark_tail_calls(&mut code);
+        Ok(code)
+    }
+
+    fn lower_op(&self, word: usize, op: &IrOp) -> std::result::Result<Op, Error> {
+        Ok(match *op {
+            IrOp::Push(value) => Op::Push(value),
+            IrOp::Address(address) => {
+                push_address(usize::try_from(address).map_err(|_| Error::InvalidAddress)?)
+            }
+            IrOp::Char(c) => push_char(c),
+            IrOp::Xt {
+                word: Some(callee), ..
+            } if callee < word => Op::PushXt(callee as Value),
+            IrOp::Xt {
+                word: None,
+                ref name,
+            } => {
+                let position = builtin_ops().position(|op| self.op_name(op) == *name);
+                Op::PushXt(-1 - position.ok_or(Error::InvalidWord)? as Value)
+            }
+            IrOp::Call { word: callee, .. } if callee <= word => Op::Call(callee),
+            IrOp::Call { .. } | IrOp::Xt { .. } => return Err(Error::InvalidWord),
+            IrOp::Unknown => Op::Unknown,
+            IrOp::Builtin { ref name, .. } => {
+                match builtins().find(|&op| self.op_name(op) == *name) {
+                    Some(op) => op,
+                    None => match self.lookup_word(name) {
+                        Ok(Operation::Marker(entry)) => Op::Rewind(entry),
+                        _ => return Err(Error::InvalidWord),
+                    },
+                }
+            }
+        })
+    }
+}
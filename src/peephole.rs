@@ -0,0 +1,130 @@
+use crate::bytecode::{mark_tail_calls, Op, Primitive};
+use crate::effects::effect;
+use crate::Forth;
+
+/// Pairs of ops the peephole stage removed from definitions, by what they
+/// were, since the interpreter was created or `Forth::reset_metrics` was
+/// last called, see `Optimizations::peephole`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PeepholeStats {
+    pub swap_swap: u64,
+    pub dup_drop: u64,
+    /// `0 +`, and `0 w+`.
+    pub add_zero: u64,
+    /// A literal, or an address, dropped right after it is pushed.
+    pub push_drop: u64,
+}
+
+impl PeepholeStats {
+    /// Pairs removed, whatever they were.
+    pub fn total(&self) -> u64 {
+        self.swap_swap + self.dup_drop + self.add_zero + self.push_drop
+    }
+}
+
+/// Where ops of `code` land after its branches.
+fn targets(code: &[Op]) -> Vec<bool> {
+    let mut targets = vec![false; code.len() + 1];
+    for (i, op) in code.iter().enumerate() {
+        if let Op::Branch(skip) | Op::BranchIfZero(skip) = *op {
+            targets[i + 1 + skip] = true;
+        }
+    }
+    targets
+}
+
+/// Removes pairs of ops that together do nothing from `code`, counting them
+/// in `stats`, pairs that become adjacent as others go included. A branch
+/// landing between the two of a pair keeps it, and so does a stack that
+/// may be too short for it, where it would fail: the ops before it since
+/// the last branch landed must be known to leave the values it uses, as
+/// `swap swap` on an empty stack underflows. `origins`, the tokens the
+/// ops came from, loses the entries of the ops removed.
+fn peephole(code: &[Op], origins: &mut Vec<usize>, stats: &mut PeepholeStats) -> Vec<Op> {
+    use Op::{Primitive as P, Push, PushXt};
+    use Primitive::*;
+    let targets = targets(code);
+    // Ops kept, with the offsets they had, and where each offset of `code`
+    // went, the end included.
+    let mut kept: Vec<(usize, Op)> = Vec::with_capacity(code.len());
+    // The least the ops kept leave on the stack, from the barrier on,
+    // since they didn't fail, alongside them.
+    let mut depths: Vec<usize> = Vec::with_capacity(code.len());
+    let mut moved = vec![0; code.len() + 1];
+    // Ops before this may be jumped past, so must not pair with later ones.
+    let mut barrier = 0;
+    for (i, &op) in code.iter().enumerate() {
+        if targets[i] {
+            barrier = kept.len();
+        }
+        moved[i] = kept.len();
+        let pair = kept[barrier..].last().map(|&(_, last)| (last, op));
+        // Values known to be there before the first of the pair.
+        let below = match kept.len().checked_sub(2) {
+            Some(before) if before >= barrier => depths[before],
+            _ => 0,
+        };
+        let count = match pair {
+            Some((P(Swap), P(Swap))) if below >= 2 => Some(&mut stats.swap_swap),
+            Some((P(Dup), P(Drop))) if below >= 1 => Some(&mut stats.dup_drop),
+            Some((Push(0), P(Add | WrappingAdd))) if below >= 1 => Some(&mut stats.add_zero),
+            Some((Push(_) | PushXt(_), P(Drop))) => Some(&mut stats.push_drop),
+            #[cfg(feature = "tagged")]
+            Some((Op::PushTagged(..), P(Drop))) => Some(&mut stats.push_drop),
+            _ => None,
+        };
+        match count {
+            Some(count) => {
+                *count += 1;
+                kept.pop();
+                depths.pop();
+            }
+            None => {
+                let depth = match kept.len().checked_sub(1) {
+                    Some(last) if last >= barrier => depths[last],
+                    _ => 0,
+                };
+                kept.push((i, op));
+                depths.push(
+                    effect(op).map_or(0, |(inputs, outputs)| depth.max(inputs) - inputs + outputs),
+                );
+            }
+        }
+    }
+    moved[code.len()] = kept.len();
+    *origins = kept.iter().map(|&(from, _)| origins[from]).collect();
+    let mut code: Vec<Op> = kept
+        .iter()
+        .enumerate()
+        .map(|(at, &(from, op))| match op {
+            Op::Branch(skip) => Op::Branch(moved[from + 1 + skip] - at - 1),
+            Op::BranchIfZero(skip) => Op::BranchIfZero(moved[from + 1 + skip] - at - 1),
+            op => op,
+        })
+        .collect();
+    // Calls the removed ops followed may now be the last thing done.
+    mark_tail_calls(&mut code);
+    code
+}
+
+impl Forth {
+    /// `code`, compiled for a definition from the tokens `origins` gives,
+    /// with the waste the peephole stage finds taken out, if
+    /// `Optimizations::peephole` asks for it, and the origins of what is
+    /// left.
+    pub(crate) fn run_peephole(
+        &mut self,
+        code: Vec<Op>,
+        mut origins: Vec<usize>,
+    ) -> (Vec<Op>, Vec<usize>) {
+        if !self.optimizations.peephole {
+            return (code, origins);
+        }
+        let code = peephole(&code, &mut origins, &mut self.peephole_stats);
+        (code, origins)
+    }
+
+    pub fn peephole_stats(&self) -> PeepholeStats {
+        self.peephole_stats
+    }
+}
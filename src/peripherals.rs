@@ -0,0 +1,164 @@
+use std::sync::{Arc, Mutex};
+
+use crate::bytecode::Op;
+use crate::{Error, Forth, Result, Value};
+
+/// The peripheral words, which run as `Op::Peripheral`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum PeripheralWord {
+    ReadPin,
+    WritePin,
+    Delay,
+    Transfer,
+}
+
+impl PeripheralWord {
+    /// Every peripheral word, in declaration order.
+    pub(crate) const ALL: [PeripheralWord; 4] = [
+        PeripheralWord::ReadPin,
+        PeripheralWord::WritePin,
+        PeripheralWord::Delay,
+        PeripheralWord::Transfer,
+    ];
+
+    /// How many values the word takes and leaves.
+    pub(crate) fn effect(self) -> (usize, usize) {
+        match self {
+            PeripheralWord::ReadPin => (1, 2),
+            PeripheralWord::WritePin => (2, 1),
+            PeripheralWord::Delay => (1, 0),
+            PeripheralWord::Transfer => (3, 1),
+        }
+    }
+}
+
+pub(crate) const OPERATIONS: [(&str, Op); 4] = [
+    ("pin@", Op::Peripheral(PeripheralWord::ReadPin)),
+    ("pin!", Op::Peripheral(PeripheralWord::WritePin)),
+    ("ms", Op::Peripheral(PeripheralWord::Delay)),
+    ("transfer", Op::Peripheral(PeripheralWord::Transfer)),
+];
+
+/// Stack effects of the peripheral words, like `doc::BUILTIN_EFFECTS`.
+pub(crate) const EFFECTS: [(&str, &str); 4] = [
+    ("pin@", "( pin -- flag ior )"),
+    ("pin!", "( flag pin -- ior )"),
+    ("ms", "( u -- )"),
+    ("transfer", "( addr u bus -- ior )"),
+];
+
+/// The hardware of the board the interpreter runs on, as the words `pin@`,
+/// `pin!`, `ms` and `transfer` reach it, so that it can serve as a shell
+/// for bringing the board up. A failure is the I/O result code the word
+/// leaves, anything but 0.
+pub trait Peripherals: Send {
+    /// The level of `pin`, high being true.
+    fn read_pin(&mut self, pin: u32) -> std::result::Result<bool, Value>;
+
+    fn write_pin(&mut self, pin: u32, high: bool) -> std::result::Result<(), Value>;
+
+    /// Waits for `ms` milliseconds.
+    fn delay_ms(&mut self, ms: u32);
+
+    /// Clocks `bytes` out on `bus` and replaces them with the bytes clocked
+    /// in, as exchanges on SPI do. Buses that only send leave them as they
+    /// are.
+    fn transfer(&mut self, bus: u32, bytes: &mut [u8]) -> std::result::Result<(), Value>;
+}
+
+/// The I/O result code of `outcome`.
+fn ior<T>(outcome: &std::result::Result<T, Value>) -> Value {
+    match *outcome {
+        Ok(_) => 0,
+        Err(0) => -1,
+        Err(code) => code,
+    }
+}
+
+/// The peripherals the words use, if any, which clones of the interpreter
+/// share.
+#[derive(Clone, Default)]
+pub(crate) struct Board(Option<Arc<Mutex<Box<dyn Peripherals>>>>);
+
+impl std::fmt::Debug for Board {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Board")
+            .field("present", &self.0.is_some())
+            .finish()
+    }
+}
+
+impl Board {
+    pub(crate) fn new(peripherals: impl Peripherals + 'static) -> Board {
+        Board(Some(Arc::new(Mutex::new(Box::new(peripherals)))))
+    }
+
+    /// What `use_` makes of the peripherals, or -1 as the I/O result code if
+    /// there are none.
+    fn with<T>(
+        &self,
+        use_: impl FnOnce(&mut dyn Peripherals) -> std::result::Result<T, Value>,
+    ) -> std::result::Result<T, Value> {
+        match &self.0 {
+            Some(peripherals) => use_(peripherals.lock().unwrap().as_mut()),
+            None => Err(-1),
+        }
+    }
+}
+
+impl Forth {
+    /// Gives the peripheral words `peripherals` to use. Without any, they
+    /// fail with an I/O result code of -1, and `ms` doesn't wait. Clones of
+    /// the interpreter share them.
+    pub fn set_peripherals(&mut self, peripherals: impl Peripherals + 'static) {
+        self.board = Board::new(peripherals);
+    }
+
+    /// Runs `word`. Failures of the hardware are left as an I/O result code
+    /// on the stack; a pin, bus or delay a `u32` can't hold or a bad buffer
+    /// fails the word.
+    pub(crate) fn peripheral_word(&mut self, word: PeripheralWord) -> Result {
+        let number = |value: Value| u32::try_from(value).map_err(|_| Error::OutOfRange);
+        match word {
+            PeripheralWord::ReadPin => {
+                let pin = number(self.pop()?)?;
+                let outcome = self.board.with(|board| board.read_pin(pin));
+                self.stack.push(if outcome == Ok(true) { -1 } else { 0 });
+                self.stack.push(ior(&outcome));
+            }
+            PeripheralWord::WritePin => {
+                let pin = number(self.pop()?)?;
+                let high = self.pop()? != 0;
+                let outcome = self.board.with(|board| board.write_pin(pin, high));
+                self.stack.push(ior(&outcome));
+            }
+            PeripheralWord::Delay => {
+                let ms = number(self.pop()?)?;
+                let _ = self.board.with(|board| {
+                    board.delay_ms(ms);
+                    Ok(())
+                });
+            }
+            PeripheralWord::Transfer => {
+                let bus = number(self.pop()?)?;
+                let len = self.pop()?;
+                let address = self.pop_address()?;
+                let buffer = self.buffer(address, len)?;
+                let mut bytes: Vec<u8> = self.data_space[buffer.clone()]
+                    .iter()
+                    .map(|&cell| cell as u8)
+                    .collect();
+                let outcome = self.board.with(|board| board.transfer(bus, &mut bytes));
+                if outcome.is_ok() {
+                    let cells = &mut Arc::make_mut(&mut self.data_space)[buffer];
+                    for (cell, &byte) in cells.iter_mut().zip(&bytes) {
+                        *cell = Value::from(byte);
+                    }
+                }
+                self.stack.push(ior(&outcome));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use crate::bytecode::{Op, Primitive};
+use crate::{Forth, Operation};
+
+/// How often each word ran since profiling was turned on, see
+/// `Forth::set_profiling`. Both lists are sorted by count, highest first,
+/// and leave out what never ran.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    /// Calls of each user-defined word.
+    pub words: Vec<(String, u64)>,
+    /// Runs of each primitive, counted inside definitions as well as at the
+    /// top level.
+    pub primitives: Vec<(String, u64)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Counts {
+    /// Indexed by word, like `Forth::words`.
+    calls: Vec<u64>,
+    /// Indexed by `Primitive`.
+    primitives: Vec<u64>,
+}
+
+impl Counts {
+    pub(crate) fn count(&mut self, op: Op) {
+        let (counts, index) = match op {
+            Op::Call(word) | Op::TailCall(word) => (&mut self.calls, word),
+            Op::Primitive(primitive) => (&mut self.primitives, primitive as usize),
+            _ => return,
+        };
+        if counts.len() <= index {
+            counts.resize(index + 1, 0);
+        }
+        counts[index] += 1;
+    }
+}
+
+impl Forth {
+    /// Turns counting of words and primitives on or off. Turning it on
+    /// starts from zero; turning it off discards the counts. Native code
+    /// is not run while profiling, so every call is seen.
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profile = enabled.then(Box::default);
+    }
+
+    /// The counts gathered so far, empty if profiling is off.
+    pub fn profile(&self) -> Profile {
+        let Some(counts) = &self.profile else {
+            return Profile::default();
+        };
+        let sorted = |counts: &[u64], name: &dyn Fn(usize) -> String| {
+            let mut sorted: Vec<(String, u64)> = counts
+                .iter()
+                .enumerate()
+                .filter(|&(_, &count)| count > 0)
+                .map(|(index, &count)| (name(index), count))
+                .collect();
+            sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            sorted
+        };
+        Profile {
+            words: sorted(&counts.calls, &|word| {
+                self.dictionary
+                    .name_of(Operation::UserDefined(word))
+                    .map_or_else(|| "?".into(), |name| self.names.resolve(name).into())
+            }),
+            primitives: sorted(&counts.primitives, &|index| {
+                self.op_name(Op::Primitive(Primitive::ALL[index]))
+                    .into_owned()
+            }),
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use crate::bytecode::{Body, Op};
+use crate::{Error, Forth, Operation, Usage, Value, BUILTINS};
+
+impl Forth {
+    /// A copy of the interpreter keeping only what running the words
+    /// `roots` may need, for `save_image` or `transpile` to write: the
+    /// roots, the user-defined words they call, directly or not, and the
+    /// variables. Words no longer found by their names are dropped, as are
+    /// markers and the synonyms no root is. Fails with `Error::UnknownWord`
+    /// for a root that isn't defined, or `Error::InvalidWord` for a builtin.
+    pub fn pruned(&self, roots: &[&str]) -> std::result::Result<Forth, Error> {
+        let mut root_entries = Vec::with_capacity(roots.len());
+        for root in roots {
+            let entry = self
+                .names
+                .get(&root.to_lowercase())
+                .and_then(|name| self.dictionary.find(name))
+                .ok_or(Error::UnknownWord)?;
+            if entry < BUILTINS {
+                return Err(Error::InvalidWord);
+            }
+            root_entries.push(entry);
+        }
+        let mut reached = vec![false; self.words.len()];
+        let mut pending: Vec<usize> = root_entries
+            .iter()
+            .filter_map(|&entry| match self.dictionary.entries_from(entry).next() {
+                Some((_, Operation::UserDefined(word), _)) => Some(word),
+                _ => None,
+            })
+            .collect();
+        while let Some(word) = pending.pop() {
+            if std::mem::replace(&mut reached[word], true) {
+                continue;
+            }
+            for &op in self.body(word) {
+                match op {
+                    Op::Call(callee) | Op::TailCall(callee) => pending.push(callee),
+                    Op::PushXt(xt) if xt >= 0 => pending.push(xt as usize),
+                    _ => {}
+                }
+            }
+        }
+
+        let entries: Vec<_> = self.dictionary.entries_from(BUILTINS).collect();
+        let kept: Vec<bool> = entries
+            .iter()
+            .enumerate()
+            .map(|(index, &(name, operation, _))| {
+                let entry = BUILTINS + index;
+                self.dictionary.find(name) == Some(entry)
+                    && match operation {
+                        Operation::UserDefined(word) => reached[word],
+                        Operation::Address(_) => true,
+                        _ => root_entries.contains(&entry),
+                    }
+            })
+            .collect();
+        // What a marker cutting the dictionary back to `len` entries cuts
+        // it back to now.
+        let cut = |len: usize| {
+            BUILTINS
+                + kept[..len.saturating_sub(BUILTINS).min(kept.len())]
+                    .iter()
+                    .filter(|&&kept| kept)
+                    .count()
+        };
+        let mut renumbered = vec![0; self.words.len()];
+        let mut next = 0;
+        for (word, &reached) in reached.iter().enumerate() {
+            if reached {
+                renumbered[word] = next;
+                next += 1;
+            }
+        }
+
+        let mut code = Vec::new();
+        let mut words = Vec::with_capacity(next);
+        let mut sources = crate::source_map::SourceMap::new();
+        for word in (0..self.words.len()).filter(|&word| reached[word]) {
+            let start = code.len();
+            let Body { start: from, end } = self.words[word];
+            for (at, location) in self.sources.range(from..end) {
+                sources.insert(start + at - from, location.clone());
+            }
+            code.extend(self.body(word).iter().map(|&op| match op {
+                Op::Call(callee) => Op::Call(renumbered[callee]),
+                Op::TailCall(callee) => Op::TailCall(renumbered[callee]),
+                Op::PushXt(xt) if xt >= 0 => Op::PushXt(renumbered[xt as usize] as Value),
+                Op::Rewind(len) => Op::Rewind(cut(len)),
+                op => op,
+            }));
+            words.push(Body {
+                start,
+                end: code.len(),
+            });
+        }
+        let mut pruned = self.clone();
+        let dictionary = Arc::make_mut(&mut pruned.dictionary);
+        dictionary.truncate(BUILTINS);
+        for (&(name, operation, effect), _) in entries.iter().zip(&kept).filter(|(_, &kept)| kept) {
+            dictionary.define(
+                name,
+                match operation {
+                    Operation::UserDefined(word) => Operation::UserDefined(renumbered[word]),
+                    Operation::Marker(len) => Operation::Marker(cut(len)),
+                    operation => operation,
+                },
+            );
+            if let Some(effect) = effect {
+                dictionary.document(Cow::Owned(effect.to_string()));
+            }
+        }
+        pruned.usage = Usage::new(
+            self.usage
+                .charges
+                .iter()
+                .zip(&kept)
+                .filter(|(_, &kept)| kept)
+                .map(|(&tokens, _)| tokens)
+                .collect(),
+        );
+        pruned.code = Arc::new(code);
+        pruned.words = Arc::new(words);
+        pruned.sources = Arc::new(sources);
+        #[cfg(feature = "jit")]
+        pruned.jit.truncate(0);
+        // The counts are by word index, which now means other words.
+        if pruned.profile.is_some() {
+            pruned.set_profiling(true);
+        }
+        if pruned.coverage.is_some() {
+            pruned.set_coverage(true);
+        }
+        Ok(pruned)
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashSet;
+
+use crate::bytecode::{Op, Primitive};
+use crate::lexer::lex;
+use crate::{Error, Forth, Value};
+
+/// Whether `primitive` only does stack math: arithmetic, shuffling the
+/// stack, reading a variable and throwing.
+fn is_pure(primitive: Primitive) -> bool {
+    matches!(
+        primitive,
+        Primitive::Add
+            | Primitive::Subtract
+            | Primitive::Multiply
+            | Primitive::Divide
+            | Primitive::Modulo
+            | Primitive::DivMod
+            | Primitive::FlooredDivide
+            | Primitive::FlooredModulo
+            | Primitive::FlooredDivMod
+            | Primitive::WrappingAdd
+            | Primitive::WrappingSubtract
+            | Primitive::WrappingMultiply
+            | Primitive::Dup
+            | Primitive::Drop
+            | Primitive::Swap
+            | Primitive::Over
+            | Primitive::Fetch
+            | Primitive::Square
+            | Primitive::SwapSubtract
+            | Primitive::OverAdd
+            | Primitive::Throw
+    )
+}
+
+impl Forth {
+    /// Like `eval_expr`, but for stack math alone, as in a config file or a
+    /// spreadsheet cell: if `input` has a definition, or a word that defines,
+    /// forgets, stores, writes, reads the clock or talks to the host or the
+    /// system, it fails with `Error::Impure` before running any of it.
+    /// User-defined words are fine as long as what they call is.
+    pub fn eval_pure(&mut self, input: &str) -> std::result::Result<Vec<Value>, Error> {
+        let mut checked = HashSet::new();
+        for token in lex(input) {
+            if token.is_word(":") {
+                return Err(Error::Impure);
+            }
+            // Words that can't be run outside a definition fail as they
+            // would anyway.
+            let Ok(op) = self.token_op(&token) else {
+                continue;
+            };
+            if !self.is_pure(op, &mut checked) {
+                return Err(Error::Impure);
+            }
+        }
+        self.eval_expr(input)
+    }
+
+    /// Whether `op` only does stack math, given that the user-defined words
+    /// in `checked` are being checked or do likewise.
+    fn is_pure(&self, op: Op, checked: &mut HashSet<usize>) -> bool {
+        match op {
+            Op::Push(_) | Op::PushXt(_) | Op::Branch(_) | Op::BranchIfZero(_) | Op::Unknown => true,
+            #[cfg(feature = "tagged")]
+            Op::PushTagged(..) => true,
+            Op::Primitive(primitive) => is_pure(primitive),
+            Op::Call(word) | Op::TailCall(word) => {
+                !checked.insert(word) || self.body(word).iter().all(|&op| self.is_pure(op, checked))
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use crate::{Forth, Result, Value};
+
+/// The state of the xorshift generator `random` draws from, which clones of
+/// the interpreter carry on from where it was.
+#[derive(Debug, Clone)]
+pub(crate) struct Random(u64);
+
+impl Default for Random {
+    /// Seeded from the random keys std gives hashers, and never 0, which
+    /// xorshift would stay at.
+    fn default() -> Self {
+        Random(RandomState::new().build_hasher().finish() | 1)
+    }
+}
+
+impl Forth {
+    /// Pushes a number from 0 up to the largest a cell holds.
+    pub(crate) fn do_random(&mut self) -> Result {
+        let Random(state) = &mut self.random;
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        self.stack.push((*state >> 33) as Value);
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+//! What the Rust source `Forth::transpile` writes calls at run time. Each op
+//! of a definition becomes a call of one of these on the interpreter the
+//! transpiled words are given, which runs it as the interpreter itself
+//! would, so the words behave alike at either end.
+
+use crate::bytecode::{Op, Primitive};
+use crate::vm::Input;
+use crate::{push_address as address_op, push_char as char_op, Error, Forth, Result, Value};
+
+fn run(forth: &mut Forth, op: Op) -> Result {
+    forth.execute(&[op], &mut Input::new(&[]))
+}
+
+/// Pushes a literal.
+pub fn push(forth: &mut Forth, value: Value) -> Result {
+    run(forth, Op::Push(value))
+}
+
+/// Pushes the address of a variable, which with the `tagged` feature tags
+/// it as one.
+pub fn push_address(forth: &mut Forth, address: Value) -> Result {
+    let address = usize::try_from(address).map_err(|_| Error::InvalidAddress)?;
+    run(forth, address_op(address))
+}
+
+/// Pushes a character literal, which with the `tagged` feature tags it as
+/// one.
+pub fn push_char(forth: &mut Forth, c: Value) -> Result {
+    run(forth, char_op(c))
+}
+
+/// Pushes an execution token, as `[']` does, which with the `tagged`
+/// feature tags it as one.
+pub fn push_xt(forth: &mut Forth, xt: Value) -> Result {
+    run(forth, Op::PushXt(xt))
+}
+
+macro_rules! primitives {
+    ($($function:ident $primitive:ident,)*) => {
+        $(
+            #[doc = concat!("Runs the builtin `Primitive::", stringify!($primitive), "`.")]
+            pub fn $function(forth: &mut Forth) -> Result {
+                run(forth, Op::Primitive(Primitive::$primitive))
+            }
+        )*
+
+        /// The name of the function above running `primitive`, for
+        /// `Forth::transpile` to call.
+        pub(crate) fn function(primitive: Primitive) -> &'static str {
+            match primitive {
+                $(Primitive::$primitive => stringify!($function),)*
+            }
+        }
+    };
+}
+
+primitives! {
+    add Add,
+    subtract Subtract,
+    multiply Multiply,
+    divide Divide,
+    modulo Modulo,
+    div_mod DivMod,
+    floored_divide FlooredDivide,
+    floored_modulo FlooredModulo,
+    floored_div_mod FlooredDivMod,
+    wrapping_add WrappingAdd,
+    wrapping_subtract WrappingSubtract,
+    wrapping_multiply WrappingMultiply,
+    dup Dup,
+    drop Drop,
+    swap Swap,
+    over Over,
+    fetch Fetch,
+    store Store,
+    square Square,
+    swap_subtract SwapSubtract,
+    over_add OverAdd,
+    test_open TestOpen,
+    test_arrow TestArrow,
+    test_close TestClose,
+    test_summary TestSummary,
+    send Send,
+    receive Receive,
+    try_receive TryReceive,
+    utime Utime,
+    elapsed Elapsed,
+    evaluate Evaluate,
+    to_stack ToStack,
+    from_stack FromStack,
+    tasks Tasks,
+    throw Throw,
+    emit Emit,
+    random Random,
+    hex Hex,
+    decimal Decimal,
+}
+
+/// Pops the flag of an `if`, true unless it is zero.
+pub fn pop_flag(forth: &mut Forth) -> std::result::Result<bool, Error> {
+    Ok(forth.pop()? != 0)
+}
+
+/// Runs `word` as `['] word catch` does: pushes 0 if it succeeds, or else puts
+/// the stack back to the depth it had, padding it with zeros, and pushes
+/// the throw code of the failure.
+pub fn catch(forth: &mut Forth, word: fn(&mut Forth) -> Result) -> Result {
+    let depth = forth.stack.len();
+    let code = match word(forth) {
+        Ok(()) => 0,
+        Err(error) => {
+            forth.stack.split_off(depth);
+            while forth.stack.len() < depth {
+                forth.stack.push(0);
+            }
+            error.throw_code()
+        }
+    };
+    forth.stack.push(code);
+    Ok(())
+}
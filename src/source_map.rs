@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::diagnostics::{token_spans, Span};
+use crate::{Error, Forth, Operation};
+
+/// Where in its source an op of a definition came from, see
+/// `Forth::source_map`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// The name given by `Forth::set_source_name`, or to `include`, if any.
+    pub file: Option<String>,
+    /// Counting from 1, as `column` does. Columns count characters.
+    pub line: usize,
+    pub column: usize,
+    /// The word whose definition the op was compiled in.
+    pub word: String,
+}
+
+impl std::fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{file}:{}:{}", self.line, self.column),
+            None => write!(f, "line {}, column {}", self.line, self.column),
+        }?;
+        write!(f, ", in `{}`", self.word)
+    }
+}
+
+/// Locations of the ops of `Forth::code` they are known for, by position.
+pub(crate) type SourceMap = BTreeMap<usize, SourceLocation>;
+
+/// The line and column of each token of the definition being evaluated,
+/// and the name of its source, for `define` to map the code it compiles.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Locating {
+    file: Option<String>,
+    tokens: Vec<(usize, usize)>,
+}
+
+impl Forth {
+    /// Names the inputs given to `eval` and its variants from now on, for
+    /// the locations of the definitions they make, such as the path of the
+    /// script they were read from. `None` leaves them unnamed, which they
+    /// are by default. Sources `include` evaluates go by the name they were
+    /// included by.
+    pub fn set_source_name(&mut self, name: Option<&str>) {
+        self.source_name = name.map(str::to_string);
+    }
+
+    /// Where each op of the user-defined word `name` came from, by the
+    /// offsets `dis` lists them at: the token it was compiled from, or
+    /// `None` for ops a pass made and words not defined from source text.
+    /// Fails with `Error::UnknownWord` for an undefined name, or
+    /// `Error::InvalidWord` for anything but a user-defined word.
+    pub fn source_map(
+        &self,
+        name: &str,
+    ) -> std::result::Result<Vec<Option<SourceLocation>>, Error> {
+        let word = match self.lookup_word(&name.to_lowercase())? {
+            Operation::UserDefined(word) => word,
+            _ => return Err(Error::InvalidWord),
+        };
+        let body = self.words[word];
+        Ok((body.start..body.end)
+            .map(|at| self.sources.get(&at).cloned())
+            .collect())
+    }
+
+    /// Where the op the last evaluation failed at came from, if it failed
+    /// inside a user-defined word whose source is known.
+    pub(crate) fn failure_location(&self) -> Option<&SourceLocation> {
+        self.sources.get(&self.failed_at?)
+    }
+
+    /// Notes where the tokens of `command`, the next command of `input`,
+    /// are, if it is a definition.
+    pub(crate) fn locate(&mut self, input: &str, command: Span) {
+        let text = &input[command.start..command.end];
+        if text.split_whitespace().next() != Some(":") {
+            return;
+        }
+        let mut tokens = Vec::new();
+        let mut line = 1 + input[..command.start].matches('\n').count();
+        let mut line_start = input[..command.start].rfind('\n').map_or(0, |at| at + 1);
+        let mut from = command.start;
+        for span in token_spans(text) {
+            let start = command.start + span.start;
+            let between = &input[from..start];
+            line += between.matches('\n').count();
+            if let Some(at) = between.rfind('\n') {
+                line_start = from + at + 1;
+            }
+            from = start;
+            tokens.push((line, 1 + input[line_start..start].chars().count()));
+        }
+        self.locating = Some(Locating {
+            file: self.source_name.clone(),
+            tokens,
+        });
+    }
+
+    /// Records that the ops compiled for the word `name`, from `start` in
+    /// `Forth::code`, came from the tokens `origins` gives, counting from
+    /// the `:`, of the definition `locate` noted.
+    pub(crate) fn map_sources(&mut self, name: &str, start: usize, origins: &[usize]) {
+        if self.sources.range(start..).next().is_some() {
+            Arc::make_mut(&mut self.sources).split_off(&start);
+        }
+        let Some(Locating { file, tokens }) = self.locating.take() else {
+            return;
+        };
+        let sources = Arc::make_mut(&mut self.sources);
+        for (offset, &origin) in origins.iter().enumerate() {
+            if let Some(&(line, column)) = tokens.get(origin) {
+                let location = SourceLocation {
+                    file: file.clone(),
+                    line,
+                    column,
+                    word: name.to_string(),
+                };
+                sources.insert(start + offset, location);
+            }
+        }
+    }
+}
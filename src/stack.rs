@@ -0,0 +1,154 @@
+#[cfg(feature = "tagged")]
+use crate::Tag;
+use crate::Value;
+
+/// With the `smallvec` feature the first cells live inline, so interpreters
+/// running short scripts never allocate for their stack.
+#[cfg(feature = "smallvec")]
+pub(crate) type Values = smallvec::SmallVec<[Value; 16]>;
+#[cfg(not(feature = "smallvec"))]
+pub(crate) type Values = Vec<Value>;
+
+/// A value as shuffles move it around, which with the `tagged` feature
+/// takes its tag along.
+#[cfg(feature = "tagged")]
+pub(crate) type Cell = (Value, Tag);
+#[cfg(not(feature = "tagged"))]
+pub(crate) type Cell = Value;
+
+/// The data stack. Every change goes through `push` and `pop` (or helpers
+/// built on them) so observers see each value come and go.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Stack {
+    values: Values,
+    /// The tag of each value.
+    #[cfg(feature = "tagged")]
+    tags: Vec<Tag>,
+    /// The lowest `len` since it was last set, see `Forth::eval_expr`.
+    lowest: usize,
+    #[cfg(feature = "observers")]
+    pub(crate) observers: crate::observers::Callbacks,
+}
+
+impl Stack {
+    pub(crate) fn as_slice(&self) -> &[Value] {
+        &self.values
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub(crate) fn last(&self) -> Option<&Value> {
+        self.values.last()
+    }
+
+    pub(crate) fn lowest(&self) -> usize {
+        self.lowest
+    }
+
+    pub(crate) fn set_lowest(&mut self, depth: usize) {
+        self.lowest = depth;
+    }
+
+    /// The values themselves, for native code that bypasses `push` and
+    /// `pop`, and so is never run with observers.
+    #[cfg(feature = "jit")]
+    pub(crate) fn values_mut(&mut self) -> &mut Values {
+        &mut self.values
+    }
+
+    #[cfg(feature = "tagged")]
+    pub(crate) fn tags(&self) -> &[Tag] {
+        &self.tags
+    }
+
+    /// Pushes `value` as a number.
+    pub(crate) fn push(&mut self, value: Value) {
+        #[cfg(feature = "observers")]
+        self.observers.pushed(value);
+        self.values.push(value);
+        #[cfg(feature = "tagged")]
+        self.tags.push(Tag::Number);
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<Value> {
+        #[cfg(feature = "tagged")]
+        self.tags.pop();
+        let value = self.values.pop();
+        self.lowest = self.lowest.min(self.values.len());
+        #[cfg(feature = "observers")]
+        if let Some(value) = value {
+            self.observers.popped(value);
+        }
+        value
+    }
+
+    #[cfg(feature = "tagged")]
+    pub(crate) fn push_tagged(&mut self, value: Value, tag: Tag) {
+        self.push(value);
+        self.retag(0, tag);
+    }
+
+    #[cfg(feature = "tagged")]
+    pub(crate) fn pop_tagged(&mut self) -> Option<(Value, Tag)> {
+        let tag = *self.tags.last()?;
+        Some((self.pop()?, tag))
+    }
+
+    /// Tags the value `below` values down from the top as `tag`.
+    #[cfg(feature = "tagged")]
+    pub(crate) fn retag(&mut self, below: usize, tag: Tag) {
+        if let Some(index) = self.tags.len().checked_sub(below + 1) {
+            self.tags[index] = tag;
+        }
+    }
+
+    /// Tags the values from the bottom up as `tags` lists them.
+    #[cfg(feature = "tagged")]
+    pub(crate) fn set_tags(&mut self, tags: Vec<Tag>) {
+        debug_assert_eq!(tags.len(), self.values.len());
+        self.tags = tags;
+    }
+
+    pub(crate) fn push_cell(&mut self, cell: Cell) {
+        #[cfg(feature = "tagged")]
+        self.push_tagged(cell.0, cell.1);
+        #[cfg(not(feature = "tagged"))]
+        self.push(cell);
+    }
+
+    pub(crate) fn pop_cell(&mut self) -> Option<Cell> {
+        #[cfg(feature = "tagged")]
+        return self.pop_tagged();
+        #[cfg(not(feature = "tagged"))]
+        self.pop()
+    }
+
+    /// Removes and returns the values from `at` upwards.
+    pub(crate) fn split_off(&mut self, at: usize) -> Vec<Value> {
+        #[cfg(feature = "observers")]
+        for &value in self.values[at.min(self.values.len())..].iter().rev() {
+            self.observers.popped(value);
+        }
+        let at = at.min(self.values.len());
+        self.lowest = self.lowest.min(at);
+        #[cfg(feature = "tagged")]
+        self.tags.truncate(at);
+        self.values.drain(at..).collect()
+    }
+
+    pub(crate) fn replace(&mut self, values: Vec<Value>) -> Vec<Value> {
+        let old = self.split_off(0);
+        #[cfg(feature = "observers")]
+        for &value in &values {
+            self.observers.pushed(value);
+        }
+        #[cfg(feature = "tagged")]
+        {
+            self.tags = vec![Tag::Number; values.len()];
+        }
+        self.values = Values::from(values);
+        old
+    }
+}
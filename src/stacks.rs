@@ -0,0 +1,113 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use crate::lexer::Lexeme;
+use crate::stack::Stack;
+use crate::{Error, Forth, Result, Value};
+
+/// The stack-effect comment of the words naming auxiliary stacks.
+pub(crate) const STACK_EFFECT: &str = "( -- stack )";
+
+/// The auxiliary stacks of an interpreter, numbered in the order they were
+/// made, which is what the words naming them push.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Stacks {
+    names: Vec<String>,
+    stacks: Vec<Stack>,
+}
+
+impl Stacks {
+    pub(crate) fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Empty stacks named `names`, as `load_image` brings them back.
+    pub(crate) fn named(names: Vec<String>) -> Stacks {
+        Stacks {
+            stacks: vec![Stack::default(); names.len()],
+            names,
+        }
+    }
+
+    /// How many values the stacks hold between them.
+    pub(crate) fn cells(&self) -> usize {
+        self.stacks.iter().map(Stack::len).sum()
+    }
+
+    /// The stack numbered `number`, as `>s` and `s>` take it.
+    fn numbered(&mut self, number: Value) -> std::result::Result<&mut Stack, Error> {
+        usize::try_from(number)
+            .ok()
+            .and_then(|index| self.stacks.get_mut(index))
+            .ok_or(Error::OutOfRange)
+    }
+
+    /// The stack last made as `name`.
+    fn position(&self, name: &str) -> Option<usize> {
+        let name = name.to_lowercase();
+        self.names.iter().rposition(|made| *made == name)
+    }
+}
+
+impl Forth {
+    /// Makes an auxiliary stack, and defines `name` as a word pushing its
+    /// number for `>s` and `s>`, as `stack name` does; the word is not
+    /// `stack:`, since a `:` anywhere in a word starts a definition. A stack
+    /// outlives its word, so redefining or forgetting `name` leaves the stack
+    /// to the words compiled with it and to `aux_stack`.
+    pub fn define_stack(&mut self, name: &str) -> Result {
+        let name = name.to_lowercase();
+        let number = Value::try_from(self.stacks.stacks.len()).map_err(|_| Error::QuotaExceeded)?;
+        self.define(&name, None, &[Lexeme::Number(number)])
+            .map_err(|fault| fault.error)?;
+        Arc::make_mut(&mut self.dictionary).document(Cow::Borrowed(STACK_EFFECT));
+        self.stacks.names.push(name);
+        self.stacks.stacks.push(Stack::default());
+        Ok(())
+    }
+
+    /// The values on the auxiliary stack last made as `name`, from the
+    /// bottom up, if there is one.
+    pub fn aux_stack(&self, name: &str) -> Option<&[Value]> {
+        let index = self.stacks.position(name)?;
+        Some(self.stacks.stacks[index].as_slice())
+    }
+
+    /// Pushes `value` onto the auxiliary stack `name`, failing with
+    /// `Error::UnknownWord` if there is none, and with
+    /// `Error::QuotaExceeded` if it doesn't fit in the data-space quota.
+    pub fn push_aux(&mut self, name: &str, value: Value) -> Result {
+        let index = self.stacks.position(name).ok_or(Error::UnknownWord)?;
+        self.check_data_space(1)?;
+        self.stacks.stacks[index].push(value);
+        Ok(())
+    }
+
+    /// Pops the value on top of the auxiliary stack `name`.
+    pub fn pop_aux(&mut self, name: &str) -> std::result::Result<Value, Error> {
+        let index = self.stacks.position(name).ok_or(Error::UnknownWord)?;
+        self.stacks.stacks[index].pop().ok_or(Error::StackUnderflow)
+    }
+
+    /// `>s ( x stack -- )`
+    pub(crate) fn do_to_stack(&mut self) -> Result {
+        let number = self.pop()?;
+        self.stacks.numbered(number)?;
+        self.check_data_space(1)?;
+        let cell = self.stack.pop_cell().ok_or(Error::StackUnderflow)?;
+        self.stacks.numbered(number)?.push_cell(cell);
+        Ok(())
+    }
+
+    /// `s> ( stack -- x )`
+    pub(crate) fn do_from_stack(&mut self) -> Result {
+        let number = self.pop()?;
+        let cell = self
+            .stacks
+            .numbered(number)?
+            .pop_cell()
+            .ok_or(Error::StackUnderflow)?;
+        self.stack.push_cell(cell);
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use crate::diagnostics::{commands, Commands};
+use crate::lexer::lex;
+use crate::vm::Input;
+use crate::{Error, Forth, Optimizations, Result, Value};
+
+/// How `Forth::steps` compiles the definitions it makes, so that no word of
+/// theirs is merged into another.
+const AS_WRITTEN: Optimizations = Optimizations {
+    fold_constants: false,
+    inline_threshold: 0,
+    superinstructions: false,
+    peephole: false,
+};
+
+/// One word run by `Forth::steps`, with the stack around it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepState {
+    /// The word as written in the input, or as compiled in the body of a
+    /// user-defined word, where a conditional is `if`, the jump past its
+    /// `else` is `else`. In words defined before the steps, `Optimizations`
+    /// may have merged words. A definition is a single step, named by its
+    /// text.
+    pub word: String,
+    /// How many user-defined words the word is nested in, 0 for the input
+    /// itself.
+    pub depth: usize,
+    pub stack_before: Vec<Value>,
+    pub stack_after: Vec<Value>,
+}
+
+/// The steps of an evaluation, see `Forth::steps`.
+pub struct Steps<'a> {
+    forth: &'a mut Forth,
+    input: &'a str,
+    commands: Commands<'a>,
+    /// The expression being run, and the frames of the words it called that
+    /// are still running.
+    expression: Option<(Input<'a>, Vec<(usize, usize)>)>,
+    /// A failure found before the first step.
+    rejected: Option<Error>,
+    done: bool,
+}
+
+impl Forth {
+    /// Evaluates `input` as `eval` does, but one word at a time: each call
+    /// of `next` runs a word and returns the stack before and after it, so
+    /// that a frontend can show a program running. A user-defined word is a
+    /// step that leaves the stack as it was, followed by the steps of its
+    /// body. Definitions made in `input` are compiled as written, whatever
+    /// the `Optimizations`, so that each word of theirs is a step. The first
+    /// failure is the last item; dropping the iterator stops the evaluation
+    /// where it got to.
+    pub fn steps<'a>(&'a mut self, input: &'a str) -> Steps<'a> {
+        self.start_run();
+        let rejected = self
+            .check_syntax(input)
+            .err()
+            .map(|located| located.fault.error);
+        Steps {
+            forth: self,
+            input,
+            commands: commands(input),
+            expression: None,
+            rejected,
+            done: false,
+        }
+    }
+
+    /// Runs `step`, recording the stack around it.
+    fn observe(
+        &mut self,
+        word: String,
+        depth: usize,
+        step: impl FnOnce(&mut Forth) -> Result,
+    ) -> std::result::Result<StepState, Error> {
+        let stack_before = self.stack().to_vec();
+        step(self)?;
+        Ok(StepState {
+            word,
+            depth,
+            stack_before,
+            stack_after: self.stack().to_vec(),
+        })
+    }
+}
+
+impl Steps<'_> {
+    fn step(&mut self) -> Option<std::result::Result<StepState, Error>> {
+        if let Some(error) = self.rejected.take() {
+            return Some(Err(error));
+        }
+        loop {
+            if let Some((input, frames)) = &mut self.expression {
+                self.forth.settle(frames);
+                if let Some(&(ip, _)) = frames.last() {
+                    let word = self.forth.op_name(self.forth.code[ip]).into_owned();
+                    let depth = frames.len();
+                    return Some(
+                        self.forth
+                            .observe(word, depth, |forth| forth.run_frames(frames, input, &mut 1)),
+                    );
+                }
+                if let Some(token) = self.forth.next_token(input) {
+                    let word = token.to_string();
+                    return Some(self.forth.observe(word, 0, |forth| {
+                        let op = forth.token_op(&token)?;
+                        forth.execute_sliced(op, input, frames, &mut 1)
+                    }));
+                }
+                self.expression = None;
+            }
+            let command = match self.commands.next()? {
+                Ok(command) => command,
+                Err(_) => return Some(Err(Error::InvalidWord)),
+            };
+            let text = &self.input[command.start..command.end];
+            if lex(text).next().is_some_and(|token| token.is_word(":")) {
+                return Some(self.forth.observe(text.trim().to_string(), 0, |forth| {
+                    let optimizations = std::mem::replace(&mut forth.optimizations, AS_WRITTEN);
+                    let defined = forth.eval_command(text);
+                    forth.optimizations = optimizations;
+                    defined.map_err(|fault| fault.error)
+                }));
+            }
+            self.expression = Some((Input::text(lex(text)), Vec::new()));
+        }
+    }
+}
+
+impl Iterator for Steps<'_> {
+    type Item = std::result::Result<StepState, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let step = self.step();
+        self.done = !matches!(step, Some(Ok(_)));
+        step
+    }
+}
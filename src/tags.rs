@@ -0,0 +1,102 @@
+use crate::bytecode::Primitive;
+use crate::{Error, Forth, Value};
+
+/// What a value on the stack or in data space is meant to be, as the
+/// `tagged` feature keeps track of it. Literals and the results of most
+/// words are numbers; variables push addresses, `'` and `[']` execution
+/// tokens, and character literals such as `'a'` characters. An address or
+/// a character plus or minus a number is one too. Words that take one of
+/// these, such as `@`, `!` and `evaluate` an address, `execute` an
+/// execution token and `emit` a character, fail with `Error::TagMismatch`
+/// when given anything else, however valid it would be as one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tag {
+    Number,
+    Address,
+    Xt,
+    Char,
+}
+
+impl std::fmt::Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Tag::Number => "a number",
+            Tag::Address => "an address",
+            Tag::Xt => "an execution token",
+            Tag::Char => "a character",
+        })
+    }
+}
+
+/// The tag of the sum of values tagged `a` and `b`: an address or a
+/// character offset by a number stays one, and anything else is a number.
+fn offset(a: Tag, b: Tag) -> Tag {
+    match (a, b) {
+        (Tag::Address | Tag::Char, Tag::Number) => a,
+        (Tag::Number, Tag::Address | Tag::Char) => b,
+        _ => Tag::Number,
+    }
+}
+
+impl Forth {
+    /// The tag of each value on the stack, from the bottom up.
+    pub fn stack_tags(&self) -> &[Tag] {
+        self.stack.tags()
+    }
+
+    /// The tags of the two values on top of the stack, the top last, as
+    /// `retag` takes them.
+    pub(crate) fn top_tags(&self) -> [Tag; 2] {
+        let tags = self.stack.tags();
+        let tag = |below: usize| {
+            tags.len()
+                .checked_sub(below + 1)
+                .map_or(Tag::Number, |index| tags[index])
+        };
+        [tag(1), tag(0)]
+    }
+
+    /// Tags what `primitive` left, where it did arithmetic on addresses,
+    /// given the tags `top_tags` gave before it ran; it pushed numbers.
+    pub(crate) fn retag(&mut self, primitive: Primitive, [second, top]: [Tag; 2]) {
+        let tag = match primitive {
+            Primitive::Add | Primitive::WrappingAdd => offset(second, top),
+            Primitive::Subtract | Primitive::WrappingSubtract => match (second, top) {
+                (Tag::Address | Tag::Char, Tag::Number) => second,
+                _ => Tag::Number,
+            },
+            Primitive::SwapSubtract => match (second, top) {
+                (Tag::Number, Tag::Address | Tag::Char) => top,
+                _ => Tag::Number,
+            },
+            Primitive::OverAdd => {
+                self.stack.retag(1, second);
+                offset(second, top)
+            }
+            _ => return,
+        };
+        self.stack.retag(0, tag);
+    }
+
+    /// Pops a value, which must be tagged `expected`.
+    pub(crate) fn pop_tagged(&mut self, expected: Tag) -> std::result::Result<Value, Error> {
+        let (value, found) = self.stack.pop_tagged().ok_or(Error::StackUnderflow)?;
+        if found != expected {
+            return Err(Error::TagMismatch { expected, found });
+        }
+        Ok(value)
+    }
+
+    /// The tag of the value in data space at `cell`.
+    pub(crate) fn cell_tag(&self, cell: usize) -> Tag {
+        self.cell_tags.get(&cell).copied().unwrap_or(Tag::Number)
+    }
+
+    /// Tags the value in data space at `cell` as `tag`.
+    pub(crate) fn tag_cell(&mut self, cell: usize, tag: Tag) {
+        match tag {
+            Tag::Number => self.cell_tags.remove(&cell),
+            tag => self.cell_tags.insert(cell, tag),
+        };
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use crate::stack::{Cell, Stack};
+use crate::vm::Input;
+use crate::{Error, Forth, Operation, Result, Value};
+
+/// A task made by `spawn`, waiting for its turn.
+#[derive(Debug, Clone)]
+struct Task {
+    stack: Stack,
+    frames: Vec<(usize, usize)>,
+    /// Its values of the user variables, in `Tasks::user` order, which are
+    /// in data space only while it runs.
+    user: Vec<Cell>,
+}
+
+/// The tasks taking turns with the evaluation at each `pause`, in the order
+/// they run.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Tasks {
+    waiting: VecDeque<Task>,
+    /// Data-space cells of the user variables, in the order they were
+    /// defined.
+    user: Vec<usize>,
+    /// Whether a task is having its turn, so that `pause` ends it.
+    running: bool,
+}
+
+impl Tasks {
+    pub(crate) fn running(&self) -> bool {
+        self.running
+    }
+
+    pub(crate) fn user_cells(&self) -> &[usize] {
+        &self.user
+    }
+
+    /// No tasks, and user variables at `cells`, as `load_image` brings them
+    /// back.
+    pub(crate) fn with_user_cells(cells: Vec<usize>) -> Tasks {
+        Tasks {
+            user: cells,
+            ..Tasks::default()
+        }
+    }
+
+    /// Drops the tasks waiting for their turn.
+    pub(crate) fn clear(&mut self) {
+        self.waiting.clear();
+    }
+
+    /// Gives up the user variables at `cells` and above, as a marker does.
+    pub(crate) fn truncate(&mut self, cells: usize) {
+        let kept = self.user.partition_point(|&cell| cell < cells);
+        self.user.truncate(kept);
+        for task in &mut self.waiting {
+            task.user.truncate(kept);
+        }
+    }
+}
+
+impl Forth {
+    /// Makes a task that runs the user-defined word `name` with a stack of
+    /// its own, as `spawn name` does. It starts at the next `pause`, with
+    /// the values of the user variables of whatever spawned it.
+    pub fn spawn(&mut self, name: &str) -> Result {
+        let Operation::UserDefined(word) = self.lookup_word(&name.to_lowercase())? else {
+            return Err(Error::InvalidWord);
+        };
+        let body = self.words[word];
+        let user = self
+            .tasks
+            .user
+            .iter()
+            .map(|&cell| self.user_cell(cell))
+            .collect();
+        self.tasks.waiting.push_back(Task {
+            stack: Stack::default(),
+            frames: vec![(body.start, body.end)],
+            user,
+        });
+        Ok(())
+    }
+
+    /// How many tasks are waiting for their turn.
+    pub fn tasks(&self) -> usize {
+        self.tasks.waiting.len()
+    }
+
+    /// Gives each task a turn, in the order they were spawned, as `pause`
+    /// does outside them: a task runs until it does `pause` itself, or to
+    /// the end of its word, when it is done with. Tasks spawned meanwhile
+    /// wait for the next round. A task that fails is done with too, and its
+    /// failure is that of the `pause`. Inside a task, `pause` ends its turn,
+    /// except in text it evaluates, where it does nothing.
+    pub fn pause(&mut self) -> Result {
+        for _ in 0..self.tasks.waiting.len() {
+            let Some(mut task) = self.tasks.waiting.pop_front() else {
+                break;
+            };
+            self.switch(&mut task);
+            self.tasks.running = true;
+            // Ends early at the task's own `pause`, which uses up the budget.
+            let mut turn = usize::MAX;
+            let outcome = self.run_frames(&mut task.frames, &mut Input::new(&[]), &mut turn);
+            self.tasks.running = false;
+            self.switch(&mut task);
+            outcome?;
+            if !task.frames.is_empty() {
+                self.tasks.waiting.push_back(task);
+            }
+        }
+        Ok(())
+    }
+
+    /// Trades the stack and the values of the user variables with `task`'s.
+    fn switch(&mut self, task: &mut Task) {
+        std::mem::swap(&mut self.stack, &mut task.stack);
+        // User variables defined since the task was spawned start as they
+        // are.
+        while let Some(&cell) = self.tasks.user.get(task.user.len()) {
+            task.user.push(self.user_cell(cell));
+        }
+        for (index, saved) in task.user.iter_mut().enumerate() {
+            let cell = self.tasks.user[index];
+            let current = self.user_cell(cell);
+            self.set_user_cell(cell, std::mem::replace(saved, current));
+        }
+    }
+
+    fn user_cell(&self, cell: usize) -> Cell {
+        #[cfg(feature = "tagged")]
+        return (self.data_space[cell], self.cell_tag(cell));
+        #[cfg(not(feature = "tagged"))]
+        self.data_space[cell]
+    }
+
+    fn set_user_cell(&mut self, cell: usize, value: Cell) {
+        #[cfg(feature = "tagged")]
+        let value = {
+            self.tag_cell(cell, value.1);
+            value.0
+        };
+        Arc::make_mut(&mut self.data_space)[cell] = value;
+    }
+
+    /// Defines `name` as a user variable, one each task has its own value
+    /// of, as `user name` does.
+    pub(crate) fn define_user(&mut self, name: &str) -> Result {
+        self.define_variable(name)?;
+        self.tasks.user.push(self.data_space.len() - 1);
+        Ok(())
+    }
+
+    /// `tasks ( -- n )`
+    pub(crate) fn do_tasks(&mut self) -> Result {
+        let count = Value::try_from(self.tasks()).map_err(|_| Error::Overflow)?;
+        self.stack.push(count);
+        Ok(())
+    }
+}
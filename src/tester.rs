@@ -0,0 +1,76 @@
+use crate::{Error, Forth, Result, Value};
+
+/// Outcomes of the `t{ ... -> ... }t` tests run so far, see
+/// `Forth::test_summary`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TestSummary {
+    pub passed: usize,
+    pub failed: usize,
+}
+
+/// State of the test words: `t{` notes the depth, `->` sets aside the
+/// values the test produced above it, and `}t` compares them with the
+/// values expected.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Tester {
+    /// Stack depth at the `t{` of the open test.
+    depth: Option<usize>,
+    /// What the open test produced, once its `->` has run. `None` inside
+    /// means it took values from below its `t{`.
+    actual: Option<Option<Vec<Value>>>,
+    summary: TestSummary,
+}
+
+impl Forth {
+    /// Counts of the tests written with `t{ ... -> ... }t` that passed and
+    /// failed in this interpreter. Failures are also written to the output
+    /// as they happen, and `test-summary` writes the counts.
+    pub fn test_summary(&self) -> TestSummary {
+        self.tester.summary
+    }
+
+    pub(crate) fn do_test_open(&mut self) -> Result {
+        self.tester.depth = Some(self.stack.len());
+        self.tester.actual = None;
+        Ok(())
+    }
+
+    pub(crate) fn do_test_arrow(&mut self) -> Result {
+        let depth = self.tester.depth.ok_or(Error::InvalidWord)?;
+        let actual = (self.stack.len() >= depth).then(|| self.stack.split_off(depth));
+        // Expected values are pushed on whatever the test left.
+        self.tester.depth = Some(self.stack.len());
+        self.tester.actual = Some(actual);
+        Ok(())
+    }
+
+    pub(crate) fn do_test_close(&mut self) -> Result {
+        let depth = self.tester.depth.take().ok_or(Error::InvalidWord)?;
+        let actual = self.tester.actual.take().ok_or(Error::InvalidWord)?;
+        let expected = self.stack.split_off(depth);
+        let failure = match actual {
+            Some(actual) if actual == expected => None,
+            Some(actual) if actual.len() == expected.len() => Some(("incorrect result", actual)),
+            Some(actual) => Some(("wrong number of results", actual)),
+            None => Some(("wrong number of results", Vec::new())),
+        };
+        let summary = &mut self.tester.summary;
+        let Some((problem, actual)) = failure else {
+            summary.passed += 1;
+            return Ok(());
+        };
+        summary.failed += 1;
+        let test = summary.passed + summary.failed;
+        writeln!(
+            self.output,
+            "test {test}: {problem}: expected {expected:?}, got {actual:?}"
+        );
+        Ok(())
+    }
+
+    pub(crate) fn do_test_summary(&mut self) -> Result {
+        let TestSummary { passed, failed } = self.tester.summary;
+        writeln!(self.output, "{passed} passed, {failed} failed");
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+//! Random programs for property-testing code built on this crate, behind
+//! the `testing` feature.
+//!
+//! Generated programs are syntactically valid: definitions are closed, every
+//! `if` has its `then`, and words are only used once they are defined. They
+//! can still fail at run time, by underflowing the stack, dividing by zero or
+//! overflowing. They never loop, as `recurse` is left out.
+//!
+//! ```ignore
+//! use proptest::prelude::*;
+//!
+//! proptest! {
+//!     #[test]
+//!     fn runs_like_the_source(program in forth::testing::programs()) {
+//!         // ...
+//!     }
+//! }
+//! ```
+
+use arbitrary::{Arbitrary, Unstructured};
+use proptest::strategy::Strategy;
+
+use crate::{Program, Token};
+
+const BUILTINS: [&str; 8] = ["+", "-", "*", "/", "dup", "drop", "swap", "over"];
+const MAX_COMMANDS: usize = 8;
+const MAX_TOKENS: usize = 8;
+const MAX_NESTING: usize = 2;
+
+impl<'a> Arbitrary<'a> for Program {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Program> {
+        Generator::default().program(u)
+    }
+}
+
+/// A proptest strategy for `Program`, shrinking by shrinking the bytes the
+/// program is generated from.
+pub fn programs() -> impl Strategy<Value = Program> {
+    proptest::collection::vec(proptest::num::u8::ANY, 0..1024).prop_map(|bytes| {
+        Program::arbitrary(&mut Unstructured::new(&bytes))
+            .expect("running out of bytes only cuts the program short")
+    })
+}
+
+/// Like `programs`, but as source text for `Forth::eval`.
+pub fn sources() -> impl Strategy<Value = String> {
+    programs().prop_map(|program| program.to_string())
+}
+
+#[derive(Default)]
+struct Generator {
+    words: Vec<String>,
+    variables: Vec<String>,
+}
+
+impl Generator {
+    fn program(&mut self, u: &mut Unstructured<'_>) -> arbitrary::Result<Program> {
+        let mut program = Program::new();
+        for _ in 0..u.int_in_range(0..=MAX_COMMANDS)? {
+            program = match u.int_in_range(0..=9)? {
+                0..=2 => {
+                    let name = self.name(u)?;
+                    let body = self.tokens(u, true, 0)?;
+                    self.words.push(name.clone());
+                    program.definition(&name, body)
+                }
+                3 => {
+                    let name = format!("v{}", self.variables.len());
+                    self.variables.push(name.clone());
+                    program.expression([Token::word("variable"), Token::word(&name)])
+                }
+                _ => program.expression(self.tokens(u, false, 0)?),
+            };
+        }
+        Ok(program)
+    }
+
+    /// A fresh name, or one to redefine.
+    fn name(&self, u: &mut Unstructured<'_>) -> arbitrary::Result<String> {
+        if !self.words.is_empty() && u.ratio(1, 4)? {
+            return Ok(u.choose(&self.words)?.clone());
+        }
+        Ok(format!("w{}", self.words.len()))
+    }
+
+    fn tokens(
+        &self,
+        u: &mut Unstructured<'_>,
+        in_definition: bool,
+        nesting: usize,
+    ) -> arbitrary::Result<Vec<Token>> {
+        let mut tokens = Vec::new();
+        for _ in 0..u.int_in_range(0..=MAX_TOKENS)? {
+            match u.int_in_range(0..=9)? {
+                0..=2 => tokens.push(Token::Number(self.number(u)?)),
+                3..=5 => tokens.push(Token::word(u.choose(&BUILTINS)?)),
+                6 if !self.words.is_empty() => {
+                    tokens.push(Token::word(u.choose(&self.words)?));
+                }
+                7 if !self.variables.is_empty() => {
+                    tokens.push(Token::word(u.choose(&self.variables)?));
+                    tokens.push(Token::word(if u.arbitrary()? { "@" } else { "!" }));
+                }
+                8 if in_definition && nesting < MAX_NESTING => {
+                    tokens.push(Token::word("if"));
+                    tokens.extend(self.tokens(u, true, nesting + 1)?);
+                    if u.arbitrary()? {
+                        tokens.push(Token::word("else"));
+                        tokens.extend(self.tokens(u, true, nesting + 1)?);
+                    }
+                    tokens.push(Token::word("then"));
+                }
+                _ => tokens.push(Token::Number(self.number(u)?)),
+            }
+        }
+        Ok(tokens)
+    }
+
+    /// Mostly small numbers, so arithmetic doesn't overflow all the time.
+    fn number(&self, u: &mut Unstructured<'_>) -> arbitrary::Result<crate::Value> {
+        if u.ratio(1, 8)? {
+            u.arbitrary()
+        } else {
+            u.int_in_range(-10..=10)
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use crate::bytecode::Op;
+use crate::Forth;
+
+/// The calls of user-defined words recorded since recording was turned
+/// on, see `Forth::set_timeline`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timeline {
+    /// In the order the calls were made.
+    pub calls: Vec<TimedCall>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimedCall {
+    pub word: String,
+    /// How many calls this one is nested in.
+    pub depth: usize,
+    /// On the interpreter's clock, see `Forth::set_clock`.
+    pub start: Duration,
+    /// Zero for a call still running.
+    pub duration: Duration,
+}
+
+impl Timeline {
+    /// The calls as a trace in the Chrome trace-event format, which
+    /// Perfetto and `chrome://tracing` display: an object whose
+    /// `traceEvents` are complete events, `"ph": "X"`, with their start
+    /// and duration in microseconds. All go on one thread.
+    #[cfg(feature = "serde")]
+    pub fn to_chrome_trace(&self) -> String {
+        use serde_json::json;
+        let micros = |time: Duration| time.as_nanos() as f64 / 1000.0;
+        let events: Vec<_> = self
+            .calls
+            .iter()
+            .map(|call| {
+                json!({
+                    "name": call.word,
+                    "cat": "word",
+                    "ph": "X",
+                    "ts": micros(call.start),
+                    "dur": micros(call.duration),
+                    "pid": 1,
+                    "tid": 1,
+                })
+            })
+            .collect();
+        json!({ "traceEvents": events, "displayTimeUnit": "ns" }).to_string()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Recorder {
+    calls: Vec<TimedCall>,
+    /// Indices into `calls` of those still running, innermost last.
+    running: Vec<usize>,
+}
+
+impl Recorder {
+    /// Ends the innermost `calls` of the calls running.
+    fn exit(&mut self, calls: usize, now: Duration) {
+        for _ in 0..calls.min(self.running.len()) {
+            let call = &mut self.calls[self.running.pop().expect("one is running")];
+            call.duration = now.saturating_sub(call.start);
+        }
+    }
+}
+
+impl Forth {
+    /// Turns recording of when each user-defined word is entered and left
+    /// on or off, for `Timeline::to_chrome_trace` to lay out. Turning it
+    /// on starts from nothing; turning it off discards what was recorded.
+    /// A word whose last act is a call ends as that call starts, since the
+    /// callee takes its place. Native code is not run while it is on.
+    pub fn set_timeline(&mut self, enabled: bool) {
+        self.timeline = enabled.then(Box::default);
+    }
+
+    /// What was recorded so far, empty if recording is off.
+    pub fn timeline(&self) -> Timeline {
+        Timeline {
+            calls: self
+                .timeline
+                .as_ref()
+                .map_or_else(Vec::new, |recorder| recorder.calls.clone()),
+        }
+    }
+
+    /// Notes that `word` was entered, if recording.
+    pub(crate) fn time_entry(&mut self, word: usize) {
+        if self.timeline.is_none() {
+            return;
+        }
+        let word = self.op_name(Op::Call(word)).into_owned();
+        let start = self.clock.now();
+        let recorder = self.timeline.as_mut().expect("recording");
+        recorder.running.push(recorder.calls.len());
+        recorder.calls.push(TimedCall {
+            word,
+            depth: recorder.running.len() - 1,
+            start,
+            duration: Duration::ZERO,
+        });
+    }
+
+    /// Notes that the innermost `calls` of the words running returned, or
+    /// were unwound, if recording.
+    pub(crate) fn time_exit(&mut self, calls: usize) {
+        if self.timeline.is_none() {
+            return;
+        }
+        let now = self.clock.now();
+        let recorder = self.timeline.as_mut().expect("recording");
+        recorder.exit(calls, now);
+    }
+
+    /// How many calls the recording has running, or 0 if not recording.
+    pub(crate) fn timed_calls(&self) -> usize {
+        self.timeline
+            .as_ref()
+            .map_or(0, |recorder| recorder.running.len())
+    }
+}
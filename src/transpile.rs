@@ -0,0 +1,288 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::Write;
+
+use crate::bytecode::Op;
+#[cfg(feature = "tagged")]
+use crate::Tag;
+use crate::{runtime, Forth, Operation};
+
+/// Rust's keywords, which `ident` won't give as they are.
+const KEYWORDS: [&str; 51] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// A Rust identifier for the word `name`: `sum-of-squares` is
+/// `sum_of_squares`, `2*` is `word_2_star`.
+fn ident(name: &str) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut run = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            run.push(c.to_ascii_lowercase());
+            continue;
+        }
+        parts.extend((!run.is_empty()).then(|| std::mem::take(&mut run)));
+        let part = match c {
+            '-' | '_' => continue,
+            '+' => "plus",
+            '*' => "star",
+            '/' => "slash",
+            '<' => "lt",
+            '>' => "gt",
+            '=' => "eq",
+            '@' => "fetch",
+            '!' => "store",
+            '?' => "query",
+            '.' => "dot",
+            '\'' => "tick",
+            '#' => "hash",
+            ',' => "comma",
+            c => {
+                parts.push(format!("u{:x}", u32::from(c)));
+                continue;
+            }
+        };
+        parts.push(part.to_string());
+    }
+    parts.extend((!run.is_empty()).then_some(run));
+    let ident = parts.join("_");
+    if ident.is_empty()
+        || ident.starts_with(|c: char| c.is_ascii_digit())
+        || KEYWORDS.contains(&ident.as_str())
+    {
+        format!("word_{ident}")
+    } else {
+        ident
+    }
+}
+
+/// Where in `code`, other than at its start, stretches of it begin that run
+/// from the start to the end: right after branches and ops that return, and
+/// where branches land.
+fn targets(code: &[Op]) -> BTreeSet<usize> {
+    let mut targets = BTreeSet::new();
+    for (i, op) in code.iter().enumerate() {
+        match *op {
+            Op::Branch(skip) | Op::BranchIfZero(skip) => {
+                targets.insert(i + 1);
+                targets.insert(i + 1 + skip);
+            }
+            Op::TailCall(_) | Op::Unknown => {
+                targets.insert(i + 1);
+            }
+            _ => {}
+        }
+    }
+    targets.remove(&code.len());
+    targets
+}
+
+/// The user-defined word the `catch` at `at` in `code` runs, if `[']`
+/// pushed its token right before with no branch landing in between, which
+/// `rt::catch` can call.
+fn caught(code: &[Op], at: usize) -> Option<usize> {
+    match code[..at].last() {
+        Some(&Op::PushXt(xt)) if !targets(code).contains(&at) => usize::try_from(xt).ok(),
+        _ => None,
+    }
+}
+
+impl Forth {
+    /// Rust source for the words defined so far, a public function taking
+    /// the interpreter to run on for each, named after the word as
+    /// `sum_of_squares` for `sum-of-squares`. The functions run the compiled
+    /// code of the words without looking anything up, through
+    /// `forth::runtime`, so they leave the same stack and fail as the words
+    /// would, except that calls nest on the Rust stack rather than being
+    /// limited by `Forth::set_max_call_depth`: a chain of them deep enough
+    /// overflows it, aborting the program. A word calling itself as its last
+    /// act loops instead, so tail recursion runs in constant space, but tail
+    /// calls of other words are calls like the rest. They
+    /// address variables as this interpreter does, so the one they are given
+    /// should define the same ones in the same order. Words that define,
+    /// forget or parse the input can't be transpiled, nor can words calling
+    /// them; a comment takes their place.
+    pub fn transpile(&self) -> String {
+        // Callees are defined before their callers, or are the caller itself.
+        let mut blocked: Vec<Option<String>> = Vec::with_capacity(self.words.len());
+        for word in 0..self.words.len() {
+            let code = self.body(word);
+            let reason =
+                code.iter()
+                    .enumerate()
+                    .find_map(|(at, &op)| match (op, caught(code, at)) {
+                        (Op::Call(callee) | Op::TailCall(callee), _)
+                        | (Op::Catch, Some(callee)) => (callee != word
+                            && blocked[callee].is_some())
+                        .then(|| format!("it calls `{}`", self.op_name(Op::Call(callee)))),
+                        #[cfg(feature = "tagged")]
+                        (Op::PushTagged(..), _) => None,
+                        (Op::Push(_) | Op::PushXt(_) | Op::Primitive(_), _) => None,
+                        (Op::Branch(_) | Op::BranchIfZero(_), _) => None,
+                        (Op::Unknown, _) => None,
+                        (op, _) => Some(format!("it uses `{}`", self.op_name(op))),
+                    });
+            blocked.push(reason);
+        }
+        let mut source = String::from(
+            "// Transpiled by `forth transpile`.\n\nuse forth::runtime as rt;\nuse forth::{Forth, Result};\n",
+        );
+        let mut used: HashSet<String> = (0..self.words.len())
+            .map(|word| format!("body_{word}"))
+            .collect();
+        let mut reached = BTreeSet::new();
+        for name in self.user_words() {
+            let Ok(Operation::UserDefined(word)) = self.lookup_word(name) else {
+                continue;
+            };
+            if let Some(reason) = &blocked[word] {
+                let _ = write!(source, "\n// `{name}` isn't transpiled, as {reason}.\n");
+                continue;
+            }
+            let mut function = ident(name);
+            while !used.insert(function.clone()) {
+                function.push('_');
+            }
+            let doc = match self.doc(name) {
+                Some(effect) => format!("{name} {effect}"),
+                None => name.to_string(),
+            };
+            let _ = write!(
+                source,
+                "\n/// `{doc}`\npub fn {function}(f: &mut Forth) -> Result {{\n    body_{word}(f)\n}}\n"
+            );
+            reached.insert(word);
+        }
+        let mut pending: Vec<usize> = reached.iter().copied().collect();
+        while let Some(word) = pending.pop() {
+            let code = self.body(word);
+            for (at, &op) in code.iter().enumerate() {
+                if let (Op::Call(callee) | Op::TailCall(callee), _) | (Op::Catch, Some(callee)) =
+                    (op, caught(code, at))
+                {
+                    if reached.insert(callee) {
+                        pending.push(callee);
+                    }
+                }
+            }
+        }
+        for word in reached {
+            let name = self.op_name(Op::Call(word));
+            let _ = write!(
+                source,
+                "\n/// `{name}`\nfn body_{word}(f: &mut Forth) -> Result {{\n"
+            );
+            self.transpile_body(word, &mut source);
+            source.push_str("}\n");
+        }
+        source
+    }
+
+    /// Writes the body of the word with index `word`.
+    fn transpile_body(&self, word: usize, source: &mut String) {
+        let code = self.body(word);
+        let targets = targets(code);
+        // Calling itself last goes back to the start of the loop.
+        let loops = code.contains(&Op::TailCall(word));
+        if targets.is_empty() && !loops {
+            if !self.transpile_block(word, code, "    ", source) {
+                source.push_str("    Ok(())\n");
+            }
+            return;
+        }
+        // One arm for each stretch of code the branches land at the start
+        // of, giving the one to run next.
+        source.push_str("    let mut at = 0;\n    loop {\n        at = match at {\n");
+        let starts: Vec<usize> = std::iter::once(0).chain(targets).collect();
+        let indent = "                ";
+        for (index, &start) in starts.iter().enumerate() {
+            let end = starts.get(index + 1).copied().unwrap_or(code.len());
+            let _ = writeln!(source, "            {start} => {{");
+            let (block, next) = match code[start..end].split_last() {
+                Some((&Op::Branch(skip), block)) => (block, format!("{}", end + skip)),
+                Some((&Op::BranchIfZero(skip), block)) => (
+                    block,
+                    format!(
+                        "if rt::pop_flag(f)? {{\n{indent}    {end}\n{indent}}} else {{\n{indent}    {}\n{indent}}}",
+                        end + skip
+                    ),
+                ),
+                _ => (&code[start..end], end.to_string()),
+            };
+            if !self.transpile_block(word, block, indent, source) {
+                let _ = writeln!(source, "{indent}{next}");
+            }
+            source.push_str("            }\n");
+        }
+        source.push_str("            _ => return Ok(()),\n        };\n    }\n");
+    }
+
+    /// Writes the ops of `block`, from the body of the word with index
+    /// `word`, which holds no branches, as statements, returning whether the
+    /// last of them returns from the function, which it does as the value of
+    /// its body unless `indent` puts it in the loop of one with branches, or
+    /// goes back to the start of that loop for a call of `word` itself.
+    fn transpile_block(
+        &self,
+        word: usize,
+        block: &[Op],
+        indent: &str,
+        source: &mut String,
+    ) -> bool {
+        let ret = |value: String| match indent.len() {
+            4 => value,
+            _ => format!("return {value};"),
+        };
+        for (at, &op) in block.iter().enumerate() {
+            let name = self.op_name(op);
+            let _ = match op {
+                Op::Push(value) => writeln!(source, "{indent}rt::push(f, {value})?;"),
+                // The `catch` that follows calls the word itself.
+                Op::PushXt(_) if block.get(at + 1) == Some(&Op::Catch) => Ok(()),
+                Op::PushXt(xt) => writeln!(source, "{indent}rt::push_xt(f, {xt})?; // {name}"),
+                #[cfg(feature = "tagged")]
+                Op::PushTagged(value, tag) => {
+                    let function = match tag {
+                        Tag::Address => "push_address",
+                        _ => "push_char",
+                    };
+                    writeln!(source, "{indent}rt::{function}(f, {value})?;")
+                }
+                Op::Primitive(primitive) => writeln!(
+                    source,
+                    "{indent}rt::{}(f)?; // {name}",
+                    runtime::function(primitive)
+                ),
+                Op::Call(callee) => writeln!(source, "{indent}body_{callee}(f)?; // {name}"),
+                Op::Catch => {
+                    let callee = caught(block, at).expect("blocked from being transpiled");
+                    let name = self.op_name(Op::Call(callee));
+                    writeln!(
+                        source,
+                        "{indent}rt::catch(f, body_{callee})?; // ['] {name} catch"
+                    )
+                }
+                Op::TailCall(callee) if callee == word => {
+                    let _ = writeln!(source, "{indent}0 // {name}");
+                    return true;
+                }
+                Op::TailCall(callee) => {
+                    let call = ret(format!("body_{callee}(f)"));
+                    let _ = writeln!(source, "{indent}{call} // {name}");
+                    return true;
+                }
+                Op::Unknown => {
+                    let error = ret("Err(forth::Error::UnknownWord)".to_string());
+                    let _ = writeln!(source, "{indent}{error}");
+                    return true;
+                }
+                _ => unreachable!("blocked from being transpiled"),
+            };
+        }
+        false
+    }
+}
@@ -0,0 +1,761 @@
+use std::sync::Arc;
+
+use crate::bytecode::{Body, Op};
+use crate::lexer::{Lexeme, Lexer};
+use crate::stack::Stack;
+#[cfg(feature = "tagged")]
+use crate::Tag;
+use crate::{builtin_ops, Division, Error, Forth, Result, Value, CELL_SIZE};
+
+/// The tokens of the command being evaluated, from which words such as
+/// `variable` parse their argument at run time. Text is lexed as it is read,
+/// so evaluating it needs no token buffer.
+pub(crate) struct Input<'a> {
+    source: Source<'a>,
+    read: usize,
+}
+
+enum Source<'a> {
+    Lexemes(std::slice::Iter<'a, Lexeme<'a>>),
+    Text(Lexer<'a>),
+}
+
+impl<'a> Input<'a> {
+    pub(crate) fn new(tokens: &'a [Lexeme<'a>]) -> Input<'a> {
+        Input {
+            source: Source::Lexemes(tokens.iter()),
+            read: 0,
+        }
+    }
+
+    pub(crate) fn text(lexer: Lexer<'a>) -> Input<'a> {
+        Input {
+            source: Source::Text(lexer),
+            read: 0,
+        }
+    }
+
+    /// Index of the token returned by the last call to `next`.
+    pub(crate) fn position(&self) -> usize {
+        self.read.saturating_sub(1)
+    }
+
+    /// The next token as it was written, for words such as `open-file`
+    /// that parse a name whose case matters.
+    pub(crate) fn name(&mut self) -> Option<std::borrow::Cow<'a, str>> {
+        use std::borrow::Cow;
+        let name = match &mut self.source {
+            Source::Lexemes(tokens) => match tokens.next()? {
+                Lexeme::Word(word) => Cow::Borrowed(&**word),
+                token => Cow::Owned(token.to_string()),
+            },
+            Source::Text(lexer) => Cow::Borrowed(lexer.next_raw()?),
+        };
+        self.read += 1;
+        Some(name)
+    }
+
+    /// Reads the text from now on in `base`, see `Lexer::set_base`.
+    pub(crate) fn set_base(&mut self, base: u32) {
+        if let Source::Text(lexer) = &mut self.source {
+            lexer.set_base(base);
+        }
+    }
+
+    pub(crate) fn next(&mut self) -> Option<Lexeme<'a>> {
+        let token = match &mut self.source {
+            Source::Lexemes(tokens) => tokens.next()?.borrowed(),
+            Source::Text(lexer) => lexer.next()?,
+        };
+        self.read += 1;
+        Some(token)
+    }
+}
+
+type PrimitiveFn = fn(&mut Forth) -> Result;
+
+/// The ip of a frame standing for a `catch`, whose end is the depth of the
+/// stack to put back if the word it called throws.
+const CATCH: usize = usize::MAX;
+
+/// Implementations of the primitives, in `Primitive` order.
+const PRIMITIVES: [PrimitiveFn; 39] = [
+    |f| do_addition(&mut f.stack),
+    |f| do_substraction(&mut f.stack),
+    |f| do_multiplication(&mut f.stack),
+    |f| do_division(&mut f.stack, Division::Symmetric, Leaves::Quotient),
+    |f| do_division(&mut f.stack, Division::Symmetric, Leaves::Remainder),
+    |f| do_division(&mut f.stack, Division::Symmetric, Leaves::Both),
+    |f| do_division(&mut f.stack, Division::Floored, Leaves::Quotient),
+    |f| do_division(&mut f.stack, Division::Floored, Leaves::Remainder),
+    |f| do_division(&mut f.stack, Division::Floored, Leaves::Both),
+    |f| do_wrapping(&mut f.stack, Value::wrapping_add),
+    |f| do_wrapping(&mut f.stack, Value::wrapping_sub),
+    |f| do_wrapping(&mut f.stack, Value::wrapping_mul),
+    |f| do_dup(&mut f.stack),
+    |f| do_drop(&mut f.stack),
+    |f| do_swap(&mut f.stack),
+    |f| do_over(&mut f.stack),
+    Forth::do_fetch,
+    Forth::do_store,
+    |f| do_square(&mut f.stack),
+    |f| do_swap_substraction(&mut f.stack),
+    |f| do_over_addition(&mut f.stack),
+    Forth::do_test_open,
+    Forth::do_test_arrow,
+    Forth::do_test_close,
+    Forth::do_test_summary,
+    Forth::do_send,
+    Forth::do_receive,
+    Forth::do_try_receive,
+    Forth::do_utime,
+    Forth::do_elapsed,
+    Forth::do_evaluate,
+    Forth::do_to_stack,
+    Forth::do_from_stack,
+    Forth::do_tasks,
+    Forth::do_throw,
+    Forth::do_emit,
+    Forth::do_random,
+    Forth::do_hex,
+    Forth::do_decimal,
+];
+
+impl Forth {
+    pub(crate) fn execute(&mut self, code: &[Op], input: &mut Input<'_>) -> Result {
+        for &op in code {
+            self.note(op);
+            self.run_noted(op, input)?;
+        }
+        Ok(())
+    }
+
+    /// Runs `op` outside a definition once `note` has been told of it.
+    fn run_noted(&mut self, op: Op, input: &mut Input<'_>) -> Result {
+        match op {
+            Op::Call(word) => {
+                if self.trace {
+                    self.trace_op(op, 0);
+                }
+                self.call(word, input)
+            }
+            Op::Execute => {
+                let op = self.pop_xt()?;
+                self.run_noted(op, input)
+            }
+            op => {
+                self.step(op, input)?;
+                if self.trace {
+                    self.trace_op(op, 0);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Like `execute` with `op` alone, except that a call runs only until
+    /// `budget` ops have run, leaving the frames still to be run in
+    /// `frames`. `op` itself counts as one.
+    pub(crate) fn execute_sliced(
+        &mut self,
+        op: Op,
+        input: &mut Input<'_>,
+        frames: &mut Vec<(usize, usize)>,
+        budget: &mut usize,
+    ) -> Result {
+        *budget = budget.saturating_sub(1);
+        if !matches!(op, Op::Call(_) | Op::Execute) {
+            return self.execute(&[op], input);
+        }
+        self.note(op);
+        let word = match op {
+            Op::Call(word) => word,
+            _ => match self.pop_xt()? {
+                Op::Call(word) => word,
+                op => return self.run_noted(op, input),
+            },
+        };
+        if self.trace {
+            self.trace_op(Op::Call(word), 0);
+        }
+        self.enter(word, frames)?;
+        self.run_frames(frames, input, budget)
+    }
+
+    /// Tells whatever watches the interpreter that `op` is about to run
+    /// outside a definition.
+    fn note(&mut self, op: Op) {
+        #[cfg(feature = "observers")]
+        self.running(op, 0);
+        if let Some(profile) = &mut self.profile {
+            profile.count(op);
+        }
+        if let Some(coverage) = &mut self.coverage {
+            coverage.count(None, op);
+        }
+    }
+
+    /// Runs a user-defined word. Calls push a frame instead of recursing, so
+    /// deep call chains don't grow the native stack, and tail calls reuse
+    /// the frame of the caller.
+    fn call(&mut self, word: usize, input: &mut Input<'_>) -> Result {
+        let mut frames = std::mem::take(&mut self.frames);
+        let mut unlimited = usize::MAX;
+        let timed = self.timed_calls();
+        let result = self
+            .enter(word, &mut frames)
+            .and_then(|()| self.run_frames(&mut frames, input, &mut unlimited));
+        if result.is_err() {
+            // The op running in the innermost frame is the one to blame.
+            self.failed_at = frames
+                .iter()
+                .rev()
+                .find(|&&(ip, _)| ip != CATCH)
+                .map(|&(ip, _)| ip - 1);
+        }
+        // Calls a failure unwound end with it.
+        self.time_exit(self.timed_calls().saturating_sub(timed));
+        frames.clear();
+        self.frames = frames;
+        result
+    }
+
+    /// Pushes the frame of `word` onto `frames`, unless it runs natively at
+    /// once.
+    pub(crate) fn enter(&mut self, word: usize, frames: &mut Vec<(usize, usize)>) -> Result {
+        if self.max_call_depth == 0 {
+            return Err(Error::ReturnStackOverflow);
+        }
+        self.metrics.words_executed += 1;
+        #[cfg(feature = "jit")]
+        if self.run_native(word)? {
+            return Ok(());
+        }
+        self.time_entry(word);
+        let Body { start, end } = self.words[word];
+        frames.push((start, end));
+        Ok(())
+    }
+
+    /// Runs `frames` until they are done, or until `budget` ops have run,
+    /// leaving the rest of them to be run by another call. A `throw`, or
+    /// any other failure, goes back to the innermost `catch` still running
+    /// among them.
+    pub(crate) fn run_frames(
+        &mut self,
+        frames: &mut Vec<(usize, usize)>,
+        input: &mut Input<'_>,
+        budget: &mut usize,
+    ) -> Result {
+        loop {
+            let Err(error) = self.run_uncaught(frames, input, budget) else {
+                return Ok(());
+            };
+            let Some(at) = frames.iter().rposition(|&(ip, _)| ip == CATCH) else {
+                return Err(error);
+            };
+            let (_, depth) = frames[at];
+            let unwound = frames[at..].iter().filter(|&&(ip, _)| ip != CATCH).count();
+            self.time_exit(unwound);
+            frames.truncate(at);
+            self.caught(depth, error);
+        }
+    }
+
+    /// Runs `op` as `catch` does once it has popped the token of it, which
+    /// `run_uncaught` does itself for a user-defined word so the call
+    /// doesn't recurse.
+    fn catch(&mut self, op: Op, input: &mut Input<'_>) -> Result {
+        let depth = self.stack.len();
+        match self.step(op, input) {
+            Ok(()) => self.stack.push(0),
+            Err(error) => {
+                // The failure ends here.
+                self.failed_at = None;
+                self.caught(depth, error);
+            }
+        }
+        Ok(())
+    }
+
+    /// Puts the stack back to `depth`, padding it with zeros, and pushes the
+    /// throw code of `error`, as `catch` does when what it runs fails.
+    fn caught(&mut self, depth: usize, error: Error) {
+        self.stack.split_off(depth);
+        while self.stack.len() < depth {
+            self.stack.push(0);
+        }
+        self.stack.push(error.throw_code());
+    }
+
+    /// Pops the frames at the top of `frames` that are done, as `run_frames`
+    /// does before running the next op.
+    pub(crate) fn settle(&mut self, frames: &mut Vec<(usize, usize)>) {
+        while let Some(&(ip, end)) = frames.last() {
+            if ip == CATCH {
+                self.stack.push(0);
+            } else if ip != end {
+                break;
+            } else {
+                self.time_exit(1);
+            }
+            frames.pop();
+        }
+    }
+
+    fn run_uncaught(
+        &mut self,
+        frames: &mut Vec<(usize, usize)>,
+        input: &mut Input<'_>,
+        budget: &mut usize,
+    ) -> Result {
+        while !frames.is_empty() {
+            let depth = frames.len();
+            let max = &mut self.run_stats.max_return_depth;
+            *max = (*max).max(depth);
+            let (ip, end) = frames.last_mut().expect("not empty");
+            if *ip == CATCH {
+                // The word called by the `catch` returned.
+                frames.pop();
+                self.stack.push(0);
+                continue;
+            }
+            if ip == end {
+                frames.pop();
+                self.time_exit(1);
+                continue;
+            }
+            if *budget == 0 {
+                break;
+            }
+            *budget -= 1;
+            let op = self.code[*ip];
+            *ip += 1;
+            #[cfg(feature = "observers")]
+            self.running(op, depth);
+            if let Some(profile) = &mut self.profile {
+                profile.count(op);
+            }
+            if let Some(coverage) = &mut self.coverage {
+                coverage.count(Some(*ip - 1), op);
+            }
+            // `execute` runs the op of the token it pops in its place.
+            let op = match op {
+                Op::Execute => self.pop_xt()?,
+                op => op,
+            };
+            let call = matches!(op, Op::Call(_) | Op::TailCall(_));
+            if self.trace && call {
+                self.trace_op(op, depth);
+            }
+            match op {
+                Op::Call(callee) => {
+                    self.metrics.words_executed += 1;
+                    #[cfg(feature = "jit")]
+                    if self.run_native(callee)? {
+                        continue;
+                    }
+                    if frames.len() >= self.max_call_depth {
+                        return Err(Error::ReturnStackOverflow);
+                    }
+                    self.time_entry(callee);
+                    let Body { start, end } = self.words[callee];
+                    frames.push((start, end));
+                }
+                Op::TailCall(callee) => {
+                    self.metrics.words_executed += 1;
+                    #[cfg(feature = "jit")]
+                    if self.run_native(callee)? {
+                        frames.pop();
+                        continue;
+                    }
+                    let body = self.words[callee];
+                    (*ip, *end) = (body.start, body.end);
+                    self.time_exit(1);
+                    self.time_entry(callee);
+                }
+                Op::Branch(offset) => {
+                    self.metrics.words_executed += 1;
+                    *ip += offset;
+                }
+                Op::BranchIfZero(offset) => {
+                    self.metrics.words_executed += 1;
+                    if self.stack.pop().ok_or(Error::StackUnderflow)? == 0 {
+                        *ip += offset;
+                    }
+                }
+                Op::Catch => match self.pop_xt()? {
+                    Op::Call(callee) => {
+                        self.metrics.words_executed += 1;
+                        if frames.len() + 1 >= self.max_call_depth {
+                            return Err(Error::ReturnStackOverflow);
+                        }
+                        frames.push((CATCH, self.stack.len()));
+                        self.time_entry(callee);
+                        let Body { start, end } = self.words[callee];
+                        frames.push((start, end));
+                    }
+                    op => self.catch(op, input)?,
+                },
+                // The turn of the task running ends here.
+                Op::Pause if self.tasks.running() => {
+                    self.metrics.words_executed += 1;
+                    *budget = 0;
+                }
+                op => self.step(op, input)?,
+            }
+            if self.trace && !call {
+                self.trace_op(op, depth);
+            }
+        }
+        Ok(())
+    }
+
+    fn step(&mut self, op: Op, input: &mut Input<'_>) -> Result {
+        match op {
+            Op::Push(value) => self.stack.push(value),
+            #[cfg(feature = "tagged")]
+            Op::PushTagged(value, tag) => self.stack.push_tagged(value, tag),
+            Op::Primitive(primitive) => {
+                self.run_stats.primitives_executed += 1;
+                #[cfg(feature = "tagged")]
+                let tags = self.top_tags();
+                PRIMITIVES[primitive as usize](self)?;
+                #[cfg(feature = "tagged")]
+                self.retag(primitive, tags);
+            }
+            Op::Variable => match input.next() {
+                Some(Lexeme::Word(name)) => self.define_variable(&name)?,
+                _ => return Err(Error::InvalidWord),
+            },
+            Op::Forget => match input.next() {
+                Some(Lexeme::Word(name)) => self.forget(&name)?,
+                _ => return Err(Error::InvalidWord),
+            },
+            Op::Mark => match input.next() {
+                Some(Lexeme::Word(name)) => self.define_marker(&name)?,
+                _ => return Err(Error::InvalidWord),
+            },
+            Op::DefineStack => match input.next() {
+                Some(Lexeme::Word(name)) => self.define_stack(&name)?,
+                _ => return Err(Error::InvalidWord),
+            },
+            Op::Spawn => match input.next() {
+                Some(Lexeme::Word(name)) => self.spawn(&name)?,
+                _ => return Err(Error::InvalidWord),
+            },
+            Op::Pause if self.tasks.running() => {}
+            Op::Pause => self.pause()?,
+            Op::User => match input.next() {
+                Some(Lexeme::Word(name)) => self.define_user(&name)?,
+                _ => return Err(Error::InvalidWord),
+            },
+            Op::PushXt(xt) => self.push_xt(xt),
+            Op::Tick => match input.next() {
+                Some(Lexeme::Word(name)) => {
+                    let xt = self.xt(&name)?;
+                    self.push_xt(xt);
+                }
+                _ => return Err(Error::InvalidWord),
+            },
+            Op::Synonym => match (input.next(), input.next()) {
+                (Some(Lexeme::Word(new)), Some(Lexeme::Word(old))) => self.alias(&new, &old)?,
+                _ => return Err(Error::InvalidWord),
+            },
+            Op::Rewind(entry) => {
+                self.check_unsealed()?;
+                self.rewind(entry)
+            }
+            Op::Disassemble => match input.next() {
+                Some(Lexeme::Word(name)) => {
+                    let listing = self.disassemble(&name)?;
+                    write!(self.output, "{listing}");
+                }
+                _ => return Err(Error::InvalidWord),
+            },
+            Op::Help => match input.next() {
+                Some(Lexeme::Word(name)) => {
+                    self.lookup_word(&name)?;
+                    match self.doc(&name) {
+                        Some(effect) => writeln!(self.output, "{name} {effect}"),
+                        None => writeln!(self.output, "{name}"),
+                    }
+                }
+                _ => return Err(Error::InvalidWord),
+            },
+            Op::Include => self.include(input)?,
+            Op::Unknown => return Err(Error::UnknownWord),
+            #[cfg(feature = "file-io")]
+            Op::File(word) => self.file_word(word, input)?,
+            #[cfg(feature = "env")]
+            Op::GetEnv => self.getenv(input)?,
+            #[cfg(feature = "env")]
+            Op::SetEnv => self.setenv(input)?,
+            #[cfg(feature = "net")]
+            Op::Net(word) => self.net_word(word, input)?,
+            #[cfg(feature = "peripherals")]
+            Op::Peripheral(word) => self.peripheral_word(word)?,
+            #[cfg(feature = "dlopen")]
+            Op::Foreign(word) => self.foreign_word(word, input)?,
+            Op::Call(word) | Op::TailCall(word) => return self.call(word, input),
+            Op::Execute => {
+                let op = self.pop_xt()?;
+                return self.step(op, input);
+            }
+            Op::Catch => {
+                let op = self.pop_xt()?;
+                self.catch(op, input)?
+            }
+            Op::Branch(_) | Op::BranchIfZero(_) => {
+                unreachable!("branches only occur in definition bodies")
+            }
+        }
+        #[cfg(feature = "tagged")]
+        let pushes = matches!(op, Op::Push(_) | Op::PushTagged(..) | Op::PushXt(_));
+        #[cfg(not(feature = "tagged"))]
+        let pushes = matches!(op, Op::Push(_) | Op::PushXt(_));
+        if !pushes {
+            self.metrics.words_executed += 1;
+        }
+        let max = &mut self.metrics.max_stack_depth;
+        *max = (*max).max(self.stack.len());
+        let max = &mut self.run_stats.max_stack_depth;
+        *max = (*max).max(self.stack.len());
+        Ok(())
+    }
+
+    /// Index into the data space of the cell at `address`.
+    fn cell(&self, address: Value) -> std::result::Result<usize, Error> {
+        usize::try_from(address)
+            .ok()
+            .filter(|&address| address < self.data_space.len())
+            .ok_or(Error::InvalidAddress)
+    }
+
+    pub(crate) fn pop(&mut self) -> std::result::Result<Value, Error> {
+        self.stack.pop().ok_or(Error::StackUnderflow)
+    }
+
+    /// Pops an address, which with the `tagged` feature must be tagged as
+    /// one.
+    pub(crate) fn pop_address(&mut self) -> std::result::Result<Value, Error> {
+        #[cfg(feature = "tagged")]
+        return self.pop_tagged(Tag::Address);
+        #[cfg(not(feature = "tagged"))]
+        self.pop()
+    }
+
+    /// Pushes an execution token, which with the `tagged` feature tags it
+    /// as one.
+    fn push_xt(&mut self, xt: Value) {
+        #[cfg(feature = "tagged")]
+        self.stack.push_tagged(xt, Tag::Xt);
+        #[cfg(not(feature = "tagged"))]
+        self.stack.push(xt);
+    }
+
+    /// Pops an execution token, which with the `tagged` feature must be
+    /// tagged as one, giving the op that runs what it stands for. That of
+    /// `execute` pops the token below it in turn, so `Op::Execute` is never
+    /// given. A builtin the capabilities don't grant fails with
+    /// `Error::InvalidWord`, as a token standing for nothing does.
+    pub(crate) fn pop_xt(&mut self) -> std::result::Result<Op, Error> {
+        loop {
+            #[cfg(feature = "tagged")]
+            let xt = self.pop_tagged(Tag::Xt)?;
+            #[cfg(not(feature = "tagged"))]
+            let xt = self.pop()?;
+            match self.xt_op(xt) {
+                Some(Op::Execute) => {}
+                Some(op) if self.capabilities.grants_op(op) => return Ok(op),
+                _ => return Err(Error::InvalidWord),
+            }
+        }
+    }
+
+    /// The op running what the execution token `xt` stands for, see
+    /// `Forth::xt`.
+    pub(crate) fn xt_op(&self, xt: Value) -> Option<Op> {
+        match usize::try_from(xt) {
+            Ok(word) => (word < self.words.len()).then_some(Op::Call(word)),
+            Err(_) => builtin_ops().nth((-1 - xt) as usize),
+        }
+    }
+
+    /// Pops a character, which with the `tagged` feature must be tagged as
+    /// one.
+    fn pop_char(&mut self) -> std::result::Result<char, Error> {
+        #[cfg(feature = "tagged")]
+        let c = self.pop_tagged(Tag::Char)?;
+        #[cfg(not(feature = "tagged"))]
+        let c = self.pop()?;
+        u32::try_from(c)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or(Error::OutOfRange)
+    }
+
+    /// The cells of data space from `address` on that hold `len` bytes,
+    /// one to a cell, as `evaluate` and the file and socket words take
+    /// buffers.
+    pub(crate) fn buffer(
+        &mut self,
+        address: Value,
+        len: Value,
+    ) -> std::result::Result<std::ops::Range<usize>, Error> {
+        let start = usize::try_from(address).map_err(|_| Error::InvalidAddress)?;
+        let len = usize::try_from(len).map_err(|_| Error::OutOfRange)?;
+        let buffer = start
+            .checked_add(len)
+            .filter(|&end| end <= self.data_space.len())
+            .map(|end| start..end)
+            .ok_or(Error::InvalidAddress)?;
+        self.run_stats.data_space_bytes_touched += len * CELL_SIZE;
+        Ok(buffer)
+    }
+
+    /// `throw ( k*x n -- k*x | i*x n )`
+    fn do_throw(&mut self) -> Result {
+        match self.pop()? {
+            0 => Ok(()),
+            code => Err(Error::Uncaught(code)),
+        }
+    }
+
+    fn do_emit(&mut self) -> Result {
+        let c = self.pop_char()?;
+        write!(self.output, "{c}");
+        Ok(())
+    }
+
+    fn do_fetch(&mut self) -> Result {
+        let address = self.pop_address()?;
+        let cell = self.cell(address)?;
+        let value = self.data_space[cell];
+        self.run_stats.data_space_bytes_touched += CELL_SIZE;
+        #[cfg(feature = "tagged")]
+        self.stack.push_tagged(value, self.cell_tag(cell));
+        #[cfg(not(feature = "tagged"))]
+        self.stack.push(value);
+        Ok(())
+    }
+
+    fn do_store(&mut self) -> Result {
+        let address = self.pop_address()?;
+        let value = self.stack.pop_cell().ok_or(Error::StackUnderflow)?;
+        let cell = self.cell(address)?;
+        #[cfg(feature = "tagged")]
+        let value = {
+            let (value, tag) = value;
+            self.tag_cell(cell, tag);
+            value
+        };
+        Arc::make_mut(&mut self.data_space)[cell] = value;
+        self.run_stats.data_space_bytes_touched += CELL_SIZE;
+        Ok(())
+    }
+}
+
+fn do_addition(stack: &mut Stack) -> Result {
+    let a = stack.pop().ok_or(Error::StackUnderflow)?;
+    let b = stack.pop().ok_or(Error::StackUnderflow)?;
+    stack.push(b.checked_add(a).ok_or(Error::Overflow)?);
+    Ok(())
+}
+
+fn do_substraction(stack: &mut Stack) -> Result {
+    let a = stack.pop().ok_or(Error::StackUnderflow)?;
+    let b = stack.pop().ok_or(Error::StackUnderflow)?;
+    stack.push(b.checked_sub(a).ok_or(Error::Overflow)?);
+    Ok(())
+}
+
+fn do_multiplication(stack: &mut Stack) -> Result {
+    let a = stack.pop().ok_or(Error::StackUnderflow)?;
+    let b = stack.pop().ok_or(Error::StackUnderflow)?;
+    stack.push(b.checked_mul(a).ok_or(Error::Overflow)?);
+    Ok(())
+}
+
+fn do_wrapping(stack: &mut Stack, op: fn(Value, Value) -> Value) -> Result {
+    let a = stack.pop().ok_or(Error::StackUnderflow)?;
+    let b = stack.pop().ok_or(Error::StackUnderflow)?;
+    stack.push(op(b, a));
+    Ok(())
+}
+
+/// What a division leaves on the stack.
+enum Leaves {
+    Quotient,
+    Remainder,
+    /// The remainder, then the quotient on top, as `/mod` does.
+    Both,
+}
+
+fn do_division(stack: &mut Stack, division: Division, leaves: Leaves) -> Result {
+    let a = stack.pop().ok_or(Error::StackUnderflow)?;
+    if a == 0 {
+        return Err(Error::DivisionByZero);
+    }
+    let b = stack.pop().ok_or(Error::StackUnderflow)?;
+    let (quotient, remainder) = division.divide(b, a).ok_or(Error::Overflow)?;
+    match leaves {
+        Leaves::Quotient => stack.push(quotient),
+        Leaves::Remainder => stack.push(remainder),
+        Leaves::Both => {
+            stack.push(remainder);
+            stack.push(quotient);
+        }
+    }
+    Ok(())
+}
+
+fn do_dup(stack: &mut Stack) -> Result {
+    let a = stack.pop_cell().ok_or(Error::StackUnderflow)?;
+    stack.push_cell(a);
+    stack.push_cell(a);
+    Ok(())
+}
+
+fn do_drop(stack: &mut Stack) -> Result {
+    stack.pop().ok_or(Error::StackUnderflow)?;
+    Ok(())
+}
+
+fn do_swap(stack: &mut Stack) -> Result {
+    let a = stack.pop_cell().ok_or(Error::StackUnderflow)?;
+    let b = stack.pop_cell().ok_or(Error::StackUnderflow)?;
+    stack.push_cell(a);
+    stack.push_cell(b);
+    Ok(())
+}
+
+fn do_over(stack: &mut Stack) -> Result {
+    let a = stack.pop_cell().ok_or(Error::StackUnderflow)?;
+    let b = stack.pop_cell().ok_or(Error::StackUnderflow)?;
+    stack.push_cell(b);
+    stack.push_cell(a);
+    stack.push_cell(b);
+    Ok(())
+}
+
+fn do_square(stack: &mut Stack) -> Result {
+    let a = stack.pop().ok_or(Error::StackUnderflow)?;
+    stack.push(a.checked_mul(a).ok_or(Error::Overflow)?);
+    Ok(())
+}
+
+fn do_swap_substraction(stack: &mut Stack) -> Result {
+    let a = stack.pop().ok_or(Error::StackUnderflow)?;
+    let b = stack.pop().ok_or(Error::StackUnderflow)?;
+    stack.push(a.checked_sub(b).ok_or(Error::Overflow)?);
+    Ok(())
+}
+
+fn do_over_addition(stack: &mut Stack) -> Result {
+    let a = stack.pop().ok_or(Error::StackUnderflow)?;
+    let b = stack.pop().ok_or(Error::StackUnderflow)?;
+    stack.push(b);
+    stack.push(b.checked_add(a).ok_or(Error::Overflow)?);
+    Ok(())
+}
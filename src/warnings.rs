@@ -0,0 +1,57 @@
+use crate::{Forth, BUILTINS};
+
+/// Something worth telling about a definition that nonetheless succeeded,
+/// kept apart from errors until the host takes it with
+/// `Forth::take_warnings`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// The name was already defined, and now means something else.
+    Redefined(String),
+    /// The name was a builtin, which it no longer means.
+    ShadowsBuiltin(String),
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::Redefined(name) => write!(f, "`{name}` isn't unique"),
+            Warning::ShadowsBuiltin(name) => {
+                write!(f, "`{name}` isn't unique, and shadows a builtin")
+            }
+        }
+    }
+}
+
+impl Forth {
+    /// Warnings since they were last taken, oldest first.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Warnings since they were last taken, oldest first, leaving none.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Warns if `name` means something already, before it is defined anew.
+    /// Builtins the capabilities hide don't count.
+    pub(crate) fn warn_if_defined(&mut self, name: &str) {
+        let Some(entry) = self
+            .names
+            .get(name)
+            .and_then(|symbol| self.dictionary.find(symbol))
+        else {
+            return;
+        };
+        let warning = if entry >= BUILTINS {
+            Warning::Redefined(name.to_string())
+        } else if self.capabilities.grants(name) {
+            Warning::ShadowsBuiltin(name.to_string())
+        } else {
+            return;
+        };
+        #[cfg(feature = "log")]
+        log::warn!("{warning}");
+        self.warnings.push(warning);
+    }
+}
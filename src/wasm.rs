@@ -0,0 +1,631 @@
+use std::collections::BTreeMap;
+
+use crate::bytecode::{Op, Primitive};
+use crate::diagnostics::commands;
+use crate::lexer::{lex, Lexeme};
+use crate::vm::Input;
+use crate::{Division, Error, Forth, Operation};
+
+/// Bytes the stack may take in the memory of a module, after data space.
+const STACK_BYTES: usize = 1 << 16;
+const PAGE_BYTES: usize = 1 << 16;
+
+/// The functions every module starts with, before a helper for each of
+/// `PRIMITIVES`.
+const PUSH: u32 = 0;
+const POP: u32 = 1;
+const DEPTH: u32 = 2;
+
+/// What a module exports besides the words asked for.
+const RESERVED: [&str; 5] = ["memory", "push", "pop", "depth", "main"];
+
+/// The builtins a module can run, each by a helper function of its own.
+const PRIMITIVES: [Primitive; 21] = [
+    Primitive::Add,
+    Primitive::Subtract,
+    Primitive::Multiply,
+    Primitive::Divide,
+    Primitive::Modulo,
+    Primitive::DivMod,
+    Primitive::FlooredDivide,
+    Primitive::FlooredModulo,
+    Primitive::FlooredDivMod,
+    Primitive::WrappingAdd,
+    Primitive::WrappingSubtract,
+    Primitive::WrappingMultiply,
+    Primitive::Dup,
+    Primitive::Drop,
+    Primitive::Swap,
+    Primitive::Over,
+    Primitive::Fetch,
+    Primitive::Store,
+    Primitive::Square,
+    Primitive::SwapSubtract,
+    Primitive::OverAdd,
+];
+
+/// Whether a module can run `op`, which isn't `Op::Call` or `Op::TailCall`
+/// of a word it can't.
+fn supported(op: Op) -> bool {
+    match op {
+        Op::Push(_)
+        | Op::PushXt(_)
+        | Op::Call(_)
+        | Op::TailCall(_)
+        | Op::Branch(_)
+        | Op::BranchIfZero(_)
+        | Op::Unknown => true,
+        #[cfg(feature = "tagged")]
+        Op::PushTagged(..) => true,
+        Op::Primitive(primitive) => {
+            primitive == Primitive::Throw || PRIMITIVES.contains(&primitive)
+        }
+        _ => false,
+    }
+}
+
+// Instructions, and the types of values, blocks and exports.
+const UNREACHABLE: u8 = 0x00;
+const BLOCK: u8 = 0x02;
+const LOOP: u8 = 0x03;
+const IF: u8 = 0x04;
+const END: u8 = 0x0b;
+const BR: u8 = 0x0c;
+const BR_TABLE: u8 = 0x0e;
+const CALL: u8 = 0x10;
+const RETURN_CALL: u8 = 0x12;
+const DROP: u8 = 0x1a;
+const SELECT: u8 = 0x1b;
+const LOCAL_GET: u8 = 0x20;
+const LOCAL_SET: u8 = 0x21;
+const LOCAL_TEE: u8 = 0x22;
+const GLOBAL_GET: u8 = 0x23;
+const GLOBAL_SET: u8 = 0x24;
+const I32_LOAD: u8 = 0x28;
+const I32_STORE: u8 = 0x36;
+const I32_CONST: u8 = 0x41;
+const I32_EQZ: u8 = 0x45;
+const I32_LT_S: u8 = 0x48;
+const I32_GE_U: u8 = 0x4f;
+const I32_LE_U: u8 = 0x4d;
+const I64_NE: u8 = 0x52;
+const I32_ADD: u8 = 0x6a;
+const I32_SUB: u8 = 0x6b;
+const I32_MUL: u8 = 0x6c;
+const I32_DIV_S: u8 = 0x6d;
+const I32_REM_S: u8 = 0x6f;
+const I32_AND: u8 = 0x71;
+const I32_XOR: u8 = 0x73;
+const I32_SHL: u8 = 0x74;
+const I32_SHR_U: u8 = 0x76;
+const I64_ADD: u8 = 0x7c;
+const I64_SUB: u8 = 0x7d;
+const I64_MUL: u8 = 0x7e;
+const I32_WRAP_I64: u8 = 0xa7;
+const I64_EXTEND_I32_S: u8 = 0xac;
+const EMPTY: u8 = 0x40;
+const I32: u8 = 0x7f;
+const I64: u8 = 0x7e;
+const EXPORT_FUNCTION: u8 = 0x00;
+const EXPORT_MEMORY: u8 = 0x02;
+
+fn unsigned(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return bytes.push(byte);
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn signed(bytes: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            return bytes.push(byte);
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn name(bytes: &mut Vec<u8>, name: &str) {
+    unsigned(bytes, name.len() as u64);
+    bytes.extend(name.as_bytes());
+}
+
+/// The code of a function, as it is written.
+#[derive(Default)]
+struct Code(Vec<u8>);
+
+impl Code {
+    fn op(&mut self, byte: u8) -> &mut Self {
+        self.0.push(byte);
+        self
+    }
+
+    fn with(&mut self, byte: u8, index: u32) -> &mut Self {
+        self.0.push(byte);
+        unsigned(&mut self.0, u64::from(index));
+        self
+    }
+
+    fn constant(&mut self, value: i32) -> &mut Self {
+        self.0.push(I32_CONST);
+        signed(&mut self.0, i64::from(value));
+        self
+    }
+
+    fn call(&mut self, function: u32) -> &mut Self {
+        self.with(CALL, function)
+    }
+
+    fn get(&mut self, local: u32) -> &mut Self {
+        self.with(LOCAL_GET, local)
+    }
+
+    fn set(&mut self, local: u32) -> &mut Self {
+        self.with(LOCAL_SET, local)
+    }
+
+    /// Traps if the flag on top is true.
+    fn trap_if(&mut self) -> &mut Self {
+        self.op(IF).op(EMPTY).op(UNREACHABLE).op(END)
+    }
+
+    /// `i32.load` or `i32.store` of an aligned cell.
+    fn memory(&mut self, op: u8) -> &mut Self {
+        self.op(op).op(2).op(0)
+    }
+}
+
+// Locals of the helpers for the primitives.
+const A: u32 = 0;
+const B: u32 = 1;
+const Q: u32 = 2;
+const R: u32 = 3;
+const T: u32 = 4;
+
+/// The helper running `primitive`, which takes the values it pops as `A`,
+/// the top, and `B`, with data space `cells` long.
+fn helper(primitive: Primitive, cells: usize) -> Code {
+    let mut code = Code::default();
+    let pop_two = |code: &mut Code| {
+        code.call(POP).set(A).call(POP).set(B);
+    };
+    // Pushes `x op y` of the two locals, failing on overflow.
+    let checked = |code: &mut Code, x: u32, op: u8, y: u32| {
+        code.get(x)
+            .op(I64_EXTEND_I32_S)
+            .get(y)
+            .op(I64_EXTEND_I32_S)
+            .op(op);
+        code.with(LOCAL_TEE, T)
+            .get(T)
+            .op(I32_WRAP_I64)
+            .op(I64_EXTEND_I32_S);
+        code.op(I64_NE).trap_if().get(T).op(I32_WRAP_I64).call(PUSH);
+    };
+    let wrapping = |code: &mut Code, op: u8| {
+        code.get(B).get(A).op(op).call(PUSH);
+    };
+    let address = |code: &mut Code| {
+        let cells = i32::try_from(cells).unwrap_or(i32::MAX);
+        code.get(A).constant(cells).op(I32_GE_U).trap_if();
+        code.get(A).constant(2).op(I32_SHL);
+    };
+    match primitive {
+        Primitive::Add => {
+            pop_two(&mut code);
+            checked(&mut code, B, I64_ADD, A);
+        }
+        Primitive::Subtract => {
+            pop_two(&mut code);
+            checked(&mut code, B, I64_SUB, A);
+        }
+        Primitive::Multiply => {
+            pop_two(&mut code);
+            checked(&mut code, B, I64_MUL, A);
+        }
+        Primitive::Square => {
+            code.call(POP).set(A);
+            checked(&mut code, A, I64_MUL, A);
+        }
+        Primitive::SwapSubtract => {
+            pop_two(&mut code);
+            checked(&mut code, A, I64_SUB, B);
+        }
+        Primitive::OverAdd => {
+            pop_two(&mut code);
+            code.get(B).call(PUSH);
+            checked(&mut code, B, I64_ADD, A);
+        }
+        Primitive::WrappingAdd => {
+            pop_two(&mut code);
+            wrapping(&mut code, I32_ADD);
+        }
+        Primitive::WrappingSubtract => {
+            pop_two(&mut code);
+            wrapping(&mut code, I32_SUB);
+        }
+        Primitive::WrappingMultiply => {
+            pop_two(&mut code);
+            wrapping(&mut code, I32_MUL);
+        }
+        Primitive::Divide
+        | Primitive::Modulo
+        | Primitive::DivMod
+        | Primitive::FlooredDivide
+        | Primitive::FlooredModulo
+        | Primitive::FlooredDivMod => {
+            code.call(POP).set(A).get(A).op(I32_EQZ).trap_if();
+            // `div_s` traps on the one quotient that overflows.
+            code.call(POP).set(B).get(B).get(A).op(I32_DIV_S).set(Q);
+            code.get(B).get(A).op(I32_REM_S).set(R);
+            if primitive.division() == Some(Division::Floored) {
+                // Into `B`, done with: whether the remainder is other than
+                // zero and of the sign opposite to that of the divisor.
+                code.get(R).op(I32_EQZ).op(I32_EQZ);
+                code.get(R)
+                    .get(A)
+                    .op(I32_XOR)
+                    .constant(0)
+                    .op(I32_LT_S)
+                    .op(I32_AND);
+                code.set(B);
+                code.get(Q).get(B).op(I32_SUB).set(Q);
+                code.get(R)
+                    .get(A)
+                    .op(I32_ADD)
+                    .get(R)
+                    .get(B)
+                    .op(SELECT)
+                    .set(R);
+            }
+            match primitive {
+                Primitive::Divide | Primitive::FlooredDivide => code.get(Q).call(PUSH),
+                Primitive::Modulo | Primitive::FlooredModulo => code.get(R).call(PUSH),
+                _ => code.get(R).call(PUSH).get(Q).call(PUSH),
+            };
+        }
+        Primitive::Dup => {
+            code.call(POP).set(A).get(A).call(PUSH).get(A).call(PUSH);
+        }
+        Primitive::Drop => {
+            code.call(POP).op(DROP);
+        }
+        Primitive::Swap => {
+            pop_two(&mut code);
+            code.get(A).call(PUSH).get(B).call(PUSH);
+        }
+        Primitive::Over => {
+            pop_two(&mut code);
+            code.get(B).call(PUSH).get(A).call(PUSH).get(B).call(PUSH);
+        }
+        Primitive::Fetch => {
+            code.call(POP).set(A);
+            address(&mut code);
+            code.memory(I32_LOAD).call(PUSH);
+        }
+        Primitive::Store => {
+            pop_two(&mut code);
+            address(&mut code);
+            code.get(B).memory(I32_STORE);
+        }
+        _ => unreachable!("not in `PRIMITIVES`"),
+    }
+    code
+}
+
+/// The words a module is made of, numbered as its functions.
+struct Functions {
+    /// The function of each word, by its index into `Forth::words`.
+    words: BTreeMap<usize, u32>,
+    /// The function of the program, which `recurse` calls there.
+    main: u32,
+}
+
+impl Functions {
+    fn of(&self, word: usize) -> u32 {
+        self.words.get(&word).copied().unwrap_or(self.main)
+    }
+}
+
+/// `code` as the body of a function, whose `if`s become the arms of a loop
+/// running one stretch of it after another, as `Forth::transpile` does.
+fn body(code: &[Op], functions: &Functions) -> Code {
+    let mut body = Code::default();
+    let mut starts = vec![0];
+    for (i, op) in code.iter().enumerate() {
+        if let Op::Branch(skip) | Op::BranchIfZero(skip) = *op {
+            starts.extend([i + 1, i + 1 + skip]);
+        }
+    }
+    starts.retain(|&start| start < code.len());
+    starts.sort_unstable();
+    starts.dedup();
+    let stretches = starts.len() as u32;
+    let block = |start: usize| {
+        starts
+            .iter()
+            .position(|&s| s == start)
+            .unwrap_or(starts.len()) as u32
+    };
+    let straight = |body: &mut Code, ops: &[Op]| {
+        for &op in ops {
+            match op {
+                Op::Push(value) | Op::PushXt(value) => body.constant(value).call(PUSH),
+                #[cfg(feature = "tagged")]
+                Op::PushTagged(value, _) => body.constant(value).call(PUSH),
+                Op::Primitive(Primitive::Throw) => body.call(POP).trap_if(),
+                Op::Primitive(primitive) => {
+                    let helper = PRIMITIVES.iter().position(|&p| p == primitive);
+                    body.call(3 + helper.expect("`supported` primitive") as u32)
+                }
+                Op::Call(word) => body.call(functions.of(word)),
+                Op::TailCall(word) => body.with(RETURN_CALL, functions.of(word)),
+                Op::Unknown => body.op(UNREACHABLE),
+                _ => unreachable!("filtered out by `supported`"),
+            };
+        }
+    };
+    if stretches == 1 {
+        straight(&mut body, code);
+        return body;
+    }
+    // The stretch to run next is in local 0, and the one past the last means
+    // the end.
+    body.op(LOOP).op(EMPTY).op(BLOCK).op(EMPTY);
+    for _ in 0..stretches {
+        body.op(BLOCK).op(EMPTY);
+    }
+    body.get(0).op(BR_TABLE);
+    unsigned(&mut body.0, u64::from(stretches));
+    for label in 0..=stretches {
+        unsigned(&mut body.0, u64::from(label));
+    }
+    for (index, &start) in starts.iter().enumerate() {
+        body.op(END);
+        let end = starts.get(index + 1).copied().unwrap_or(code.len());
+        // Blocks of the stretches after this one, then the end and the loop.
+        let to_loop = stretches - index as u32;
+        match code[start..end].split_last() {
+            Some((&Op::Branch(skip), ops)) => {
+                straight(&mut body, ops);
+                body.constant(block(end + skip) as i32);
+            }
+            Some((&Op::BranchIfZero(skip), ops)) => {
+                straight(&mut body, ops);
+                body.constant(block(end) as i32)
+                    .constant(block(end + skip) as i32);
+                body.call(POP).op(SELECT);
+            }
+            _ => {
+                straight(&mut body, &code[start..end]);
+                body.constant(block(end) as i32);
+            }
+        }
+        body.set(0).with(BR, to_loop);
+    }
+    body.op(END).op(END);
+    body
+}
+
+/// A section of a module with its `id` and `count` entries in `contents`.
+fn section(module: &mut Vec<u8>, id: u8, count: usize, contents: &[u8]) {
+    let mut counted = Vec::new();
+    unsigned(&mut counted, count as u64);
+    counted.extend(contents);
+    module.push(id);
+    unsigned(module, counted.len() as u64);
+    module.extend(counted);
+}
+
+/// A function with its locals, as the code section lists it.
+fn function(locals: &[(u32, u8)], code: &Code) -> Vec<u8> {
+    let mut function = Vec::new();
+    unsigned(&mut function, locals.len() as u64);
+    for &(count, kind) in locals {
+        unsigned(&mut function, u64::from(count));
+        function.push(kind);
+    }
+    function.extend(&code.0);
+    function.push(END);
+    let mut sized = Vec::new();
+    unsigned(&mut sized, function.len() as u64);
+    sized.extend(function);
+    sized
+}
+
+impl Forth {
+    /// `script` as a WebAssembly module, which runs it without the
+    /// interpreter. Its definitions and the variables, markers and other
+    /// words it defines are defined here as `eval` would; the rest of it is
+    /// the program, exported as `main`, as it would run after all of them.
+    /// The user-defined words named in `words` are exported too, under
+    /// their names, lower-cased. Data space starts as it is here once the
+    /// script's words are defined.
+    ///
+    /// The stack is in the exported `memory`, after data space, which holds
+    /// a cell for every variable: a host calls the exported `push` for each
+    /// value it gives, then an exported word, then `depth` and `pop` for the
+    /// results. A failure traps, whether in the word or in `pop`. Only
+    /// arithmetic, the stack and data-space words, `throw`, branches and
+    /// calls can be compiled; anything else fails with `Error::InvalidWord`.
+    /// A word's last call is a `return_call`, so tail recursion runs in
+    /// constant space, and a host must support tail calls, which
+    /// WebAssembly 3.0 made standard.
+    pub fn compile_wasm(
+        &mut self,
+        script: &str,
+        words: &[&str],
+    ) -> std::result::Result<Vec<u8>, Error> {
+        let mut program = Vec::new();
+        for command in commands(script) {
+            let command = command.map_err(|malformed| self.malformed(malformed).fault.error)?;
+            let text = &script[command.start..command.end];
+            if lex(text).next().is_some_and(|token| token.is_word(":")) {
+                self.eval_command(text).map_err(|fault| fault.error)?;
+                continue;
+            }
+            let mut tokens = lex(text);
+            while let Some(token) = tokens.next() {
+                let parsed = match self.token_op(&token) {
+                    Ok(Op::Variable | Op::User | Op::DefineStack | Op::Mark) => 1,
+                    Ok(Op::Synonym) => 2,
+                    _ => {
+                        program.push(token.into_owned());
+                        continue;
+                    }
+                };
+                let definition: Vec<Lexeme> = std::iter::once(token)
+                    .chain(tokens.by_ref().take(parsed))
+                    .collect();
+                self.run_expression(Input::new(&definition))
+                    .map_err(|fault| fault.error)?;
+            }
+        }
+        let main = self
+            .compile_definition(&program, None)
+            .map_err(|fault| fault.error)?;
+        let mut exported: Vec<(String, usize)> = Vec::new();
+        for name in words {
+            let name = name.to_lowercase();
+            let Operation::UserDefined(word) = self.lookup_word(&name)? else {
+                return Err(Error::InvalidWord);
+            };
+            // Export names must be unique.
+            if RESERVED.contains(&name.as_str()) || exported.iter().any(|(n, _)| *n == name) {
+                return Err(Error::InvalidWord);
+            }
+            exported.push((name, word));
+        }
+        // The words called, which are defined before their callers.
+        let mut reached = std::collections::BTreeSet::new();
+        let mut pending: Vec<usize> = exported.iter().map(|&(_, word)| word).collect();
+        let calls = |code: &[Op]| {
+            code.iter()
+                .filter_map(|op| match *op {
+                    Op::Call(word) | Op::TailCall(word) => Some(word),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        pending.extend(
+            calls(&main)
+                .into_iter()
+                .filter(|&word| word < self.words.len()),
+        );
+        while let Some(word) = pending.pop() {
+            if reached.insert(word) {
+                pending.extend(calls(self.body(word)).into_iter().filter(|&w| w != word));
+            }
+        }
+        let bodies: Vec<&[Op]> = reached.iter().map(|&word| self.body(word)).collect();
+        if !bodies
+            .iter()
+            .chain([&&main[..]])
+            .all(|code| code.iter().all(|&op| supported(op)))
+        {
+            return Err(Error::InvalidWord);
+        }
+        let first = 3 + PRIMITIVES.len() as u32;
+        let functions = Functions {
+            words: reached
+                .iter()
+                .enumerate()
+                .map(|(index, &word)| (word, first + index as u32))
+                .collect(),
+            main: first + reached.len() as u32,
+        };
+        Ok(self.module(&functions, &bodies, &main, &exported))
+    }
+
+    fn module(
+        &self,
+        functions: &Functions,
+        bodies: &[&[Op]],
+        main: &[Op],
+        exported: &[(String, usize)],
+    ) -> Vec<u8> {
+        let cells = self.data_space.len();
+        let base = i32::try_from(cells * 4).unwrap_or(i32::MAX);
+        let mut module = b"\0asm\x01\0\0\0".to_vec();
+        // `() -> ()`, `(i32) -> ()` and `() -> i32`.
+        section(
+            &mut module,
+            1,
+            3,
+            &[0x60, 0, 0, 0x60, 1, I32, 0, 0x60, 0, 1, I32],
+        );
+        let count = 3 + PRIMITIVES.len() + bodies.len() + 1;
+        let mut types = vec![1, 2, 2];
+        types.resize(count, 0);
+        section(&mut module, 3, count, &types);
+        let pages = (cells * 4 + STACK_BYTES).div_ceil(PAGE_BYTES);
+        let mut memory = vec![0];
+        unsigned(&mut memory, pages as u64);
+        section(&mut module, 5, 1, &memory);
+        // The stack pointer, at the bottom of the stack.
+        let mut global = vec![I32, 1, I32_CONST];
+        signed(&mut global, i64::from(base));
+        global.push(END);
+        section(&mut module, 6, 1, &global);
+        let mut exports = Vec::new();
+        let mut export = |label: &str, kind: u8, index: u32| {
+            name(&mut exports, label);
+            exports.push(kind);
+            unsigned(&mut exports, u64::from(index));
+        };
+        export("memory", EXPORT_MEMORY, 0);
+        export("push", EXPORT_FUNCTION, PUSH);
+        export("pop", EXPORT_FUNCTION, POP);
+        export("depth", EXPORT_FUNCTION, DEPTH);
+        export("main", EXPORT_FUNCTION, functions.main);
+        for (label, word) in exported {
+            export(label, EXPORT_FUNCTION, functions.of(*word));
+        }
+        section(&mut module, 7, 5 + exported.len(), &exports);
+        let mut code = Vec::new();
+        let mut push = Code::default();
+        push.with(GLOBAL_GET, 0).get(0).memory(I32_STORE);
+        push.with(GLOBAL_GET, 0)
+            .constant(4)
+            .op(I32_ADD)
+            .with(GLOBAL_SET, 0);
+        code.extend(function(&[], &push));
+        let mut pop = Code::default();
+        pop.with(GLOBAL_GET, 0)
+            .constant(base)
+            .op(I32_LE_U)
+            .trap_if();
+        pop.with(GLOBAL_GET, 0)
+            .constant(4)
+            .op(I32_SUB)
+            .with(GLOBAL_SET, 0);
+        pop.with(GLOBAL_GET, 0).memory(I32_LOAD);
+        code.extend(function(&[], &pop));
+        let mut depth = Code::default();
+        depth
+            .with(GLOBAL_GET, 0)
+            .constant(base)
+            .op(I32_SUB)
+            .constant(2)
+            .op(I32_SHR_U);
+        code.extend(function(&[], &depth));
+        for primitive in PRIMITIVES {
+            code.extend(function(&[(4, I32), (1, I64)], &helper(primitive, cells)));
+        }
+        for &word in bodies.iter().chain([&main]) {
+            code.extend(function(&[(1, I32)], &body(word, functions)));
+        }
+        section(&mut module, 10, count, &code);
+        let mut data = vec![0, I32_CONST, 0, END];
+        unsigned(&mut data, (cells * 4) as u64);
+        data.extend(self.data_space.iter().flat_map(|value| value.to_le_bytes()));
+        section(&mut module, 11, 1, &data);
+        module
+    }
+}
@@ -0,0 +1,41 @@
+//! Counts heap allocations, so this file holds its own test binary.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use forth::Forth;
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[test]
+fn repeated_evaluation_does_not_allocate() {
+    let mut f = Forth::new();
+    f.eval(": sq dup * ; : w sq swap sq + ; variable x")
+        .unwrap();
+    let input = "1 2 w x ! x @ drop";
+    // Warm up, long enough for any word to be compiled to native code.
+    for _ in 0..100 {
+        f.eval(input).unwrap();
+    }
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..100 {
+        f.eval(input).unwrap();
+    }
+    assert_eq!(before, ALLOCATIONS.load(Ordering::Relaxed));
+}
@@ -0,0 +1,67 @@
+use forth::*;
+
+#[test]
+fn batch_programs_share_the_prelude() {
+    let mut prelude = Forth::new();
+    assert!(prelude.eval(": sq dup * ;").is_ok());
+    let results = prelude.eval_batch(&["3 sq", "sq", "1 0 /", "foo"]);
+    assert_eq!(
+        vec![
+            Ok(()),
+            Err(Error::StackUnderflow),
+            Err(Error::DivisionByZero),
+            Err(Error::UnknownWord),
+        ],
+        results
+    );
+}
+
+#[test]
+fn batch_programs_are_isolated() {
+    let mut prelude = Forth::new();
+    assert!(prelude.eval("1").is_ok());
+    let results = prelude.eval_batch(&[": w 2 ; drop", "w", "drop drop"]);
+    assert_eq!(
+        vec![Ok(()), Err(Error::UnknownWord), Err(Error::StackUnderflow)],
+        results
+    );
+    assert_eq!(vec![1], prelude.stack());
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_batch_matches_serial_batch() {
+    let mut prelude = Forth::new();
+    assert!(prelude.eval(": sq dup * ;").is_ok());
+    let programs: Vec<String> = (0..1_000)
+        .map(|i| match i % 3 {
+            0 => format!("{i} sq"),
+            1 => "sq".to_string(),
+            _ => format!("{i} 0 /"),
+        })
+        .collect();
+    let programs: Vec<&str> = programs.iter().map(String::as_str).collect();
+    assert_eq!(
+        prelude.eval_batch(&programs),
+        prelude.par_eval_batch(&programs)
+    );
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn par_eval_prelude_returns_each_stack() {
+    let results = par_eval_prelude(&["3 sq", "sq", "2 sq sq"], ": sq dup * ;");
+    assert_eq!(
+        Ok(vec![Ok(vec![9]), Err(Error::StackUnderflow), Ok(vec![16])]),
+        results
+    );
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn par_eval_prelude_reports_a_failing_prelude() {
+    assert_eq!(
+        Err(Error::UnknownWord),
+        par_eval_prelude(&["1"], ": sq dup * ; oops")
+    );
+}
@@ -0,0 +1,84 @@
+use forth::*;
+
+#[test]
+fn everything_is_granted_by_default() {
+    assert_eq!(Capabilities::ALL, Forth::new().capabilities());
+    assert_eq!(Capabilities::ALL, Capabilities::default());
+    assert!(Capabilities::ALL.contains(Capabilities::FILE_IO | Capabilities::RANDOM));
+    assert!(!Capabilities::NONE.contains(Capabilities::TIME));
+    assert_eq!(
+        Capabilities::NET,
+        (Capabilities::NET | Capabilities::ENV) - Capabilities::ENV
+    );
+}
+
+#[test]
+fn denied_words_are_unknown() {
+    let mut f = Forth::builder()
+        .capabilities(Capabilities::ALL - Capabilities::TIME)
+        .build();
+    assert_eq!(Capabilities::ALL - Capabilities::TIME, f.capabilities());
+    assert_eq!(Err(Error::UnknownWord), f.eval("utime"));
+    assert_eq!(Err(Error::UnknownWord), f.eval(": stamp utime ;"));
+    assert_eq!(Err(Error::UnknownWord), f.eval("0 elapsed"));
+    assert!(!f.words().any(|word| word == "utime"));
+    assert!(f.eval("1 2 +").is_ok());
+    assert_eq!(vec![0, 3], f.stack());
+}
+
+#[test]
+fn scripts_may_define_words_of_denied_names() {
+    let mut f = Forth::builder().capabilities(Capabilities::NONE).build();
+    assert!(f.eval(": utime 42 ; utime").is_ok());
+    assert_eq!(vec![42], f.stack());
+    assert!(f.words().any(|word| word == "utime"));
+}
+
+#[test]
+fn granted_words_still_work() {
+    let mut f = Forth::builder().capabilities(Capabilities::TIME).build();
+    assert!(f.eval("utime elapsed").is_ok());
+    assert_eq!(1, f.stack().len());
+}
+
+#[test]
+fn random_numbers_can_be_denied() {
+    let mut f = Forth::builder().capabilities(Capabilities::RANDOM).build();
+    assert!(f.eval("random random").is_ok());
+    let drawn = f.stack();
+    assert!(drawn.iter().all(|&n| n >= 0), "{drawn:?}");
+    assert_ne!(drawn[0], drawn[1]);
+    let mut f = Forth::builder()
+        .capabilities(Capabilities::ALL - Capabilities::RANDOM)
+        .build();
+    assert_eq!(Err(Error::UnknownWord), f.eval("random"));
+    assert_eq!(Err(Error::UnknownWord), f.eval(": roll random 6 mod ;"));
+}
+
+#[cfg(feature = "file-io")]
+#[test]
+fn file_words_can_be_denied() {
+    let mut f = Forth::builder()
+        .capabilities(Capabilities::ALL - Capabilities::FILE_IO)
+        .build();
+    assert_eq!(Err(Error::UnknownWord), f.eval("r/o"));
+    assert_eq!(Err(Error::UnknownWord), f.eval(": load open-file x r/o ;"));
+}
+
+#[cfg(feature = "env")]
+#[test]
+fn env_words_can_be_denied() {
+    let mut f = Forth::builder()
+        .capabilities(Capabilities::ALL - Capabilities::ENV)
+        .build();
+    assert_eq!(Err(Error::UnknownWord), f.eval("getenv HOME"));
+}
+
+#[cfg(feature = "net")]
+#[test]
+fn net_words_can_be_denied() {
+    let mut f = Forth::builder()
+        .capabilities(Capabilities::ALL - Capabilities::NET)
+        .build();
+    assert_eq!(Err(Error::UnknownWord), f.eval("80 open-socket localhost"));
+}
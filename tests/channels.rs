@@ -0,0 +1,50 @@
+use std::sync::mpsc;
+
+use forth::*;
+
+#[test]
+fn values_flow_both_ways_while_a_program_runs() {
+    let mut f = Forth::new();
+    let (to_forth, from_forth) = f.open_channels();
+    let host = std::thread::spawn(move || {
+        // Doubles each value it is sent, until it is sent 0.
+        for value in from_forth {
+            to_forth.send(value * 2).unwrap();
+            if value == 0 {
+                break;
+            }
+        }
+    });
+    assert!(f
+        .eval(": twice send recv ; 1 twice 2 twice 3 twice 0 twice")
+        .is_ok());
+    host.join().unwrap();
+    assert_eq!(vec![2, 4, 6, 0], f.stack());
+}
+
+#[test]
+fn recv_question_does_not_wait() {
+    let (to_host, _from_forth) = mpsc::channel();
+    let (to_forth, from_host) = mpsc::channel();
+    let mut f = Forth::builder().channels(to_host, from_host).build();
+    assert!(f.eval("recv?").is_ok());
+    assert_eq!(vec![0, 0], f.drain_stack());
+    to_forth.send(7).unwrap();
+    assert!(f.eval("recv? recv?").is_ok());
+    assert_eq!(vec![7, -1, 0, 0], f.stack());
+}
+
+#[test]
+fn channels_fail_when_missing_or_dropped() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::ChannelClosed), f.eval("recv"));
+    assert_eq!(Err(Error::ChannelClosed), f.eval("1 send"));
+    assert_eq!(vec![1], f.drain_stack());
+    let (to_forth, from_forth) = f.open_channels();
+    drop(to_forth);
+    drop(from_forth);
+    assert_eq!(Err(Error::ChannelClosed), f.eval("5 send"));
+    assert_eq!(Err(Error::ChannelClosed), f.eval("recv"));
+    assert_eq!(Err(Error::ChannelClosed), f.eval("recv?"));
+    assert_eq!(vec![5], f.stack());
+}
@@ -0,0 +1,348 @@
+#![cfg(feature = "cli")]
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn repl(input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn prints_the_stack_after_each_line() {
+    assert_eq!("> <2> 1 2\n> <1> 3\n> \n", repl("1 2\n+\n"));
+}
+
+#[test]
+fn keeps_definitions_across_lines() {
+    assert_eq!("> <0>\n> <1> 9\n> \n", repl(": sq dup * ;\n3 sq\n"));
+}
+
+#[test]
+fn prints_errors_and_carries_on() {
+    let output = repl("nope\n1\n");
+    assert!(output.starts_with("> error: unknown word in `nope`"));
+    assert!(output.ends_with("> <1> 1\n> \n"));
+}
+
+#[test]
+fn bye_ends_the_session() {
+    assert_eq!("> <1> 1\n> ", repl("1\nBYE\n2\n"));
+}
+
+#[test]
+fn piped_sessions_keep_no_history() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("piped_history");
+    let _ = std::fs::remove_file(&path);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .env("FORTH_HISTORY", &path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"1 2\n").unwrap();
+    assert!(child.wait_with_output().unwrap().status.success());
+    assert!(!path.exists());
+}
+
+#[test]
+fn tui_shows_the_words_running_when_a_line_fails() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .arg("tui")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"1 2\n: f 0 / ;\n3 f\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let last = stdout.rsplit("\x1b[2J").next().unwrap();
+    assert!(last.contains("┌ stack <3> "), "{last}");
+    assert!(last.contains("│                    3 ││ f                    │"));
+    assert!(last.contains("│ error: division by zero in `3 f`"));
+}
+
+fn run(name: &str, script: &str, args: &[&str]) -> std::process::Output {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, script).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_forth"))
+        .arg("run")
+        .arg(&path)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn run_pushes_arguments_and_prints_the_final_stack() {
+    let output = run("sum.fs", ": sum + ;\nsum\n", &["3", "4"]);
+    assert!(output.status.success());
+    assert_eq!("<1> 7\n", String::from_utf8(output.stdout).unwrap());
+}
+
+#[test]
+fn run_reports_the_line_and_column_of_a_failure() {
+    let output = run("broken.fs", "1 2 +\n  3 nope\n", &[]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("broken.fs:2:5: error: unknown word"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn run_reports_where_in_a_definition_a_failure_was_written() {
+    let output = run("halve.fs", ": halve\n  0 / ;\n8 halve\n", &[]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("halve.fs:2:5, in `halve`"), "{stderr}");
+}
+
+#[test]
+fn run_rejects_arguments_that_are_not_numbers() {
+    let output = run("args.fs", "", &["three"]);
+    assert_eq!(Some(2), output.status.code());
+}
+
+#[test]
+fn doc_lists_the_words_a_script_defines() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("doc.fs");
+    std::fs::write(
+        &path,
+        ": sq ( n -- n*n ) dup * ;\n: or ( a b -- a|b ) + ;\nvariable v\n",
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .arg("doc")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("# Words in `"), "{stdout}");
+    assert!(stdout.ends_with(
+        "| Word | Stack effect |\n| --- | --- |\n| `sq` | `( n -- n*n )` |\n| `or` | `( a b -- a\\|b )` |\n| `v` |  |\n"
+    ), "{stdout}");
+}
+
+#[test]
+fn coverage_flag_writes_an_lcov_report_next_to_the_script() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("covered.fs");
+    std::fs::write(&path, ": sq\n  dup * ;\n: unused\n  1 ;\n3 sq\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .args(["--coverage", "run"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let lcov = std::fs::read_to_string(format!("{}.lcov", path.display())).unwrap();
+    assert!(lcov.contains("DA:2,1\nDA:4,0\n"), "{lcov}");
+}
+
+#[test]
+fn timeline_flag_writes_a_chrome_trace_next_to_the_script() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("timed.fs");
+    std::fs::write(&path, ": sq dup * ;\n: cube dup sq * ;\n3 cube\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .args(["--timeline", "run"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let trace = std::fs::read_to_string(format!("{}.trace.json", path.display())).unwrap();
+    assert!(
+        trace.starts_with(r#"{"displayTimeUnit":"ns","traceEvents":["#),
+        "{trace}"
+    );
+    assert!(trace.contains(r#""name":"cube""#), "{trace}");
+    assert!(trace.contains(r#""name":"sq""#), "{trace}");
+}
+
+#[test]
+fn fmt_rewrites_files_in_place() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("messy.fs");
+    std::fs::write(&path, ":   sq dup   * ;  3 sq").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .args(["fmt", "--indent=4"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        ": sq dup * ;\n3 sq\n",
+        std::fs::read_to_string(&path).unwrap()
+    );
+}
+
+#[test]
+fn fmt_check_names_unformatted_files_and_changes_nothing() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let (tidy, messy) = (dir.join("tidy.fs"), dir.join("unformatted.fs"));
+    std::fs::write(&tidy, ": sq dup * ;\n").unwrap();
+    std::fs::write(&messy, ": sq dup * ;\n: cube\n  dup sq * ;\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .args(["fmt", "--check", "--line-length=40"])
+        .args([&tidy, &messy])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("unformatted.fs: not formatted"), "{stderr}");
+    assert!(!stderr.contains("tidy.fs"), "{stderr}");
+    assert_eq!(
+        ": sq dup * ;\n: cube\n  dup sq * ;\n",
+        std::fs::read_to_string(&messy).unwrap()
+    );
+}
+
+#[test]
+fn format_settings_need_fmt() {
+    let output = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .args(["--check", "run", "script.fs"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(2), output.status.code());
+}
+
+#[test]
+fn debug_steps_through_words_and_stops_at_breakpoints() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("debug.fs");
+    std::fs::write(&path, ": sq dup * ;\n3 sq 1 +\n").unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .arg("debug")
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"step\nstep\nstep\nstack\nbreak +\ncontinue\nstack\ncontinue\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let expected = "3\n(debug) sq\n(debug)   dup\n(debug)   *\n(debug) <2> 3 3\n\
+                    (debug) (debug) +\n(debug) <2> 9 1\n(debug) <1> 10\n";
+    assert_eq!(expected, String::from_utf8(output.stdout).unwrap());
+}
+
+#[test]
+fn trace_flag_prints_every_word() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("trace.fs");
+    std::fs::write(&path, "1 2 +\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .args(["--trace", "run"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert_eq!(
+        "1 <1> 1\n2 <2> 1 2\n+ <1> 3\n<1> 3\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn profile_flag_prints_counts_to_stderr() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("profile.fs");
+    std::fs::write(&path, ": inc 1 + ;\n0 inc inc\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .args(["run", "--profile"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert_eq!("<1> 2\n", String::from_utf8(output.stdout).unwrap());
+    assert_eq!(
+        "     calls  word\n         2  inc\n      runs  word\n         2  +\n",
+        String::from_utf8(output.stderr).unwrap()
+    );
+}
+
+#[test]
+fn prints_redefinition_warnings() {
+    assert_eq!(
+        "> <0>\n> warning: `sq` isn't unique\n<0>\n> \n",
+        repl(": sq dup * ;\n: sq dup dup * * ;\n")
+    );
+}
+
+#[test]
+fn transpile_prints_rust_source_for_the_words() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("transpile.fs");
+    std::fs::write(&path, ": sq ( n -- n*n ) dup * ;\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .arg("transpile")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.starts_with("// Transpiled by `forth transpile`."),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("pub fn sq(f: &mut Forth) -> Result {"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn transpile_keeps_only_the_words_named_and_what_they_call() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("transpile-roots.fs");
+    std::fs::write(&path, ": sq dup * ; : quad sq sq ; : unused 1 ;\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .arg("transpile")
+        .arg(&path)
+        .arg("quad")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("pub fn quad("), "{stdout}");
+    assert!(!stdout.contains("unused"), "{stdout}");
+    let output = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .arg("transpile")
+        .arg(&path)
+        .arg("nope")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn wasm_writes_a_module_next_to_the_script() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let path = dir.join("compiled.fs");
+    let _ = std::fs::remove_file(dir.join("compiled.wasm"));
+    std::fs::write(&path, ": sq ( n -- n*n ) dup * ;\n3 sq\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .arg("wasm")
+        .arg(&path)
+        .arg("sq")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let module = std::fs::read(dir.join("compiled.wasm")).unwrap();
+    assert!(module.starts_with(b"\0asm"));
+}
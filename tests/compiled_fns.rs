@@ -0,0 +1,50 @@
+use forth::{Error, Forth};
+
+#[test]
+fn a_compiled_word_maps_arguments_to_results() {
+    let mut f = Forth::new();
+    f.eval(": sum-of-squares ( a b -- n ) dup * swap dup * + ;")
+        .unwrap();
+    let mut sum_of_squares = f.compile_fn("SUM-OF-SQUARES").unwrap();
+    assert_eq!(Ok(vec![25]), sum_of_squares(&mut [3, 4]));
+    assert_eq!(Ok(vec![1, 2]), sum_of_squares(&mut [1, 1, 1]));
+    assert!(f.stack().is_empty());
+}
+
+#[test]
+fn only_user_defined_words_compile() {
+    let f = Forth::new();
+    assert!(matches!(f.compile_fn("nope"), Err(Error::UnknownWord)));
+    assert!(matches!(f.compile_fn("dup"), Err(Error::InvalidWord)));
+}
+
+#[test]
+fn failures_leave_the_function_usable() {
+    let mut f = Forth::new();
+    f.eval(": ratio / ;").unwrap();
+    let mut ratio = f.compile_fn("ratio").unwrap();
+    assert_eq!(Err(Error::DivisionByZero), ratio(&mut [1, 0]));
+    assert_eq!(Err(Error::StackUnderflow), ratio(&mut [1]));
+    assert_eq!(Ok(vec![3]), ratio(&mut [7, 2]));
+}
+
+#[test]
+fn later_definitions_do_not_change_the_function() {
+    let mut f = Forth::new();
+    f.eval(": step 1 ; : next step + ;").unwrap();
+    let mut next = f.compile_fn("next").unwrap();
+    f.eval(": step 10 ; forget next").unwrap();
+    assert_eq!(Ok(vec![6]), next(&mut [5]));
+}
+
+#[test]
+fn stores_persist_between_calls() {
+    let mut f = Forth::new();
+    f.eval("variable total : tally ( n -- sum ) total @ + dup total ! ;")
+        .unwrap();
+    let mut tally = f.compile_fn("tally").unwrap();
+    assert_eq!(Ok(vec![2]), tally(&mut [2]));
+    assert_eq!(Ok(vec![5]), tally(&mut [3]));
+    f.eval("total @").unwrap();
+    assert_eq!([0], f.stack());
+}
@@ -0,0 +1,100 @@
+use forth::*;
+
+fn hits(f: &Forth, word: &str) -> Vec<(String, Option<u64>)> {
+    let coverage = f.coverage();
+    let definition = coverage
+        .definitions
+        .iter()
+        .find(|definition| definition.name == word)
+        .unwrap();
+    definition
+        .tokens
+        .iter()
+        .map(|token| (token.text.clone(), token.hits))
+        .collect()
+}
+
+#[test]
+fn tokens_count_how_often_they_ran() {
+    let mut f = Forth::new();
+    f.set_coverage(true);
+    assert!(f
+        .eval(": sign ( n -- f ) dup if drop 1 else drop 0 then ; 5 sign 7 sign")
+        .is_ok());
+    let expected = [
+        ("dup", Some(2)),
+        ("if", Some(2)),
+        ("drop", Some(2)),
+        ("1", Some(2)),
+        ("else", Some(2)),
+        ("drop", Some(0)),
+        ("0", Some(0)),
+        ("then", None),
+    ];
+    let expected: Vec<_> = expected.map(|(text, hits)| (text.to_string(), hits)).into();
+    assert_eq!(expected, hits(&f, "sign"));
+}
+
+#[test]
+fn calls_are_counted_tail_calls_included() {
+    let mut f = Forth::new();
+    f.set_coverage(true);
+    assert!(f
+        .eval(": countdown dup if 1 - recurse then ; : unused 1 ; 3 countdown")
+        .is_ok());
+    let calls: Vec<(String, u64)> = f
+        .coverage()
+        .definitions
+        .into_iter()
+        .map(|definition| (definition.name, definition.calls))
+        .collect();
+    assert_eq!(
+        vec![("countdown".to_string(), 4), ("unused".to_string(), 0)],
+        calls
+    );
+}
+
+#[test]
+fn only_definitions_made_while_on_are_covered() {
+    let mut f = Forth::new();
+    assert!(f.eval(": sq dup * ;").is_ok());
+    assert_eq!(Coverage::default(), f.coverage());
+    f.set_coverage(true);
+    assert!(f.eval(": cube dup sq * ; 2 sq 2 cube").is_ok());
+    let coverage = f.coverage();
+    assert_eq!(1, coverage.definitions.len());
+    assert_eq!(1, coverage.definitions[0].calls);
+    f.set_coverage(false);
+    assert_eq!(Coverage::default(), f.coverage());
+}
+
+#[test]
+fn rolled_back_definitions_are_dropped() {
+    let mut f = Forth::new();
+    f.set_coverage(true);
+    assert!(f.eval(": one 1 ;").is_ok());
+    assert!(f.eval_atomic(": two 2 ; 1 0 /").is_err());
+    assert!(f.eval(": three 3 ;").is_ok());
+    let names: Vec<String> = f
+        .coverage()
+        .definitions
+        .into_iter()
+        .map(|definition| definition.name)
+        .collect();
+    assert_eq!(vec!["one", "three"], names);
+}
+
+#[test]
+fn lcov_reports_lines_and_functions() {
+    let mut f = Forth::new();
+    f.set_coverage(true);
+    assert!(f
+        .eval(": sq\n  dup * ;\n: unused\n  1 ;\n: pos? dup if drop 1 then ;\n3 sq 2 pos?")
+        .is_ok());
+    let expected = "TN:\nSF:lib.fs\n\
+        FN:1,sq\nFNDA:1,sq\nFN:3,unused\nFNDA:0,unused\nFN:5,pos?\nFNDA:1,pos?\n\
+        FNF:3\nFNH:2\n\
+        DA:2,1\nDA:4,0\nDA:5,1\n\
+        LF:3\nLH:2\nend_of_record\n";
+    assert_eq!(expected, f.coverage().to_lcov("lib.fs"));
+}
@@ -0,0 +1,304 @@
+use forth::*;
+
+#[test]
+fn definitions_keep_the_meaning_words_had_when_defined() {
+    let mut f = Forth::new();
+    assert!(f.eval(": a 1 ; : b a a ; : a 2 ; b a").is_ok());
+    assert_eq!(vec![1, 1, 2], f.stack());
+}
+
+/// Inputs that redefine words other definitions use, with the stack each
+/// leaves when every definition keeps the meanings its words had.
+const REDEFINITIONS: [(&str, &[Value]); 8] = [
+    (": foo 5 ; : bar foo ; : foo 6 ; bar foo", &[5, 6]),
+    (": add + ; : + * ; 2 3 add 2 3 +", &[5, 6]),
+    (": dup dup dup ; 1 dup", &[1, 1, 1]),
+    (
+        ": swap over ; : s swap ; : swap drop ; 1 2 s 3 4 swap",
+        &[1, 2, 1, 3],
+    ),
+    (
+        ": inc 1 + ; : inc2 inc inc ; : inc 10 + ; : + - ; 0 inc2 0 inc",
+        &[2, 10],
+    ),
+    (
+        ": sq dup * ; : quad sq sq ; : dup 3 ; : sq 0 ; 2 quad",
+        &[16],
+    ),
+    ("variable x : getx x @ ; variable x 5 x ! getx x @", &[0, 5]),
+    (": a 1 ; : b a ; : a 2 ; forget a b a", &[1, 1]),
+];
+
+#[test]
+fn redefinitions_leave_earlier_definitions_alone() {
+    let as_written = Optimizations {
+        fold_constants: false,
+        inline_threshold: 0,
+        superinstructions: false,
+        peephole: false,
+    };
+    for optimizations in [Optimizations::default(), as_written] {
+        for (input, stack) in REDEFINITIONS {
+            let mut f = Forth::builder().optimizations(optimizations).build();
+            assert!(f.eval(input).is_ok(), "{input}");
+            assert_eq!(stack, f.stack(), "{input} with {optimizations:?}");
+        }
+    }
+}
+
+#[test]
+fn redefinitions_leave_earlier_definitions_alone_line_by_line() {
+    for (input, stack) in REDEFINITIONS {
+        let mut f = Forth::new();
+        for command in input.split_inclusive(';') {
+            assert!(f.eval(command).is_ok(), "{command} in {input}");
+        }
+        assert_eq!(stack, f.stack(), "{input}");
+    }
+}
+
+#[test]
+fn redefining_a_control_word_changes_later_definitions_only() {
+    let mut f = Forth::new();
+    assert!(f.eval(": t if 2 then ; : if 7 ; 1 t if").is_ok());
+    assert_eq!(vec![2, 7], f.stack());
+    assert_eq!(Err(Error::InvalidWord), f.eval(": u 1 if 2 then ;"));
+}
+
+#[test]
+fn long_call_chains_run_without_native_recursion() {
+    let mut f = Forth::new();
+    assert!(f.eval(": w0 1 ;").is_ok());
+    for i in 1..10_000 {
+        assert!(f.eval(&format!(": w{i} w{} ;", i - 1)).is_ok());
+    }
+    assert!(f.eval("w9999").is_ok());
+    assert_eq!(vec![1], f.stack());
+}
+
+#[test]
+fn if_then_runs_its_body_on_a_true_flag() {
+    let mut f = Forth::new();
+    assert!(f.eval(": maybe if 10 then ; 1 maybe 0 maybe").is_ok());
+    assert_eq!(vec![10], f.stack());
+}
+
+#[test]
+fn if_else_then_picks_a_branch() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": pick if 1 else 2 then 3 + ; 5 pick 0 pick")
+        .is_ok());
+    assert_eq!(vec![4, 5], f.stack());
+}
+
+#[test]
+fn conditionals_nest() {
+    let mut f = Forth::new();
+    assert!(f.eval(": w if if 1 else 2 then else 3 then ;").is_ok());
+    assert!(f.eval("1 1 w 0 1 w 0 w").is_ok());
+    assert_eq!(vec![1, 2, 3], f.stack());
+}
+
+#[test]
+fn conditionals_need_a_flag() {
+    let mut f = Forth::new();
+    assert!(f.eval(": w if 1 then ;").is_ok());
+    assert_eq!(Err(Error::StackUnderflow), f.eval("w"));
+}
+
+#[test]
+fn unbalanced_conditionals_are_rejected() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::InvalidWord), f.eval(": w if 1 ;"));
+    assert_eq!(Err(Error::InvalidWord), f.eval(": w 1 then ;"));
+    assert_eq!(Err(Error::InvalidWord), f.eval(": w else ;"));
+    assert_eq!(Err(Error::UnknownWord), f.eval("w"));
+}
+
+#[test]
+fn semicolons_outside_definitions_are_rejected() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::InvalidWord), f.eval("; 1"));
+    assert_eq!(Err(Error::InvalidWord), f.eval(": one 1 ; ;"));
+    assert!(f.eval("one").is_ok());
+    assert_eq!(vec![1], f.stack());
+    assert_eq!(Err(Error::InvalidWord), Program::parse("1 ;").map(|_| ()));
+}
+
+#[test]
+fn colons_need_a_name_and_a_body() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::InvalidWord), f.eval(":"));
+    assert_eq!(Err(Error::InvalidWord), f.eval(": ;"));
+    assert_eq!(Err(Error::InvalidWord), f.eval(": foo"));
+    assert_eq!(Err(Error::InvalidWord), Program::parse(": ;").map(|_| ()));
+    assert!(f.eval(": nothing ; nothing").is_ok());
+}
+
+#[test]
+fn malformed_definitions_are_rejected() {
+    let malformed = [
+        ": a : b 1 ; ;",
+        ": a : b 1 ;",
+        ": a 1 : b 2 ;",
+        ": a : : ; ; ;",
+        ":",
+        ": ;",
+        ": a",
+        "; 1",
+        ": a 1 ; ;",
+        ": 1 2 ;",
+    ];
+    for input in malformed {
+        let mut f = Forth::new();
+        assert_eq!(Err(Error::InvalidWord), f.eval(input), "{input}");
+        assert_eq!(Err(Error::UnknownWord), f.eval("b"), "{input}");
+    }
+}
+
+#[test]
+fn nested_definitions_are_rejected_as_a_whole() {
+    let mut f = Forth::new();
+    let report = f.eval_lenient("1 : a : b 2 ; 3 ; 4");
+    assert_eq!(
+        vec![CommandFailure {
+            command: ": a : b 2 ; 3 ;".to_string(),
+            error: Error::InvalidWord,
+        }],
+        report.failures
+    );
+    assert_eq!(vec![1, 4], f.stack());
+    assert_eq!(Err(Error::UnknownWord), f.eval("a"));
+    assert_eq!(Err(Error::UnknownWord), f.eval("b"));
+}
+
+#[test]
+fn names_must_be_words_that_can_be_looked_up() {
+    let mut f = Forth::new();
+    for input in [
+        ": 1 2 ;",
+        ": -3 2 ;",
+        ": : 1 ;",
+        ": ; ;",
+        "variable 5",
+        "marker 7",
+    ] {
+        assert_eq!(Err(Error::InvalidWord), f.eval(input), "{input}");
+    }
+    for name in ["", "42", ":", ";", "a:b", "a;b", "two words"] {
+        let program = Program::new().definition(name, [Token::Number(1)]);
+        assert_eq!(Err(Error::InvalidWord), f.run(&program), "{name:?}");
+        assert_eq!(
+            Err(Error::InvalidWord),
+            f.define_constant(name, 1),
+            "{name:?}"
+        );
+        for defining in ["variable", "marker"] {
+            let tokens = [Token::word(defining), Token::word(name)];
+            assert_eq!(Err(Error::InvalidWord), f.eval_tokens(&tokens), "{name:?}");
+        }
+    }
+    assert_eq!(f.words().count(), Forth::new().words().count());
+}
+
+#[test]
+fn control_words_are_only_valid_in_definitions() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::InvalidWord), f.eval("1 if 2 then"));
+}
+
+#[test]
+fn control_words_can_be_redefined() {
+    let mut f = Forth::new();
+    assert!(f.eval(": then 7 ; then").is_ok());
+    assert_eq!(vec![7], f.stack());
+}
+
+#[test]
+fn recurse_calls_the_word_being_defined() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": fact dup 1 - dup if recurse * else drop then ; 5 fact")
+        .is_ok());
+    assert_eq!(vec![120], f.stack());
+}
+
+#[test]
+fn tail_recursion_runs_in_constant_space() {
+    let mut f = Forth::new();
+    assert!(f.eval(": countdown dup if 1 - recurse then ;").is_ok());
+    assert!(f.eval("1000000 countdown").is_ok());
+    assert_eq!(vec![0], f.stack());
+}
+
+#[test]
+fn clones_keep_their_own_definitions() {
+    let mut f = Forth::new();
+    assert!(f.eval(": w 1 ;").is_ok());
+    let mut g = f.clone();
+    assert!(g.eval(": w 2 ; w").is_ok());
+    assert!(f.eval("w").is_ok());
+    assert_eq!(vec![1], f.stack());
+    assert_eq!(vec![2], g.stack());
+}
+
+#[test]
+fn words_are_case_insensitive_beyond_ascii() {
+    let mut f = Forth::new();
+    assert!(f.eval(": ÉCHO 1 ; écho Écho").is_ok());
+    assert_eq!(vec![1, 1], f.stack());
+}
+
+#[test]
+fn unknown_words_in_a_body_are_rejected() {
+    let mut f = Forth::new();
+    assert_eq!(UnknownWords::Reject, f.unknown_words());
+    assert_eq!(Err(Error::UnknownWord), f.eval(": sq dpu * ;"));
+    assert_eq!(Err(Error::UnknownWord), f.eval("sq"));
+}
+
+#[test]
+fn deferred_unknown_words_fail_when_run() {
+    let mut f = Forth::builder().unknown_words(UnknownWords::Defer).build();
+    assert!(f.eval(": w dup if later then ; : later 5 ;").is_ok());
+    assert!(f.eval("0 w").is_ok());
+    assert_eq!(Err(Error::UnknownWord), f.eval("1 w"));
+    assert_eq!(vec![0, 1], f.stack());
+
+    f.set_unknown_words(UnknownWords::Reject);
+    assert_eq!(Err(Error::UnknownWord), f.eval(": v nope ;"));
+}
+
+#[test]
+fn runaway_recursion_overflows_the_return_stack() {
+    let mut f = Forth::new();
+    assert!(f.eval(": down 1 - dup if recurse then 1 + ;").is_ok());
+    assert!(f.eval("1000 down").is_ok());
+    assert_eq!(vec![1000], f.drain_stack());
+    assert!(f.eval(": forever recurse 1 ;").is_ok());
+    assert_eq!(Err(Error::ReturnStackOverflow), f.eval("forever"));
+    assert!(f.eval("3 down").is_ok());
+    assert_eq!(vec![3], f.stack());
+}
+
+#[test]
+fn call_depth_is_configurable() {
+    let mut f = Forth::builder().max_call_depth(10).build();
+    assert_eq!(10, f.max_call_depth());
+    assert!(f.eval(": down 1 - dup if recurse then 1 + ;").is_ok());
+    assert!(f.eval("10 down").is_ok());
+    assert_eq!(Err(Error::ReturnStackOverflow), f.eval("11 down"));
+    f.set_max_call_depth(0);
+    assert_eq!(Err(Error::ReturnStackOverflow), f.eval(": one 1 ; one"));
+    assert!(f.eval("1 2 +").is_ok());
+}
+
+#[test]
+fn tail_calls_do_not_count_towards_the_call_depth() {
+    let mut f = Forth::builder().max_call_depth(1).build();
+    assert!(f
+        .eval(": countdown dup if 1 - recurse then ; 100000 countdown")
+        .is_ok());
+    assert_eq!(vec![0], f.stack());
+}
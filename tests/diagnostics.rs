@@ -0,0 +1,241 @@
+use forth::{Dialect, Error, Forth, Span, StackEffects};
+
+#[test]
+fn success_has_no_diagnostics() {
+    let mut f = Forth::new();
+    assert!(f.eval_diagnostics("1 2 +").is_ok());
+    assert_eq!(vec![3], f.stack());
+}
+
+#[test]
+fn unknown_word_span_and_suggestion() {
+    let mut f = Forth::new();
+    let input = ": one 1 ; one dpu";
+    let d = f.eval_diagnostics(input).unwrap_err();
+    assert_eq!(Error::UnknownWord, d.error);
+    assert_eq!(Span::new(14, 17), d.span);
+    assert_eq!("dpu", &input[d.span.start..d.span.end]);
+    assert_eq!("one dpu", d.command);
+    assert_eq!(vec!["did you mean `dup`?".to_string()], d.suggestions);
+}
+
+#[test]
+fn unknown_word_in_a_definition_points_at_the_word() {
+    let mut f = Forth::new();
+    let input = "1 : sq ( n -- n ) dpu * ;";
+    let d = f.eval_diagnostics(input).unwrap_err();
+    assert_eq!(Error::UnknownWord, d.error);
+    assert_eq!("dpu", &input[d.span.start..d.span.end]);
+    assert_eq!(vec!["did you mean `dup`?".to_string()], d.suggestions);
+}
+
+#[test]
+fn comments_are_skipped_in_pointing_at_a_word() {
+    let mut f = Forth::new();
+    let input = ": sq ( n -- n ) dup ( x ) \\ y\n dpu * ; ( z ) 2 sq";
+    let d = f.eval_diagnostics(input).unwrap_err();
+    assert_eq!("dpu", &input[d.span.start..d.span.end]);
+    let input = "1 ( a b ) 2 \\ c d\n nope";
+    let d = f.eval_diagnostics(input).unwrap_err();
+    assert_eq!("nope", &input[d.span.start..d.span.end]);
+}
+
+#[test]
+fn malformed_numbers_are_noted() {
+    let mut f = Forth::new();
+    let d = f.eval_diagnostics("1 $fg").unwrap_err();
+    assert_eq!(Error::UnknownWord, d.error);
+    assert_eq!(
+        vec!["it looks like a number, but is malformed or out of range".to_string()],
+        d.notes
+    );
+    let d = f.eval_diagnostics("nope").unwrap_err();
+    assert!(d.notes.is_empty());
+}
+
+#[test]
+fn return_stack_overflow_notes_the_limit() {
+    let mut f = Forth::builder().max_call_depth(50).build();
+    let d = f
+        .eval_diagnostics(": forever recurse 1 ; forever")
+        .unwrap_err();
+    assert_eq!(Error::ReturnStackOverflow, d.error);
+    assert_eq!(
+        vec![
+            "calls may nest at most 50 deep; does a recursion lack a base case?".to_string(),
+            "raised at line 1, column 11, in `forever`, while running the user-defined word `forever`"
+                .to_string(),
+        ],
+        d.notes
+    );
+}
+
+#[test]
+fn underflow_notes_stack_depth() {
+    let mut f = Forth::new();
+    let d = f.eval_diagnostics("1 2 + +").unwrap_err();
+    assert_eq!(Error::StackUnderflow, d.error);
+    assert_eq!(Span::new(6, 7), d.span);
+    assert_eq!(
+        vec!["the stack held 1 value(s) when `+` started".to_string()],
+        d.notes
+    );
+}
+
+#[test]
+fn errors_inside_user_words_point_at_the_call() {
+    let mut f = Forth::new();
+    assert!(f.eval(": halve 2 / ; : bad 0 / ;").is_ok());
+    let d = f.eval_diagnostics("8 halve 1 bad").unwrap_err();
+    assert_eq!(Error::DivisionByZero, d.error);
+    assert_eq!(Span::new(10, 13), d.span);
+    assert!(d.notes.contains(
+        &"raised at line 1, column 23, in `bad`, while running the user-defined word `bad`"
+            .to_string()
+    ));
+}
+
+#[test]
+fn invalid_definition_spans_the_command() {
+    let mut f = Forth::new();
+    let d = f.eval_diagnostics("1 : w if 3 ;").unwrap_err();
+    assert_eq!(Error::InvalidWord, d.error);
+    assert_eq!(Span::new(2, 12), d.span);
+    assert_eq!(": w if 3 ;", d.command);
+}
+
+#[test]
+fn definitions_without_a_valid_name_point_at_the_offending_token() {
+    for (input, span) in [
+        (":", Span::new(0, 1)),
+        ("1 :", Span::new(2, 3)),
+        (": ;", Span::new(0, 1)),
+        ("1 : foo", Span::new(2, 3)),
+        ("1 : 2 3 ;", Span::new(4, 5)),
+    ] {
+        let d = Forth::new().eval_diagnostics(input).unwrap_err();
+        assert_eq!(Error::InvalidWord, d.error, "{input}");
+        assert_eq!(span, d.span, "{input}");
+    }
+}
+
+#[test]
+fn stray_semicolon_points_at_itself() {
+    let mut f = Forth::new();
+    let d = f.eval_diagnostics("1 2 ; +").unwrap_err();
+    assert_eq!(Error::InvalidWord, d.error);
+    assert_eq!(Span::new(4, 5), d.span);
+    assert_eq!(";", d.command);
+    assert_eq!(
+        vec!["`;` ends a definition, but none was open".to_string()],
+        d.notes
+    );
+    assert_eq!(vec![1, 2], f.stack());
+}
+
+#[test]
+fn nested_definition_points_at_the_inner_colon() {
+    let mut f = Forth::new();
+    let input = "1 : a : b 1 ; ;";
+    let d = f.eval_diagnostics(input).unwrap_err();
+    assert_eq!(Error::InvalidWord, d.error);
+    assert_eq!(Span::new(6, 7), d.span);
+    assert_eq!(": a : b 1 ; ;", d.command);
+    assert_eq!(
+        vec!["`:` can't start a definition inside another".to_string()],
+        d.notes
+    );
+}
+
+#[test]
+fn control_word_outside_a_definition() {
+    let mut f = Forth::new();
+    let d = f.eval_diagnostics("1 if 2 then").unwrap_err();
+    assert_eq!(Error::InvalidWord, d.error);
+    assert_eq!(Span::new(2, 4), d.span);
+    assert_eq!(
+        vec!["`if` can only be used inside a definition".to_string()],
+        d.notes
+    );
+}
+
+#[test]
+fn strict_dialect_points_at_glued_word() {
+    let mut f = Forth::builder().dialect(Dialect::Strict).build();
+    let d = f.eval_diagnostics(": one 1 ;one").unwrap_err();
+    assert_eq!(Error::InvalidWord, d.error);
+    assert_eq!(Span::new(8, 12), d.span);
+}
+
+#[test]
+fn diagnostics_display() {
+    let mut f = Forth::new();
+    let d = f.eval_diagnostics("swp").unwrap_err();
+    assert_eq!(
+        "error: unknown word in `swp`\n  help: did you mean `swap`?",
+        d.to_string()
+    );
+}
+
+#[test]
+fn closed_channels_note_whether_any_were_set() {
+    let mut f = Forth::new();
+    let d = f.eval_diagnostics("recv").unwrap_err();
+    assert_eq!(
+        vec!["no channels to the host were set, see `Forth::set_channels`".to_string()],
+        d.notes
+    );
+    drop(f.open_channels());
+    let d = f.eval_diagnostics("recv").unwrap_err();
+    assert_eq!(
+        vec!["the host has dropped its end of the channel".to_string()],
+        d.notes
+    );
+}
+
+#[test]
+fn missing_includes_note_whether_a_resolver_was_set() {
+    let mut f = Forth::new();
+    let d = f.eval_diagnostics("include prelude").unwrap_err();
+    assert_eq!(Error::IncludeNotFound, d.error);
+    assert_eq!(
+        vec!["no include resolver was set, see `Forth::set_include_resolver`".to_string()],
+        d.notes
+    );
+    f.set_include_resolver(|_| None);
+    let d = f.eval_diagnostics("include prelude").unwrap_err();
+    assert_eq!(
+        vec!["the include resolver has no source by that name".to_string()],
+        d.notes
+    );
+}
+
+#[test]
+fn unbalanced_branches_point_at_the_if() {
+    let mut f = Forth::builder()
+        .stack_effects(StackEffects::Checked)
+        .build();
+    let input = ": w ( n -- ) dup if drop then ;";
+    let d = f.eval_diagnostics(input).unwrap_err();
+    assert_eq!("if", &input[d.span.start..d.span.end]);
+    assert_eq!(
+        vec![
+            "the part run when the flag is true changes the depth by -1, but the rest by 0"
+                .to_string()
+        ],
+        d.notes
+    );
+}
+
+#[test]
+fn uncaught_throws_name_their_code() {
+    let mut f = Forth::new();
+    let input = "1 -3 throw";
+    let d = f.eval_diagnostics(input).unwrap_err();
+    assert_eq!(Error::Uncaught(-3), d.error);
+    assert_eq!("throw", &input[d.span.start..d.span.end]);
+    assert_eq!(
+        vec!["`throw` was given -3, and no `catch` around it caught it".to_string()],
+        d.notes
+    );
+}
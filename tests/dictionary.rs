@@ -0,0 +1,70 @@
+use forth::*;
+
+#[test]
+fn forgetting_a_redefinition_restores_the_previous_one() {
+    let mut f = Forth::new();
+    assert!(f.eval(": w 1 ; : w 2 ; forget w w").is_ok());
+    assert_eq!(vec![1], f.stack());
+}
+
+#[test]
+fn forgetting_a_shadowed_builtin_restores_it() {
+    let mut f = Forth::new();
+    assert!(f.eval(": dup 7 ; 1 dup forget dup dup").is_ok());
+    assert_eq!(vec![1, 7, 7], f.stack());
+}
+
+#[test]
+fn forget_removes_everything_defined_later() {
+    let mut f = Forth::new();
+    assert!(f.eval(": a 1 ; : b 2 ; forget a").is_ok());
+    assert_eq!(Err(Error::UnknownWord), f.eval("b"));
+    assert_eq!(Err(Error::UnknownWord), f.eval("a"));
+}
+
+#[test]
+fn builtins_cannot_be_forgotten() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::InvalidWord), f.eval("forget dup"));
+    assert_eq!(Err(Error::UnknownWord), f.eval("forget nothing"));
+}
+
+#[test]
+fn marker_forgets_itself_and_later_definitions() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": keep 1 ; marker -scratch : keep 2 ; variable v")
+        .is_ok());
+    assert!(f.eval("-scratch keep").is_ok());
+    assert_eq!(vec![1], f.stack());
+    assert_eq!(Err(Error::UnknownWord), f.eval("v"));
+    assert_eq!(Err(Error::UnknownWord), f.eval("-scratch"));
+}
+
+#[test]
+fn forgotten_variables_give_up_their_data_space() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval("variable a variable b forget b variable c 5 c ! a @")
+        .is_ok());
+    assert_eq!(vec![0], f.stack());
+    assert_eq!(Some(5), f.get_var("c"));
+    assert_eq!(None, f.get_var("b"));
+}
+
+#[test]
+fn words_keep_running_after_being_forgotten() {
+    let mut f = Forth::new();
+    assert!(f.eval("marker m : w 1 m 2 ; w").is_ok());
+    assert_eq!(vec![1, 2], f.stack());
+    assert_eq!(Err(Error::UnknownWord), f.eval("w"));
+}
+
+#[test]
+fn words_are_listed_in_definition_order() {
+    let mut f = Forth::new();
+    assert!(f.eval(": b 1 ; : a 2 ; : dup 3 ; : b 4 ;").is_ok());
+    let words: Vec<&str> = f.words().collect();
+    assert_eq!(1, words.iter().filter(|&&word| word == "dup").count());
+    assert_eq!(vec!["a", "dup", "b"], words[words.len() - 3..]);
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::Buffer;
+use forth::*;
+
+#[test]
+fn listing_shows_opcodes_and_operands() {
+    let mut f = Forth::builder()
+        .fold_constants(false)
+        .superinstructions(false)
+        .build();
+    assert!(f.eval(": sq dup * ; : w 2 3 + sq ;").is_ok());
+    assert_eq!(
+        ": w\n   0  push           2\n   1  push           3\n   2  primitive      +\n   3  primitive      dup\n   4  primitive      *\n;\n",
+        f.disassemble("W").unwrap()
+    );
+}
+
+#[test]
+fn listing_shows_what_the_optimizer_produced() {
+    let mut f = Forth::new();
+    assert!(f.eval(": w 2 3 + dup * ;").is_ok());
+    assert_eq!(
+        ": w\n   0  push           25\n;\n",
+        f.disassemble("w").unwrap()
+    );
+}
+
+#[test]
+fn listing_shows_deferred_unknown_words() {
+    let mut f = Forth::builder().unknown_words(UnknownWords::Defer).build();
+    assert!(f.eval(": w 1 later ;").is_ok());
+    assert_eq!(
+        ": w\n   0  push           1\n   1  unknown\n;\n",
+        f.disassemble("w").unwrap()
+    );
+}
+
+#[test]
+fn listing_shows_jump_targets_and_calls() {
+    let mut f = Forth::builder().inline_threshold(0).build();
+    assert!(f
+        .eval(": one 1 ; : countdown dup if 1 - recurse else one then ;")
+        .is_ok());
+    assert_eq!(
+        ": countdown\n\
+         \x20  0  primitive      dup\n\
+         \x20  1  branch-if-zero -> 6\n\
+         \x20  2  push           1\n\
+         \x20  3  primitive      -\n\
+         \x20  4  tail-call      countdown #1\n\
+         \x20  5  branch         -> 7\n\
+         \x20  6  tail-call      one #0\n\
+         ;\n",
+        f.disassemble("countdown").unwrap()
+    );
+}
+
+#[test]
+fn only_user_defined_words_can_be_disassembled() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable v").is_ok());
+    assert_eq!(Err(Error::InvalidWord), f.disassemble("dup"));
+    assert_eq!(Err(Error::InvalidWord), f.disassemble("v"));
+    assert_eq!(Err(Error::UnknownWord), f.disassemble("nope"));
+}
+
+#[test]
+fn dis_writes_the_listing_to_the_output() {
+    let buffer = Buffer::default();
+    let mut f = Forth::new();
+    f.set_output(buffer.clone());
+    assert!(f.eval(": sq dup * ; dis sq").is_ok());
+    let output = buffer.text();
+    assert_eq!(": sq\n   0  primitive      dup *\n;\n", output);
+}
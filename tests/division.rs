@@ -0,0 +1,96 @@
+use forth::*;
+
+/// Dividends and divisors of every combination of signs.
+const OPERANDS: [(Value, Value); 4] = [(7, 2), (-7, 2), (7, -2), (-7, -2)];
+
+fn results(division: Division, words: &str) -> Vec<Vec<Value>> {
+    let mut f = Forth::builder().division(division).build();
+    OPERANDS
+        .iter()
+        .map(|(b, a)| {
+            assert!(f.eval(&format!("{b} {a} {words}")).is_ok());
+            f.drain_stack()
+        })
+        .collect()
+}
+
+#[test]
+fn division_is_symmetric_by_default() {
+    assert_eq!(Division::Symmetric, Forth::new().division());
+    let expected = vec![vec![3], vec![-3], vec![-3], vec![3]];
+    assert_eq!(expected, results(Division::Symmetric, "/"));
+    let expected = vec![vec![1], vec![-1], vec![1], vec![-1]];
+    assert_eq!(expected, results(Division::Symmetric, "mod"));
+    let expected = vec![vec![1, 3], vec![-1, -3], vec![1, -3], vec![-1, 3]];
+    assert_eq!(expected, results(Division::Symmetric, "/mod"));
+}
+
+#[test]
+fn floored_division_rounds_down() {
+    let expected = vec![vec![3], vec![-4], vec![-4], vec![3]];
+    assert_eq!(expected, results(Division::Floored, "/"));
+    let expected = vec![vec![1], vec![1], vec![-1], vec![-1]];
+    assert_eq!(expected, results(Division::Floored, "mod"));
+    let expected = vec![vec![1, 3], vec![1, -4], vec![-1, -4], vec![-1, 3]];
+    assert_eq!(expected, results(Division::Floored, "/mod"));
+}
+
+#[test]
+fn sm_rem_and_fm_mod_ignore_the_setting() {
+    for division in [Division::Symmetric, Division::Floored] {
+        assert_eq!(
+            results(Division::Symmetric, "/mod"),
+            results(division, "sm/rem")
+        );
+        assert_eq!(
+            results(Division::Floored, "/mod"),
+            results(division, "fm/mod")
+        );
+    }
+}
+
+#[test]
+fn definitions_keep_the_division_they_were_made_with() {
+    let mut f = Forth::new();
+    assert!(f.eval(": half 2 / ; : folded -7 2 / ;").is_ok());
+    f.set_division(Division::Floored);
+    assert!(f
+        .eval(": floored-half 2 / ; -7 half -7 floored-half folded -7 2 /")
+        .is_ok());
+    assert_eq!(vec![-3, -4, -3, -4], f.stack());
+}
+
+#[test]
+fn folding_divides_like_run_time() {
+    for division in [Division::Symmetric, Division::Floored] {
+        for words in ["/", "mod", "/mod", "sm/rem", "fm/mod"] {
+            let (b, a) = (-7, 2);
+            let source = format!(": w {b} {a} {words} ; : v {words} ; w {b} {a} v");
+            let mut f = Forth::builder().division(division).build();
+            assert!(f.eval(&source).is_ok());
+            let (folded, run) = f.stack().split_at(f.stack().len() / 2);
+            assert_eq!(folded, run, "{words} under {division:?}");
+        }
+    }
+}
+
+#[test]
+fn remainders_fail_like_quotients() {
+    let mut f = Forth::builder().division(Division::Floored).build();
+    for words in ["mod", "/mod", "sm/rem", "fm/mod"] {
+        assert_eq!(Err(Error::DivisionByZero), f.eval(&format!("1 0 {words}")));
+        assert_eq!(
+            Err(Error::Overflow),
+            f.eval(&format!("-2147483648 -1 {words}"))
+        );
+        f.drain_stack();
+        assert_eq!(Err(Error::StackUnderflow), f.eval(&format!("1 {words}")));
+    }
+}
+
+#[test]
+fn redefined_division_words_are_left_alone() {
+    let mut f = Forth::builder().division(Division::Floored).build();
+    assert!(f.eval(": / * ; 3 2 /").is_ok());
+    assert_eq!(vec![6], f.stack());
+}
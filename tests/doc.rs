@@ -0,0 +1,85 @@
+mod common;
+
+use common::Buffer;
+use forth::*;
+
+#[test]
+fn definitions_keep_their_stack_effect() {
+    let mut f = Forth::new();
+    assert!(f.eval(": sq ( n -- n*n )  dup * ; : two 2 ;").is_ok());
+    assert_eq!(Some("( n -- n*n )"), f.doc("SQ"));
+    assert_eq!(None, f.doc("two"));
+    assert_eq!(None, f.doc("nope"));
+}
+
+#[test]
+fn the_stack_effect_is_not_part_of_the_body() {
+    let mut f = Forth::new();
+    assert!(f.eval(": sq ( n -- n ) dup * ; 3 sq").is_ok());
+    assert_eq!(vec![9], f.stack());
+}
+
+#[test]
+fn stack_effects_may_hold_colons_and_semicolons() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": len ( addr:len -- len; ) swap drop ; 1 2 len")
+        .is_ok());
+    assert_eq!(Some("( addr:len -- len; )"), f.doc("len"));
+    assert_eq!(vec![2], f.stack());
+}
+
+#[test]
+fn comments_may_appear_anywhere() {
+    let mut f = Forth::new();
+    assert!(f.eval(": a 1 ( note ) 2 ; ( c ) 3 a").is_ok());
+    assert!(f.eval("\\ a whole line\n4 \\ the rest of one\n5").is_ok());
+    assert_eq!(vec![3, 1, 2, 4, 5], f.stack());
+    assert_eq!(None, f.doc("a"));
+}
+
+#[test]
+fn only_the_comment_after_the_name_is_the_stack_effect() {
+    let mut f = Forth::new();
+    assert!(f.eval(": sq ( n -- n*n ) ( squares ) dup * ; 3 sq").is_ok());
+    assert_eq!(Some("( n -- n*n )"), f.doc("sq"));
+    assert_eq!(vec![9], f.stack());
+    let program = Program::parse(": sq ( N -- n*n ) \\ squares\n dup * ;").unwrap();
+    assert_eq!(": sq ( n -- n*n ) dup * ;\n", program.to_string());
+}
+
+#[test]
+fn builtins_are_documented() {
+    let f = Forth::new();
+    assert_eq!(Some("( x1 x2 -- x2 x1 )"), f.doc("swap"));
+    assert_eq!(Some("( flag -- )"), f.doc("if"));
+}
+
+#[test]
+fn redefinitions_replace_the_stack_effect() {
+    let mut f = Forth::new();
+    assert!(f.eval(": dup ( x -- x x x ) dup dup ;").is_ok());
+    assert_eq!(Some("( x -- x x x )"), f.doc("dup"));
+    assert!(f.eval(": dup 1 ;").is_ok());
+    assert_eq!(None, f.doc("dup"));
+}
+
+#[test]
+fn help_writes_the_word_and_its_stack_effect() {
+    let mut f = Forth::new();
+    let buffer = Buffer::default();
+    f.set_output(buffer.clone());
+    assert!(f
+        .eval(": sq ( n -- n*n ) dup * ; : two 2 ; help sq help two help +")
+        .is_ok());
+    let output = buffer.text();
+    assert_eq!("sq ( n -- n*n )\ntwo\n+ ( n1 n2 -- n3 )\n", output);
+    assert!(f.eval("help nope").is_err());
+}
+
+#[test]
+fn user_words_are_listed_in_definition_order() {
+    let mut f = Forth::new();
+    assert!(f.eval(": sq dup * ; variable v : dup 1 ;").is_ok());
+    assert_eq!(vec!["sq", "v", "dup"], f.user_words().collect::<Vec<_>>());
+}
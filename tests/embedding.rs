@@ -0,0 +1,154 @@
+use forth::{Error, Forth};
+
+#[test]
+fn eval_expr_returns_produced_values() {
+    let mut f = Forth::new();
+    assert_eq!(Ok(vec![3, 4]), f.eval_expr("1 2 + 4"));
+    assert!(f.stack().is_empty());
+}
+
+#[test]
+fn eval_expr_keeps_values_below_starting_depth() {
+    let mut f = Forth::new();
+    assert!(f.eval("10 20").is_ok());
+    assert_eq!(Ok(vec![5]), f.eval_expr("5"));
+    assert_eq!(vec![10, 20], f.stack());
+}
+
+#[test]
+fn eval_expr_returns_what_it_made_of_values_it_consumed() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 2 3").is_ok());
+    assert_eq!(Ok(vec![5, 10]), f.eval_expr("+ 10"));
+    assert_eq!(vec![1], f.stack());
+    assert_eq!(Ok(vec![]), f.eval_expr("drop"));
+    assert!(f.stack().is_empty());
+}
+
+#[test]
+fn eval_expr_returns_values_it_only_read() {
+    let mut f = Forth::new();
+    assert!(f.eval("1").is_ok());
+    assert_eq!(Ok(vec![1]), f.eval_expr("dup drop"));
+    assert!(f.stack().is_empty());
+    assert!(f.eval("1 2").is_ok());
+    assert_eq!(Ok(vec![1, 2, 1]), f.eval_expr("over"));
+    assert!(f.stack().is_empty());
+}
+
+#[test]
+fn eval_expr_sees_values_consumed_by_hot_words() {
+    let mut f = Forth::builder().inline_threshold(0).build();
+    assert!(f.eval(": add + ;").is_ok());
+    for _ in 0..32 {
+        assert!(f.eval("1 2 3").is_ok());
+        assert_eq!(Ok(vec![5, 10]), f.eval_expr("add 10"));
+        assert_eq!(Ok(vec![1]), f.eval_expr("dup drop"));
+    }
+}
+
+#[test]
+fn eval_expr_error() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::StackUnderflow), f.eval_expr("1 +"));
+}
+
+#[test]
+fn pop_bool() {
+    let mut f = Forth::new();
+    assert!(f.eval("0 -1 7").is_ok());
+    assert_eq!(Ok(true), f.pop_bool());
+    assert_eq!(Ok(true), f.pop_bool());
+    assert_eq!(Ok(false), f.pop_bool());
+    assert_eq!(Err(Error::StackUnderflow), f.pop_bool());
+}
+
+#[test]
+fn pop_pair_in_push_order() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 2 3").is_ok());
+    assert_eq!(Ok((2, 3)), f.pop_pair());
+    assert_eq!(Err(Error::StackUnderflow), f.pop_pair());
+    assert_eq!(vec![1], f.stack());
+}
+
+#[test]
+fn try_pop_converts() {
+    let mut f = Forth::new();
+    assert!(f.eval("-1 300 42").is_ok());
+    assert_eq!(Ok(42u8), f.try_pop::<u8>());
+    assert_eq!(Err(Error::OutOfRange), f.try_pop::<u8>());
+    assert_eq!(Ok(300u16), f.try_pop::<u16>());
+    assert_eq!(Err(Error::OutOfRange), f.try_pop::<usize>());
+    assert_eq!(Ok(-1i64), f.try_pop::<i64>());
+}
+
+#[test]
+fn errors_are_descriptive() {
+    assert_eq!("stack underflow", Error::StackUnderflow.to_string());
+    assert_eq!(
+        "value out of range for the requested type",
+        Error::OutOfRange.to_string()
+    );
+}
+
+#[test]
+fn drain_stack_empties_the_stack() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 2 3").is_ok());
+    assert_eq!(vec![1, 2, 3], f.drain_stack());
+    assert!(f.stack().is_empty());
+}
+
+#[test]
+fn replace_stack_feeds_inputs() {
+    let mut f = Forth::new();
+    assert!(f.eval(": sum + + ;").is_ok());
+    for (input, expected) in [(vec![1, 2, 3], 6), (vec![4, 5, 6], 15)] {
+        f.replace_stack(input);
+        assert!(f.eval("sum").is_ok());
+        assert_eq!(vec![expected], f.drain_stack());
+    }
+}
+
+#[test]
+fn define_constant_as_a_word() {
+    let mut f = Forth::new();
+    assert!(f.define_constant("Screen-Width", 800).is_ok());
+    assert!(f
+        .eval(": half-width screen-width 2 / ; half-width SCREEN-WIDTH")
+        .is_ok());
+    assert_eq!(vec![400, 800], f.stack());
+}
+
+#[test]
+fn define_constants_in_bulk() {
+    let mut f = Forth::new();
+    let config = [("width", 640), ("height", 480)];
+    assert!(f.define_constants(config).is_ok());
+    assert!(f.eval("width height *").is_ok());
+    assert_eq!(vec![307200], f.stack());
+}
+
+#[test]
+fn constants_follow_definition_order() {
+    let mut f = Forth::new();
+    assert!(f.eval(": answer 41 ;").is_ok());
+    assert!(f.define_constant("answer", 42).is_ok());
+    assert!(f.eval("answer").is_ok());
+    assert_eq!(vec![42], f.stack());
+}
+
+#[test]
+fn invalid_constant_names() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::InvalidWord), f.define_constant("12", 1));
+    assert_eq!(Err(Error::InvalidWord), f.define_constant("", 1));
+    assert_eq!(Err(Error::InvalidWord), f.define_constant("two words", 1));
+    assert_eq!(
+        Err(Error::InvalidWord),
+        f.define_constants([("ok", 1), ("3", 3), ("never", 2)])
+    );
+    assert!(f.eval("ok").is_ok());
+    assert_eq!(Err(Error::UnknownWord), f.eval("never"));
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "env")]
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use forth::*;
+
+/// An interpreter whose environment is `vars` rather than the process's.
+fn forth(vars: &Arc<Mutex<HashMap<String, String>>>) -> Forth {
+    let mut f = Forth::new();
+    let lookup = Arc::clone(vars);
+    f.set_env_lookup(move |name| lookup.lock().unwrap().get(name).cloned());
+    let store = Arc::clone(vars);
+    f.set_env_store(move |name, value| {
+        store
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), value.to_string());
+    });
+    f
+}
+
+fn vars(pairs: &[(&str, &str)]) -> Arc<Mutex<HashMap<String, String>>> {
+    let vars = pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    Arc::new(Mutex::new(vars))
+}
+
+#[test]
+fn getenv_pushes_a_value_and_a_flag() {
+    let vars = vars(&[("WORKERS", "8"), ("Mask", " $ff\n"), ("NAME", "web")]);
+    let mut f = forth(&vars);
+    assert!(f.eval("getenv WORKERS getenv Mask").is_ok());
+    assert_eq!(vec![8, -1, 255, -1], f.drain_stack());
+    assert!(f.eval("getenv NAME getenv MASK getenv UNSET").is_ok());
+    assert_eq!(vec![0, 0, 0, 0, 0, 0], f.stack());
+}
+
+#[test]
+fn setenv_stores_decimal_text() {
+    let vars = vars(&[]);
+    let mut f = forth(&vars);
+    assert!(f.eval("-12 setenv Retries").is_ok());
+    assert_eq!(
+        Some("-12"),
+        vars.lock().unwrap().get("Retries").map(String::as_str)
+    );
+    assert!(f.eval("getenv Retries").is_ok());
+    assert_eq!(vec![-12, -1], f.stack());
+}
+
+#[test]
+fn names_are_parsed_at_run_time() {
+    let vars = vars(&[("PORT", "8080")]);
+    let mut f = forth(&vars);
+    assert!(f.eval(": port getenv ; : keep setenv ;").is_ok());
+    assert!(f.eval("port PORT drop 1 + keep PORT").is_ok());
+    assert!(f.stack().is_empty());
+    assert_eq!(
+        Some("8081"),
+        vars.lock().unwrap().get("PORT").map(String::as_str)
+    );
+    assert_eq!(Err(Error::InvalidWord), f.eval("port"));
+}
+
+#[test]
+fn bad_names_and_missing_values_fail() {
+    let vars = vars(&[]);
+    let mut f = forth(&vars);
+    assert_eq!(Err(Error::InvalidWord), f.eval("getenv a=b"));
+    assert_eq!(Err(Error::InvalidWord), f.eval("1 setenv a=b"));
+    assert_eq!(Err(Error::StackUnderflow), f.eval("setenv A"));
+    assert!(vars.lock().unwrap().is_empty());
+}
+
+#[test]
+fn the_process_environment_is_the_default() {
+    std::env::set_var("FORTH_ENV_TEST_IN", "42");
+    let mut f = Forth::new();
+    assert!(f
+        .eval("getenv FORTH_ENV_TEST_IN 7 setenv FORTH_ENV_TEST_OUT")
+        .is_ok());
+    assert_eq!(vec![42, -1], f.stack());
+    assert_eq!(Ok("7".to_string()), std::env::var("FORTH_ENV_TEST_OUT"));
+}
@@ -0,0 +1,94 @@
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use forth::*;
+
+struct Noop;
+
+impl Wake for Noop {
+    fn wake(self: Arc<Self>) {}
+}
+
+/// Runs `future` to completion, returning its output and how many times it
+/// had to be polled.
+fn block_on<F: Future>(future: F) -> (F::Output, usize) {
+    let waker = Waker::from(Arc::new(Noop));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    let mut polls = 0;
+    loop {
+        polls += 1;
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return (output, polls);
+        }
+    }
+}
+
+const COUNTDOWN: &str = ": countdown dup if 1 - recurse then ; 7 1000 countdown 1 +";
+
+#[test]
+fn long_scripts_yield_and_end_as_eval_does() {
+    let mut f = Forth::builder().yield_interval(100).build();
+    let (outcome, polls) = block_on(f.eval_async(COUNTDOWN));
+    assert_eq!(Ok(()), outcome);
+    assert!(polls > 10, "{polls} polls");
+
+    let mut expected = Forth::new();
+    assert!(expected.eval(COUNTDOWN).is_ok());
+    assert_eq!(expected.stack(), f.stack());
+}
+
+#[test]
+fn short_scripts_finish_in_one_poll() {
+    let mut f = Forth::new();
+    assert_eq!((Ok(()), 1), block_on(f.eval_async(COUNTDOWN)));
+}
+
+#[test]
+fn failures_are_those_of_eval() {
+    let mut f = Forth::builder().yield_interval(1).build();
+    assert_eq!(
+        Err(Error::DivisionByZero),
+        block_on(f.eval_async("1 2 0 /")).0
+    );
+    assert_eq!(Err(Error::UnknownWord), block_on(f.eval_async("nope")).0);
+    assert_eq!(Err(Error::InvalidWord), block_on(f.eval_async(": 1 2 ;")).0);
+    assert_eq!(
+        Err(Error::ReturnStackOverflow),
+        block_on(f.eval_async(": deep recurse 1 ; deep")).0
+    );
+}
+
+#[test]
+fn dropping_the_future_cancels_the_evaluation() {
+    let mut f = Forth::builder().yield_interval(10).build();
+    assert!(f.eval(": forever 1 drop recurse ;").is_ok());
+    {
+        let waker = Waker::from(Arc::new(Noop));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(f.eval_async("forever"));
+        for _ in 0..5 {
+            assert!(future.as_mut().poll(&mut cx).is_pending());
+        }
+    }
+    f.replace_stack(Vec::new());
+    assert!(f.eval("2 3 +").is_ok());
+    assert_eq!(vec![5], f.stack());
+}
+
+#[test]
+fn the_interval_is_at_least_one() {
+    let mut f = Forth::builder().yield_interval(0).build();
+    assert_eq!(1, f.yield_interval());
+    f.set_yield_interval(64);
+    assert_eq!(64, f.yield_interval());
+}
+
+#[test]
+fn the_future_can_move_between_threads() {
+    fn assert_send<T: Send>(_: &T) {}
+    let mut f = Forth::new();
+    assert_send(&f.eval_async("1"));
+}
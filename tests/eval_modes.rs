@@ -0,0 +1,128 @@
+use forth::{CommandFailure, Dialect, Error, Forth};
+
+#[test]
+fn lenient_eval_continues_after_errors() {
+    let mut f = Forth::new();
+    let report = f.eval_lenient(": one 1 ; one foo : two 2 ; 0 0 / : 3 3 ; two");
+    assert_eq!(
+        vec![
+            CommandFailure {
+                command: "one foo".to_string(),
+                error: Error::UnknownWord,
+            },
+            CommandFailure {
+                command: "0 0 /".to_string(),
+                error: Error::DivisionByZero,
+            },
+            CommandFailure {
+                command: ": 3 3 ;".to_string(),
+                error: Error::InvalidWord,
+            },
+        ],
+        report.failures
+    );
+    assert_eq!(vec![1, 0, 2], f.stack());
+}
+
+#[test]
+fn lenient_eval_skips_stray_semicolons() {
+    let mut f = Forth::new();
+    let report = f.eval_lenient("1 2 ; + : one 1 ;; one");
+    let semicolon = || CommandFailure {
+        command: ";".to_string(),
+        error: Error::InvalidWord,
+    };
+    assert_eq!(vec![semicolon(), semicolon()], report.failures);
+    assert_eq!(vec![3, 1], f.stack());
+}
+
+#[test]
+fn lenient_eval_clean_input() {
+    let mut f = Forth::new();
+    assert!(f.eval_lenient(": sq dup * ; 3 sq").is_ok());
+    assert_eq!(vec![9], f.stack());
+}
+
+#[test]
+fn atomic_eval_rolls_back_stack_and_definitions() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 : foo 5 ;").is_ok());
+    assert_eq!(
+        Err(Error::DivisionByZero),
+        f.eval_atomic("2 3 : foo 6 ; : bar 7 ; foo 0 /")
+    );
+    assert_eq!(vec![1], f.stack());
+    assert!(f.eval("foo").is_ok());
+    assert_eq!(vec![1, 5], f.stack());
+    assert_eq!(Err(Error::UnknownWord), f.eval("bar"));
+}
+
+#[test]
+fn atomic_eval_success_keeps_effects() {
+    let mut f = Forth::new();
+    assert!(f.eval_atomic(": foo 5 ; foo foo +").is_ok());
+    assert_eq!(vec![10], f.stack());
+}
+
+#[test]
+fn snapshot_and_restore() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 2").is_ok());
+    let snapshot = f.snapshot();
+    assert!(f.eval("+ : two 2 ;").is_ok());
+    f.restore(snapshot);
+    assert_eq!(vec![1, 2], f.stack());
+    assert_eq!(Err(Error::UnknownWord), f.eval("two"));
+}
+
+#[test]
+fn lenient_dialect_accepts_glued_colons() {
+    let mut f = Forth::new();
+    assert_eq!(Dialect::Lenient, f.dialect());
+    assert!(f.eval("1: inc 1 + ;2 inc").is_ok());
+    assert_eq!(vec![1, 3], f.stack());
+}
+
+#[test]
+fn strict_dialect_rejects_glued_colons() {
+    let mut f = Forth::builder().dialect(Dialect::Strict).build();
+    assert_eq!(Err(Error::InvalidWord), f.eval("1: inc 1 + ;"));
+    assert_eq!(Err(Error::InvalidWord), f.eval(": inc 1 + ;2"));
+    assert_eq!(Err(Error::InvalidWord), f.eval(": inc 1 + ;;"));
+    assert!(f.stack().is_empty());
+    assert!(f.eval(": inc 1 + ; 1 inc").is_ok());
+    assert_eq!(vec![2], f.stack());
+}
+
+#[test]
+fn colons_in_comments_and_characters_delimit_nothing() {
+    for dialect in [Dialect::Lenient, Dialect::Strict] {
+        let mut f = Forth::builder().dialect(dialect).build();
+        assert!(f.eval(": foo ( a:b -- ) ':' ; foo ';'").is_ok());
+        assert_eq!(vec![58, 59], f.stack());
+    }
+}
+
+#[test]
+fn dialect_can_be_switched() {
+    let mut f = Forth::new();
+    f.set_dialect(Dialect::Strict);
+    assert_eq!(Err(Error::InvalidWord), f.eval(": one 1 ;one"));
+    f.set_dialect(Dialect::Lenient);
+    assert!(f.eval(": one 1 ;one").is_ok());
+    assert_eq!(vec![1], f.stack());
+}
+
+#[test]
+fn strict_dialect_in_lenient_eval_rejects_whole_input() {
+    let mut f = Forth::builder().dialect(Dialect::Strict).build();
+    let report = f.eval_lenient("1 2 : three 3 ;3");
+    assert_eq!(
+        vec![CommandFailure {
+            command: "1 2 : three 3 ;3".to_string(),
+            error: Error::InvalidWord,
+        }],
+        report.failures
+    );
+    assert!(f.stack().is_empty());
+}
@@ -0,0 +1,103 @@
+use forth::{Error, Forth, UnknownWords};
+
+#[test]
+fn uncaught_codes_reach_the_host() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::Uncaught(42)), f.eval("1 2 42 throw"));
+    assert!(f.eval(": check ( flag -- ) if -7 throw then ;").is_ok());
+    assert_eq!(Err(Error::Uncaught(-7)), f.eval("-1 check"));
+    assert!(f.eval("0 throw 0 check").is_ok());
+}
+
+#[test]
+fn catch_pushes_zero_when_nothing_is_thrown() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": double 2 * ; : try ( n -- n 0 ) ['] double catch ;")
+        .is_ok());
+    assert!(f.eval("21 try").is_ok());
+    assert_eq!(vec![42, 0], f.stack());
+}
+
+#[test]
+fn catch_puts_the_depth_back_and_pushes_the_code() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": deep 1 2 3 99 throw ; : shallow drop drop 5 throw ; : try-deep ['] deep catch ; : try-shallow ['] shallow catch ;")
+        .is_ok());
+    assert!(f.eval("10 try-deep").is_ok());
+    assert_eq!(vec![10, 99], f.stack());
+    assert!(f.eval("7 8 try-shallow").is_ok());
+    assert_eq!(vec![10, 99, 0, 0, 5], f.stack());
+}
+
+#[test]
+fn the_innermost_catch_wins_and_rethrows_go_out() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(
+            ": inner 1 throw ; : middle ['] inner catch 10 + throw ; : outer ['] middle catch ; \
+             : host-bound ['] inner catch 2 + throw ;"
+        )
+        .is_ok());
+    assert!(f.eval("outer").is_ok());
+    assert_eq!(vec![11], f.stack());
+    assert_eq!(Err(Error::Uncaught(3)), f.eval("host-bound"));
+}
+
+#[test]
+fn builtin_errors_are_caught_as_standard_codes() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": divide 1 0 / ; : underflow drop ; : try-divide ['] divide catch ; : try-underflow ['] underflow catch ;")
+        .is_ok());
+    assert!(f.eval("try-underflow try-divide").is_ok());
+    assert_eq!(vec![-4, -10], f.stack());
+    assert_eq!(Err(Error::DivisionByZero), f.eval("divide"));
+}
+
+#[test]
+fn errors_map_to_standard_codes() {
+    assert_eq!(-4, Error::StackUnderflow.throw_code());
+    assert_eq!(-10, Error::DivisionByZero.throw_code());
+    assert_eq!(-13, Error::UnknownWord.throw_code());
+    assert_eq!(-9, Error::InvalidAddress.throw_code());
+    assert_eq!(-5, Error::ReturnStackOverflow.throw_code());
+    assert_eq!(7, Error::Uncaught(7).throw_code());
+}
+
+#[test]
+fn catch_runs_any_execution_token_at_the_top_level_too() {
+    let mut f = Forth::new();
+    assert!(f.eval(": t 5 throw ; : c ['] t catch ; c").is_ok());
+    assert_eq!(vec![5], f.stack());
+    f.replace_stack(Vec::new());
+    assert!(f.eval("1 2 ' swap catch ' t catch").is_ok());
+    assert_eq!(vec![2, 1, 0, 5], f.stack());
+    f.replace_stack(Vec::new());
+    assert!(f.eval("7 1 0 ' / catch").is_ok());
+    assert_eq!(vec![7, 1, 0, -10], f.stack());
+    f.replace_stack(Vec::new());
+    assert!(f.eval(": try-drop ['] drop catch ; try-drop").is_ok());
+    assert_eq!(vec![-4], f.stack());
+    f.replace_stack(Vec::new());
+    assert!(f.eval("' t ' catch catch").is_ok());
+    assert_eq!(vec![5, 0], f.stack());
+}
+
+#[test]
+fn catch_needs_an_execution_token() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::StackUnderflow), f.eval("catch"));
+    assert_eq!(Err(Error::StackUnderflow), f.eval(": bad catch ; bad"));
+    #[cfg(not(feature = "tagged"))]
+    assert_eq!(Err(Error::InvalidWord), f.eval("99 catch"));
+}
+
+#[test]
+fn deferred_unknown_words_are_caught() {
+    let mut f = Forth::builder().unknown_words(UnknownWords::Defer).build();
+    assert!(f.eval(": later missing ; : try ['] later catch ;").is_ok());
+    assert!(f.eval("1 try").is_ok());
+    assert_eq!(vec![1, -13], f.stack());
+}
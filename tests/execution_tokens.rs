@@ -0,0 +1,97 @@
+mod common;
+
+use common::Buffer;
+use forth::{Capabilities, Division, Error, Forth};
+
+#[test]
+fn execute_runs_the_word_a_tick_names() {
+    let mut f = Forth::new();
+    assert!(f.eval(": sq dup * ; 3 ' sq execute").is_ok());
+    assert_eq!(vec![9], f.stack());
+}
+
+#[test]
+fn bracket_tick_takes_the_word_when_the_definition_is_compiled() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": sq dup * ; : apply ['] sq execute ; : sq drop 0 ; 4 apply")
+        .is_ok());
+    assert_eq!(vec![16], f.stack());
+}
+
+#[test]
+fn executed_words_nest_and_recurse_as_calls_do() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval("variable next : down dup if 1 - next @ execute then 1 + ;")
+        .is_ok());
+    assert!(f.eval("' down next ! 1000 down").is_ok());
+    assert_eq!(vec![1001], f.stack());
+    f.replace_stack(vec![0]);
+    assert!(f
+        .eval(": twice ( n xt -- n ) swap over execute swap execute ;")
+        .is_ok());
+    assert!(f.eval(": inc 1 + ; 5 ' inc twice").is_ok());
+    assert_eq!(vec![0, 7], f.stack());
+}
+
+#[test]
+fn builtins_have_execution_tokens_too() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval("3 ' dup execute * : add ['] + execute ; 1 add")
+        .is_ok());
+    assert_eq!(vec![10], f.stack());
+    f.replace_stack(Vec::new());
+    assert!(f.eval(": sq dup * ; 4 ' sq ' execute execute").is_ok());
+    assert_eq!(vec![16], f.stack());
+    f.replace_stack(Vec::new());
+    assert!(f
+        .eval(": later ['] ' execute ; later sq 5 swap execute")
+        .is_ok());
+    assert_eq!(vec![25], f.stack());
+}
+
+#[test]
+fn builtins_take_the_variant_the_settings_pick() {
+    let mut f = Forth::builder().division(Division::Floored).build();
+    assert!(f.eval("-7 2 ' / execute").is_ok());
+    assert_eq!(vec![-4], f.stack());
+}
+
+#[test]
+fn variables_markers_and_control_words_have_no_execution_tokens() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable v marker m").is_ok());
+    assert_eq!(Err(Error::InvalidWord), f.eval("' v"));
+    assert_eq!(Err(Error::InvalidWord), f.eval("' m"));
+    assert_eq!(Err(Error::InvalidWord), f.eval("' if"));
+    assert_eq!(Err(Error::UnknownWord), f.eval("' nope"));
+    assert_eq!(Err(Error::InvalidWord), f.eval(": f ['] v ;"));
+    assert_eq!(Err(Error::InvalidWord), f.eval(": g ['] ;"));
+    #[cfg(not(feature = "tagged"))]
+    assert_eq!(Err(Error::InvalidWord), f.eval("99 execute"));
+    #[cfg(not(feature = "tagged"))]
+    assert_eq!(Err(Error::InvalidWord), f.eval("-9999 execute"));
+    assert_eq!(Err(Error::StackUnderflow), f.eval("execute"));
+}
+
+#[test]
+fn builtins_denied_by_capabilities_have_no_execution_tokens() {
+    let mut f = Forth::builder()
+        .capabilities(Capabilities::ALL - Capabilities::TIME)
+        .build();
+    assert_eq!(Err(Error::UnknownWord), f.eval("' utime"));
+}
+
+#[test]
+fn emit_writes_characters_to_the_output() {
+    let buffer = Buffer::default();
+    let mut f = Forth::new();
+    f.set_output(buffer.clone());
+    assert!(f.eval("'h' emit 'é' emit ':' emit ';' emit").is_ok());
+    assert!(f.eval(": bang '!' emit ; bang").is_ok());
+    let output = buffer.text();
+    assert_eq!("hé:;!", output);
+    assert!(f.stack().is_empty());
+}
@@ -0,0 +1,57 @@
+use forth::*;
+
+#[test]
+fn definitions_follow_their_declared_effect() {
+    let f = Forth::new();
+    assert_eq!(
+        concat!(
+            ": sq ( n -- n*n )  ( n )\n",
+            "  dup              ( n n )\n",
+            "  *                ( n3 )\n",
+            ";                  ( n3 )\n",
+            "3                  ( 3 )\n",
+            "sq                 ( n*n )\n",
+        ),
+        f.explain(": sq ( n -- n*n ) dup * ; 3 sq")
+    );
+}
+
+#[test]
+fn shuffles_keep_track_of_values() {
+    let f = Forth::new();
+    assert_eq!(
+        "1     ( 1 )\n2     ( 1 2 )\nswap  ( 2 1 )\nover  ( 2 1 2 )\n",
+        f.explain("1 2 swap over")
+    );
+}
+
+#[test]
+fn branches_meet_at_then() {
+    let f = Forth::new();
+    let explained =
+        f.explain(": sign ( n -- flag ) if 1 else -1 then ; : odd ( n -- ) dup if drop then ;");
+    let pictures: Vec<&str> = explained
+        .lines()
+        .filter(|line| line.trim_start().starts_with("then"))
+        .map(|line| line.split_once("  (").unwrap().1)
+        .collect();
+    assert_eq!(vec![" x )", " ? )"], pictures);
+}
+
+#[test]
+fn variables_defined_in_the_input_are_known() {
+    let f = Forth::new();
+    assert_eq!(
+        "variable v  ( )\nv           ( addr )\n@           ( x )\n",
+        f.explain("variable v v @")
+    );
+}
+
+#[test]
+fn unknown_words_lose_the_picture() {
+    let f = Forth::new();
+    assert_eq!(
+        "1           ( 1 )\nfrobnicate  ( ? )\n2           ( ? )\n",
+        f.explain("1 frobnicate 2")
+    );
+}
@@ -0,0 +1,164 @@
+#![cfg(feature = "file-io")]
+
+use std::path::PathBuf;
+
+use forth::*;
+
+/// An empty directory of its own for each test.
+fn sandbox(name: &str) -> PathBuf {
+    let root = std::path::Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("files")
+        .join(name);
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    root
+}
+
+/// An interpreter rooted at `root`, with a buffer of `cells` cells from
+/// address 0.
+fn forth(root: &PathBuf, cells: usize) -> Forth {
+    let mut f = Forth::builder().file_root(root).build();
+    for i in 0..cells {
+        assert!(f.eval(&format!("variable b{i}")).is_ok());
+    }
+    f
+}
+
+#[test]
+fn files_are_read_into_data_space_a_byte_a_cell() {
+    let root = sandbox("read");
+    std::fs::write(root.join("Data.txt"), "hi!").unwrap();
+    let mut f = forth(&root, 4);
+    assert!(f.eval("r/o open-file Data.txt").is_ok());
+    assert_eq!(vec![1, 0], f.drain_stack());
+    assert!(f
+        .eval("b0 4 1 read-file 1 file-position 1 file-size 1 close-file")
+        .is_ok());
+    assert_eq!(vec![3, 0, 3, 0, 3, 0, 0], f.drain_stack());
+    assert!(f.eval("b0 @ b1 @ b2 @ b3 @").is_ok());
+    assert_eq!(vec![104, 105, 33, 0], f.stack());
+}
+
+#[test]
+fn files_are_written_from_data_space() {
+    let root = sandbox("write");
+    let mut f = forth(&root, 2);
+    assert!(f.eval("'o' b0 ! 'k' b1 !").is_ok());
+    assert!(f
+        .eval("w/o create-file out.txt drop b0 2 1 write-file 1 close-file")
+        .is_ok());
+    assert_eq!(vec![1, 0, 0], f.drain_stack());
+    assert_eq!("ok", std::fs::read_to_string(root.join("out.txt")).unwrap());
+
+    assert!(f
+        .eval("r/w open-file out.txt drop 1 1 reposition-file")
+        .is_ok());
+    assert!(f.eval("b0 1 1 write-file 1 close-file").is_ok());
+    assert_eq!(vec![1, 0, 0, 0], f.drain_stack());
+    assert_eq!("oo", std::fs::read_to_string(root.join("out.txt")).unwrap());
+
+    assert!(f.eval("delete-file out.txt delete-file out.txt").is_ok());
+    let [deleted, missing] = f.stack() else {
+        panic!("expected two results");
+    };
+    assert_eq!((0, true), (*deleted, *missing != 0));
+    assert!(!root.join("out.txt").exists());
+}
+
+#[test]
+fn file_names_are_parsed_at_run_time() {
+    let root = sandbox("parsing");
+    std::fs::write(root.join("a.txt"), "a").unwrap();
+    let mut f = forth(&root, 1);
+    assert!(f.eval(": open r/o open-file ; open a.txt").is_ok());
+    assert_eq!(vec![1, 0], f.stack());
+    assert_eq!(Err(Error::InvalidWord), f.eval("open"));
+}
+
+#[test]
+fn failures_leave_an_io_result_code() {
+    let root = sandbox("failures");
+    let mut f = forth(&root, 1);
+    assert!(f.eval("r/o open-file missing.txt").is_ok());
+    let [id, ior] = f.drain_stack()[..] else {
+        panic!("expected two results");
+    };
+    assert_eq!(0, id);
+    assert_ne!(0, ior);
+    for words in [
+        "7 close-file",
+        "b0 1 7 read-file swap drop",
+        "7 file-size swap drop",
+        "5 open-file a.txt swap drop",
+    ] {
+        assert!(f.eval(words).is_ok(), "{words}");
+        assert_ne!(vec![0], f.drain_stack(), "{words}");
+    }
+}
+
+#[test]
+fn buffers_must_lie_in_data_space() {
+    let root = sandbox("buffers");
+    std::fs::write(root.join("a.txt"), "abc").unwrap();
+    let mut f = forth(&root, 2);
+    assert!(f.eval("r/o open-file a.txt drop").is_ok());
+    assert_eq!(Err(Error::InvalidAddress), f.eval("b1 2 1 read-file"));
+    assert_eq!(Err(Error::InvalidAddress), f.eval("b0 2 - 1 1 read-file"));
+    assert_eq!(Err(Error::OutOfRange), f.eval("b0 -1 1 write-file"));
+}
+
+#[test]
+fn files_stay_inside_the_sandbox() {
+    let root = sandbox("escape");
+    std::fs::create_dir(root.join("inner")).unwrap();
+    std::fs::write(root.join("secret.txt"), "x").unwrap();
+    let mut f = Forth::builder().file_root(root.join("inner")).build();
+    assert_eq!(Some(root.join("inner").as_path()), f.file_root());
+    let escapes = [
+        "../secret.txt",
+        "/etc/passwd",
+        "inner/../../secret.txt",
+        ".",
+    ];
+    for name in escapes {
+        assert!(f.eval(&format!("r/o open-file {name}")).is_ok());
+        assert_eq!(0, f.drain_stack()[0], "{name}");
+    }
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(root.join("secret.txt"), root.join("inner/link")).unwrap();
+        assert!(f.eval("r/o open-file link").is_ok());
+        assert_eq!(0, f.drain_stack()[0]);
+    }
+}
+
+#[test]
+fn no_files_can_be_opened_without_a_root() {
+    let root = sandbox("unrooted");
+    std::fs::write(root.join("a.txt"), "a").unwrap();
+    let mut f = Forth::new();
+    assert_eq!(None, f.file_root());
+    assert!(f.eval("r/o open-file a.txt").is_ok());
+    assert_eq!(0, f.drain_stack()[0]);
+    f.set_file_root(Some(root));
+    assert!(f.eval("r/o open-file a.txt").is_ok());
+    assert_eq!(vec![1, 0], f.stack());
+}
+
+#[test]
+fn file_words_are_compiled_and_saved_like_builtins() {
+    let root = sandbox("image");
+    std::fs::write(root.join("a.txt"), "abc").unwrap();
+    let mut f = forth(&root, 0);
+    assert!(f.eval(": size r/o open-file drop file-size ;").is_ok());
+    assert_eq!(
+        ": size\n   0  push           0\n   1  file           open-file\n   2  primitive      drop\n   3  file           file-size\n;\n",
+        f.disassemble("size").unwrap()
+    );
+    let image = root.join("size.img");
+    f.save_image(&image).unwrap();
+    let mut f = Forth::builder().file_root(&root).build();
+    f.load_image(&image).unwrap();
+    assert!(f.eval("size a.txt").is_ok());
+    assert_eq!(vec![3, 0], f.stack());
+}
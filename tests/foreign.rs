@@ -0,0 +1,47 @@
+#![cfg(all(feature = "dlopen", target_os = "linux"))]
+
+use forth::*;
+
+fn forth() -> Forth {
+    let mut f = Forth::new();
+    // SAFETY: the scripts below only call `labs` and `getpid`.
+    unsafe { f.allow_foreign_calls() };
+    f
+}
+
+#[test]
+fn functions_are_called_with_arguments_from_the_stack() {
+    let mut f = forth();
+    assert!(f.eval("dlopen libc.so.6 drop dup dlsym labs drop").is_ok());
+    assert_eq!(vec![1, 1], f.drain_stack());
+    assert!(f.eval("-42 1 1 dlcall 7 1 1 dlcall").is_ok());
+    assert_eq!(vec![42, 0, 7, 0], f.drain_stack());
+    assert!(f.eval("1 dlsym getpid drop 0 swap dlcall").is_ok());
+    assert_eq!(vec![std::process::id() as Value, 0], f.stack());
+}
+
+#[test]
+fn failures_leave_an_io_result() {
+    let mut f = forth();
+    assert!(f.eval("dlopen no-such-library.so").is_ok());
+    assert!(f.eval("dlopen libc.so.6 drop dlsym no_such_symbol").is_ok());
+    assert!(f.eval("5 1 9 dlcall").is_ok());
+    assert_eq!(vec![0, -1, 0, -1, 0, -1], f.stack());
+    assert_eq!(Err(Error::OutOfRange), f.eval("7 1 dlcall"));
+    assert_eq!(Err(Error::StackUnderflow), {
+        f.replace_stack(Vec::new());
+        f.eval("2 1 dlcall")
+    });
+}
+
+#[test]
+fn calls_are_refused_until_allowed() {
+    let mut f = Forth::new();
+    assert!(f.eval("dlopen libc.so.6").is_ok());
+    assert_eq!(vec![0, -1], f.stack());
+    let kinds: Vec<_> = check("dlopen libc.so.6 drop dlsym labs drop 1 swap dlcall dlopen")
+        .into_iter()
+        .map(|lint| lint.kind)
+        .collect();
+    assert_eq!(vec![LintKind::MissingName], kinds);
+}
@@ -0,0 +1,66 @@
+use forth::*;
+
+fn formatted(source: &str) -> String {
+    format(source, FormatOptions::default())
+}
+
+#[test]
+fn short_definitions_fit_on_a_line() {
+    assert_eq!(
+        ": sq ( n -- n*n ) dup * ;\n3 sq\n",
+        formatted(":   sq ( n -- n*n )\n   dup\t* ;   3   sq")
+    );
+}
+
+#[test]
+fn long_definitions_are_indented_by_branch() {
+    let options = FormatOptions {
+        indent: 4,
+        line_length: 24,
+    };
+    let source = ": sign ( n -- f ) dup if drop 1 else dup drop drop 0 then 7 + ;";
+    let expected = "\
+: sign ( n -- f )
+    dup if
+        drop 1
+    else
+        dup drop drop 0
+    then 7 + ;
+";
+    assert_eq!(expected, format(source, options));
+}
+
+#[test]
+fn line_breaks_outside_definitions_are_kept_and_long_lines_wrapped() {
+    let options = FormatOptions {
+        indent: 2,
+        line_length: 9,
+    };
+    assert_eq!(
+        "1 2 +\n\n3 4 5 6 7\n8\n",
+        format("\n1   2 +\n\n\n\n3 4 5 6 7 8\n", options)
+    );
+}
+
+#[test]
+fn comments_and_strings_are_kept_as_written() {
+    let source = ": greet ( -- )   .\"  hi   there\" \\ says  hi\n  1 ;";
+    let expected = ": greet ( -- )\n  .\"  hi   there\" \\ says  hi\n  1 ;\n";
+    assert_eq!(expected, formatted(source));
+}
+
+#[test]
+fn formatting_is_idempotent_and_keeps_the_meaning() {
+    let source = "variable v : fact dup if dup 1 - recurse * else drop 1 then ;   5 fact v ! v @";
+    let options = FormatOptions {
+        indent: 3,
+        line_length: 20,
+    };
+    let once = format(source, options);
+    assert_eq!(once, format(&once, options));
+
+    let (mut before, mut after) = (Forth::new(), Forth::new());
+    assert!(before.eval(source).is_ok());
+    assert!(after.eval(&once).is_ok());
+    assert_eq!(before.stack(), after.stack());
+}
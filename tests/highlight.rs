@@ -0,0 +1,72 @@
+use forth::*;
+
+fn classes(input: &str) -> Vec<(&str, TokenClass)> {
+    highlight(input)
+        .into_iter()
+        .map(|(span, class)| (&input[span.start..span.end], class))
+        .collect()
+}
+
+#[test]
+fn numbers_builtins_and_unknown_words() {
+    use TokenClass::*;
+    assert_eq!(
+        vec![
+            ("1", Number),
+            ("-2", Number),
+            ("$ff", Number),
+            ("'a'", Number),
+            ("DUP", Builtin),
+            ("nope", Unknown)
+        ],
+        classes("1 -2 $ff 'a' DUP nope")
+    );
+}
+
+#[test]
+fn definitions_make_user_words() {
+    use TokenClass::*;
+    assert_eq!(
+        vec![
+            ("sq", Unknown),
+            (":", Builtin),
+            ("sq", UserWord),
+            ("dup", Builtin),
+            ("*", Builtin),
+            (";", Builtin),
+            ("variable", Builtin),
+            ("v", UserWord),
+            ("3", Number),
+            ("sq", UserWord),
+            ("v", UserWord),
+            ("!", Builtin),
+        ],
+        classes("sq : sq dup * ; variable v 3 sq v !")
+    );
+}
+
+#[test]
+fn comments_and_strings_are_one_span_each() {
+    use TokenClass::*;
+    assert_eq!(
+        vec![
+            ("( n -- n*n )", Comment),
+            ("dup", Builtin),
+            ("\\ squares", Comment),
+            (".\" hi there\"", String),
+            ("1", Number),
+            ("( unclosed", Comment),
+        ],
+        classes("( n -- n*n ) dup \\ squares\n.\" hi there\" 1 ( unclosed")
+    );
+}
+
+#[test]
+fn the_dictionary_decides_what_is_a_user_word() {
+    use TokenClass::*;
+    let mut f = Forth::new();
+    assert!(f.eval(": sq dup * ; : dup 1 ;").is_ok());
+    let input = "sq dup drop";
+    let classes: Vec<TokenClass> = f.highlight(input).into_iter().map(|(_, c)| c).collect();
+    assert_eq!(vec![UserWord, UserWord, Builtin], classes);
+}
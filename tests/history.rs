@@ -0,0 +1,61 @@
+use forth::{Error, Forth, HistoryEntry};
+
+#[test]
+fn history_is_off_by_default() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 2 +").is_ok());
+    assert!(f.history().is_empty());
+}
+
+#[test]
+fn history_records_inputs_and_outcomes() {
+    let mut f = Forth::new();
+    f.set_history(true);
+    assert!(f.eval(": foo 5 ;").is_ok());
+    assert!(f.eval("foo bar").is_err());
+    assert_eq!(
+        vec![
+            HistoryEntry {
+                input: ": foo 5 ;".to_string(),
+                outcome: Ok(()),
+            },
+            HistoryEntry {
+                input: "foo bar".to_string(),
+                outcome: Err(Error::UnknownWord),
+            },
+        ],
+        f.history()
+    );
+    f.set_history(false);
+    assert!(f.history().is_empty());
+}
+
+#[test]
+fn replay_reproduces_a_session() {
+    let mut f = Forth::new();
+    f.set_history(true);
+    assert!(f.eval(": sq dup * ;").is_ok());
+    assert!(f.eval("3 sq 0 /").is_err());
+    assert!(f.eval("sq").is_ok());
+    let history = f.history().to_vec();
+
+    let mut g = Forth::new();
+    assert_eq!(None, g.replay(&history));
+    assert_eq!(f.stack(), g.stack());
+}
+
+#[test]
+fn replay_reports_first_divergence() {
+    let mut f = Forth::new();
+    let history = vec![
+        HistoryEntry {
+            input: "1 2".to_string(),
+            outcome: Ok(()),
+        },
+        HistoryEntry {
+            input: "foo".to_string(),
+            outcome: Ok(()),
+        },
+    ];
+    assert_eq!(Some(1), f.replay(&history));
+}
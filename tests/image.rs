@@ -0,0 +1,204 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use forth::*;
+
+fn image_path(name: &str) -> PathBuf {
+    std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+#[test]
+fn loaded_words_run_as_they_did() {
+    let path = image_path("words.img");
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": sq dup * ; : fact dup if dup 1 - recurse * else drop 1 then ;")
+        .is_ok());
+    f.save_image(&path).unwrap();
+
+    let mut f = Forth::new();
+    f.load_image(&path).unwrap();
+    assert!(f.eval("3 sq 5 fact").is_ok());
+    assert_eq!(vec![9, 120], f.stack());
+}
+
+#[test]
+fn execution_tokens_and_characters_survive() {
+    let path = image_path("xts.img");
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": sq dup * ; : run 'a' swap ['] sq execute 1 ['] + execute ;")
+        .is_ok());
+    f.save_image(&path).unwrap();
+
+    let mut f = Forth::new();
+    f.load_image(&path).unwrap();
+    assert!(f.eval("3 run").is_ok());
+    assert_eq!(vec![97, 10], f.stack());
+    #[cfg(feature = "tagged")]
+    assert_eq!(&[Tag::Char, Tag::Number], f.stack_tags());
+}
+
+#[test]
+fn variables_keep_their_values() {
+    let path = image_path("variables.img");
+    let mut f = Forth::new();
+    assert!(f.eval("variable x 42 x !").is_ok());
+    f.save_image(&path).unwrap();
+
+    let mut f = Forth::new();
+    f.load_image(&path).unwrap();
+    assert_eq!(Some(42), f.get_var("x"));
+    assert!(f.eval("variable y 7 y ! x @ y @").is_ok());
+    assert_eq!(vec![42, 7], f.stack());
+}
+
+#[test]
+fn redefinitions_and_markers_survive() {
+    let path = image_path("markers.img");
+    let mut f = Forth::new();
+    assert!(f.eval(": n 1 ; marker base : n 2 ;").is_ok());
+    f.save_image(&path).unwrap();
+
+    let mut f = Forth::new();
+    f.load_image(&path).unwrap();
+    assert!(f.eval("n base n").is_ok());
+    assert_eq!(vec![2, 1], f.stack());
+}
+
+#[test]
+fn loading_replaces_the_words_but_not_the_stack() {
+    let path = image_path("replace.img");
+    let mut f = Forth::new();
+    assert!(f.eval(": a 1 ;").is_ok());
+    f.save_image(&path).unwrap();
+
+    let mut f = Forth::new();
+    assert!(f.eval(": b 2 ; 5").is_ok());
+    f.load_image(&path).unwrap();
+    assert_eq!(vec![5], f.stack());
+    assert_eq!(Err(Error::UnknownWord), f.eval("b"));
+    assert!(f.eval("a").is_ok());
+    assert_eq!(vec![5, 1], f.stack());
+}
+
+#[test]
+fn stack_effects_survive() {
+    let path = image_path("effects.img");
+    let mut f = Forth::new();
+    assert!(f.eval(": sq ( n -- n*n ) dup * ; : two 2 ;").is_ok());
+    f.save_image(&path).unwrap();
+
+    let mut f = Forth::new();
+    f.load_image(&path).unwrap();
+    assert_eq!(Some("( n -- n*n )"), f.doc("sq"));
+    assert_eq!(None, f.doc("two"));
+    assert_eq!(Some("( x -- x x )"), f.doc("dup"));
+}
+
+#[test]
+fn loaded_words_keep_the_meanings_they_had() {
+    let path = image_path("meanings.img");
+    let mut f = Forth::new();
+    assert!(f.eval(": a 1 ; : b a ; : a 2 ; : dup 3 ;").is_ok());
+    f.save_image(&path).unwrap();
+
+    let mut f = Forth::new();
+    f.load_image(&path).unwrap();
+    assert!(f.eval("b a 4 dup").is_ok());
+    assert_eq!(vec![1, 2, 4, 3], f.stack());
+}
+
+#[test]
+fn deferred_unknown_words_still_fail_when_loaded() {
+    let path = image_path("deferred.img");
+    let mut f = Forth::builder().unknown_words(UnknownWords::Defer).build();
+    assert!(f.eval(": w 1 later ;").is_ok());
+    f.save_image(&path).unwrap();
+
+    let mut f = Forth::new();
+    f.load_image(&path).unwrap();
+    assert_eq!(Err(Error::UnknownWord), f.eval("w"));
+    assert_eq!(vec![1], f.stack());
+}
+
+#[test]
+fn other_files_are_rejected() {
+    let path = image_path("not-an-image.img");
+    std::fs::write(&path, ": sq dup * ;").unwrap();
+    let mut f = Forth::new();
+    assert!(f.eval(": a 1 ;").is_ok());
+    let error = f.load_image(&path).unwrap_err();
+    assert_eq!(ErrorKind::InvalidData, error.kind());
+    assert!(f.eval("a").is_ok());
+}
+
+#[test]
+fn truncated_images_are_rejected() {
+    let path = image_path("truncated.img");
+    let mut f = Forth::new();
+    assert!(f.eval(": sq dup * ; variable x").is_ok());
+    f.save_image(&path).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    for len in 0..bytes.len() {
+        std::fs::write(&path, &bytes[..len]).unwrap();
+        let error = Forth::new().load_image(&path).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+    }
+}
+
+#[test]
+fn branches_out_of_their_body_are_rejected() {
+    let path = image_path("corrupt-branch.img");
+    let mut f = Forth::new();
+    assert!(f.eval(": f if 1 then ;").is_ok());
+    f.save_image(&path).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    let branch = [5, 1, 0, 0, 0, 0, 0, 0, 0];
+    let at = bytes
+        .windows(branch.len())
+        .position(|window| window == branch)
+        .unwrap();
+    for skip in [2, u64::MAX - 1, u64::MAX] {
+        let mut corrupt = bytes.clone();
+        corrupt[at + 1..at + 9].copy_from_slice(&skip.to_le_bytes());
+        std::fs::write(&path, &corrupt).unwrap();
+        let error = Forth::new().load_image(&path).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, error.kind(), "{skip}");
+    }
+}
+
+#[test]
+fn images_of_other_versions_are_rejected() {
+    let path = image_path("version.img");
+    Forth::new().save_image(&path).unwrap();
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[8] += 1;
+    std::fs::write(&path, &bytes).unwrap();
+    let error = Forth::new().load_image(&path).unwrap_err();
+    assert_eq!(ErrorKind::InvalidData, error.kind());
+    assert!(error.to_string().contains("version"));
+}
+
+#[cfg(feature = "file-io")]
+#[test]
+fn words_the_capabilities_deny_are_rejected() {
+    let mut sandboxed = Forth::builder()
+        .capabilities(Capabilities::ALL - Capabilities::FILE_IO)
+        .build();
+    assert!(sandboxed.eval(": a 1 ;").is_ok());
+    for (name, source) in [
+        ("file-op.img", ": gone delete-file ;"),
+        ("file-synonym.img", "synonym rm delete-file"),
+        ("file-xt.img", ": gone ['] delete-file ;"),
+    ] {
+        let path = image_path(name);
+        let mut f = Forth::new();
+        assert!(f.eval(source).is_ok());
+        f.save_image(&path).unwrap();
+        let error = sandboxed.load_image(&path).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, error.kind(), "{source}");
+    }
+    assert!(sandboxed.eval("a").is_ok());
+    assert_eq!(Err(Error::UnknownWord), sandboxed.eval("gone"));
+}
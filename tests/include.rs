@@ -0,0 +1,73 @@
+use std::sync::{Arc, Mutex};
+
+use forth::*;
+
+/// Modules kept in memory, as a host might keep them in its own store;
+/// names asked for are logged.
+fn forth(log: &Arc<Mutex<Vec<String>>>) -> Forth {
+    let log = Arc::clone(log);
+    Forth::builder()
+        .include_resolver(move |name| {
+            log.lock().unwrap().push(name.to_string());
+            match name {
+                "Math.fs" => Some(": square dup * ;\n: cube dup square * ;".to_string()),
+                "app.fs" => Some("include Math.fs 2 cube".to_string()),
+                "broken.fs" => Some("1 2 0 / 3".to_string()),
+                "self.fs" => Some("include self.fs".to_string()),
+                _ => None,
+            }
+        })
+        .build()
+}
+
+#[test]
+fn included_sources_define_words_and_run() {
+    let log = Arc::default();
+    let mut f = forth(&log);
+    assert!(f.eval("include app.fs 3 square").is_ok());
+    assert_eq!(vec![8, 9], f.stack());
+    assert_eq!(vec!["app.fs", "Math.fs"], *log.lock().unwrap());
+    // Like `variable`, `include` in a definition parses its name when run.
+    assert!(f.eval(": load include ; load Math.fs 4 cube").is_ok());
+    assert_eq!(vec![8, 9, 64], f.stack());
+}
+
+#[test]
+fn includes_fail_as_their_sources_do() {
+    let log = Arc::default();
+    let mut f = forth(&log);
+    assert_eq!(Err(Error::IncludeNotFound), f.eval("include nope.fs"));
+    assert_eq!(Err(Error::InvalidWord), f.eval("include"));
+    assert_eq!(Err(Error::DivisionByZero), f.eval("include broken.fs"));
+    assert_eq!(vec![1, 2], f.drain_stack());
+    assert_eq!(Err(Error::ReturnStackOverflow), f.eval("include self.fs"));
+    assert_eq!(
+        Err(Error::IncludeNotFound),
+        Forth::new().eval("include app.fs")
+    );
+}
+
+#[test]
+fn evaluate_runs_text_held_in_data_space() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable s0 variable s1 variable s2").is_ok());
+    // "2 *", a byte to a cell
+    assert!(f.eval("50 s0 ! 32 s1 ! 42 s2 !").is_ok());
+    assert!(f.eval("21 s0 3 evaluate").is_ok());
+    assert_eq!(vec![42], f.drain_stack());
+    assert!(f.eval(": twice s0 3 evaluate ; 5 twice").is_ok());
+    assert_eq!(vec![10], f.drain_stack());
+    assert_eq!(Err(Error::StackUnderflow), f.eval("s0 3 evaluate"));
+    assert_eq!(Err(Error::InvalidAddress), f.eval("s0 4 evaluate"));
+    assert!(f.eval("255 s0 !").is_ok());
+    assert_eq!(Err(Error::InvalidWord), f.eval("s0 1 evaluate"));
+}
+
+#[test]
+fn include_parses_its_name_when_linted() {
+    let kinds: Vec<_> = check("include whatever.fs include")
+        .into_iter()
+        .map(|lint| lint.kind)
+        .collect();
+    assert_eq!(vec![LintKind::MissingName], kinds);
+}
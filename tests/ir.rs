@@ -0,0 +1,136 @@
+use forth::*;
+
+fn builtin(name: &str, effect: (usize, usize)) -> IrOp {
+    IrOp::Builtin {
+        name: name.to_string(),
+        effect: Some(effect),
+    }
+}
+
+#[test]
+fn words_without_branches_are_one_block() {
+    let mut f = Forth::new();
+    f.eval(": sq dup * ; : four 2 sq ;").unwrap();
+    let ir = f.ir();
+    assert_eq!(2, ir.words.len());
+    assert_eq!(
+        IrWord {
+            name: "sq".to_string(),
+            effect: Some((1, 1)),
+            blocks: vec![Block {
+                ops: vec![builtin("dup *", (1, 1))],
+                exit: Exit::Return,
+            }],
+        },
+        ir.words[0]
+    );
+    assert_eq!(
+        vec![
+            IrOp::Push(2),
+            // Inlined.
+            builtin("dup *", (1, 1))
+        ],
+        ir.words[1].blocks[0].ops
+    );
+}
+
+#[test]
+fn branches_split_words_into_blocks() {
+    let mut f = Forth::new();
+    f.eval(": choose if if 1 else 2 then else 3 then ;")
+        .unwrap();
+    let blocks = &f.ir().words[0].blocks;
+    let exits: Vec<Exit> = blocks.iter().map(|block| block.exit).collect();
+    assert_eq!(
+        vec![
+            Exit::If {
+                then: 1,
+                otherwise: 5
+            },
+            Exit::If {
+                then: 2,
+                otherwise: 3
+            },
+            Exit::Jump(4),
+            Exit::Jump(4),
+            Exit::Jump(6),
+            Exit::Jump(6),
+            Exit::Return,
+        ],
+        exits
+    );
+    assert_eq!(vec![IrOp::Push(3)], blocks[5].ops);
+    assert!(blocks[6].ops.is_empty());
+}
+
+#[test]
+fn calls_are_resolved_to_the_definition_they_compiled_to() {
+    let as_written = Optimizations {
+        fold_constants: false,
+        inline_threshold: 0,
+        superinstructions: false,
+        peephole: false,
+    };
+    let mut f = Forth::builder().optimizations(as_written).build();
+    f.eval(": w 1 ; : v w ; : w 2 ;").unwrap();
+    let ir = f.ir();
+    assert_eq!(
+        ["w", "v", "w"],
+        ir.words.iter().map(|w| w.name.as_str()).collect::<Vec<_>>()[..]
+    );
+    assert_eq!(
+        vec![IrOp::Call {
+            word: 0,
+            name: "w".to_string()
+        }],
+        ir.words[1].blocks[0].ops
+    );
+}
+
+#[test]
+fn tokens_give_back_an_equivalent_body() {
+    let mut f = Forth::new();
+    f.eval(": fact dup 1 - dup if recurse * else drop then ;")
+        .unwrap();
+    f.eval(": choose if if 1 else 2 then else 3 then ;")
+        .unwrap();
+    let ir = f.ir();
+    let program = Program::new()
+        .definition("fact2", ir.tokens(0))
+        .definition("choose2", ir.tokens(1));
+    f.run(&program).unwrap();
+    f.eval("5 fact2 1 1 choose2 0 1 choose2 0 choose2").unwrap();
+    assert_eq!([120, 1, 2, 3], f.stack());
+}
+
+#[test]
+fn transformed_blocks_run_through_eval_tokens() {
+    let mut f = Forth::new();
+    f.eval(": step 3 + 2 * ;").unwrap();
+    let mut ir = f.ir();
+    ir.words[0].blocks[0].ops.insert(0, IrOp::Push(1));
+    f.eval_tokens(&ir.tokens(0)).unwrap();
+    assert_eq!([8], f.stack());
+}
+
+#[test]
+fn words_that_fail_to_infer_have_no_effect() {
+    let mut f = Forth::new();
+    f.eval(": r ( n -- ) dup if 1 - recurse then ;").unwrap();
+    assert_eq!(None, f.ir().words[0].effect);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn ir_as_json() {
+    let mut f = Forth::new();
+    f.eval(": pos if 1 then ;").unwrap();
+    assert_eq!(
+        concat!(
+            r#"[{"blocks":[{"exit":{"if":[1,2]},"ops":[]},"#,
+            r#"{"exit":{"jump":2},"ops":[{"push":1}]},{"exit":"return","ops":[]}],"#,
+            r#""effect":null,"name":"pos"}]"#
+        ),
+        f.ir().to_json()
+    );
+}
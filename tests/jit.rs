@@ -0,0 +1,147 @@
+#![cfg(feature = "jit")]
+
+use forth::*;
+
+/// Enough calls for any word to be compiled.
+const CALLS: usize = 100;
+
+#[test]
+fn hot_words_compute_the_same_results() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": w dup * 3 swap - 2 over + 7 / 1 2 drop swap ;")
+        .is_ok());
+    for i in 0..CALLS as Value {
+        assert!(f.eval(&format!("{i} w")).is_ok());
+        let x = 3 - i * i;
+        let expected = vec![x, 1, (2 + x) / 7];
+        assert_eq!(expected, f.drain_stack(), "call {i}");
+    }
+}
+
+#[test]
+fn hot_words_fail_like_the_interpreter() {
+    let mut f = Forth::new();
+    assert!(f.eval(": sub - ; : div / ;").is_ok());
+    for _ in 0..CALLS {
+        assert_eq!(Err(Error::StackUnderflow), f.eval("1 sub"));
+        assert_eq!(Vec::<Value>::new(), f.stack());
+        assert_eq!(Err(Error::DivisionByZero), f.eval("5 0 div"));
+        assert_eq!(vec![5], f.drain_stack());
+    }
+}
+
+#[test]
+fn hot_words_overflow_like_the_interpreter() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": inc 1 + ; : sq dup * ; : div / ; : neg 0 swap - ;")
+        .is_ok());
+    for _ in 0..CALLS {
+        assert_eq!(Err(Error::Overflow), f.eval("2147483647 inc"));
+        assert_eq!(Err(Error::Overflow), f.eval("65536 sq"));
+        assert_eq!(Err(Error::Overflow), f.eval("-2147483648 -1 div"));
+        assert_eq!(Err(Error::Overflow), f.eval("-2147483648 neg"));
+        assert_eq!(Vec::<Value>::new(), f.drain_stack());
+    }
+}
+
+#[test]
+fn hot_words_branch() {
+    let mut f = Forth::new();
+    assert!(f.eval(": pick if 1 else 2 then ;").is_ok());
+    for i in 0..CALLS as Value {
+        assert!(f.eval(&format!("{} pick", i % 2)).is_ok());
+    }
+    let expected: Vec<Value> = (0..CALLS).map(|i| 2 - (i % 2) as Value).collect();
+    assert_eq!(expected, f.stack());
+}
+
+#[test]
+fn hot_words_loop_and_grow_the_stack() {
+    let mut f = Forth::new();
+    assert!(f.eval(": count dup if dup 1 - recurse then ;").is_ok());
+    for _ in 0..CALLS {
+        assert!(f.eval("1000 count").is_ok());
+        let stack = f.drain_stack();
+        assert_eq!((0..=1000).rev().collect::<Vec<_>>(), stack);
+    }
+}
+
+#[test]
+fn clones_and_restores_keep_native_code_consistent() {
+    let mut f = Forth::new();
+    assert!(f.eval(": w 1 ;").is_ok());
+    let snapshot = f.snapshot();
+    for _ in 0..CALLS {
+        assert!(f.eval("w drop").is_ok());
+    }
+    f.restore(snapshot);
+    assert!(f.eval(": v 2 ;").is_ok());
+    let mut g = f.clone();
+    for _ in 0..CALLS {
+        assert!(f.eval("v w + drop").is_ok());
+        assert!(g.eval("v w + drop").is_ok());
+    }
+    assert!(f.eval("v w").is_ok());
+    assert_eq!(vec![2, 1], f.stack());
+}
+
+#[test]
+fn native_code_keeps_the_meanings_words_had() {
+    let mut f = Forth::new();
+    assert!(f.eval(": a 1 ; : b a a + ;").is_ok());
+    for _ in 0..CALLS {
+        assert!(f.eval("b").is_ok());
+        assert_eq!(vec![2], f.drain_stack());
+    }
+    assert!(f.eval(": a 5 ; : + * ; b a").is_ok());
+    assert_eq!(vec![2, 5], f.stack());
+}
+
+#[test]
+fn hot_words_divide_both_ways() {
+    for division in [Division::Symmetric, Division::Floored] {
+        let mut native = Forth::builder().division(division).build();
+        let mut interpreted = Forth::builder()
+            .division(division)
+            .fold_constants(false)
+            .build();
+        let source = ": w / ; : m mod ; : dm /mod ; : sm sm/rem ; : fm fm/mod ;";
+        assert!(native.eval(source).is_ok());
+        assert!(interpreted.eval(source).is_ok());
+        for i in 0..CALLS as Value {
+            let input = format!(
+                "{} {} w {0} {1} m {0} {1} dm {0} {1} sm {0} {1} fm",
+                50 - i,
+                i % 7 - 3
+            );
+            let expected = interpreted.eval(&input).map(|_| interpreted.drain_stack());
+            assert_eq!(
+                expected,
+                native.eval(&input).map(|_| native.drain_stack()),
+                "call {i}"
+            );
+            native.drain_stack();
+            interpreted.drain_stack();
+        }
+    }
+}
+
+#[test]
+fn hot_words_wrap_like_the_interpreter() {
+    let mut f = Forth::new();
+    assert!(f.eval(": w w+ ; : d w- ; : m w* ;").is_ok());
+    for i in 0..CALLS as Value {
+        let b = Value::MAX - i;
+        assert!(f
+            .eval(&format!("{b} {i} w {0} {i} d -{b} {i} m", -b))
+            .is_ok());
+        let expected = vec![
+            b.wrapping_add(i),
+            (-b).wrapping_sub(i),
+            (-b).wrapping_mul(i),
+        ];
+        assert_eq!(expected, f.drain_stack(), "call {i}");
+    }
+}
@@ -0,0 +1,241 @@
+#![cfg(feature = "jupyter")]
+
+use std::net::TcpListener;
+use std::process::{Child, Command};
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use zeromq::{DealerSocket, Socket, SocketRecv, SocketSend, SubSocket, ZmqMessage};
+
+const KEY: &str = "secret";
+
+fn sign(parts: &[String]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(KEY.as_bytes()).unwrap();
+    parts.iter().for_each(|part| mac.update(part.as_bytes()));
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+struct Kernel {
+    child: Child,
+    shell: DealerSocket,
+    iopub: SubSocket,
+    sent: u64,
+}
+
+impl Drop for Kernel {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
+
+impl Kernel {
+    async fn start(name: &str) -> Kernel {
+        let ports: Vec<u16> = (0..5).map(|_| free_port()).collect();
+        let connection = json!({
+            "transport": "tcp",
+            "ip": "127.0.0.1",
+            "shell_port": ports[0],
+            "iopub_port": ports[1],
+            "stdin_port": ports[2],
+            "control_port": ports[3],
+            "hb_port": ports[4],
+            "key": KEY,
+            "signature_scheme": "hmac-sha256",
+        });
+        let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+        std::fs::write(&path, connection.to_string()).unwrap();
+        let child = Command::new(env!("CARGO_BIN_EXE_forth-jupyter"))
+            .arg(&path)
+            .spawn()
+            .unwrap();
+        let mut shell = DealerSocket::new();
+        let mut iopub = SubSocket::new();
+        for _ in 0..50 {
+            if shell
+                .connect(&format!("tcp://127.0.0.1:{}", ports[0]))
+                .await
+                .is_ok()
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        iopub
+            .connect(&format!("tcp://127.0.0.1:{}", ports[1]))
+            .await
+            .unwrap();
+        iopub.subscribe("").await.unwrap();
+        let mut kernel = Kernel {
+            child,
+            shell,
+            iopub,
+            sent: 0,
+        };
+        // A subscription takes a moment to reach the kernel, and anything
+        // published before then is lost.
+        loop {
+            kernel.request("kernel_info_request", json!({})).await;
+            let published = tokio::time::timeout(Duration::from_millis(100), kernel.iopub.recv());
+            if published.await.is_ok() {
+                break;
+            }
+        }
+        kernel.drain().await;
+        kernel
+    }
+
+    /// Sends a request on the shell socket and returns its reply.
+    async fn request(&mut self, msg_type: &str, content: Value) -> Value {
+        self.sent += 1;
+        let header = json!({
+            "msg_id": self.sent.to_string(),
+            "session": "test",
+            "username": "test",
+            "msg_type": msg_type,
+            "version": "5.3",
+        });
+        let parts = [
+            header.to_string(),
+            "{}".to_string(),
+            "{}".to_string(),
+            content.to_string(),
+        ];
+        let mut message = ZmqMessage::from(b"<IDS|MSG>".to_vec());
+        message.push_back(sign(&parts).into_bytes().into());
+        for part in parts {
+            message.push_back(part.into_bytes().into());
+        }
+        self.shell.send(message).await.unwrap();
+        let reply = decode(self.shell.recv().await.unwrap());
+        assert_eq!(self.sent.to_string(), reply["parent_header"]["msg_id"]);
+        reply
+    }
+
+    /// Messages published for the last request, up to the kernel going idle
+    /// after it.
+    async fn drain(&mut self) -> Vec<Value> {
+        let (id, mut published) = (self.sent.to_string(), Vec::new());
+        loop {
+            let message = decode(self.iopub.recv().await.unwrap());
+            if message["parent_header"]["msg_id"] != id.as_str() {
+                continue;
+            }
+            let idle = message["content"]["execution_state"] == "idle";
+            published.push(message);
+            if idle {
+                return published;
+            }
+        }
+    }
+
+    async fn execute(&mut self, code: &str) -> (Value, Vec<Value>) {
+        let reply = self
+            .request("execute_request", json!({ "code": code, "silent": false }))
+            .await;
+        (reply, self.drain().await)
+    }
+}
+
+fn decode(message: ZmqMessage) -> Value {
+    let frames: Vec<Vec<u8>> = message.into_vec().into_iter().map(|f| f.to_vec()).collect();
+    let delimiter = frames.iter().position(|f| f == b"<IDS|MSG>").unwrap();
+    let parts: Vec<String> = frames[delimiter + 1..delimiter + 6]
+        .iter()
+        .map(|f| String::from_utf8(f.clone()).unwrap())
+        .collect();
+    assert_eq!(parts[0], sign(&parts[1..]));
+    let json = |part: &String| serde_json::from_str::<Value>(part).unwrap();
+    json!({
+        "header": json(&parts[1]),
+        "parent_header": json(&parts[2]),
+        "content": json(&parts[4]),
+    })
+}
+
+fn published<'a>(messages: &'a [Value], msg_type: &str) -> Option<&'a Value> {
+    messages
+        .iter()
+        .find(|message| message["header"]["msg_type"] == msg_type)
+        .map(|message| &message["content"])
+}
+
+#[tokio::test]
+async fn cells_share_definitions_and_show_the_stack() {
+    let mut kernel = Kernel::start("cells.json").await;
+    let (reply, _) = kernel.execute(": sq dup * ;").await;
+    assert_eq!("ok", reply["content"]["status"]);
+    let (reply, messages) = kernel.execute("3 sq 4").await;
+    assert_eq!("ok", reply["content"]["status"]);
+    assert_eq!(2, reply["content"]["execution_count"]);
+    let result = published(&messages, "execute_result").unwrap();
+    assert_eq!("<2> 9 4", result["data"]["text/plain"]);
+}
+
+#[tokio::test]
+async fn failing_cells_show_the_diagnostics() {
+    let mut kernel = Kernel::start("errors.json").await;
+    let (reply, messages) = kernel.execute("1 0 /").await;
+    assert_eq!("error", reply["content"]["status"]);
+    assert_eq!("DivisionByZero", reply["content"]["ename"]);
+    let error = published(&messages, "error").unwrap();
+    assert!(error["traceback"][0]
+        .as_str()
+        .unwrap()
+        .contains("error: division by zero in `1 0 /`"));
+    assert!(published(&messages, "execute_result").is_none());
+}
+
+#[tokio::test]
+async fn interpreter_output_is_streamed() {
+    let mut kernel = Kernel::start("stream.json").await;
+    let (_, messages) = kernel.execute(": sq dup * ; dis sq").await;
+    let stream = published(&messages, "stream").unwrap();
+    assert_eq!("stdout", stream["name"]);
+    assert!(stream["text"].as_str().unwrap().contains("sq"));
+}
+
+#[tokio::test]
+async fn completes_user_defined_words() {
+    let mut kernel = Kernel::start("complete.json").await;
+    kernel.execute(": square dup * ; : sum + ;").await;
+    let reply = kernel
+        .request(
+            "complete_request",
+            json!({ "code": "3 sq", "cursor_pos": 4 }),
+        )
+        .await;
+    kernel.drain().await;
+    assert_eq!(json!(["square"]), reply["content"]["matches"]);
+    assert_eq!(2, reply["content"]["cursor_start"]);
+    assert_eq!(4, reply["content"]["cursor_end"]);
+}
+
+#[test]
+fn install_writes_a_kernel_spec() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("jupyter");
+    let status = Command::new(env!("CARGO_BIN_EXE_forth-jupyter"))
+        .arg("install")
+        .env("JUPYTER_DATA_DIR", &dir)
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success());
+    let spec = std::fs::read_to_string(dir.join("kernels/forth/kernel.json")).unwrap();
+    let spec: Value = serde_json::from_str(&spec).unwrap();
+    assert_eq!("forth", spec["language"]);
+    assert_eq!("{connection_file}", spec["argv"][1]);
+}
@@ -0,0 +1,86 @@
+use forth::*;
+
+fn kinds(input: &str) -> Vec<(LintKind, &str)> {
+    check(input)
+        .into_iter()
+        .map(|lint| (lint.kind, &input[lint.span.start..lint.span.end]))
+        .collect()
+}
+
+#[test]
+fn clean_scripts_have_no_lints() {
+    let script = ": sq dup * ; variable v 3 sq v ! v @ : pos? dup if drop 1 else drop 0 then ;";
+    assert_eq!(Vec::<Lint>::new(), check(script));
+}
+
+#[test]
+fn stack_effects_are_not_words() {
+    assert_eq!(Vec::<Lint>::new(), check(": sq ( n -- n*n ) dup * ; 3 sq"));
+}
+
+#[test]
+fn unknown_words_are_reported_where_they_are_used() {
+    assert_eq!(
+        vec![
+            (LintKind::UnknownWord, "later"),
+            (LintKind::UnknownWord, "typo")
+        ],
+        kinds("later : later 1 ; : w typo later ;")
+    );
+}
+
+#[test]
+fn unbalanced_definitions_are_reported() {
+    assert_eq!(
+        vec![
+            (LintKind::UnmatchedSemicolon, ";"),
+            (LintKind::UnclosedDefinition, ":")
+        ],
+        kinds("1 ; : w 2")
+    );
+    assert_eq!(vec![(LintKind::MissingName, ":")], kinds(": ;"));
+}
+
+#[test]
+fn unbalanced_ifs_are_reported() {
+    assert_eq!(
+        vec![
+            (LintKind::UnmatchedControl, "then"),
+            (LintKind::UnclosedIf, "if")
+        ],
+        kinds(": a then ; : b if ;")
+    );
+    assert_eq!(
+        vec![(LintKind::ControlOutsideDefinition, "IF")],
+        kinds("1 IF")
+    );
+}
+
+#[test]
+fn builtins_that_underflow_are_reported() {
+    assert_eq!(vec![(LintKind::StackUnderflow, "+")], kinds("1 +"));
+    assert_eq!(
+        vec![(LintKind::StackUnderflow, "!")],
+        kinds("variable v v !")
+    );
+    assert_eq!(Vec::<Lint>::new(), check(": two 1 2 ; two +"));
+}
+
+#[test]
+fn interpreters_check_against_their_own_state() {
+    let mut f = Forth::new();
+    assert!(f.eval(": inc 1 + ; 5").is_ok());
+    assert_eq!(Vec::<Lint>::new(), f.check("inc +"));
+    let lints = f.check("+");
+    assert_eq!(1, lints.len());
+    assert_eq!(LintKind::StackUnderflow, lints[0].kind);
+}
+
+#[test]
+fn channel_words_have_known_effects() {
+    assert_eq!(Vec::<Lint>::new(), check("recv? send send recv drop"));
+    assert_eq!(
+        vec![(LintKind::StackUnderflow, "send")],
+        kinds("recv send send")
+    );
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "log")]
+
+use std::cell::RefCell;
+
+use forth::*;
+
+/// Keeps the records logged on each thread, so that tests running side by
+/// side don't see each other's.
+struct Capture;
+
+thread_local! {
+    static RECORDS: RefCell<Vec<(log::Level, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+impl log::Log for Capture {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target().starts_with("forth")
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            let entry = (record.level(), record.args().to_string());
+            RECORDS.with(|records| records.borrow_mut().push(entry));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// What evaluating `input` logged.
+fn logged(f: &mut Forth, input: &str) -> Vec<(log::Level, String)> {
+    static CAPTURE: Capture = Capture;
+    if log::set_logger(&CAPTURE).is_ok() {
+        log::set_max_level(log::LevelFilter::Trace);
+    }
+    RECORDS.with(|records| records.borrow_mut().clear());
+    let _ = f.eval(input);
+    RECORDS.with(|records| records.take())
+}
+
+fn record(level: log::Level, message: &str) -> (log::Level, String) {
+    (level, message.to_string())
+}
+
+#[test]
+fn commands_and_definitions_are_logged() {
+    let mut f = Forth::new();
+    assert_eq!(
+        vec![
+            record(log::Level::Trace, "evaluating `: sq dup * ;`"),
+            record(log::Level::Debug, "defined `sq`"),
+            record(
+                log::Level::Trace,
+                "evaluating `variable v 3 sq v ! marker m`"
+            ),
+            record(log::Level::Debug, "defined variable `v`"),
+            record(log::Level::Debug, "defined marker `m`"),
+        ],
+        logged(&mut f, ": sq dup * ; variable v 3 sq v ! marker m")
+    );
+}
+
+#[test]
+fn errors_are_logged_with_their_command() {
+    let mut f = Forth::new();
+    assert_eq!(
+        vec![
+            record(log::Level::Trace, "evaluating `1 0 / 2`"),
+            record(log::Level::Debug, "`1 0 / 2` failed: division by zero"),
+        ],
+        logged(&mut f, "1 0 / 2 : never 1 ;")
+    );
+    let records = logged(&mut f, ": broken 1 +");
+    assert_eq!(
+        Some(&log::Level::Debug),
+        records.last().map(|(level, _)| level)
+    );
+    assert!(records
+        .last()
+        .unwrap()
+        .1
+        .starts_with("`: broken 1 +` failed"));
+}
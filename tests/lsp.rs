@@ -0,0 +1,163 @@
+#![cfg(feature = "lsp")]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use serde_json::{json, Value};
+
+struct Server {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Server {
+    fn start() -> Server {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_forth-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut server = Server {
+            child,
+            stdin,
+            stdout,
+        };
+        server.request(1, "initialize", json!({ "capabilities": {} }));
+        server.notify("initialized", json!({}));
+        server
+    }
+
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            self.stdout.read_line(&mut header).unwrap();
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(value) = header.strip_prefix("Content-Length: ") {
+                length = value.parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn request(&mut self, id: u64, method: &str, params: Value) -> Value {
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        loop {
+            let message = self.receive();
+            if message["id"] == id {
+                return message["result"].clone();
+            }
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    /// Opens `text` and returns the diagnostics published for it.
+    fn open(&mut self, text: &str) -> Value {
+        self.notify(
+            "textDocument/didOpen",
+            json!({ "textDocument": {
+                "uri": "file:///test.fs", "languageId": "forth", "version": 1, "text": text
+            }}),
+        );
+        self.receive()["params"]["diagnostics"].clone()
+    }
+
+    fn at(&mut self, id: u64, method: &str, line: u32, character: u32) -> Value {
+        self.request(
+            id,
+            method,
+            json!({
+                "textDocument": { "uri": "file:///test.fs" },
+                "position": { "line": line, "character": character }
+            }),
+        )
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.request(99, "shutdown", Value::Null);
+        self.notify("exit", Value::Null);
+        self.child.wait().unwrap();
+    }
+}
+
+const DOCUMENT: &str = ": sq ( n -- n*n ) dup * ;\n3 sq nope\n";
+
+#[test]
+fn publishes_the_first_error() {
+    let mut server = Server::start();
+    let diagnostics = server.open(DOCUMENT);
+    assert_eq!(1, diagnostics.as_array().unwrap().len());
+    assert_eq!("unknown word", diagnostics[0]["message"]);
+    assert_eq!(
+        json!({ "start": { "line": 1, "character": 5 }, "end": { "line": 1, "character": 9 } }),
+        diagnostics[0]["range"]
+    );
+}
+
+#[test]
+fn hover_shows_the_stack_effect_comment() {
+    let mut server = Server::start();
+    server.open(DOCUMENT);
+    let hover = server.at(2, "textDocument/hover", 1, 3);
+    assert_eq!("```forth\nsq ( n -- n*n )\n```", hover["contents"]["value"]);
+    let hover = server.at(3, "textDocument/hover", 0, 19);
+    assert_eq!(
+        "```forth\ndup ( x -- x x )\n```",
+        hover["contents"]["value"]
+    );
+}
+
+#[test]
+fn goes_to_the_definition_in_effect() {
+    let mut server = Server::start();
+    server.open(DOCUMENT);
+    let location = server.at(2, "textDocument/definition", 1, 2);
+    assert_eq!(
+        json!({ "start": { "line": 0, "character": 2 }, "end": { "line": 0, "character": 4 } }),
+        location["range"]
+    );
+}
+
+#[test]
+fn completes_from_the_dictionary() {
+    let mut server = Server::start();
+    server.open(DOCUMENT);
+    let items = server.at(2, "textDocument/completion", 1, 0);
+    let labels: Vec<&str> = items
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect();
+    assert!(labels.contains(&"sq"));
+    assert!(labels.contains(&"swap"));
+}
+
+#[test]
+fn runaway_loops_are_cut_short() {
+    let mut server = Server::start();
+    let diagnostics = server.open(": spin recurse ; spin");
+    assert_eq!(
+        "stopped evaluating after 1000000 words",
+        diagnostics[0]["message"]
+    );
+}
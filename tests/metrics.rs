@@ -0,0 +1,41 @@
+use forth::{Forth, Metrics};
+
+#[test]
+fn fresh_interpreter_has_no_metrics() {
+    assert_eq!(Metrics::default(), Forth::new().metrics());
+}
+
+#[test]
+fn metrics_count_execution() {
+    let mut f = Forth::builder().superinstructions(false).build();
+    assert!(f.eval(": sq dup * ; 1 2 3 sq + +").is_ok());
+    assert_eq!(
+        Metrics {
+            words_executed: 5,
+            max_stack_depth: 4,
+            definitions_created: 1,
+            data_space_bytes: 0,
+        },
+        f.metrics()
+    );
+}
+
+#[test]
+fn superinstructions_count_as_one_word() {
+    let mut f = Forth::new();
+    assert!(f.eval(": sq dup * ; 1 2 3 sq + +").is_ok());
+    assert_eq!(4, f.metrics().words_executed);
+    assert_eq!(3, f.metrics().max_stack_depth);
+}
+
+#[test]
+fn metrics_accumulate_until_reset() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 2 drop").is_ok());
+    assert!(f.eval("dup drop").is_ok());
+    assert_eq!(3, f.metrics().words_executed);
+    f.reset_metrics();
+    assert!(f.eval("drop").is_ok());
+    assert_eq!(1, f.metrics().words_executed);
+    assert_eq!(0, f.metrics().max_stack_depth);
+}
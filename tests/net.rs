@@ -0,0 +1,140 @@
+#![cfg(feature = "net")]
+
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+
+use forth::*;
+
+/// A transport that answers every connection with `reply` and records what
+/// was sent, with the host and port connected to.
+#[derive(Clone, Default)]
+struct Mock {
+    reply: &'static [u8],
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+struct MockConnection {
+    reply: &'static [u8],
+    sent: Vec<u8>,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl NetProvider for Mock {
+    fn connect(&self, host: &str, port: u16) -> io::Result<Box<dyn Connection>> {
+        if host == "unreachable" {
+            return Err(io::Error::from(io::ErrorKind::ConnectionRefused));
+        }
+        self.log.lock().unwrap().push(format!("{host}:{port}"));
+        Ok(Box::new(MockConnection {
+            reply: self.reply,
+            sent: Vec::new(),
+            log: Arc::clone(&self.log),
+        }))
+    }
+}
+
+impl Read for MockConnection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reply.read(buf)
+    }
+}
+
+impl Write for MockConnection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.sent.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let sent = String::from_utf8_lossy(&std::mem::take(&mut self.sent)).into_owned();
+        self.log.lock().unwrap().push(sent);
+        Ok(())
+    }
+}
+
+/// An interpreter connecting through `mock`, with a buffer of `cells`
+/// cells from address 0.
+fn forth(mock: &Mock, cells: usize) -> Forth {
+    let mut f = Forth::builder().net_provider(mock.clone()).build();
+    for i in 0..cells {
+        assert!(f.eval(&format!("variable b{i}")).is_ok());
+    }
+    f
+}
+
+#[test]
+fn sockets_send_and_receive_through_the_provider() {
+    let mock = Mock {
+        reply: b"pong",
+        ..Mock::default()
+    };
+    let mut f = forth(&mock, 4);
+    assert!(f.eval("80 open-socket Example.com").is_ok());
+    assert_eq!(vec![1, 0], f.drain_stack());
+    assert!(f.eval("'h' b0 ! 'i' b1 ! b0 2 1 write-socket").is_ok());
+    assert!(f.eval("b0 4 1 read-socket 1 close-socket").is_ok());
+    assert_eq!(vec![0, 4, 0, 0], f.drain_stack());
+    assert!(f.eval("b0 @ b3 @").is_ok());
+    assert_eq!(vec![112, 103], f.stack());
+    assert_eq!(vec!["Example.com:80", "hi"], *mock.log.lock().unwrap());
+}
+
+#[test]
+fn failures_leave_an_io_result_code() {
+    let mut f = forth(&Mock::default(), 1);
+    for words in [
+        "80 open-socket unreachable swap drop",
+        "70000 open-socket host swap drop",
+        "1 close-socket",
+        "b0 1 1 read-socket swap drop",
+        "b0 1 1 write-socket",
+    ] {
+        assert!(f.eval(words).is_ok(), "{words}");
+        assert_ne!(vec![0], f.drain_stack(), "{words}");
+    }
+    assert_eq!(Err(Error::InvalidWord), f.eval("80 open-socket"));
+    assert!(f.eval("80 open-socket host drop").is_ok());
+    assert_eq!(Err(Error::InvalidAddress), f.eval("b0 1 + 1 1 read-socket"));
+}
+
+#[test]
+fn socket_words_are_listed_and_saved_like_builtins() {
+    let mock = Mock {
+        reply: b"!",
+        ..Mock::default()
+    };
+    let mut f = forth(&mock, 1);
+    assert!(f.eval(": probe open-socket drop ;").is_ok());
+    assert_eq!(
+        ": probe\n   0  net            open-socket\n   1  primitive      drop\n;\n",
+        f.disassemble("probe").unwrap()
+    );
+    let image = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("probe.img");
+    f.save_image(&image).unwrap();
+    let mut f = Forth::builder().net_provider(mock.clone()).build();
+    f.load_image(&image).unwrap();
+    assert!(f.eval("25 probe mail").is_ok());
+    assert_eq!(vec![1], f.stack());
+    assert_eq!(vec!["mail:25"], *mock.log.lock().unwrap());
+}
+
+#[test]
+fn tcp_is_the_default_provider() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut byte = [0];
+        stream.read_exact(&mut byte).unwrap();
+        stream.write_all(&[byte[0] + 1]).unwrap();
+    });
+    let mut f = Forth::new();
+    assert!(f.eval("variable b0 41 b0 !").is_ok());
+    assert!(f
+        .eval(&format!(
+            "{port} open-socket 127.0.0.1 drop b0 1 1 write-socket"
+        ))
+        .is_ok());
+    assert!(f.eval("b0 1 1 read-socket 1 close-socket b0 @").is_ok());
+    server.join().unwrap();
+    assert_eq!(vec![1, 0, 1, 0, 0, 42], f.stack());
+}
@@ -0,0 +1,139 @@
+use forth::*;
+
+fn value(literal: &str) -> std::result::Result<Value, Error> {
+    let mut f = Forth::new();
+    f.eval(literal)?;
+    Ok(f.stack()[0])
+}
+
+#[test]
+fn decimal_numbers_may_be_signed_and_separated() {
+    assert_eq!(Ok(5), value("+5"));
+    assert_eq!(Ok(-5), value("-5"));
+    assert_eq!(Ok(1_000_000), value("1_000_000"));
+    assert_eq!(Ok(Value::MIN), value("-2_147_483_648"));
+}
+
+#[test]
+fn hexadecimal_and_binary_numbers_have_prefixes() {
+    assert_eq!(Ok(255), value("$ff"));
+    assert_eq!(Ok(255), value("$FF"));
+    assert_eq!(Ok(255), value("0xff"));
+    assert_eq!(Ok(-255), value("-0Xff"));
+    assert_eq!(Ok(10), value("%1010"));
+    assert_eq!(Ok(-10), value("-%1010"));
+    assert_eq!(Ok(0xdead_beef_u32 as Value), value("$dead_beef"));
+}
+
+#[test]
+fn hexadecimal_and_binary_numbers_may_use_every_bit() {
+    assert_eq!(Ok(-1), value("$ffffffff"));
+    assert_eq!(
+        Ok(Value::MIN),
+        value("%1000_0000_0000_0000_0000_0000_0000_0000")
+    );
+    assert_eq!(Err(Error::UnknownWord), value("$1_0000_0000"));
+    assert_eq!(Err(Error::UnknownWord), value("2147483648"));
+}
+
+#[test]
+fn base_holds_the_radix_of_plain_digits() {
+    assert_eq!(Ok(10), value("base @"));
+    assert_eq!(Ok(255), value("hex ff decimal"));
+    assert_eq!(Ok(16), value("16 base ! 10 decimal"));
+    assert_eq!(Ok(5), value("2 base ! 101"));
+    assert_eq!(Ok(35), value("36 base ! z"));
+    assert_eq!(Ok(16), value("hex base @ decimal"));
+}
+
+#[test]
+fn prefixes_override_the_base() {
+    let mut f = Forth::new();
+    assert!(f.eval("hex #10 %11 $10 0x10 10").is_ok());
+    assert_eq!([10, 3, 16, 16, 16], f.stack());
+}
+
+#[test]
+fn words_are_found_before_digits() {
+    let mut f = Forth::new();
+    assert!(f.eval(": add + ; : c 1 ; hex 1 2 add c ab").is_ok());
+    assert_eq!([3, 1, 0xab], f.stack());
+    assert_eq!(Err(Error::UnknownWord), f.eval("fg"));
+}
+
+#[test]
+fn definitions_are_read_in_the_base_they_are_made_in() {
+    let mut f = Forth::new();
+    assert!(f.eval("hex : x 10 ; decimal : y 10 ; x y").is_ok());
+    assert_eq!([16, 10], f.stack());
+}
+
+#[test]
+fn an_invalid_base_reads_decimal() {
+    for base in ["0", "1", "37", "-16"] {
+        let mut f = Forth::new();
+        assert!(f.eval(&format!("{base} base !")).is_ok());
+        assert!(f.eval("10").is_ok(), "{base}");
+        assert_eq!([10], f.stack(), "{base}");
+    }
+}
+
+#[test]
+fn resetting_data_space_restores_decimal() {
+    let mut f = Forth::new();
+    assert!(f.eval("hex").is_ok());
+    f.reset(ResetOptions {
+        data_space: true,
+        ..ResetOptions::default()
+    });
+    assert!(f.eval("10").is_ok());
+    assert_eq!([10], f.stack());
+}
+
+#[test]
+fn characters_are_their_code_points() {
+    assert_eq!(Ok(97), value("'a'"));
+    assert_eq!(Ok(0x00e9), value("'é'"));
+    assert_eq!(Ok(39), value("'''"));
+    assert_eq!(Err(Error::UnknownWord), value("'ab'"));
+}
+
+#[test]
+fn malformed_numbers_are_words() {
+    for word in [
+        "+x", "$", "0x", "%", "1_", "_1", "1__0", "$fg", "%102", "+-1", "1+",
+    ] {
+        assert_eq!(Err(Error::UnknownWord), value(word), "{word}");
+    }
+    let mut f = Forth::new();
+    assert!(f.eval(": 1+ 1 + ; : 0x 3 ; 1 1+ 0x").is_ok());
+    assert_eq!(vec![2, 3], f.stack());
+}
+
+#[test]
+fn numbers_cannot_be_redefined_whatever_their_syntax() {
+    let mut f = Forth::new();
+    for name in ["+5", "$ff", "%1", "'a'", "1_0"] {
+        assert_eq!(
+            Err(Error::InvalidWord),
+            f.eval(&format!(": {name} 1 ;")),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn numbers_work_in_definitions() {
+    let mut f = Forth::new();
+    assert!(f.eval(": w $ff %11 + 'a' ; w").is_ok());
+    assert_eq!(vec![258, 97], f.stack());
+}
+
+#[test]
+fn colons_and_semicolons_can_be_characters() {
+    assert_eq!(Ok(58), value("':'"));
+    assert_eq!(Ok(59), value("';'"));
+    let mut f = Forth::builder().dialect(Dialect::Strict).build();
+    assert!(f.eval(": delimiters ':' ';' ; delimiters").is_ok());
+    assert_eq!(vec![58, 59], f.stack());
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "observers")]
+
+use std::sync::{Arc, Mutex};
+
+use forth::Forth;
+
+fn recorder() -> (Arc<Mutex<Vec<String>>>, impl Fn(String) + Send + Clone) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    (events, move |event| sink.lock().unwrap().push(event))
+}
+
+#[test]
+fn push_and_pop_events() {
+    let (events, record) = recorder();
+    let mut f = Forth::new();
+    let push = record.clone();
+    f.on_push(move |v| push(format!("push {v}")));
+    f.on_pop(move |v| record(format!("pop {v}")));
+    assert!(f.eval("1 2 swap").is_ok());
+    assert_eq!(
+        vec!["push 1", "push 2", "pop 2", "pop 1", "push 2", "push 1"],
+        *events.lock().unwrap()
+    );
+}
+
+#[test]
+fn define_events() {
+    let (events, record) = recorder();
+    let mut f = Forth::new();
+    f.on_define(move |name| record(name.to_string()));
+    assert!(f.eval(": foo 1 ; : BAR foo ;").is_ok());
+    assert_eq!(vec!["foo", "bar"], *events.lock().unwrap());
+}
+
+#[test]
+fn mirror_stays_in_sync() {
+    let mirror = Arc::new(Mutex::new(Vec::new()));
+    let mut f = Forth::new();
+    let pushed = mirror.clone();
+    f.on_push(move |v| pushed.lock().unwrap().push(v));
+    let popped = mirror.clone();
+    f.on_pop(move |_| {
+        popped.lock().unwrap().pop();
+    });
+    assert!(f.eval(": sq dup * ; 1 2 3 sq over").is_ok());
+    f.replace_stack(vec![7, 8]);
+    assert!(f.eval("+ 3").is_ok());
+    assert_eq!(f.stack(), mirror.lock().unwrap().as_slice());
+    f.drain_stack();
+    assert!(mirror.lock().unwrap().is_empty());
+}
+
+#[test]
+fn word_events_carry_the_call_depth() {
+    let (events, record) = recorder();
+    let mut f = Forth::builder().inline_threshold(0).build();
+    f.on_word(move |forth, word, depth| record(format!("{depth} {word} {:?}", forth.stack())));
+    assert!(f.eval(": inc 1 + ; 2 inc").is_ok());
+    assert_eq!(
+        vec!["0 2 []", "0 inc [2]", "1 1 [2]", "1 + [2, 1]"],
+        *events.lock().unwrap()
+    );
+}
+
+#[test]
+fn clones_and_tasks_start_without_observers() {
+    let (events, record) = recorder();
+    let mut f = Forth::new();
+    f.on_push(move |v| record(format!("parent {v}")));
+    let mut clone = f.clone();
+    let (clone_events, record) = recorder();
+    clone.on_push(move |v| record(format!("clone {v}")));
+    assert!(f.eval("1").is_ok());
+    assert!(clone.eval("2").is_ok());
+    assert!(f.eval(": task 3 ; spawn task pause 4").is_ok());
+    assert_eq!(vec!["parent 1", "parent 4"], *events.lock().unwrap());
+    assert_eq!(vec!["clone 2"], *clone_events.lock().unwrap());
+}
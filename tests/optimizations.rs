@@ -0,0 +1,120 @@
+use forth::*;
+
+fn words_executed(f: &mut Forth, input: &str) -> u64 {
+    f.reset_metrics();
+    assert!(f.eval(input).is_ok());
+    f.metrics().words_executed
+}
+
+#[test]
+fn literal_arithmetic_is_folded() {
+    let mut f = Forth::new();
+    assert!(f.eval(": five 2 3 + ;").is_ok());
+    assert_eq!(1, words_executed(&mut f, "five"));
+    assert_eq!(vec![5], f.stack());
+}
+
+#[test]
+fn shuffles_of_literals_are_folded() {
+    let mut f = Forth::new();
+    assert!(f.eval(": w 1 dup drop 2 swap 3 over * - + ;").is_ok());
+    assert_eq!(1, words_executed(&mut f, "w"));
+    assert_eq!(vec![0], f.stack());
+}
+
+#[test]
+fn folding_keeps_runtime_errors() {
+    // The peephole stage would take out the `dup drop`.
+    let mut f = Forth::builder().peephole(false).build();
+    assert!(f.eval(": bad 1 0 / ; : shuffle dup drop ;").is_ok());
+    assert_eq!(Err(Error::StackUnderflow), f.eval("shuffle"));
+    assert_eq!(Err(Error::DivisionByZero), f.eval("bad"));
+}
+
+#[test]
+fn folding_can_be_disabled() {
+    let mut f = Forth::builder().fold_constants(false).build();
+    assert!(!f.optimizations().fold_constants);
+    assert!(f.eval(": five 2 3 + ;").is_ok());
+    assert_eq!(2, words_executed(&mut f, "five"));
+    assert_eq!(vec![5], f.stack());
+}
+
+#[test]
+fn short_words_are_inlined_and_folded_into_callers() {
+    let mut f = Forth::new();
+    assert!(f.eval(": two 2 ; : four two two + ;").is_ok());
+    assert_eq!(1, words_executed(&mut f, "four"));
+    assert_eq!(vec![4], f.stack());
+}
+
+#[test]
+fn words_over_the_threshold_are_called() {
+    let mut f = Forth::builder()
+        .inline_threshold(2)
+        .superinstructions(false)
+        .build();
+    assert!(f.eval(": sq dup * ; : quad sq sq ; : w quad ;").is_ok());
+    assert_eq!(6, words_executed(&mut f, "2 w"));
+    assert_eq!(vec![16], f.stack());
+}
+
+#[test]
+fn inlining_keeps_early_binding() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": one 1 ; : two one one + ; : one 10 ; two one")
+        .is_ok());
+    assert_eq!(vec![2, 10], f.stack());
+}
+
+#[test]
+fn inlining_can_be_disabled() {
+    let mut f = Forth::builder().inline_threshold(0).build();
+    assert!(f.eval(": nop ; : w nop nop ;").is_ok());
+    assert_eq!(3, words_executed(&mut f, "w"));
+}
+
+#[test]
+fn words_with_branches_are_inlined_without_folding_across_them() {
+    let mut f = Forth::new();
+    assert!(f.eval(": pick if 1 else 2 then ; : w pick 5 + ;").is_ok());
+    assert!(f.eval("1 w 0 w").is_ok());
+    assert_eq!(vec![6, 7], f.stack());
+}
+
+#[test]
+fn tail_recursive_words_inline_as_calls() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": down dup if 1 - recurse then ; : w down 7 ;")
+        .is_ok());
+    assert!(f.eval("3 w").is_ok());
+    assert_eq!(vec![0, 7], f.stack());
+}
+
+#[test]
+fn common_pairs_run_as_one_op() {
+    let mut f = Forth::new();
+    assert!(f.eval(": w dup * swap - over + ;").is_ok());
+    assert_eq!(4, words_executed(&mut f, "5 2 3 w"));
+    assert_eq!(vec![5, 12], f.stack());
+}
+
+#[test]
+fn fused_ops_fail_like_the_pairs_they_replace() {
+    let mut f = Forth::new();
+    assert!(f.eval(": sq dup * ; : sub swap - ; : add over + ;").is_ok());
+    assert_eq!(Err(Error::StackUnderflow), f.eval("sq"));
+    assert_eq!(Err(Error::StackUnderflow), f.eval("1 sub"));
+    assert_eq!(Err(Error::StackUnderflow), f.eval("1 add"));
+    assert_eq!(Vec::<Value>::new(), f.stack());
+}
+
+#[test]
+fn superinstructions_can_be_disabled() {
+    let mut f = Forth::builder().superinstructions(false).build();
+    assert!(f.eval(": w dup * ;").is_ok());
+    assert_eq!(3, words_executed(&mut f, "3 w"));
+    assert_eq!(vec![9], f.stack());
+}
@@ -0,0 +1,135 @@
+use forth::*;
+
+struct Identity;
+
+impl Pass for Identity {
+    fn run(&self, _: &mut IrWord) {}
+}
+
+/// Strength reduction: `2 *` to `dup +`.
+struct Double;
+
+impl Pass for Double {
+    fn run(&self, word: &mut IrWord) {
+        for block in &mut word.blocks {
+            for i in (1..block.ops.len()).rev() {
+                let multiply = matches!(&block.ops[i], IrOp::Builtin { name, .. } if name == "*");
+                if multiply && block.ops[i - 1] == IrOp::Push(2) {
+                    let builtin = |name: &str| IrOp::Builtin {
+                        name: name.to_string(),
+                        effect: None,
+                    };
+                    block.ops.splice(i - 1..=i, [builtin("dup"), builtin("+")]);
+                }
+            }
+        }
+    }
+}
+
+/// Appends `n` to every word.
+struct Append(Value);
+
+impl Pass for Append {
+    fn run(&self, word: &mut IrWord) {
+        word.blocks.last_mut().unwrap().ops.push(IrOp::Push(self.0));
+    }
+}
+
+const WORDS: &str = ": sq dup * ; : fact dup 1 - dup if recurse * else drop then ; \
+    : choose if if 1 else 2 then else 3 then ; : twice 2 * ;";
+
+#[test]
+fn passes_that_change_nothing_leave_the_code_as_it_was() {
+    let mut plain = Forth::new();
+    let mut passed = Forth::builder().add_pass(Identity).build();
+    plain.eval(WORDS).unwrap();
+    passed.eval(WORDS).unwrap();
+    for word in ["sq", "fact", "choose", "twice"] {
+        assert_eq!(plain.disassemble(word), passed.disassemble(word), "{word}");
+    }
+    passed
+        .eval("5 fact 1 1 choose 0 1 choose 0 choose 3 twice")
+        .unwrap();
+    assert_eq!([120, 1, 2, 3, 6], passed.stack());
+}
+
+#[test]
+fn passes_rewrite_definitions() {
+    let mut f = Forth::builder().add_pass(Double).build();
+    f.eval(": twice 2 * ; : more 3 * ;").unwrap();
+    f.eval("21 twice 2 more").unwrap();
+    assert_eq!([42, 6], f.stack());
+    let listing = f.disassemble("twice").unwrap();
+    assert!(listing.contains("primitive      dup"), "{listing}");
+    assert!(!listing.contains("push"), "{listing}");
+}
+
+#[test]
+fn passes_run_in_the_order_they_were_added() {
+    let mut f = Forth::builder()
+        .add_pass(Append(2))
+        .add_pass(Double)
+        .build();
+    f.eval(": w 5 ;").unwrap();
+    f.eval("w").unwrap();
+    assert_eq!([5, 2], f.stack());
+    let mut f = Forth::builder()
+        .add_pass(Append(2))
+        .add_pass(Append(3))
+        .build();
+    f.eval(": w ; w").unwrap();
+    assert_eq!([2, 3], f.stack());
+}
+
+#[test]
+fn passes_apply_to_clones_but_not_to_expressions() {
+    let f = Forth::builder().add_pass(Append(7)).build();
+    let mut clone = f.clone();
+    clone.eval("1 : w ; w").unwrap();
+    assert_eq!([1, 7], clone.stack());
+}
+
+#[test]
+fn passes_may_rewire_branches() {
+    struct Otherwise;
+    impl Pass for Otherwise {
+        fn run(&self, word: &mut IrWord) {
+            for block in &mut word.blocks {
+                if let Exit::If { otherwise, .. } = block.exit {
+                    block.ops.push(IrOp::Builtin {
+                        name: "drop".to_string(),
+                        effect: Some((1, 0)),
+                    });
+                    block.exit = Exit::Jump(otherwise);
+                }
+            }
+        }
+    }
+    let mut f = Forth::builder().add_pass(Otherwise).build();
+    f.eval(": pick if 1 else 2 then ; -1 pick").unwrap();
+    assert_eq!([2], f.stack());
+}
+
+#[test]
+fn code_that_cannot_be_laid_out_fails_the_definition() {
+    struct Back;
+    impl Pass for Back {
+        fn run(&self, word: &mut IrWord) {
+            word.blocks.last_mut().unwrap().exit = Exit::Jump(0);
+        }
+    }
+    struct Misnamed;
+    impl Pass for Misnamed {
+        fn run(&self, word: &mut IrWord) {
+            word.blocks[0].ops.push(IrOp::Builtin {
+                name: "no-such-builtin".to_string(),
+                effect: None,
+            });
+        }
+    }
+    let mut f = Forth::builder().add_pass(Back).build();
+    assert_eq!(Err(Error::InvalidWord), f.eval(": w 1 ;"));
+    assert_eq!(Err(Error::UnknownWord), f.eval("w"));
+    let mut f = Forth::builder().add_pass(Misnamed).build();
+    assert_eq!(Err(Error::InvalidWord), f.eval(": w 1 ;"));
+}
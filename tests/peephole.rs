@@ -0,0 +1,93 @@
+use forth::*;
+
+#[test]
+fn waste_is_taken_out_of_definitions() {
+    let mut f = Forth::builder().fold_constants(false).build();
+    f.eval(": w 1 2 swap swap dup drop 0 + 7 drop ;").unwrap();
+    assert_eq!(
+        ": w\n   0  push           1\n   1  push           2\n;\n",
+        f.disassemble("w").unwrap()
+    );
+    assert_eq!(
+        PeepholeStats {
+            swap_swap: 1,
+            dup_drop: 1,
+            add_zero: 1,
+            push_drop: 1,
+        },
+        f.peephole_stats()
+    );
+    assert_eq!(4, f.peephole_stats().total());
+}
+
+#[test]
+fn pairs_left_adjacent_are_taken_out_too() {
+    let mut f = Forth::builder().fold_constants(false).build();
+    f.eval(": w 1 2 swap dup dup drop drop swap ;").unwrap();
+    assert_eq!(
+        ": w\n   0  push           1\n   1  push           2\n;\n",
+        f.disassemble("w").unwrap()
+    );
+    assert_eq!(3, f.peephole_stats().total());
+}
+
+#[test]
+fn branches_still_land_where_they_did() {
+    let mut f = Forth::builder().fold_constants(false).build();
+    f.eval(": w if 1 dup drop else 2 3 swap swap then 4 dup drop ;")
+        .unwrap();
+    f.eval("1 w 0 w").unwrap();
+    assert_eq!([1, 4, 2, 3, 4], f.stack());
+    assert_eq!(
+        3,
+        f.peephole_stats().dup_drop + f.peephole_stats().swap_swap
+    );
+}
+
+#[test]
+fn pairs_split_by_a_branch_target_are_kept() {
+    let mut f = Forth::new();
+    // The `then` lands on the `drop`, which drops the flag's copy only when
+    // the `if` is skipped.
+    f.eval(": w dup if 5 swap then drop ;").unwrap();
+    f.eval("0 w 1 w").unwrap();
+    assert_eq!([5], f.stack());
+    assert_eq!(0, f.peephole_stats().total());
+}
+
+#[test]
+fn calls_left_last_become_tail_calls() {
+    let mut f = Forth::new();
+    f.eval(": down dup if 1 - recurse 7 drop then ;").unwrap();
+    f.eval("100000 down").unwrap();
+    assert_eq!([0], f.stack());
+}
+
+#[test]
+fn the_stage_can_be_turned_off() {
+    let mut f = Forth::builder()
+        .peephole(false)
+        .fold_constants(false)
+        .build();
+    f.eval(": w 1 2 swap swap ;").unwrap();
+    assert_eq!(PeepholeStats::default(), f.peephole_stats());
+    let mut f = Forth::builder().fold_constants(false).build();
+    f.eval(": w 1 2 swap swap ;").unwrap();
+    assert_eq!(1, f.peephole_stats().total());
+    f.reset_metrics();
+    assert_eq!(PeepholeStats::default(), f.peephole_stats());
+}
+
+#[test]
+fn pairs_on_a_stack_that_may_be_too_short_are_kept() {
+    let mut f = Forth::new();
+    f.eval(": f swap swap ; : g dup drop ; : h 0 + ; : k 1 swap swap ;")
+        .unwrap();
+    assert_eq!(0, f.peephole_stats().total());
+    for word in ["f", "g", "h", "k"] {
+        assert_eq!(Err(Error::StackUnderflow), f.eval(word), "{word}");
+    }
+    f.eval(": l dup 0 + over swap swap ; 5 l").unwrap();
+    assert_eq!([5, 5, 5], f.stack());
+    assert_eq!(2, f.peephole_stats().total());
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "peripherals")]
+
+use std::sync::{Arc, Mutex};
+
+use forth::*;
+
+/// Eight pins, and a bus 0 that answers each byte with its complement;
+/// what it was asked to do is logged.
+#[derive(Default)]
+struct Board {
+    pins: [bool; 8],
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Peripherals for Board {
+    fn read_pin(&mut self, pin: u32) -> std::result::Result<bool, Value> {
+        self.pins.get(pin as usize).copied().ok_or(22)
+    }
+
+    fn write_pin(&mut self, pin: u32, high: bool) -> std::result::Result<(), Value> {
+        *self.pins.get_mut(pin as usize).ok_or(22)? = high;
+        Ok(())
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.log.lock().unwrap().push(format!("delay {ms}"));
+    }
+
+    fn transfer(&mut self, bus: u32, bytes: &mut [u8]) -> std::result::Result<(), Value> {
+        if bus != 0 {
+            return Err(19);
+        }
+        self.log.lock().unwrap().push(format!("sent {bytes:?}"));
+        bytes.iter_mut().for_each(|byte| *byte = !*byte);
+        Ok(())
+    }
+}
+
+fn forth(log: &Arc<Mutex<Vec<String>>>) -> Forth {
+    let board = Board {
+        log: Arc::clone(log),
+        ..Board::default()
+    };
+    Forth::builder().peripherals(board).build()
+}
+
+#[test]
+fn pins_are_read_and_written() {
+    let log = Arc::default();
+    let mut f = forth(&log);
+    assert!(f.eval("3 pin@ -1 3 pin! 3 pin@ 0 3 pin! 3 pin@").is_ok());
+    assert_eq!(vec![0, 0, 0, -1, 0, 0, 0, 0], f.drain_stack());
+    assert!(f.eval("8 pin@ 1 8 pin!").is_ok());
+    assert_eq!(vec![0, 22, 22], f.stack());
+    assert_eq!(Err(Error::OutOfRange), f.eval("-1 pin@"));
+}
+
+#[test]
+fn buses_exchange_data_space_a_byte_a_cell() {
+    let log = Arc::default();
+    let mut f = forth(&log);
+    assert!(f.eval("variable b0 variable b1 $0f b0 ! $a5 b1 !").is_ok());
+    assert!(f.eval("b0 2 0 transfer b0 @ b1 @ b0 2 1 transfer").is_ok());
+    assert_eq!(vec![0, 0xf0, 0x5a, 19], f.drain_stack());
+    assert_eq!(Err(Error::InvalidAddress), f.eval("b1 2 0 transfer"));
+    assert!(f.eval("10 ms").is_ok());
+    assert_eq!(vec!["sent [15, 165]", "delay 10"], *log.lock().unwrap());
+}
+
+#[test]
+fn words_fail_without_peripherals() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval("variable b0 0 pin@ 1 0 pin! b0 1 0 transfer 5 ms")
+        .is_ok());
+    assert_eq!(vec![0, -1, -1, -1], f.stack());
+}
+
+#[test]
+fn peripheral_words_compile_and_lint_like_builtins() {
+    let log = Arc::default();
+    let mut f = forth(&log);
+    assert!(f
+        .eval(": blink dup -1 swap pin! drop 500 ms 0 swap pin! ;")
+        .is_ok());
+    assert_eq!(
+        ": blink\n   0  primitive      dup\n   1  push           -1\n   2  primitive      swap\n   3  peripheral     pin!\n   4  primitive      drop\n   5  push           500\n   6  peripheral     ms\n   7  push           0\n   8  primitive      swap\n   9  peripheral     pin!\n;\n",
+        f.disassemble("blink").unwrap()
+    );
+    assert!(f.eval("2 blink 2 pin@").is_ok());
+    assert_eq!(vec![0, 0, 0], f.stack());
+    assert_eq!(vec!["delay 500"], *log.lock().unwrap());
+    let kinds: Vec<_> = check("1 pin! 1 pin@")
+        .into_iter()
+        .map(|lint| lint.kind)
+        .collect();
+    assert_eq!(vec![LintKind::StackUnderflow], kinds);
+}
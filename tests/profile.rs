@@ -0,0 +1,49 @@
+use forth::{Forth, Profile};
+
+fn profiled() -> Forth {
+    let mut f = Forth::builder()
+        .inline_threshold(0)
+        .superinstructions(false)
+        .build();
+    f.set_profiling(true);
+    f
+}
+
+#[test]
+fn profile_counts_calls_and_primitives() {
+    let mut f = profiled();
+    assert!(f.eval(": sq dup * ; : quad sq sq ; 2 quad 3 sq +").is_ok());
+    let profile = f.profile();
+    assert_eq!(
+        vec![("sq".to_string(), 3), ("quad".to_string(), 1)],
+        profile.words
+    );
+    assert_eq!(
+        vec![
+            ("*".to_string(), 3),
+            ("dup".to_string(), 3),
+            ("+".to_string(), 1)
+        ],
+        profile.primitives
+    );
+}
+
+#[test]
+fn profile_counts_tail_calls() {
+    let mut f = profiled();
+    assert!(f
+        .eval(": countdown dup if 1 - recurse then ; 5 countdown")
+        .is_ok());
+    assert_eq!(vec![("countdown".to_string(), 6)], f.profile().words);
+}
+
+#[test]
+fn profiling_is_off_by_default_and_restarts_when_turned_on() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 2 +").is_ok());
+    assert_eq!(Profile::default(), f.profile());
+    f.set_profiling(true);
+    assert!(f.eval("dup").is_ok());
+    f.set_profiling(true);
+    assert_eq!(Profile::default(), f.profile());
+}
@@ -0,0 +1,47 @@
+use forth::{Error, Forth, Program, Token};
+
+#[test]
+fn run_parsed_program() {
+    let program = Program::parse(": sq dup * ; 3 sq").unwrap();
+    let mut f = Forth::new();
+    assert!(f.run(&program).is_ok());
+    assert!(f.run(&program).is_ok());
+    assert_eq!(vec![9, 9], f.stack());
+}
+
+#[test]
+fn parse_errors_are_reported_up_front() {
+    assert_eq!(Err(Error::InvalidWord), Program::parse("1 2 : foo"));
+}
+
+#[test]
+fn build_program_from_tokens() {
+    let program = Program::new()
+        .definition("Twice", [Token::word("DUP"), Token::word("+")])
+        .expression([21.into(), Token::word("twice")]);
+    let mut f = Forth::new();
+    assert!(f.run(&program).is_ok());
+    assert_eq!(vec![42], f.stack());
+}
+
+#[test]
+fn programmatic_definitions_cannot_name_numbers() {
+    let program = Program::new().definition("1", [2.into()]);
+    assert_eq!(Err(Error::InvalidWord), Forth::new().run(&program));
+}
+
+#[test]
+fn eval_tokens_in_a_loop() {
+    let mut f = Forth::new();
+    assert!(f.eval(": inc 1 + ;").is_ok());
+    let tokens = [Token::word("inc")];
+    f.replace_stack(vec![0]);
+    for _ in 0..10 {
+        assert!(f.eval_tokens(&tokens).is_ok());
+    }
+    assert_eq!(vec![10], f.stack());
+    assert_eq!(
+        Err(Error::UnknownWord),
+        f.eval_tokens(&[Token::Word("INC".to_string())])
+    );
+}
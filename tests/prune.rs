@@ -0,0 +1,90 @@
+use forth::*;
+
+const PRELUDE: &str = ": sq dup * ; : cube dup sq * ; : unused 1 2 + ; \
+    variable total : add total @ + total ! ;";
+
+fn image_path(name: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+#[test]
+fn roots_keep_the_words_they_call() {
+    let mut f = Forth::builder().inline_threshold(0).build();
+    f.eval(PRELUDE).unwrap();
+    let mut pruned = f.pruned(&["Cube"]).unwrap();
+    assert_eq!(vec!["cube", "sq", "total"], {
+        let mut words: Vec<&str> = pruned.user_words().collect();
+        words.sort_unstable();
+        words
+    });
+    assert_eq!(Err(Error::UnknownWord), pruned.eval("unused"));
+    pruned.eval("3 cube 2 sq").unwrap();
+    assert_eq!([27, 4], pruned.stack());
+    assert!(f.eval("unused").is_ok());
+}
+
+#[test]
+fn words_inlined_everywhere_are_dropped() {
+    let mut f = Forth::new();
+    f.eval(PRELUDE).unwrap();
+    let mut pruned = f.pruned(&["cube"]).unwrap();
+    assert_eq!(Err(Error::UnknownWord), pruned.eval("2 sq"));
+    pruned.eval("2 cube").unwrap();
+    assert_eq!([2, 8], pruned.stack());
+}
+
+#[test]
+fn variables_keep_their_values() {
+    let mut f = Forth::new();
+    f.eval(PRELUDE).unwrap();
+    f.eval("5 add").unwrap();
+    let mut pruned = f.pruned(&["add"]).unwrap();
+    pruned.eval("2 add total @").unwrap();
+    assert_eq!([7], pruned.stack());
+}
+
+#[test]
+fn words_shadowed_since_are_called_but_not_named() {
+    let mut f = Forth::builder().inline_threshold(0).build();
+    f.eval(": w 1 ; : v w ; : w 2 ;").unwrap();
+    let mut pruned = f.pruned(&["v"]).unwrap();
+    assert_eq!(Err(Error::UnknownWord), pruned.eval("w"));
+    pruned.eval("v").unwrap();
+    assert_eq!([1], pruned.stack());
+}
+
+#[test]
+fn pruned_images_are_smaller_and_load_as_saved() {
+    let mut f = Forth::new();
+    f.eval(PRELUDE).unwrap();
+    for n in 0..50 {
+        f.eval(&format!(": helper{n} {n} dup * 1 + ;")).unwrap();
+    }
+    let (full, small) = (image_path("full.img"), image_path("pruned.img"));
+    f.save_image(&full).unwrap();
+    f.pruned(&["cube"]).unwrap().save_image(&small).unwrap();
+    let size = |path| std::fs::metadata(path).unwrap().len();
+    assert!(size(&small) * 4 < size(&full));
+    let mut loaded = Forth::new();
+    loaded.load_image(&small).unwrap();
+    loaded.eval("2 cube").unwrap();
+    assert_eq!([8], loaded.stack());
+    assert_eq!(Err(Error::UnknownWord), loaded.eval("helper3"));
+}
+
+#[test]
+fn transpiling_a_pruned_copy_leaves_the_rest_out() {
+    let mut f = Forth::new();
+    f.eval(PRELUDE).unwrap();
+    let source = f.pruned(&["cube"]).unwrap().transpile();
+    assert!(source.contains("pub fn cube("), "{source}");
+    assert!(!source.contains("unused"), "{source}");
+}
+
+#[test]
+fn roots_must_be_user_definitions() {
+    let mut f = Forth::new();
+    f.eval(PRELUDE).unwrap();
+    assert!(matches!(f.pruned(&["nope"]), Err(Error::UnknownWord)));
+    assert!(matches!(f.pruned(&["dup"]), Err(Error::InvalidWord)));
+}
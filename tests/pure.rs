@@ -0,0 +1,63 @@
+use forth::{Error, Forth};
+
+#[test]
+fn stack_math_is_evaluated() {
+    let mut f = Forth::new();
+    assert_eq!(Ok(vec![14, 2]), f.eval_pure("2 3 4 * + dup 7 /"));
+    assert!(f.stack().is_empty());
+}
+
+#[test]
+fn definitions_are_rejected() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::Impure), f.eval_pure("1 : one 1 ;"));
+    assert!(f.stack().is_empty());
+    assert_eq!(Err(Error::UnknownWord), f.eval("one"));
+}
+
+#[test]
+fn defining_storing_and_io_words_are_rejected_before_anything_runs() {
+    let mut f = Forth::new();
+    f.eval("variable x").unwrap();
+    for input in [
+        "1 variable y",
+        "1 marker here",
+        "1 synonym twice dup",
+        "1 forget x",
+        "1 2 x !",
+        "1 utime",
+        "1 help dup",
+        "1 send",
+        "1 2 >s",
+        "0 0 evaluate",
+    ] {
+        assert_eq!(Err(Error::Impure), f.eval_pure(input), "{input}");
+        assert!(f.stack().is_empty(), "{input}");
+    }
+}
+
+#[test]
+fn user_defined_words_are_as_pure_as_what_they_call() {
+    let mut f = Forth::new();
+    f.eval(": cube dup dup * * ; : at-least-one dup if else drop 1 then ; variable total")
+        .unwrap();
+    f.eval(": tally dup total ! ; : tally-cube cube tally ;")
+        .unwrap();
+    assert_eq!(Ok(vec![27, 1]), f.eval_pure("3 cube 0 at-least-one"));
+    assert_eq!(Err(Error::Impure), f.eval_pure("2 tally-cube"));
+    assert_eq!([0], f.eval_pure("total @").unwrap().as_slice());
+}
+
+#[test]
+fn recursive_words_are_checked_once() {
+    let mut f = Forth::new();
+    f.eval(": countdown dup if 1 - recurse then ;").unwrap();
+    assert_eq!(Ok(vec![0]), f.eval_pure("5 countdown"));
+}
+
+#[test]
+fn errors_of_pure_expressions_come_through() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::DivisionByZero), f.eval_pure("1 0 /"));
+    assert_eq!(Err(Error::UnknownWord), f.eval_pure("1 nope"));
+}
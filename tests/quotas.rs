@@ -0,0 +1,55 @@
+use forth::{Error, Forth, Quotas};
+
+#[test]
+fn unlimited_by_default() {
+    let f = Forth::new();
+    assert_eq!(Quotas::default(), f.quotas());
+}
+
+#[test]
+fn definition_count_quota() {
+    let mut f = Forth::builder().max_definitions(2).build();
+    assert!(f.eval(": a 1 ; : b 2 ;").is_ok());
+    assert_eq!(Err(Error::QuotaExceeded), f.eval(": c 3 ;"));
+    assert_eq!(Err(Error::QuotaExceeded), f.eval(": a 4 ;"));
+    assert_eq!(Err(Error::UnknownWord), f.eval("c"));
+    assert!(f.eval("a b").is_ok());
+    assert_eq!(vec![1, 2], f.stack());
+}
+
+#[test]
+fn token_quota() {
+    let mut f = Forth::builder().max_tokens(5).build();
+    assert!(f.eval(": a 1 2 3 ;").is_ok());
+    assert_eq!(Err(Error::QuotaExceeded), f.eval(": b a a a ;"));
+    assert!(f.eval(": b a a ;").is_ok());
+    assert_eq!(Err(Error::QuotaExceeded), f.eval(": c 1 ;"));
+}
+
+#[test]
+fn rolled_back_definitions_free_quota() {
+    let mut f = Forth::builder().max_definitions(1).build();
+    assert!(f.eval_atomic(": a 1 ; foo").is_err());
+    assert!(f.eval(": b 2 ;").is_ok());
+}
+
+#[test]
+fn forgotten_definitions_free_quota() {
+    let mut f = Forth::builder().max_definitions(1).max_tokens(2).build();
+    assert!(f.eval(": a 1 2 ; forget a : b 3 4 ;").is_ok());
+    assert_eq!(Err(Error::QuotaExceeded), f.eval(": c 5 ;"));
+    assert!(f.eval("forget b marker m").is_ok());
+    assert!(f.eval("m : c 5 6 ; c").is_ok());
+    assert_eq!(vec![5, 6], f.stack());
+}
+
+#[test]
+fn values_on_auxiliary_stacks_count_as_data_space() {
+    let mut f = Forth::builder().max_data_space(8).build();
+    assert!(f.eval("stack s variable v 1 s >s").is_ok());
+    assert_eq!(Err(Error::QuotaExceeded), f.eval("2 s >s"));
+    assert_eq!(Err(Error::QuotaExceeded), f.push_aux("s", 2));
+    assert_eq!(Err(Error::QuotaExceeded), f.eval("variable w"));
+    assert!(f.eval("s s> drop 3 s >s").is_ok());
+    assert_eq!(Some(&[3][..]), f.aux_stack("s"));
+}
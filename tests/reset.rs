@@ -0,0 +1,65 @@
+use forth::{Forth, ResetOptions};
+
+fn forth() -> Forth {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": dup 10 ; variable x 42 x ! : work pause 1 send ; spawn work 1 2 3")
+        .is_ok());
+    f
+}
+
+#[test]
+fn clearing_the_stack_keeps_definitions() {
+    let mut f = forth();
+    f.reset(ResetOptions {
+        stack: true,
+        ..ResetOptions::default()
+    });
+    assert_eq!(Vec::<i32>::new(), f.stack());
+    assert!(f.eval("dup x @").is_ok());
+    assert_eq!(vec![10, 42], f.stack());
+    assert_eq!(1, f.tasks());
+}
+
+#[test]
+fn clearing_the_dictionary_brings_builtins_back() {
+    let mut f = forth();
+    f.reset(ResetOptions {
+        dictionary: true,
+        ..ResetOptions::default()
+    });
+    assert_eq!(vec![1, 2, 3], f.stack());
+    assert_eq!(None, f.get_var("x"));
+    assert!(f.eval("dup").is_ok());
+    assert_eq!(vec![1, 2, 3, 3], f.stack());
+}
+
+#[test]
+fn clearing_data_space_zeroes_variables() {
+    let mut f = forth();
+    f.reset(ResetOptions {
+        data_space: true,
+        ..ResetOptions::default()
+    });
+    assert_eq!(Some(0), f.get_var("x"));
+}
+
+#[test]
+fn clearing_the_return_stack_drops_tasks() {
+    let mut f = forth();
+    f.reset(ResetOptions {
+        return_stack: true,
+        ..ResetOptions::default()
+    });
+    assert_eq!(0, f.tasks());
+    assert_eq!(vec![1, 2, 3], f.stack());
+}
+
+#[test]
+fn the_default_clears_nothing() {
+    let mut f = forth();
+    f.reset(ResetOptions::default());
+    assert_eq!(vec![1, 2, 3], f.stack());
+    assert_eq!(Some(42), f.get_var("x"));
+    assert_eq!(1, f.tasks());
+}
@@ -0,0 +1,47 @@
+use forth::*;
+
+#[test]
+fn overflowing_arithmetic_is_an_error() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::Overflow), f.eval("2147483647 1 +"));
+    assert_eq!(Err(Error::Overflow), f.eval("-2147483648 1 -"));
+    assert_eq!(Err(Error::Overflow), f.eval("65536 65536 *"));
+    assert_eq!(Err(Error::Overflow), f.eval("-2147483648 -1 /"));
+}
+
+#[test]
+fn overflow_is_an_error_inside_definitions() {
+    let mut f = Forth::new();
+    assert!(f.eval(": sq dup * ; : inc 1 over + swap drop ;").is_ok());
+    assert_eq!(Err(Error::Overflow), f.eval("65536 sq"));
+    assert_eq!(Err(Error::Overflow), f.eval("2147483647 inc"));
+}
+
+#[test]
+fn constants_that_would_overflow_are_not_folded() {
+    let mut f = Forth::new();
+    assert!(f.eval(": big 2147483647 1 + ;").is_ok());
+    assert_eq!(Err(Error::Overflow), f.eval("big"));
+}
+
+#[test]
+fn arithmetic_at_the_limits_succeeds() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval("2147483646 1 + -2147483647 1 - 46340 46340 * -2147483648 1 /")
+        .is_ok());
+    assert_eq!(
+        vec![Value::MAX, Value::MIN, 2147395600, Value::MIN],
+        f.stack()
+    );
+}
+
+#[test]
+fn malformed_input_is_an_error_not_a_panic() {
+    for input in [
+        ":", ";", ": ;", ": :", "; ;", ": 1", "variable", "forget", "marker",
+    ] {
+        let mut f = Forth::new();
+        assert!(f.eval(input).is_err(), "{input:?}");
+    }
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::Buffer;
+use forth::*;
+
+#[test]
+fn stats_cover_the_most_recent_evaluation_only() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 2 3 4 + + +").is_ok());
+    assert_eq!(
+        RunStats {
+            primitives_executed: 3,
+            max_stack_depth: 4,
+            ..RunStats::default()
+        },
+        f.last_run_stats()
+    );
+    assert!(f.eval("dup").is_ok());
+    assert_eq!(
+        RunStats {
+            primitives_executed: 1,
+            max_stack_depth: 2,
+            ..RunStats::default()
+        },
+        f.last_run_stats()
+    );
+}
+
+#[test]
+fn calls_and_data_space_are_counted() {
+    let mut f = Forth::builder().inline_threshold(0).build();
+    assert!(f.eval("variable v : a 1 ; : b a a ; b").is_ok());
+    let stats = f.last_run_stats();
+    assert_eq!(2, stats.max_return_depth);
+    assert_eq!(0, stats.data_space_bytes_touched);
+    assert!(f.eval("5 v ! v @ v @").is_ok());
+    let stats = f.last_run_stats();
+    assert_eq!(
+        3 * std::mem::size_of::<Value>(),
+        stats.data_space_bytes_touched
+    );
+    assert_eq!(0, stats.max_return_depth);
+}
+
+#[test]
+fn output_is_counted_per_interpreter() {
+    let mut f = Forth::new();
+    f.set_output(Buffer::default());
+    assert!(f.eval(": sq dup * ; help sq").is_ok());
+    assert_eq!("sq\n".len(), f.last_run_stats().output_bytes);
+    let mut g = f.clone();
+    assert!(g.eval("help dup").is_ok());
+    assert_eq!("dup ( x -- x x )\n".len(), g.last_run_stats().output_bytes);
+    assert_eq!("sq\n".len(), f.last_run_stats().output_bytes);
+}
+
+#[test]
+fn failed_evaluations_report_what_they_used() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::DivisionByZero), f.eval("1 2 + 0 /"));
+    let stats = f.last_run_stats();
+    assert_eq!(2, stats.primitives_executed);
+    assert_eq!(2, stats.max_stack_depth);
+}
@@ -0,0 +1,67 @@
+use forth::{Error, Forth};
+
+fn sealed() -> Forth {
+    let mut f = Forth::new();
+    f.eval(": square dup * ; variable total marker clean")
+        .unwrap();
+    f.seal();
+    f
+}
+
+#[test]
+fn sealed_words_still_run() {
+    let mut f = sealed();
+    assert!(f.is_sealed());
+    f.eval("3 square total ! total @").unwrap();
+    assert_eq!([9], f.stack());
+}
+
+#[test]
+fn nothing_can_be_defined() {
+    let mut f = sealed();
+    for input in [
+        ": cube dup square * ;",
+        "variable count",
+        "marker again",
+        "stack aux",
+        "user mine",
+        "synonym sq square",
+    ] {
+        assert_eq!(Err(Error::Sealed), f.eval(input), "{input}");
+    }
+    assert_eq!(Err(Error::Sealed), f.define_constant("ten", 10));
+    assert_eq!(Err(Error::Sealed), f.alias("sq", "square"));
+}
+
+#[test]
+fn sealing_comes_before_compiling() {
+    let mut f = sealed();
+    assert_eq!(Err(Error::Sealed), f.eval(": broken nope ;"));
+}
+
+#[test]
+fn nothing_can_be_forgotten() {
+    let mut f = sealed();
+    assert_eq!(Err(Error::Sealed), f.eval("forget square"));
+    assert_eq!(Err(Error::Sealed), f.eval("clean"));
+    f.eval("2 square").unwrap();
+    assert_eq!([4], f.stack());
+}
+
+#[test]
+fn catch_sees_the_unsupported_operation_code() {
+    let mut f = Forth::new();
+    f.eval("marker clean : undo clean ; : try ['] undo catch ;")
+        .unwrap();
+    f.seal();
+    f.eval("try").unwrap();
+    assert_eq!([-21], f.stack());
+}
+
+#[test]
+fn diagnostics_explain_the_seal() {
+    let mut f = sealed();
+    let diagnostics = f.eval_diagnostics(": cube dup square * ;").unwrap_err();
+    assert_eq!(Error::Sealed, diagnostics.error);
+    assert!(diagnostics.notes[0].contains("sealed"));
+}
@@ -0,0 +1,40 @@
+use forth::{DictDiff, Forth};
+
+fn strings(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn lists_words_added_and_redefined() {
+    let mut f = Forth::new();
+    assert!(f.eval(": square dup * ; variable total").is_ok());
+    let before = f.snapshot();
+    assert!(f
+        .eval(": cube dup square * ; : square dup dup * * ; : dup over over drop ; variable count")
+        .is_ok());
+    let diff = before.diff(&f.snapshot());
+    assert_eq!(strings(&["cube", "count"]), diff.added);
+    assert_eq!(strings(&["square", "dup"]), diff.redefined);
+    assert!(diff.removed.is_empty());
+}
+
+#[test]
+fn lists_words_forgotten() {
+    let mut f = Forth::new();
+    assert!(f.eval(": a 1 ; : b 2 ; : c 3 ;").is_ok());
+    let before = f.snapshot();
+    assert!(f.eval("forget b").is_ok());
+    let diff = before.diff(&f.snapshot());
+    assert_eq!(strings(&["b", "c"]), diff.removed);
+    assert!(diff.added.is_empty() && diff.redefined.is_empty());
+}
+
+#[test]
+fn evaluating_without_defining_leaves_no_diff() {
+    let mut f = Forth::new();
+    let before = f.snapshot();
+    assert!(f.eval("1 2 + dup").is_ok());
+    let diff = before.diff(&f.snapshot());
+    assert!(diff.is_empty());
+    assert_eq!(DictDiff::default(), diff);
+}
@@ -0,0 +1,102 @@
+use forth::*;
+
+fn at(file: Option<&str>, line: usize, column: usize, word: &str) -> Option<SourceLocation> {
+    Some(SourceLocation {
+        file: file.map(str::to_string),
+        line,
+        column,
+        word: word.to_string(),
+    })
+}
+
+struct Identity;
+
+impl Pass for Identity {
+    fn run(&self, _: &mut IrWord) {}
+}
+
+#[test]
+fn each_op_maps_to_the_token_it_came_from() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 2 +\n: halve ( n -- n )\n  2 / ;").is_ok());
+    assert_eq!(
+        vec![at(None, 3, 3, "halve"), at(None, 3, 5, "halve")],
+        f.source_map("HALVE").unwrap()
+    );
+}
+
+#[test]
+fn ops_folded_or_fused_map_to_their_last_token() {
+    let mut f = Forth::new();
+    assert!(f.eval(": f 2 3 + ;\n: g swap - ;").is_ok());
+    assert_eq!(vec![at(None, 1, 9, "f")], f.source_map("f").unwrap());
+    assert_eq!(vec![at(None, 2, 10, "g")], f.source_map("g").unwrap());
+}
+
+#[test]
+fn sources_are_named_as_the_host_and_include_say() {
+    let mut f = Forth::new();
+    f.set_include_resolver(|name| (name == "lib.fs").then(|| "\n: two 2 ;".to_string()));
+    f.set_source_name(Some("main.fs"));
+    assert!(f.eval(": one 1 ;\ninclude lib.fs\n: three 3 ;").is_ok());
+    assert_eq!(
+        vec![at(Some("main.fs"), 1, 7, "one")],
+        f.source_map("one").unwrap()
+    );
+    assert_eq!(
+        vec![at(Some("lib.fs"), 2, 7, "two")],
+        f.source_map("two").unwrap()
+    );
+    assert_eq!(
+        vec![at(Some("main.fs"), 3, 9, "three")],
+        f.source_map("three").unwrap()
+    );
+}
+
+#[test]
+fn words_not_compiled_from_text_map_to_nothing() {
+    let mut f = Forth::builder().add_pass(Identity).build();
+    assert!(f.eval(": sq dup * ;").is_ok());
+    assert_eq!(vec![None], f.source_map("sq").unwrap());
+    let mut f = Forth::new();
+    f.define_constant("answer", 42).unwrap();
+    assert_eq!(vec![None], f.source_map("answer").unwrap());
+    assert_eq!(Err(Error::InvalidWord), f.source_map("dup"));
+    assert_eq!(Err(Error::UnknownWord), f.source_map("nope"));
+}
+
+#[test]
+fn failures_inside_definitions_report_where_the_op_was_written() {
+    let mut f = Forth::builder().inline_threshold(0).build();
+    f.set_source_name(Some("lib.fs"));
+    assert!(f.eval(": inner\n  0 / ;\n: outer 1 inner ;").is_ok());
+    let d = f.eval_diagnostics("outer").unwrap_err();
+    assert_eq!(Error::DivisionByZero, d.error);
+    assert_eq!(
+        at(Some("lib.fs"), 2, 5, "inner"),
+        d.location.map(|location| *location)
+    );
+    assert!(d.notes.contains(
+        &"raised at lib.fs:2:5, in `inner`, while running the user-defined word `outer`"
+            .to_string()
+    ));
+    assert_eq!(None, f.eval_diagnostics("0 0 /").unwrap_err().location);
+}
+
+#[test]
+fn rewinding_and_pruning_keep_the_map_right() {
+    let mut f = Forth::builder().inline_threshold(0).build();
+    assert!(f.eval(": sq dup * ;").is_ok());
+    let snapshot = f.snapshot();
+    assert!(f.eval(": cube dup sq * ;").is_ok());
+    f.restore(snapshot);
+    f.define_constant("cube", 27).unwrap();
+    assert_eq!(vec![None], f.source_map("cube").unwrap());
+    assert!(f.eval(": unused 1 ;\n: quad sq sq ;").is_ok());
+    let pruned = f.pruned(&["quad"]).unwrap();
+    assert_eq!(
+        vec![at(None, 2, 8, "quad"), at(None, 2, 11, "quad")],
+        pruned.source_map("quad").unwrap()
+    );
+    assert_eq!(f.source_map("sq"), pruned.source_map("sq"));
+}
@@ -0,0 +1,76 @@
+use forth::*;
+
+fn checked() -> Forth {
+    Forth::builder()
+        .stack_effects(StackEffects::Checked)
+        .build()
+}
+
+#[test]
+fn unbalanced_arms_are_rejected_when_defined() {
+    let mut f = checked();
+    assert_eq!(
+        Err(Error::UnbalancedBranches {
+            taken: -1,
+            skipped: 0
+        }),
+        f.eval(": w ( n -- ) dup if drop then ;")
+    );
+    assert_eq!(
+        Err(Error::UnbalancedBranches {
+            taken: 1,
+            skipped: 2
+        }),
+        f.eval(": v if 1 else 1 2 then ;")
+    );
+    assert_eq!(Err(Error::UnknownWord), f.eval("w"));
+    assert!(f.stack().is_empty());
+}
+
+#[test]
+fn balanced_arms_are_defined() {
+    let mut f = checked();
+    assert!(f.eval(": sign ( flag -- n ) if 1 else -1 then ;").is_ok());
+    assert!(f
+        .eval(": bump ( n -- n ) dup if 1 + else 2 swap - then ;")
+        .is_ok());
+    assert!(f.eval("0 sign 5 bump").is_ok());
+    assert_eq!(vec![-1, 6], f.stack());
+}
+
+#[test]
+fn effects_of_called_words_are_inferred() {
+    let mut f = checked();
+    assert!(f.eval(": two 1 2 ; : gone drop drop ;").is_ok());
+    assert!(f.eval(": w if two else 3 4 then ;").is_ok());
+    assert_eq!(
+        Err(Error::UnbalancedBranches {
+            taken: -2,
+            skipped: 0
+        }),
+        f.eval(": v if gone then ;")
+    );
+    assert_eq!(
+        Err(Error::UnbalancedBranches {
+            taken: 0,
+            skipped: -1
+        }),
+        f.eval(": u 1 if 0 if two gone else drop then then ;")
+    );
+}
+
+#[test]
+fn words_with_varying_effects_pass() {
+    let mut f = checked();
+    assert!(f.eval(": down dup if 1 - recurse then ;").is_ok());
+    assert!(f.eval(": e if evaluate then ;").is_ok());
+}
+
+#[test]
+fn checking_is_opt_in() {
+    let mut f = Forth::new();
+    assert_eq!(StackEffects::Unchecked, f.stack_effects());
+    assert!(f.eval(": w dup if drop then ;").is_ok());
+    f.set_stack_effects(StackEffects::Checked);
+    assert!(f.eval(": v dup if drop then ;").is_err());
+}
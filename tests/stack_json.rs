@@ -0,0 +1,35 @@
+#![cfg(feature = "serde")]
+
+use forth::*;
+
+#[test]
+fn the_stack_round_trips_through_json() {
+    let mut f = Forth::new();
+    assert_eq!("[]", f.stack_to_json());
+    assert!(f.eval("1 -2 2147483647").is_ok());
+    let json = f.stack_to_json();
+    assert_eq!("[1,-2,2147483647]", json);
+
+    let mut g = Forth::new();
+    assert!(g.load_stack_json(&json).is_ok());
+    assert!(g.eval("+").is_ok());
+    assert_eq!(vec![1, 2147483645], g.stack());
+}
+
+#[test]
+fn loading_replaces_the_stack() {
+    let mut f = Forth::new();
+    assert!(f.eval("9 9 9").is_ok());
+    assert!(f.load_stack_json(" [ 4, 5 ] ").is_ok());
+    assert_eq!(vec![4, 5], f.stack());
+}
+
+#[test]
+fn bad_json_leaves_the_stack_alone() {
+    let mut f = Forth::new();
+    assert!(f.eval("3").is_ok());
+    for json in ["", "[1,", "{\"a\": 1}", "[1.5]", "[\"1\"]", "[2147483648]"] {
+        assert!(f.load_stack_json(json).is_err(), "{json}");
+    }
+    assert_eq!(vec![3], f.stack());
+}
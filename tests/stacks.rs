@@ -0,0 +1,48 @@
+use forth::{Error, Forth};
+
+#[test]
+fn values_move_between_stacks() {
+    let mut f = Forth::new();
+    assert!(f.eval("stack temp 1 2 temp >s 3 temp s>").is_ok());
+    assert_eq!(vec![1, 3, 2], f.stack());
+    assert_eq!(Some(&[][..]), f.aux_stack("temp"));
+}
+
+#[test]
+fn stacks_are_separate_and_used_in_definitions() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": stash ( x -- ) evens >s ; stack evens stack odds")
+        .is_err());
+    assert!(f
+        .eval("stack evens stack odds : stash ( x -- ) dup 2 mod if odds else evens then >s ;")
+        .is_ok());
+    assert!(f.eval("1 2 3 4 5 stash stash stash stash stash").is_ok());
+    assert_eq!(Some(&[4, 2][..]), f.aux_stack("evens"));
+    assert_eq!(Some(&[5, 3, 1][..]), f.aux_stack("ODDS"));
+    assert_eq!(Some("( -- stack )"), f.doc("evens"));
+}
+
+#[test]
+fn embedders_push_and_pop() {
+    let mut f = Forth::new();
+    f.define_stack("queue").unwrap();
+    f.push_aux("queue", 7).unwrap();
+    assert!(f.eval("queue s> 1 +").is_ok());
+    assert_eq!(vec![8], f.stack());
+    assert!(f.eval("9 queue >s").is_ok());
+    assert_eq!(Ok(9), f.pop_aux("queue"));
+    assert_eq!(Err(Error::StackUnderflow), f.pop_aux("queue"));
+    assert_eq!(Err(Error::UnknownWord), f.push_aux("missing", 1));
+    assert_eq!(None, f.aux_stack("missing"));
+}
+
+#[test]
+fn bad_stacks_fail() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::InvalidWord), f.eval("stack"));
+    assert!(f.eval("stack temp").is_ok());
+    assert_eq!(Err(Error::StackUnderflow), f.eval("temp >s"));
+    assert_eq!(Err(Error::StackUnderflow), f.eval("temp s>"));
+    assert_eq!(Err(Error::OutOfRange), f.eval("1 5 >s"));
+}
@@ -0,0 +1,108 @@
+use forth::*;
+
+fn step(word: &str, depth: usize, before: &[Value], after: &[Value]) -> StepState {
+    StepState {
+        word: word.to_string(),
+        depth,
+        stack_before: before.to_vec(),
+        stack_after: after.to_vec(),
+    }
+}
+
+#[test]
+fn each_word_is_a_step() {
+    let mut f = Forth::new();
+    let steps: Vec<_> = f.steps("1 2 + DUP").collect();
+    assert_eq!(
+        vec![
+            Ok(step("1", 0, &[], &[1])),
+            Ok(step("2", 0, &[1], &[1, 2])),
+            Ok(step("+", 0, &[1, 2], &[3])),
+            Ok(step("dup", 0, &[3], &[3, 3])),
+        ],
+        steps
+    );
+    assert_eq!(vec![3, 3], f.stack());
+}
+
+#[test]
+fn user_defined_words_are_stepped_into() {
+    let mut f = Forth::new();
+    let steps: Vec<_> = f
+        .steps(": sq dup * ; 3 sq")
+        .map(std::result::Result::unwrap)
+        .collect();
+    assert_eq!(
+        vec![
+            step(": sq dup * ;", 0, &[], &[]),
+            step("3", 0, &[], &[3]),
+            step("sq", 0, &[3], &[3]),
+            step("dup", 1, &[3], &[3, 3]),
+            step("*", 1, &[3, 3], &[9]),
+        ],
+        steps
+    );
+}
+
+#[test]
+fn conditionals_step_through_their_branches() {
+    let mut f = Forth::new();
+    let words: Vec<_> = f
+        .steps(": sign if 1 else -1 then ; 0 sign")
+        .map(|step| step.unwrap().word)
+        .collect();
+    assert_eq!(
+        vec![": sign if 1 else -1 then ;", "0", "sign", "if", "-1"],
+        words
+    );
+    assert_eq!(vec![-1], f.stack());
+}
+
+#[test]
+fn definitions_made_in_the_steps_are_not_optimized() {
+    let mut f = Forth::new();
+    let words: Vec<_> = f
+        .steps(": sq dup * ; : f 2 3 + sq 1 swap swap ; f")
+        .map(|step| step.unwrap())
+        .skip(2)
+        .map(|step| (step.word, step.depth))
+        .collect();
+    let expected = [
+        ("f", 0),
+        ("2", 1),
+        ("3", 1),
+        ("+", 1),
+        ("sq", 1),
+        ("dup", 2),
+        ("*", 2),
+        ("1", 1),
+        ("swap", 1),
+        ("swap", 1),
+    ];
+    let expected: Vec<_> = expected
+        .iter()
+        .map(|&(word, depth)| (word.to_string(), depth))
+        .collect();
+    assert_eq!(expected, words);
+    assert_eq!(vec![25, 1], f.stack());
+    assert_eq!(Optimizations::default(), f.optimizations());
+}
+
+#[test]
+fn the_first_failure_ends_the_steps() {
+    let mut f = Forth::new();
+    let steps: Vec<_> = f.steps("1 0 / 2").collect();
+    assert_eq!(3, steps.len());
+    assert_eq!(Err(Error::DivisionByZero), steps[2]);
+    assert_eq!(
+        vec![Err(Error::UnknownWord)],
+        f.steps("nope 1").collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn dropping_the_steps_stops_the_evaluation() {
+    let mut f = Forth::new();
+    assert_eq!(2, f.steps("1 2 3 4").take(2).count());
+    assert_eq!(vec![1, 2], f.stack());
+}
@@ -0,0 +1,48 @@
+use forth::{Error, Forth};
+
+#[test]
+fn synonyms_share_the_meaning_of_a_word() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": square ( n -- n ) dup * ; synonym carre square 3 carre")
+        .is_ok());
+    assert_eq!(vec![9], f.stack());
+    assert_eq!(Some("( n -- n )"), f.doc("carre"));
+    let body = |listing: String| listing.lines().skip(1).collect::<Vec<_>>().join("\n");
+    assert_eq!(
+        body(f.disassemble("square").unwrap()),
+        body(f.disassemble("carre").unwrap())
+    );
+}
+
+#[test]
+fn synonyms_of_builtins_and_variables() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval("synonym plus + variable x synonym y x 40 2 plus y ! x @")
+        .is_ok());
+    assert_eq!(vec![42], f.stack());
+}
+
+#[test]
+fn synonyms_keep_their_meaning_when_the_word_is_redefined() {
+    let mut f = Forth::new();
+    f.alias("double", "dup").unwrap();
+    assert!(f.eval(": dup drop ; 1 double").is_ok());
+    assert_eq!(vec![1, 1], f.stack());
+}
+
+#[test]
+fn forgetting_a_synonym_keeps_the_variable() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable x 5 x ! synonym y x forget y x @").is_ok());
+    assert_eq!(vec![5], f.stack());
+}
+
+#[test]
+fn synonyms_of_unknown_words_fail() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::UnknownWord), f.eval("synonym a nothing"));
+    assert_eq!(Err(Error::InvalidWord), f.eval("synonym a"));
+    assert_eq!(Err(Error::InvalidWord), f.alias("1", "dup"));
+}
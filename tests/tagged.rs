@@ -0,0 +1,116 @@
+#![cfg(feature = "tagged")]
+
+use forth::*;
+
+#[test]
+fn variables_push_addresses() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable v v 1 v dup").is_ok());
+    assert_eq!(
+        &[Tag::Address, Tag::Number, Tag::Address, Tag::Address],
+        f.stack_tags()
+    );
+}
+
+#[test]
+fn numbers_are_not_addresses() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable v 5 v !").is_ok());
+    assert_eq!(
+        Err(Error::TagMismatch {
+            expected: Tag::Address,
+            found: Tag::Number
+        }),
+        f.eval("0 @")
+    );
+    assert_eq!(
+        Err(Error::TagMismatch {
+            expected: Tag::Address,
+            found: Tag::Number
+        }),
+        f.eval(": poke 7 swap ! ; 0 poke")
+    );
+    assert_eq!(Some(5), f.get_var("v"));
+}
+
+#[test]
+fn addresses_survive_arithmetic_and_memory() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable a variable b a b !").is_ok());
+    assert!(f.eval("b @ @ a 1 + 1 - @").is_ok());
+    assert_eq!(vec![0, 0], f.stack());
+    assert!(f.eval("a b swap - a a -").is_ok());
+    assert_eq!(
+        &[Tag::Number, Tag::Number, Tag::Number, Tag::Number],
+        f.stack_tags()
+    );
+}
+
+#[test]
+fn definitions_keep_tags() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval("variable v : skip ( n addr -- n addr ) over + ;")
+        .is_ok());
+    assert!(f.eval("0 v skip").is_ok());
+    assert_eq!(&[Tag::Number, Tag::Address], f.stack_tags());
+}
+
+#[test]
+fn mismatches_are_diagnosed() {
+    let mut f = Forth::new();
+    let d = f.eval_diagnostics("variable v 0 @").unwrap_err();
+    assert_eq!(
+        vec!["`@` takes an address, but was given a number".to_string()],
+        d.notes
+    );
+}
+
+#[test]
+fn ticks_push_execution_tokens_and_literals_characters() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": sq dup * ; : xt ['] sq ; ' sq xt 'a' 'a' 1 + 'b' 'a' -")
+        .is_ok());
+    assert_eq!(
+        &[Tag::Xt, Tag::Xt, Tag::Char, Tag::Char, Tag::Number],
+        f.stack_tags()
+    );
+}
+
+#[test]
+fn execute_and_emit_check_their_tags() {
+    let mut f = Forth::new();
+    f.set_output(std::io::sink());
+    assert!(f.eval(": zero 0 ; variable v").is_ok());
+    assert_eq!(
+        Err(Error::TagMismatch {
+            expected: Tag::Xt,
+            found: Tag::Number
+        }),
+        f.eval("0 execute")
+    );
+    assert_eq!(
+        Err(Error::TagMismatch {
+            expected: Tag::Xt,
+            found: Tag::Char
+        }),
+        f.eval("'a' execute")
+    );
+    assert_eq!(
+        Err(Error::TagMismatch {
+            expected: Tag::Char,
+            found: Tag::Number
+        }),
+        f.eval("65 emit")
+    );
+    assert_eq!(
+        Err(Error::TagMismatch {
+            expected: Tag::Char,
+            found: Tag::Xt
+        }),
+        f.eval("' zero emit")
+    );
+    assert!(f.eval("'a' v ! v @ emit ' zero v ! v @ execute").is_ok());
+    assert_eq!(vec![0], f.stack());
+}
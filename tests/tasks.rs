@@ -0,0 +1,64 @@
+use forth::{Error, Forth};
+
+#[test]
+fn tasks_take_turns_at_pause() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": ping 1 send pause 3 send ; : pong 2 send pause 4 send ;")
+        .is_ok());
+    let (_, from_forth) = f.open_channels();
+    assert!(f.eval("spawn ping spawn pong tasks").is_ok());
+    assert_eq!(vec![2], f.stack());
+    assert!(f.eval("pause tasks pause tasks").is_ok());
+    assert_eq!(vec![2, 2, 0], f.stack());
+    assert_eq!(vec![1, 2, 3, 4], from_forth.try_iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn tasks_have_their_own_stacks() {
+    let mut f = Forth::new();
+    assert!(f.eval(": worker 10 20 pause + send ;").is_ok());
+    let (_, from_forth) = f.open_channels();
+    assert!(f.eval("1 spawn worker pause 2 pause").is_ok());
+    assert_eq!(vec![1, 2], f.stack());
+    assert_eq!(Ok(30), from_forth.try_recv());
+}
+
+#[test]
+fn user_variables_are_per_task() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval("user id variable shared : worker 5 id ! id @ shared ! pause id @ send ;")
+        .is_ok());
+    let (_, from_forth) = f.open_channels();
+    assert!(f.eval("7 id ! spawn worker pause id @ shared @").is_ok());
+    assert_eq!(vec![7, 5], f.stack());
+    assert!(f.eval("pause").is_ok());
+    assert_eq!(Ok(5), from_forth.try_recv());
+    assert_eq!(Some(7), f.get_var("id"));
+}
+
+#[test]
+fn the_host_spawns_and_pauses() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval("variable ticks : ticker ticks @ 1 + ticks ! pause recurse ;")
+        .is_ok());
+    f.spawn("ticker").unwrap();
+    for _ in 0..3 {
+        f.pause().unwrap();
+    }
+    assert_eq!(Some(3), f.get_var("ticks"));
+    assert_eq!(1, f.tasks());
+}
+
+#[test]
+fn bad_tasks_fail() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::UnknownWord), f.eval("spawn nothing"));
+    assert_eq!(Err(Error::InvalidWord), f.eval("spawn dup"));
+    assert_eq!(Err(Error::InvalidWord), f.eval("spawn"));
+    assert!(f.eval(": broken drop ; spawn broken").is_ok());
+    assert_eq!(Err(Error::StackUnderflow), f.eval("pause"));
+    assert_eq!(0, f.tasks());
+}
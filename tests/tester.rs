@@ -0,0 +1,75 @@
+mod common;
+
+use common::Buffer;
+use forth::*;
+
+fn tested(input: &str) -> (TestSummary, String, Vec<Value>) {
+    let buffer = Buffer::default();
+    let mut f = Forth::new();
+    f.set_output(buffer.clone());
+    assert!(f.eval(input).is_ok());
+    let output = buffer.text();
+    (f.test_summary(), output, f.stack().to_vec())
+}
+
+#[test]
+fn passing_tests_are_counted_quietly() {
+    let (summary, output, stack) = tested("T{ 1 2 + -> 3 }T t{ 1 2 swap -> 2 1 }t T{ -> }T");
+    assert_eq!(
+        TestSummary {
+            passed: 3,
+            failed: 0
+        },
+        summary
+    );
+    assert_eq!("", output);
+    assert_eq!(Vec::<Value>::new(), stack);
+}
+
+#[test]
+fn failures_are_reported_through_the_output() {
+    let (summary, output, _) = tested("T{ 1 1 + -> 3 }T T{ 1 dup -> 1 }T T{ 1 -> 1 }T");
+    assert_eq!(
+        TestSummary {
+            passed: 1,
+            failed: 2
+        },
+        summary
+    );
+    assert_eq!(
+        "test 1: incorrect result: expected [3], got [2]\n\
+         test 2: wrong number of results: expected [1], got [1, 1]\n",
+        output
+    );
+}
+
+#[test]
+fn tests_leave_values_below_them_alone() {
+    let (summary, _, stack) = tested("7 T{ dup 1 + -> 8 }T");
+    assert_eq!(1, summary.passed);
+    assert_eq!(vec![7], stack);
+}
+
+#[test]
+fn tests_taking_values_from_below_fail() {
+    let (summary, output, _) = tested("1 2 T{ + -> 3 }T");
+    assert_eq!(1, summary.failed);
+    assert_eq!(
+        "test 1: wrong number of results: expected [3], got []\n",
+        output
+    );
+}
+
+#[test]
+fn tests_run_inside_definitions() {
+    let (summary, output, _) = tested(": sq dup * ; : tests T{ 3 sq -> 9 }T ; tests test-summary");
+    assert_eq!(1, summary.passed);
+    assert_eq!("1 passed, 0 failed\n", output);
+}
+
+#[test]
+fn arrow_without_an_open_test_is_an_error() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::InvalidWord), f.eval("1 -> 1 }T"));
+    assert_eq!(Err(Error::InvalidWord), f.eval("T{ 1 }T"));
+}
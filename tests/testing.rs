@@ -0,0 +1,31 @@
+#![cfg(feature = "testing")]
+
+use forth::{Forth, Program};
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn generated_programs_run_like_their_source(program in forth::testing::programs()) {
+        let (mut parsed, mut source) = (Forth::new(), Forth::new());
+        let outcome = parsed.run(&program);
+        prop_assert_eq!(outcome, source.eval(&program.to_string()));
+        prop_assert_eq!(parsed.stack(), source.stack());
+    }
+
+    #[test]
+    fn generated_sources_parse(source in forth::testing::sources()) {
+        prop_assert!(Program::parse(&source).is_ok());
+        prop_assert!(forth::check(&source)
+            .iter()
+            .all(|lint| lint.kind == forth::LintKind::StackUnderflow));
+    }
+}
+
+#[test]
+fn programs_can_be_generated_from_raw_bytes() {
+    use arbitrary::{Arbitrary, Unstructured};
+    let bytes: Vec<u8> = (0..=255).rev().collect();
+    let program = Program::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+    assert!(!program.to_string().is_empty());
+    assert!(Program::parse(&program.to_string()).is_ok());
+}
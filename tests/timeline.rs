@@ -0,0 +1,127 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use forth::*;
+
+/// A clock moving a microsecond each time it is read.
+#[derive(Clone, Default)]
+struct Ticking(Arc<AtomicU64>);
+
+impl Clock for Ticking {
+    fn now(&self) -> Duration {
+        Duration::from_micros(self.0.fetch_add(1, Ordering::SeqCst))
+    }
+}
+
+fn timed() -> Forth {
+    let mut f = Forth::builder()
+        .clock(Ticking::default())
+        .inline_threshold(0)
+        .build();
+    f.set_timeline(true);
+    f
+}
+
+fn call(word: &str, depth: usize, start: u64, duration: u64) -> TimedCall {
+    TimedCall {
+        word: word.to_string(),
+        depth,
+        start: Duration::from_micros(start),
+        duration: Duration::from_micros(duration),
+    }
+}
+
+/// Words and depths of the calls recorded.
+fn shape(f: &Forth) -> Vec<(String, usize)> {
+    f.timeline()
+        .calls
+        .into_iter()
+        .map(|call| (call.word, call.depth))
+        .collect()
+}
+
+#[test]
+fn calls_nest_inside_their_callers() {
+    let mut f = timed();
+    assert!(f.eval(": inner 1 ; : outer inner inner 2 ;").is_ok());
+    assert!(f.eval("outer").is_ok());
+    assert_eq!(
+        vec![
+            call("outer", 0, 0, 5),
+            call("inner", 1, 1, 1),
+            call("inner", 1, 3, 1),
+        ],
+        f.timeline().calls
+    );
+}
+
+#[test]
+fn a_tail_call_ends_its_caller() {
+    let mut f = timed();
+    assert!(f.eval(": b 2 ; : a 1 b ;").is_ok());
+    assert!(f.eval("a").is_ok());
+    assert_eq!(
+        vec![call("a", 0, 0, 1), call("b", 0, 2, 1)],
+        f.timeline().calls
+    );
+}
+
+#[test]
+fn calls_a_failure_unwinds_end_with_it() {
+    let mut f = timed();
+    assert!(f
+        .eval(": boom 0 / ; : fuse boom 1 ; : safe ['] fuse catch drop ;")
+        .is_ok());
+    assert_eq!(Err(Error::DivisionByZero), f.eval("1 fuse"));
+    assert!(f.eval("1 safe").is_ok());
+    assert!(f.eval("1 safe").is_ok());
+    assert_eq!(
+        [
+            ("fuse", 0),
+            ("boom", 1),
+            ("safe", 0),
+            ("fuse", 1),
+            ("boom", 2)
+        ]
+        .into_iter()
+        .chain([("safe", 0), ("fuse", 1), ("boom", 2)])
+        .map(|(word, depth)| (word.to_string(), depth))
+        .collect::<Vec<_>>(),
+        shape(&f)
+    );
+    assert!(f
+        .timeline()
+        .calls
+        .iter()
+        .all(|call| call.duration > Duration::ZERO));
+}
+
+#[test]
+fn recording_starts_empty_and_stops_when_turned_off() {
+    let mut f = Forth::builder().inline_threshold(0).build();
+    assert!(f.eval(": one 1 ; one").is_ok());
+    assert_eq!(Timeline::default(), f.timeline());
+    f.set_timeline(true);
+    assert!(f.eval("one").is_ok());
+    assert_eq!(vec![("one".to_string(), 0)], shape(&f));
+    f.set_timeline(false);
+    assert!(f.eval("one").is_ok());
+    assert!(f.timeline().calls.is_empty());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn timeline_as_a_chrome_trace() {
+    let mut f = timed();
+    assert!(f.eval(": inner 1 ; : outer inner ;").is_ok());
+    assert!(f.eval("outer").is_ok());
+    assert_eq!(
+        concat!(
+            r#"{"displayTimeUnit":"ns","traceEvents":["#,
+            r#"{"cat":"word","dur":1.0,"name":"outer","ph":"X","pid":1,"tid":1,"ts":0.0},"#,
+            r#"{"cat":"word","dur":1.0,"name":"inner","ph":"X","pid":1,"tid":1,"ts":2.0}]}"#
+        ),
+        f.timeline().to_chrome_trace()
+    );
+}
@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use forth::*;
+
+/// A clock that only moves when the test moves it.
+#[derive(Clone, Default)]
+struct Manual(Arc<AtomicU64>);
+
+impl Manual {
+    fn advance(&self, micros: u64) {
+        self.0.fetch_add(micros, Ordering::SeqCst);
+    }
+}
+
+impl Clock for Manual {
+    fn now(&self) -> Duration {
+        Duration::from_micros(self.0.load(Ordering::SeqCst))
+    }
+}
+
+#[test]
+fn elapsed_counts_microseconds_since_utime() {
+    let clock = Manual::default();
+    let mut f = Forth::builder().clock(clock.clone()).build();
+    clock.advance(1_500);
+    assert!(f.eval("utime").is_ok());
+    clock.advance(250);
+    assert!(f.eval("dup elapsed utime").is_ok());
+    assert_eq!(vec![1_500, 250, 1_750], f.stack());
+    f.replace_stack(Vec::new());
+    assert_eq!(Err(Error::StackUnderflow), f.eval("elapsed"));
+}
+
+#[test]
+fn differences_survive_the_cell_wrapping() {
+    let clock = Manual::default();
+    let mut f = Forth::new();
+    f.set_clock(clock.clone());
+    clock.advance(u64::from(u32::MAX) - 10);
+    assert!(f.eval("utime").is_ok());
+    clock.advance(100);
+    assert!(f.eval("elapsed").is_ok());
+    assert_eq!(vec![100], f.stack());
+}
+
+#[test]
+fn the_default_clock_goes_forward() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": spin dup if 1 - recurse then ; utime 500 spin drop elapsed")
+        .is_ok());
+    assert!(f.stack()[0] >= 0);
+    let kinds: Vec<_> = check("elapsed utime")
+        .into_iter()
+        .map(|lint| lint.kind)
+        .collect();
+    assert_eq!(vec![LintKind::StackUnderflow], kinds);
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::Buffer;
+use forth::Forth;
+
+fn traced() -> (Forth, Buffer) {
+    let buffer = Buffer::default();
+    let mut f = Forth::builder()
+        .inline_threshold(0)
+        .superinstructions(false)
+        .build();
+    f.set_output(buffer.clone());
+    f.set_trace(true);
+    (f, buffer)
+}
+
+#[test]
+fn trace_shows_each_word_and_the_stack_it_leaves() {
+    let (mut f, buffer) = traced();
+    assert!(f.eval("1 2 swap -").is_ok());
+    assert_eq!("1 <1> 1\n2 <2> 1 2\nswap <2> 2 1\n- <1> 1\n", buffer.text());
+}
+
+#[test]
+fn trace_indents_the_bodies_of_user_defined_words() {
+    let (mut f, buffer) = traced();
+    assert!(f.eval(": sq dup * ; 3 sq").is_ok());
+    assert_eq!(
+        "3 <1> 3\nsq <1> 3\n  dup <2> 3 3\n  * <1> 9\n",
+        buffer.text()
+    );
+}
+
+#[test]
+fn trace_is_off_by_default_and_can_be_turned_off() {
+    let (mut f, buffer) = traced();
+    f.set_trace(false);
+    assert!(f.eval("1 2 +").is_ok());
+    assert_eq!("", buffer.text());
+}
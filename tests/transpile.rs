@@ -0,0 +1,85 @@
+use forth::{Error, Forth};
+
+#[cfg_attr(feature = "tagged", allow(dead_code))]
+mod words {
+    include!("transpiled/words.rs");
+}
+
+type Word = fn(&mut Forth) -> forth::Result;
+
+const SCRIPT: &str = include_str!("transpiled/words.fs");
+
+fn interpreter() -> Forth {
+    let mut f = Forth::new();
+    f.eval(SCRIPT).unwrap();
+    f
+}
+
+// Regenerate it with `forth transpile tests/transpiled/words.fs`.
+#[test]
+#[cfg(not(feature = "tagged"))]
+fn transpiles_as_the_checked_in_source() {
+    assert_eq!(
+        include_str!("transpiled/words.rs"),
+        interpreter().transpile()
+    );
+}
+
+#[test]
+fn transpiled_words_leave_the_stack_the_words_leave() {
+    let calls: [(&str, Word, &[i32]); 7] = [
+        ("sum-of-squares", words::sum_of_squares, &[3, 4]),
+        ("magnitude", words::magnitude, &[-5]),
+        ("magnitude", words::magnitude, &[0]),
+        ("countdown", words::countdown, &[5]),
+        ("safe-ratio", words::safe_ratio, &[7, 2]),
+        ("safe-ratio", words::safe_ratio, &[7, 0]),
+        ("2*", words::word_2_star, &[21]),
+    ];
+    for (name, function, args) in calls {
+        let mut interpreted = interpreter();
+        interpreted.replace_stack(args.to_vec());
+        interpreted.eval(name).unwrap();
+        let mut transpiled = interpreter();
+        transpiled.replace_stack(args.to_vec());
+        function(&mut transpiled).unwrap();
+        assert_eq!(interpreted.stack(), transpiled.stack(), "{name} {args:?}");
+    }
+}
+
+// The source was transpiled without `tagged`, so it pushes addresses as
+// numbers.
+#[test]
+#[cfg(not(feature = "tagged"))]
+fn transpiled_words_share_the_variables_of_the_interpreter() {
+    let mut f = interpreter();
+    f.replace_stack(vec![2]);
+    words::tally(&mut f).unwrap();
+    f.replace_stack(vec![3]);
+    words::tally(&mut f).unwrap();
+    f.eval("total @").unwrap();
+    assert_eq!([5], f.stack());
+}
+
+#[test]
+fn transpiled_words_fail_as_the_words_do() {
+    let mut f = interpreter();
+    assert_eq!(Err(Error::StackUnderflow), words::sum_of_squares(&mut f));
+    f.replace_stack(vec![1, 0]);
+    assert_eq!(Err(Error::DivisionByZero), words::ratio(&mut f));
+}
+
+#[test]
+fn words_that_parse_the_input_are_left_out() {
+    let source = interpreter().transpile();
+    assert!(source.contains("// `define-it` isn't transpiled, as it uses `variable`."));
+    assert!(!source.contains("pub fn define_it"));
+}
+
+#[test]
+fn transpiled_tail_recursion_runs_in_constant_space() {
+    let mut f = interpreter();
+    f.replace_stack(vec![1_000_000]);
+    words::countdown(&mut f).unwrap();
+    assert_eq!([0], f.stack());
+}
@@ -0,0 +1,9 @@
+: sum-of-squares ( a b -- n ) dup * swap dup * + ;
+: magnitude ( n -- u ) dup if 0 swap - else drop 1 then ;
+: countdown ( n -- 0 ) dup if 1 - recurse then ;
+variable total
+: tally ( n -- ) total @ + total ! ;
+: ratio / ;
+: safe-ratio ( a b -- q code ) ['] ratio catch ;
+: 2* 2 * ;
+: define-it variable ;
@@ -0,0 +1,130 @@
+// Transpiled by `forth transpile`.
+
+use forth::runtime as rt;
+use forth::{Forth, Result};
+
+/// `sum-of-squares ( a b -- n )`
+pub fn sum_of_squares(f: &mut Forth) -> Result {
+    body_0(f)
+}
+
+/// `magnitude ( n -- u )`
+pub fn magnitude(f: &mut Forth) -> Result {
+    body_1(f)
+}
+
+/// `countdown ( n -- 0 )`
+pub fn countdown(f: &mut Forth) -> Result {
+    body_2(f)
+}
+
+/// `tally ( n -- )`
+pub fn tally(f: &mut Forth) -> Result {
+    body_3(f)
+}
+
+/// `ratio`
+pub fn ratio(f: &mut Forth) -> Result {
+    body_4(f)
+}
+
+/// `safe-ratio ( a b -- q code )`
+pub fn safe_ratio(f: &mut Forth) -> Result {
+    body_5(f)
+}
+
+/// `2*`
+pub fn word_2_star(f: &mut Forth) -> Result {
+    body_6(f)
+}
+
+// `define-it` isn't transpiled, as it uses `variable`.
+
+/// `sum-of-squares`
+fn body_0(f: &mut Forth) -> Result {
+    rt::square(f)?; // dup *
+    rt::swap(f)?; // swap
+    rt::square(f)?; // dup *
+    rt::add(f)?; // +
+    Ok(())
+}
+
+/// `magnitude`
+fn body_1(f: &mut Forth) -> Result {
+    let mut at = 0;
+    loop {
+        at = match at {
+            0 => {
+                rt::dup(f)?; // dup
+                if rt::pop_flag(f)? {
+                    2
+                } else {
+                    5
+                }
+            }
+            2 => {
+                rt::push(f, 0)?;
+                rt::swap_subtract(f)?; // swap -
+                7
+            }
+            5 => {
+                rt::drop(f)?; // drop
+                rt::push(f, 1)?;
+                7
+            }
+            _ => return Ok(()),
+        };
+    }
+}
+
+/// `countdown`
+fn body_2(f: &mut Forth) -> Result {
+    let mut at = 0;
+    loop {
+        at = match at {
+            0 => {
+                rt::dup(f)?; // dup
+                if rt::pop_flag(f)? {
+                    2
+                } else {
+                    5
+                }
+            }
+            2 => {
+                rt::push(f, 1)?;
+                rt::subtract(f)?; // -
+                0 // countdown
+            }
+            _ => return Ok(()),
+        };
+    }
+}
+
+/// `tally`
+fn body_3(f: &mut Forth) -> Result {
+    rt::push(f, 1)?;
+    rt::fetch(f)?; // @
+    rt::add(f)?; // +
+    rt::push(f, 1)?;
+    rt::store(f)?; // !
+    Ok(())
+}
+
+/// `ratio`
+fn body_4(f: &mut Forth) -> Result {
+    rt::divide(f)?; // /
+    Ok(())
+}
+
+/// `safe-ratio`
+fn body_5(f: &mut Forth) -> Result {
+    rt::catch(f, body_4)?; // ['] ratio catch
+    Ok(())
+}
+
+/// `2*`
+fn body_6(f: &mut Forth) -> Result {
+    rt::push(f, 2)?;
+    rt::multiply(f)?; // *
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use forth::{Error, Forth};
+
+#[test]
+fn store_and_fetch() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable x 42 x ! x @ x @ +").is_ok());
+    assert_eq!(vec![84], f.stack());
+}
+
+#[test]
+fn variables_in_definitions() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval("variable count : bump count @ 1 + count ! ;")
+        .is_ok());
+    assert!(f.eval("bump bump bump count @").is_ok());
+    assert_eq!(vec![3], f.stack());
+}
+
+#[test]
+fn variables_start_at_zero_and_are_case_insensitive() {
+    let mut f = Forth::new();
+    assert!(f.eval("VARIABLE Total total @").is_ok());
+    assert_eq!(vec![0], f.stack());
+}
+
+#[test]
+fn variable_needs_a_name() {
+    let mut f = Forth::new();
+    assert_eq!(Err(Error::InvalidWord), f.eval("variable"));
+    assert_eq!(Err(Error::InvalidWord), f.eval("variable 5"));
+}
+
+#[test]
+fn invalid_addresses() {
+    let mut f = Forth::new();
+    // With the `tagged` feature, numbers are no addresses at all.
+    #[cfg(not(feature = "tagged"))]
+    {
+        assert_eq!(Err(Error::InvalidAddress), f.eval("7 @"));
+        assert_eq!(Err(Error::InvalidAddress), f.eval("1 -1 !"));
+    }
+    assert_eq!(Err(Error::StackUnderflow), f.eval("@"));
+    assert!(f.eval("variable v").is_ok());
+    assert_eq!(Err(Error::InvalidAddress), f.eval("v 7 + @"));
+    assert_eq!(Err(Error::InvalidAddress), f.eval("1 v 2 - !"));
+}
+
+#[test]
+fn host_reads_and_writes_variables() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval("variable speed : double speed @ 2 * speed ! ;")
+        .is_ok());
+    assert_eq!(Some(0), f.get_var("speed"));
+    assert!(f.set_var("SPEED", 21).is_ok());
+    assert!(f.eval("double").is_ok());
+    assert_eq!(Some(42), f.get_var("speed"));
+    assert!(f.stack().is_empty());
+}
+
+#[test]
+fn only_variables_are_accessible_from_the_host() {
+    let mut f = Forth::new();
+    assert!(f.eval(": foo 1 ; variable bar : bar 2 ;").is_ok());
+    assert_eq!(None, f.get_var("foo"));
+    assert_eq!(None, f.get_var("bar"));
+    assert_eq!(None, f.get_var("dup"));
+    assert_eq!(Err(Error::UnknownWord), f.set_var("foo", 1));
+}
+
+#[test]
+fn data_space_quota_and_metrics() {
+    let mut f = Forth::builder().max_data_space(8).build();
+    assert!(f.eval("variable a variable b").is_ok());
+    assert_eq!(Err(Error::QuotaExceeded), f.eval("variable c"));
+    assert_eq!(8, f.metrics().data_space_bytes);
+    assert_eq!(2, f.metrics().definitions_created);
+}
+
+#[test]
+fn atomic_eval_restores_variables() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable x 1 x !").is_ok());
+    assert!(f.eval_atomic("2 x ! foo").is_err());
+    assert_eq!(Some(1), f.get_var("x"));
+}
+
+#[test]
+fn definitions_capture_the_variable_they_were_compiled_with() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval("variable x : get-x x @ ; 1 x ! variable x 2 x !")
+        .is_ok());
+    assert!(f.eval("get-x x @").is_ok());
+    assert_eq!(vec![1, 2], f.stack());
+}
+
+#[test]
+fn variable_inside_a_definition_parses_from_the_input() {
+    let mut f = Forth::new();
+    assert!(f.eval(": var variable ; var x 5 x ! x @").is_ok());
+    assert_eq!(vec![5], f.stack());
+}
+
+#[test]
+fn clones_have_their_own_data_space() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable x 1 x !").is_ok());
+    let mut g = f.clone();
+    assert!(g.eval("2 x ! variable y 3 y !").is_ok());
+    assert_eq!(Some(1), f.get_var("x"));
+    assert_eq!(Some(2), g.get_var("x"));
+    assert_eq!(None, f.get_var("y"));
+    assert_eq!(Some(3), g.get_var("y"));
+}
@@ -0,0 +1,64 @@
+use forth::{Capabilities, Forth, Warning};
+
+#[test]
+fn a_first_definition_warns_of_nothing() {
+    let mut f = Forth::new();
+    f.eval(": foo 1 ; variable bar marker baz").unwrap();
+    assert!(f.warnings().is_empty());
+}
+
+#[test]
+fn redefinitions_warn() {
+    let mut f = Forth::new();
+    f.eval(": foo 1 ; : foo 2 ; variable foo").unwrap();
+    assert_eq!(
+        [
+            Warning::Redefined("foo".to_string()),
+            Warning::Redefined("foo".to_string()),
+        ],
+        f.warnings()
+    );
+}
+
+#[test]
+fn shadowing_a_builtin_warns() {
+    let mut f = Forth::new();
+    f.eval(": DUP dup ; synonym swap over").unwrap();
+    assert_eq!(
+        [
+            Warning::ShadowsBuiltin("dup".to_string()),
+            Warning::ShadowsBuiltin("swap".to_string()),
+        ],
+        f.warnings()
+    );
+    assert_eq!(
+        "`dup` isn't unique, and shadows a builtin",
+        f.warnings()[0].to_string()
+    );
+}
+
+#[test]
+fn taking_warnings_leaves_none() {
+    let mut f = Forth::new();
+    f.eval(": foo 1 ; : foo 2 ;").unwrap();
+    assert_eq!(
+        vec![Warning::Redefined("foo".to_string())],
+        f.take_warnings()
+    );
+    assert!(f.warnings().is_empty());
+}
+
+#[test]
+fn failed_definitions_warn_of_nothing() {
+    let mut f = Forth::new();
+    f.eval(": foo 1 ;").unwrap();
+    assert!(f.eval(": foo nope ;").is_err());
+    assert!(f.warnings().is_empty());
+}
+
+#[test]
+fn hidden_builtins_are_not_shadowed() {
+    let mut f = Forth::builder().capabilities(Capabilities::NONE).build();
+    f.eval(": utime 0 ;").unwrap();
+    assert!(f.warnings().is_empty());
+}
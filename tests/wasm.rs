@@ -0,0 +1,142 @@
+use forth::{Error, Forth};
+
+fn leb(bytes: &[u8], at: &mut usize) -> usize {
+    let (mut value, mut shift) = (0, 0);
+    loop {
+        let byte = bytes[*at];
+        *at += 1;
+        value |= usize::from(byte & 0x7f) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+    }
+}
+
+/// The names `module` exports, in order.
+fn exports(module: &[u8]) -> Vec<String> {
+    assert_eq!(b"\0asm\x01\0\0\0", &module[..8]);
+    let mut at = 8;
+    while at < module.len() {
+        let id = module[at];
+        at += 1;
+        let size = leb(module, &mut at);
+        if id != 7 {
+            at += size;
+            continue;
+        }
+        let mut names = Vec::new();
+        for _ in 0..leb(module, &mut at) {
+            let len = leb(module, &mut at);
+            names.push(String::from_utf8(module[at..at + len].to_vec()).unwrap());
+            at += len + 1;
+            leb(module, &mut at);
+        }
+        return names;
+    }
+    Vec::new()
+}
+
+#[test]
+fn exports_the_program_and_the_words_asked_for() {
+    let mut f = Forth::new();
+    let module = f
+        .compile_wasm(": square dup * ; : cube dup square * ; 3 cube", &["CUBE"])
+        .unwrap();
+    assert_eq!(
+        ["memory", "push", "pop", "depth", "main", "cube"],
+        exports(&module).as_slice()
+    );
+}
+
+#[test]
+fn definitions_are_made_here_and_the_rest_is_not_run() {
+    let mut f = Forth::new();
+    f.compile_wasm("variable total : add total @ + total ! ; 5 add", &[])
+        .unwrap();
+    assert!(f.stack().is_empty());
+    f.eval("2 add total @").unwrap();
+    assert_eq!([2], f.stack());
+}
+
+#[test]
+fn only_what_a_module_can_run_compiles() {
+    let mut f = Forth::new();
+    f.eval(": later variable ; : now utime ; : fine 1 2 + ;")
+        .unwrap();
+    assert_eq!(Err(Error::InvalidWord), f.compile_wasm("now", &[]));
+    assert_eq!(Err(Error::InvalidWord), f.compile_wasm("", &["later"]));
+    assert_eq!(Err(Error::InvalidWord), f.compile_wasm("", &["dup"]));
+    assert_eq!(Err(Error::UnknownWord), f.compile_wasm("", &["nope"]));
+    assert!(f.compile_wasm("fine", &["fine"]).is_ok());
+}
+
+#[test]
+fn export_names_are_unique() {
+    let mut f = Forth::new();
+    f.eval(": main 1 ; : one 1 ;").unwrap();
+    assert_eq!(Err(Error::InvalidWord), f.compile_wasm("", &["main"]));
+    assert_eq!(Err(Error::InvalidWord), f.compile_wasm("", &["one", "ONE"]));
+}
+
+#[test]
+fn data_space_is_laid_out_in_memory() {
+    let mut f = Forth::new();
+    let module = f.compile_wasm("variable a variable b", &[]).unwrap();
+    // The data section, last, ends with the cell of `base`, holding 10,
+    // then the two cells, both zero.
+    assert_eq!(
+        [12, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        module[module.len() - 13..]
+    );
+}
+
+/// Instantiates `module`, once it validates, for `run` to call into.
+fn instantiate(module: &[u8]) -> (wasmi::Store<()>, wasmi::Instance) {
+    wasmparser::Validator::new().validate_all(module).unwrap();
+    let engine = wasmi::Engine::default();
+    let compiled = wasmi::Module::new(&engine, module).unwrap();
+    let mut store = wasmi::Store::new(&engine, ());
+    let instance = wasmi::Linker::new(&engine)
+        .instantiate_and_start(&mut store, &compiled)
+        .unwrap();
+    (store, instance)
+}
+
+/// Pushes `args`, calls the export `word` and pops what it leaves.
+fn run(module: &[u8], word: &str, args: &[i32]) -> Vec<i32> {
+    let (mut store, instance) = instantiate(module);
+    let push = instance.get_typed_func::<i32, ()>(&store, "push").unwrap();
+    let pop = instance.get_typed_func::<(), i32>(&store, "pop").unwrap();
+    let depth = instance.get_typed_func::<(), i32>(&store, "depth").unwrap();
+    let word = instance.get_typed_func::<(), ()>(&store, word).unwrap();
+    for &arg in args {
+        push.call(&mut store, arg).unwrap();
+    }
+    word.call(&mut store, ()).unwrap();
+    let mut results: Vec<i32> = (0..depth.call(&mut store, ()).unwrap())
+        .map(|_| pop.call(&mut store, ()).unwrap())
+        .collect();
+    results.reverse();
+    results
+}
+
+#[test]
+fn modules_validate_and_run_as_the_words_do() {
+    let mut f = Forth::new();
+    let script = ": square dup * ; : cube dup square * ; \
+        : negate-or-1 dup if 0 swap - else drop 1 then ; 3 cube";
+    let module = f.compile_wasm(script, &["negate-or-1"]).unwrap();
+    assert_eq!([27], run(&module, "main", &[]).as_slice());
+    assert_eq!([4], run(&module, "negate-or-1", &[-4]).as_slice());
+    assert_eq!([1], run(&module, "negate-or-1", &[0]).as_slice());
+}
+
+#[test]
+fn tail_calls_run_in_constant_space() {
+    let mut f = Forth::new();
+    let module = f
+        .compile_wasm(": cd dup if 1 - recurse then ; : down cd ;", &["down"])
+        .unwrap();
+    assert_eq!([0], run(&module, "down", &[100_000]).as_slice());
+}
@@ -0,0 +1,72 @@
+use forth::*;
+
+/// Operands at the edges of the cell range, with the results of `+`, `-`
+/// and `*` on them when they wrap.
+const BOUNDARIES: [((Value, Value), [Value; 3]); 5] = [
+    ((Value::MAX, 1), [Value::MIN, Value::MAX - 1, Value::MAX]),
+    ((Value::MIN, 1), [Value::MIN + 1, Value::MAX, Value::MIN]),
+    ((Value::MIN, -1), [Value::MAX, Value::MIN + 1, Value::MIN]),
+    ((Value::MIN, Value::MIN), [0, 0, 0]),
+    ((65536, 65537), [131073, -1, 65536]),
+];
+
+#[test]
+fn wrapping_words_keep_the_low_bits() {
+    let mut f = Forth::new();
+    for ((b, a), expected) in BOUNDARIES {
+        assert!(f.eval(&format!("{b} {a} w+ {b} {a} w- {b} {a} w*")).is_ok());
+        assert_eq!(expected.to_vec(), f.drain_stack(), "{b} {a}");
+    }
+}
+
+#[test]
+fn arithmetic_is_checked_by_default() {
+    let mut f = Forth::new();
+    assert_eq!(Arithmetic::Checked, f.arithmetic());
+    for ((b, a), wrapped) in BOUNDARIES {
+        let checked = [b.checked_add(a), b.checked_sub(a), b.checked_mul(a)];
+        for ((word, checked), wrapped) in ["+", "-", "*"].into_iter().zip(checked).zip(wrapped) {
+            let outcome = f.eval(&format!("{b} {a} {word}")).map(|_| f.drain_stack());
+            let expected = checked.map(|value| vec![value]).ok_or(Error::Overflow);
+            assert_eq!(expected, outcome, "{b} {a} {word}");
+            assert!(checked.is_none() || checked == Some(wrapped));
+            f.drain_stack();
+        }
+    }
+}
+
+#[test]
+fn wrapping_arithmetic_makes_the_builtins_wrap() {
+    let mut f = Forth::builder().arithmetic(Arithmetic::Wrapping).build();
+    for ((b, a), expected) in BOUNDARIES {
+        assert!(f.eval(&format!("{b} {a} + {b} {a} - {b} {a} *")).is_ok());
+        assert_eq!(expected.to_vec(), f.drain_stack(), "{b} {a}");
+    }
+}
+
+#[test]
+fn division_overflows_whatever_the_arithmetic() {
+    let mut f = Forth::builder().arithmetic(Arithmetic::Wrapping).build();
+    assert_eq!(Err(Error::Overflow), f.eval("-2147483648 -1 /"));
+    assert_eq!(Err(Error::Overflow), f.eval("-2147483648 -1 mod"));
+}
+
+#[test]
+fn definitions_keep_the_arithmetic_they_were_made_with() {
+    let mut f = Forth::new();
+    assert!(f.eval(": inc 1 + ;").is_ok());
+    f.set_arithmetic(Arithmetic::Wrapping);
+    assert!(f.eval(": winc 1 + ; 2147483647 winc").is_ok());
+    assert_eq!(vec![Value::MIN], f.stack());
+    assert_eq!(Err(Error::Overflow), f.eval("2147483647 inc"));
+}
+
+#[test]
+fn folding_wraps_like_run_time() {
+    let mut f = Forth::new();
+    for ((b, a), expected) in BOUNDARIES {
+        let source = format!(": w {b} {a} w+ {b} {a} w- {b} {a} w* ; w");
+        assert!(f.eval(&source).is_ok());
+        assert_eq!(expected.to_vec(), f.drain_stack(), "{b} {a}");
+    }
+}